@@ -1,18 +1,32 @@
 use pyo3::prelude::*;
 use pyo3::types::PyType;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
 
 use sayr_engine::{
-    basic_toolkit, Agent as RustAgent, AppConfig, Attachment, AttachmentKind, DeploymentConfig,
-    Message, ModelConfig, OpenAIClient, ProviderConfig, Role, SecurityConfig, ServerConfig,
-    TelemetryConfig, ToolCall, ToolDescription, ToolRegistry, ToolResult,
-    CohereClient,
+    basic_toolkit, Agent as RustAgent, AgnoError, AnthropicClient, AppConfig, Attachment,
+    AttachmentKind, CohereClient, DeploymentConfig, Document, GeminiClient, GroqClient,
+    InMemoryVectorStore, KnowledgeBase, LanguageModel, Message, MistralClient, ModelConfig,
+    OllamaClient, OpenAIClient, ProviderConfig, Retriever, Role, ScoredDocument, SecurityConfig,
+    ServerConfig, StubModel, TelemetryConfig, Tool, ToolCall, ToolDescription, ToolRegistry,
+    ToolResult, WhitespaceEmbedder,
 };
 
 // ─────────────────────────────────────────────────────────────────────────────
 // Helpers
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// Multi-threaded Tokio runtime shared by every binding, so agents and knowledge bases
+/// don't each spin up their own.
+fn runtime() -> &'static tokio::runtime::Runtime {
+    static RUNTIME: OnceLock<tokio::runtime::Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .expect("failed to start the Tokio runtime")
+    })
+}
+
 fn not_implemented_error(name: &str) -> PyErr {
     pyo3::exceptions::PyNotImplementedError::new_err(format!(
         "{name} is not yet bound for Python usage."
@@ -85,6 +99,12 @@ impl OpenAIChat {
 enum AgentInner {
     OpenAI(RustAgent<OpenAIClient>),
     Cohere(RustAgent<CohereClient>),
+    Groq(RustAgent<GroqClient>),
+    Mistral(RustAgent<MistralClient>),
+    Ollama(RustAgent<OllamaClient>),
+    Anthropic(RustAgent<AnthropicClient>),
+    Gemini(RustAgent<GeminiClient>),
+    Stub(RustAgent<StubModel>),
 }
 
 impl AgentInner {
@@ -92,30 +112,84 @@ impl AgentInner {
         match self {
             Self::OpenAI(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
             Self::Cohere(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Groq(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Mistral(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Ollama(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Anthropic(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Gemini(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
+            Self::Stub(agent) => agent.respond(message).await.map_err(|e| e.to_string()),
         }
     }
 }
 
+/// Build an agent around `model` with the options shared by every provider.
+fn build_agent<M: LanguageModel>(
+    model: Arc<M>,
+    description: Option<String>,
+    tools: ToolRegistry,
+    retriever: Option<Arc<dyn Retriever>>,
+) -> RustAgent<M> {
+    let mut agent = RustAgent::new(model).with_tools(tools);
+    if let Some(desc) = description {
+        agent = agent.with_system_prompt(desc);
+    }
+    if let Some(retriever) = retriever {
+        agent = agent.with_retriever(retriever);
+    }
+    agent
+}
+
+/// Resolve an API key from the provider section, the top-level config, then `env_var`.
+fn provider_api_key(
+    provider_key: Option<String>,
+    config_key: Option<String>,
+    env_var: &str,
+) -> PyResult<String> {
+    provider_key
+        .or(config_key)
+        .or_else(|| std::env::var(env_var).ok())
+        .ok_or_else(|| {
+            PyErr::new::<pyo3::exceptions::PyValueError, _>(format!("{env_var} not found"))
+        })
+}
+
+fn value_error(err: AgnoError) -> PyErr {
+    PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string())
+}
+
 /// The main Agent class
 #[pyclass]
 struct Agent {
     inner: Arc<tokio::sync::Mutex<AgentInner>>,
-    rt: tokio::runtime::Runtime,
 }
 
 #[pymethods]
 impl Agent {
     #[new]
-    #[pyo3(signature = (model=None, description=None, _markdown=true))]
+    #[pyo3(signature = (model=None, description=None, _markdown=true, tools=None, retriever=None))]
     fn new(
-        model: Option<PyModelConfig>,
+        model: Option<Bound<'_, PyAny>>,
         description: Option<String>,
         _markdown: bool,
+        tools: Option<PyToolRegistry>,
+        retriever: Option<PyRef<'_, PyKnowledgeBase>>,
     ) -> PyResult<Self> {
-        let rt = tokio::runtime::Builder::new_current_thread()
-            .enable_all()
-            .build()
-            .unwrap();
+        let tools = tools.map(|registry| registry.inner).unwrap_or_default();
+        let retriever = retriever.map(|kb| kb.inner.clone() as Arc<dyn Retriever>);
+
+        // `model` is either a `ModelConfig` or a scripted `StubModel`
+        if let Some(stub) = model.as_ref().and_then(|m| m.extract::<PyStubModel>().ok()) {
+            let agent = build_agent(
+                StubModel::new(stub.responses),
+                description,
+                tools,
+                retriever,
+            );
+            return Ok(Agent {
+                inner: Arc::new(tokio::sync::Mutex::new(AgentInner::Stub(agent))),
+            });
+        }
+        let model = model.map(|m| m.extract::<PyModelConfig>()).transpose()?;
 
         // Default to OpenAI if no model config provided
         let model_config = model.unwrap_or_else(|| {
@@ -138,46 +212,86 @@ impl Agent {
         });
 
         let inner = match model_config.provider().as_str() {
-            "cohere" => {
-                let cohere_config = model_config.cohere();
-                let api_key = cohere_config.api_key().or(model_config.api_key()).or(std::env::var("COHERE_API_KEY").ok())
-                    .ok_or_else(|| PyErr::new::<pyo3::exceptions::PyValueError, _>("COHERE_API_KEY not found"))?;
-                
-                let client = CohereClient::new(api_key).with_model(model_config.model());
-                let mut agent = RustAgent::new(std::sync::Arc::new(client));
-                if let Some(desc) = description {
-                    agent = agent.with_system_prompt(desc);
-                }
-                AgentInner::Cohere(agent)
-            },
-            "openai" | _ => {
+            "openai" => {
                 let openai_config = model_config.openai();
                 let client = if let Some(key) = openai_config.api_key().or(model_config.api_key()) {
                     OpenAIClient::new(key)
                 } else {
-                    OpenAIClient::from_env()
-                        .map_err(|e| PyErr::new::<pyo3::exceptions::PyValueError, _>(e.to_string()))?
+                    OpenAIClient::from_env().map_err(value_error)?
                 };
                 let client = client.with_model(model_config.model());
-                let mut agent = RustAgent::new(std::sync::Arc::new(client));
-                if let Some(desc) = description {
-                    agent = agent.with_system_prompt(desc);
+                AgentInner::OpenAI(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "cohere" => {
+                let api_key = provider_api_key(
+                    model_config.cohere().api_key(),
+                    model_config.api_key(),
+                    "COHERE_API_KEY",
+                )?;
+                let client = CohereClient::new(api_key).with_model(model_config.model());
+                AgentInner::Cohere(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "groq" => {
+                let api_key = provider_api_key(None, model_config.api_key(), "GROQ_API_KEY")?;
+                let client = GroqClient::new(api_key).with_model(model_config.model());
+                AgentInner::Groq(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "mistral" => {
+                let api_key = provider_api_key(None, model_config.api_key(), "MISTRAL_API_KEY")?;
+                let client = MistralClient::new(api_key).with_model(model_config.model());
+                AgentInner::Mistral(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "ollama" => {
+                let mut client = OllamaClient::from_env().with_model(model_config.model());
+                if let Some(host) = model_config.base_url() {
+                    client = client.with_host(host);
                 }
-                AgentInner::OpenAI(agent)
+                AgentInner::Ollama(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "anthropic" => {
+                let mut config = model_config.inner.clone();
+                config.api_key = Some(provider_api_key(
+                    config.anthropic.api_key.clone(),
+                    config.api_key.clone(),
+                    "ANTHROPIC_API_KEY",
+                )?);
+                let client = AnthropicClient::from_config(&config).map_err(value_error)?;
+                AgentInner::Anthropic(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "gemini" => {
+                let mut config = model_config.inner.clone();
+                config.api_key = Some(provider_api_key(
+                    config.gemini.api_key.clone(),
+                    config.api_key.clone(),
+                    "GEMINI_API_KEY",
+                )?);
+                let client = GeminiClient::from_config(&config).map_err(value_error)?;
+                AgentInner::Gemini(build_agent(Arc::new(client), description, tools, retriever))
+            }
+            "stub" => AgentInner::Stub(build_agent(
+                StubModel::new(Vec::new()),
+                description,
+                tools,
+                retriever,
+            )),
+            other => {
+                return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(format!(
+                    "unknown model provider '{other}'"
+                )))
             }
         };
 
         Ok(Agent {
             inner: Arc::new(tokio::sync::Mutex::new(inner)),
-            rt,
         })
     }
 
     /// Get the response from the model and print it
-    fn print_response(&self, message: String) -> PyResult<()> {
+    fn print_response(&self, py: Python<'_>, message: String) -> PyResult<()> {
         let agent = self.inner.clone();
 
-        self.rt.block_on(async move {
+        // Release the GIL so Python tools can re-acquire it while the agent runs.
+        py.allow_threads(|| runtime().block_on(async move {
             let mut agent_lock = agent.lock().await;
             match agent_lock.respond(&message).await {
                 Ok(response) => {
@@ -188,13 +302,13 @@ impl Agent {
                     e,
                 )),
             }
-        })
+        }))
     }
 
     /// Get the response as a string
-    fn run(&self, message: String) -> PyResult<String> {
+    fn run(&self, py: Python<'_>, message: String) -> PyResult<String> {
         let agent = self.inner.clone();
-        self.rt.block_on(async move {
+        py.allow_threads(|| runtime().block_on(async move {
             let mut agent_lock = agent.lock().await;
             match agent_lock.respond(&message).await {
                 Ok(response) => Ok(response),
@@ -202,7 +316,17 @@ impl Agent {
                     e,
                 )),
             }
-        })
+        }))
+    }
+
+    /// Get the response as a string without blocking the event loop.
+    ///
+    /// Returns an awaitable that runs `run` on the loop's default executor, so several
+    /// agents can be awaited concurrently under `asyncio`.
+    fn arun<'py>(slf: &Bound<'py, Self>, message: String) -> PyResult<Bound<'py, PyAny>> {
+        let py = slf.py();
+        let event_loop = py.import("asyncio")?.call_method0("get_running_loop")?;
+        event_loop.call_method1("run_in_executor", (py.None(), slf.getattr("run")?, message))
     }
 }
 
@@ -219,13 +343,23 @@ struct PyServerConfig {
 #[pymethods]
 impl PyServerConfig {
     #[new]
-    #[pyo3(signature = (host="0.0.0.0".to_string(), port=8080, tls_enabled=false))]
-    fn new(host: String, port: u16, tls_enabled: bool) -> Self {
+    #[pyo3(signature = (host="0.0.0.0".to_string(), port=8080, tls_enabled=false, tls_cert_path=None, tls_key_path=None, max_steps_limit=12))]
+    fn new(
+        host: String,
+        port: u16,
+        tls_enabled: bool,
+        tls_cert_path: Option<String>,
+        tls_key_path: Option<String>,
+        max_steps_limit: usize,
+    ) -> Self {
         Self {
             inner: ServerConfig {
                 host,
                 port,
                 tls_enabled,
+                tls_cert_path,
+                tls_key_path,
+                max_steps_limit,
             },
         }
     }
@@ -259,6 +393,36 @@ impl PyServerConfig {
     fn set_tls_enabled(&mut self, tls_enabled: bool) {
         self.inner.tls_enabled = tls_enabled;
     }
+
+    #[getter]
+    fn tls_cert_path(&self) -> Option<String> {
+        self.inner.tls_cert_path.clone()
+    }
+
+    #[setter]
+    fn set_tls_cert_path(&mut self, tls_cert_path: Option<String>) {
+        self.inner.tls_cert_path = tls_cert_path;
+    }
+
+    #[getter]
+    fn tls_key_path(&self) -> Option<String> {
+        self.inner.tls_key_path.clone()
+    }
+
+    #[setter]
+    fn set_tls_key_path(&mut self, tls_key_path: Option<String>) {
+        self.inner.tls_key_path = tls_key_path;
+    }
+
+    #[getter]
+    fn max_steps_limit(&self) -> usize {
+        self.inner.max_steps_limit
+    }
+
+    #[setter]
+    fn set_max_steps_limit(&mut self, max_steps_limit: usize) {
+        self.inner.max_steps_limit = max_steps_limit;
+    }
 }
 
 #[pyclass(name = "SecurityConfig")]
@@ -281,6 +445,7 @@ impl PySecurityConfig {
                 allowed_origins: allowed_origins.unwrap_or_default(),
                 allowed_tenants: allowed_tenants.unwrap_or_default(),
                 encryption_required,
+                api_keys: Vec::new(),
             },
         }
     }
@@ -454,6 +619,8 @@ impl PyProviderConfig {
                 api_key,
                 endpoint,
                 organization,
+                deployment: None,
+                api_version: None,
             },
         }
     }
@@ -527,6 +694,7 @@ impl PyModelConfig {
                 anthropic: anthropic.map(|p| p.inner).unwrap_or_default(),
                 gemini: gemini.map(|p| p.inner).unwrap_or_default(),
                 cohere: cohere.map(|p| p.inner).unwrap_or_default(),
+                azure: ProviderConfig::default(),
                 #[cfg(feature = "aws")]
                 bedrock: bedrock.map(|p| p.inner).unwrap_or_default(),
             },
@@ -1122,7 +1290,132 @@ impl PyMessage {
 // Tool bindings
 // ─────────────────────────────────────────────────────────────────────────────
 
-stub_pyclass!(PyTool, "Tool");
+/// Serialize a Python object with the `json` module and parse it as a JSON value.
+fn py_to_json(py: Python<'_>, value: &Bound<'_, PyAny>) -> PyResult<serde_json::Value> {
+    let dumped: String = py
+        .import("json")?
+        .call_method1("dumps", (value,))?
+        .extract()?;
+    serde_json::from_str(&dumped)
+        .map_err(|err| PyErr::new::<pyo3::exceptions::PyValueError, _>(err.to_string()))
+}
+
+/// Convert a JSON value into the equivalent Python object via the `json` module.
+fn json_to_py<'py>(py: Python<'py>, value: &serde_json::Value) -> PyResult<Bound<'py, PyAny>> {
+    py.import("json")?
+        .call_method1("loads", (value.to_string(),))
+}
+
+/// A Rust `Tool` backed by a Python callable
+struct PyCallableTool {
+    name: String,
+    description: String,
+    parameters: Option<serde_json::Value>,
+    func: Py<PyAny>,
+}
+
+impl PyCallableTool {
+    fn clone_ref(&self, py: Python<'_>) -> Self {
+        Self {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            parameters: self.parameters.clone(),
+            func: self.func.clone_ref(py),
+        }
+    }
+
+    /// Call the function with the arguments as keyword arguments (or a single positional
+    /// argument when they are not an object). A returned `str` is kept as a JSON string;
+    /// anything else must be JSON-serializable.
+    fn invoke(&self, py: Python<'_>, input: &serde_json::Value) -> PyResult<serde_json::Value> {
+        let args = json_to_py(py, input)?;
+        let func = self.func.bind(py);
+        let result = match args.downcast::<pyo3::types::PyDict>() {
+            Ok(kwargs) => func.call((), Some(kwargs))?,
+            Err(_) => func.call1((args,))?,
+        };
+        match result.extract::<String>() {
+            Ok(text) => Ok(serde_json::Value::String(text)),
+            Err(_) => py_to_json(py, &result),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl Tool for PyCallableTool {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        &self.description
+    }
+
+    fn parameters(&self) -> Option<serde_json::Value> {
+        self.parameters.clone()
+    }
+
+    async fn call(&self, input: serde_json::Value) -> sayr_engine::Result<serde_json::Value> {
+        Python::with_gil(|py| self.invoke(py, &input)).map_err(|err| AgnoError::ToolExecution {
+            tool: self.name.clone(),
+            source: err.to_string().into(),
+        })
+    }
+}
+
+/// A tool implemented by a Python function
+#[pyclass(name = "Tool")]
+struct PyTool {
+    inner: PyCallableTool,
+}
+
+#[pymethods]
+impl PyTool {
+    #[new]
+    #[pyo3(signature = (name, description, func, parameters=None))]
+    fn new(
+        py: Python<'_>,
+        name: String,
+        description: String,
+        func: Py<PyAny>,
+        parameters: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        if !func.bind(py).is_callable() {
+            return Err(PyErr::new::<pyo3::exceptions::PyTypeError, _>(
+                "func must be callable",
+            ));
+        }
+        let parameters = match parameters {
+            Some(value) => Some(py_to_json(py, &value)?),
+            None => None,
+        };
+        Ok(Self {
+            inner: PyCallableTool {
+                name,
+                description,
+                parameters,
+                func,
+            },
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> String {
+        self.inner.name.clone()
+    }
+
+    #[getter]
+    fn description(&self) -> String {
+        self.inner.description.clone()
+    }
+
+    /// Invoke the wrapped function with a dict of arguments.
+    fn call<'py>(&self, py: Python<'py>, input: Bound<'py, PyAny>) -> PyResult<Bound<'py, PyAny>> {
+        let input = py_to_json(py, &input)?;
+        let output = self.inner.invoke(py, &input)?;
+        json_to_py(py, &output)
+    }
+}
 
 #[pyclass(name = "ToolDescription")]
 #[derive(Clone)]
@@ -1186,6 +1479,26 @@ impl PyToolRegistry {
         }
     }
 
+    /// Register a Python function as a tool. `parameters` is a JSON-schema dict.
+    #[pyo3(signature = (name, description, parameters, func))]
+    fn register(
+        &mut self,
+        py: Python<'_>,
+        name: String,
+        description: String,
+        parameters: Option<Bound<'_, PyAny>>,
+        func: Py<PyAny>,
+    ) -> PyResult<()> {
+        let tool = PyTool::new(py, name, description, func, parameters)?;
+        self.inner.register(tool.inner);
+        Ok(())
+    }
+
+    /// Register an existing `Tool`.
+    fn add(&mut self, py: Python<'_>, tool: PyRef<'_, PyTool>) {
+        self.inner.register(tool.inner.clone_ref(py));
+    }
+
     fn names(&self) -> Vec<String> {
         self.inner.names()
     }
@@ -1206,6 +1519,170 @@ fn basic_toolkit_py() -> PyToolRegistry {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Knowledge bindings
+// ─────────────────────────────────────────────────────────────────────────────
+
+type PyKnowledgeBaseInner = KnowledgeBase<WhitespaceEmbedder, InMemoryVectorStore>;
+
+#[pyclass(name = "Document")]
+#[derive(Clone)]
+struct PyDocument {
+    inner: Document,
+}
+
+#[pymethods]
+impl PyDocument {
+    #[new]
+    #[pyo3(signature = (id, text, metadata=None))]
+    fn new(
+        py: Python<'_>,
+        id: String,
+        text: String,
+        metadata: Option<Bound<'_, PyAny>>,
+    ) -> PyResult<Self> {
+        let metadata = match metadata {
+            Some(metadata) => py_to_json(py, &metadata)?,
+            None => serde_json::json!({}),
+        };
+        Ok(Self {
+            inner: Document { id, text, metadata },
+        })
+    }
+
+    #[getter]
+    fn id(&self) -> String {
+        self.inner.id.clone()
+    }
+
+    #[getter]
+    fn text(&self) -> String {
+        self.inner.text.clone()
+    }
+
+    #[getter]
+    fn metadata<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_to_py(py, &self.inner.metadata)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("Document(id={:?})", self.inner.id)
+    }
+}
+
+#[pyclass(name = "ScoredDocument")]
+#[derive(Clone)]
+struct PyScoredDocument {
+    inner: ScoredDocument,
+}
+
+#[pymethods]
+impl PyScoredDocument {
+    #[getter]
+    fn document(&self) -> PyDocument {
+        PyDocument {
+            inner: self.inner.document.clone(),
+        }
+    }
+
+    #[getter]
+    fn text(&self) -> String {
+        self.inner.document.text.clone()
+    }
+
+    #[getter]
+    fn score(&self) -> f32 {
+        self.inner.score
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ScoredDocument(id={:?}, score={})",
+            self.inner.document.id, self.inner.score
+        )
+    }
+}
+
+#[pyclass(name = "WhitespaceEmbedder")]
+#[derive(Clone)]
+struct PyWhitespaceEmbedder {
+    inner: Arc<WhitespaceEmbedder>,
+}
+
+#[pymethods]
+impl PyWhitespaceEmbedder {
+    #[new]
+    #[pyo3(signature = (buckets=32))]
+    fn new(buckets: usize) -> PyResult<Self> {
+        if buckets == 0 {
+            return Err(PyErr::new::<pyo3::exceptions::PyValueError, _>(
+                "buckets must be greater than zero",
+            ));
+        }
+        Ok(Self {
+            inner: Arc::new(WhitespaceEmbedder::new(buckets)),
+        })
+    }
+}
+
+#[pyclass(name = "InMemoryVectorStore")]
+#[derive(Clone, Default)]
+struct PyInMemoryVectorStore {
+    inner: Arc<InMemoryVectorStore>,
+}
+
+#[pymethods]
+impl PyInMemoryVectorStore {
+    #[new]
+    fn new() -> Self {
+        Self::default()
+    }
+}
+
+/// A knowledge base that embeds documents and retrieves them by similarity
+#[pyclass(name = "KnowledgeBase")]
+struct PyKnowledgeBase {
+    inner: Arc<PyKnowledgeBaseInner>,
+}
+
+#[pymethods]
+impl PyKnowledgeBase {
+    #[new]
+    #[pyo3(signature = (embedder=None, store=None))]
+    fn new(embedder: Option<PyWhitespaceEmbedder>, store: Option<PyInMemoryVectorStore>) -> Self {
+        let embedder = embedder.map(|embedder| embedder.inner).unwrap_or_default();
+        let store = store.unwrap_or_default().inner;
+        Self {
+            inner: Arc::new(KnowledgeBase::new(embedder, store)),
+        }
+    }
+
+    /// Embed a document and add it to the store.
+    fn add_document(&self, py: Python<'_>, document: PyDocument) -> PyResult<()> {
+        let kb = self.inner.clone();
+        py.allow_threads(|| runtime().block_on(kb.add_document(document.inner)))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))
+    }
+
+    /// Return the `top_k` documents most similar to `query`, best match first.
+    #[pyo3(signature = (query, top_k=3))]
+    fn retrieve(
+        &self,
+        py: Python<'_>,
+        query: String,
+        top_k: usize,
+    ) -> PyResult<Vec<PyScoredDocument>> {
+        let kb = self.inner.clone();
+        let scored = py
+            .allow_threads(|| runtime().block_on(kb.retrieve(&query, top_k)))
+            .map_err(|e| PyErr::new::<pyo3::exceptions::PyRuntimeError, _>(e.to_string()))?;
+        Ok(scored
+            .into_iter()
+            .map(|inner| PyScoredDocument { inner })
+            .collect())
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Telemetry bindings
 // ─────────────────────────────────────────────────────────────────────────────
@@ -1229,6 +1706,7 @@ impl PyTelemetryLabels {
                 tenant,
                 tool,
                 workflow,
+                run_id: None,
             },
         }
     }
@@ -1377,11 +1855,8 @@ impl PyGovernanceRole {
 stub_pyclass!(PyAgentHook, "AgentHook");
 stub_pyclass!(PyConfirmationHandler, "ConfirmationHandler");
 
-stub_pyclass!(PyDocument, "Document");
 stub_pyclass!(PyDocumentChunker, "DocumentChunker");
 stub_pyclass!(PyEmbedder, "Embedder");
-stub_pyclass!(PyInMemoryVectorStore, "InMemoryVectorStore");
-stub_pyclass!(PyKnowledgeBase, "KnowledgeBase");
 stub_pyclass!(PyOpenAiEmbedder, "OpenAiEmbedder");
 stub_pyclass!(PyOpenAiEmbeddingClient, "OpenAiEmbeddingClient");
 stub_pyclass!(PyPgVectorClient, "PgVectorClient");
@@ -1392,14 +1867,12 @@ stub_pyclass!(PyRetrievalConfig, "RetrievalConfig");
 stub_pyclass!(PyRetrievalEvaluation, "RetrievalEvaluation");
 stub_pyclass!(PyRetrievalOverrides, "RetrievalOverrides");
 stub_pyclass!(PyRetriever, "Retriever");
-stub_pyclass!(PyScoredDocument, "ScoredDocument");
 stub_pyclass!(PySearchParams, "SearchParams");
 stub_pyclass!(PySimilarityMetric, "SimilarityMetric");
 stub_pyclass!(PySlidingWindowChunker, "SlidingWindowChunker");
 stub_pyclass!(PyTransformerClient, "TransformerClient");
 stub_pyclass!(PyTransformerEmbedder, "TransformerEmbedder");
 stub_pyclass!(PyVectorStore, "VectorStore");
-stub_pyclass!(PyWhitespaceEmbedder, "WhitespaceEmbedder");
 
 stub_pyclass!(PyAwsBedrockClient, "AwsBedrockClient");
 stub_pyclass!(PyAzureOpenAIClient, "AzureOpenAIClient");
@@ -1419,7 +1892,20 @@ impl PyLanguageModel {
 stub_pyclass!(PyMistralClient, "MistralClient");
 stub_pyclass!(PyModelCompletion, "ModelCompletion");
 stub_pyclass!(PyOllamaClient, "OllamaClient");
-stub_pyclass!(PyStubModel, "StubModel");
+/// A scripted model that replays `responses` in order, for tests and demos
+#[pyclass(name = "StubModel")]
+#[derive(Clone)]
+struct PyStubModel {
+    responses: Vec<String>,
+}
+
+#[pymethods]
+impl PyStubModel {
+    #[new]
+    fn new(responses: Vec<String>) -> Self {
+        Self { responses }
+    }
+}
 stub_pyclass!(PyTogetherClient, "TogetherClient");
 
 stub_pyclass!(PyConversationMemory, "ConversationMemory");
@@ -0,0 +1,34 @@
+"""Awaiting agents from asyncio with `Agent.arun`.
+
+Runs offline against scripted StubModels:
+
+    maturin develop && python tests/test_async.py
+"""
+
+import asyncio
+import json
+
+import sayr
+
+
+def make_agent(reply):
+    model = sayr.llm.StubModel([json.dumps({"action": "respond", "content": reply})])
+    return sayr.Agent(model=model)
+
+
+def test_arun_awaits_two_agents_concurrently():
+    async def main():
+        first, second = make_agent("first"), make_agent("second")
+        return await asyncio.gather(first.arun("hi"), second.arun("hello"))
+
+    assert asyncio.run(main()) == ["first", "second"]
+
+
+def test_run_still_blocks_for_scripts():
+    assert make_agent("sync").run("hi") == "sync"
+
+
+if __name__ == "__main__":
+    test_arun_awaits_two_agents_concurrently()
+    test_run_still_blocks_for_scripts()
+    print("All async agent tests passed.")
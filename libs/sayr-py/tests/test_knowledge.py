@@ -0,0 +1,61 @@
+"""Knowledge base bindings backed by the in-memory vector store.
+
+Runs offline with the deterministic whitespace embedder:
+
+    maturin develop && python tests/test_knowledge.py
+"""
+
+import json
+
+import sayr
+
+
+DOCUMENTS = [
+    ("rust", "Rust is a systems programming language focused on memory safety"),
+    ("python", "Python is a dynamic language popular for scripting and data science"),
+    ("coffee", "Espresso is brewed by forcing hot water through finely ground coffee"),
+]
+
+
+def build_knowledge_base():
+    kb = sayr.knowledge.KnowledgeBase(
+        embedder=sayr.knowledge.WhitespaceEmbedder(64),
+        store=sayr.knowledge.InMemoryVectorStore(),
+    )
+    for doc_id, text in DOCUMENTS:
+        kb.add_document(sayr.knowledge.Document(doc_id, text, {"source": "test"}))
+    return kb
+
+
+def test_document_round_trips_metadata():
+    doc = sayr.knowledge.Document("a", "hello", {"tags": ["x"]})
+    assert doc.id == "a"
+    assert doc.text == "hello"
+    assert doc.metadata == {"tags": ["x"]}
+
+
+def test_retrieve_ranks_most_relevant_document_first():
+    kb = build_knowledge_base()
+
+    results = kb.retrieve("hot water through ground coffee", top_k=2)
+
+    assert len(results) == 2
+    assert results[0].document.id == "coffee"
+    assert results[0].text == DOCUMENTS[2][1]
+    assert results[0].document.metadata == {"source": "test"}
+    assert results[0].score >= results[1].score
+
+
+def test_agent_accepts_knowledge_base_retriever():
+    kb = build_knowledge_base()
+    model = sayr.llm.StubModel([json.dumps({"action": "respond", "content": "Espresso."})])
+    agent = sayr.Agent(model=model, retriever=kb)
+
+    assert agent.run("How is espresso brewed?") == "Espresso."
+
+
+if __name__ == "__main__":
+    test_document_round_trips_metadata()
+    test_retrieve_ranks_most_relevant_document_first()
+    test_agent_accepts_knowledge_base_retriever()
+    print("All knowledge binding tests passed.")
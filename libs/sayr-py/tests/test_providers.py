@@ -0,0 +1,32 @@
+"""Provider dispatch for `Agent(model=ModelConfig(...))`.
+
+Constructs each provider with a dummy key; no requests are sent:
+
+    maturin develop && python tests/test_providers.py
+"""
+
+import sayr
+
+PROVIDERS = ["openai", "cohere", "groq", "mistral", "ollama", "anthropic", "gemini", "stub"]
+
+
+def test_each_provider_builds_with_dummy_key():
+    for provider in PROVIDERS:
+        config = sayr.config.ModelConfig(provider=provider, model="test-model", api_key="dummy-key")
+        sayr.Agent(model=config)
+
+
+def test_unknown_provider_raises_value_error():
+    config = sayr.config.ModelConfig(provider="not-a-provider", model="x", api_key="dummy-key")
+    try:
+        sayr.Agent(model=config)
+    except ValueError as err:
+        assert "not-a-provider" in str(err)
+    else:
+        raise AssertionError("expected ValueError for an unknown provider")
+
+
+if __name__ == "__main__":
+    test_each_provider_builds_with_dummy_key()
+    test_unknown_provider_raises_value_error()
+    print("All provider tests passed.")
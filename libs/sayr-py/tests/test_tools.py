@@ -0,0 +1,54 @@
+"""Custom Python tools registered with the Rust agent.
+
+Runs offline against a scripted StubModel:
+
+    maturin develop && python tests/test_tools.py
+"""
+
+import json
+
+import sayr
+
+
+def test_tool_wraps_python_callable():
+    tool = sayr.tools.Tool("add", "Add two numbers", lambda a, b: {"sum": a + b})
+    assert tool.name == "add"
+    assert tool.call({"a": 2, "b": 3}) == {"sum": 5}
+
+
+def test_agent_calls_registered_python_tool():
+    calls = []
+
+    def add(a, b):
+        calls.append((a, b))
+        return {"sum": a + b}
+
+    registry = sayr.tools.ToolRegistry()
+    registry.register(
+        "add",
+        "Add two numbers",
+        {
+            "type": "object",
+            "properties": {"a": {"type": "number"}, "b": {"type": "number"}},
+            "required": ["a", "b"],
+        },
+        add,
+    )
+    assert registry.names() == ["add"]
+
+    model = sayr.llm.StubModel(
+        [
+            json.dumps({"action": "call_tool", "name": "add", "arguments": {"a": 2, "b": 40}}),
+            json.dumps({"action": "respond", "content": "The answer is 42"}),
+        ]
+    )
+    agent = sayr.Agent(model=model, tools=registry)
+
+    assert agent.run("What is 2 + 40?") == "The answer is 42"
+    assert calls == [(2, 40)]
+
+
+if __name__ == "__main__":
+    test_tool_wraps_python_callable()
+    test_agent_calls_registered_python_tool()
+    print("All tool binding tests passed.")
@@ -0,0 +1,344 @@
+//! Spreads completions across several interchangeable model backends.
+
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use futures::future::BoxFuture;
+use tokio::sync::mpsc;
+
+use crate::error::{AgnoError, Result};
+use crate::llm::{CompletionOptions, LanguageModel, ModelCompletion, OutputFormat, ToolCallDelta};
+use crate::message::Message;
+use crate::tool::ToolDescription;
+
+/// How [`LoadBalancedModel`] picks the backend that serves the next request.
+#[derive(Clone, Debug, PartialEq)]
+pub enum BalanceStrategy {
+    /// Cycle through the backends in order.
+    RoundRobin,
+    /// Pick the backend that has gone longest without a request.
+    LeastRecentlyUsed,
+    /// Smooth weighted round-robin: backend `i` gets `weights[i]` out of every
+    /// `weights.iter().sum()` requests, interleaved rather than in bursts.
+    Weighted(Vec<u32>),
+}
+
+/// A [`LanguageModel`] that distributes requests over `backends` according to a
+/// [`BalanceStrategy`]. When the chosen backend fails with a retryable error (see
+/// [`AgnoError::is_retryable`]) the remaining backends are tried in turn; other errors
+/// are returned as-is.
+pub struct LoadBalancedModel {
+    backends: Vec<Arc<dyn LanguageModel>>,
+    strategy: BalanceStrategy,
+    state: Mutex<BalancerState>,
+}
+
+struct BalancerState {
+    requests: u64,
+    /// Request sequence number each backend last served, for least-recently-used.
+    last_used: Vec<u64>,
+    /// Running weights for smooth weighted round-robin.
+    current_weights: Vec<i64>,
+}
+
+impl LoadBalancedModel {
+    pub fn new(backends: Vec<Arc<dyn LanguageModel>>, strategy: BalanceStrategy) -> Result<Self> {
+        if backends.is_empty() {
+            return Err(AgnoError::LanguageModel(
+                "load balancer needs at least one backend".into(),
+            ));
+        }
+        if let BalanceStrategy::Weighted(weights) = &strategy {
+            if weights.len() != backends.len() || weights.iter().all(|w| *w == 0) {
+                return Err(AgnoError::LanguageModel(format!(
+                    "weighted load balancing needs one weight per backend ({}) and at least one non-zero weight",
+                    backends.len()
+                )));
+            }
+        }
+        let state = BalancerState {
+            requests: 0,
+            last_used: vec![0; backends.len()],
+            current_weights: vec![0; backends.len()],
+        };
+        Ok(Self {
+            backends,
+            strategy,
+            state: Mutex::new(state),
+        })
+    }
+
+    /// Pick the backend for the next request and record that it was used.
+    fn select(&self) -> usize {
+        let mut state = self.state.lock().expect("load balancer poisoned");
+        state.requests += 1;
+        let index = match &self.strategy {
+            BalanceStrategy::RoundRobin => {
+                ((state.requests - 1) % self.backends.len() as u64) as usize
+            }
+            BalanceStrategy::LeastRecentlyUsed => state
+                .last_used
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, used)| **used)
+                .map(|(index, _)| index)
+                .unwrap_or_default(),
+            BalanceStrategy::Weighted(weights) => {
+                let total: i64 = weights.iter().map(|w| i64::from(*w)).sum();
+                for (current, weight) in state.current_weights.iter_mut().zip(weights) {
+                    *current += i64::from(*weight);
+                }
+                let (index, _) = state
+                    .current_weights
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(index, current)| (**current, std::cmp::Reverse(*index)))
+                    .expect("at least one backend");
+                state.current_weights[index] -= total;
+                index
+            }
+        };
+        let sequence = state.requests;
+        state.last_used[index] = sequence;
+        index
+    }
+
+    /// Count `index` as used by the current request, when it serves as a failover target.
+    fn touch(&self, index: usize) {
+        let mut state = self.state.lock().expect("load balancer poisoned");
+        let sequence = state.requests;
+        state.last_used[index] = sequence;
+    }
+
+    /// Run `call` on the selected backend, moving on to the next one after each retryable
+    /// failure until every backend has been tried.
+    async fn with_failover<'a, F>(&'a self, mut call: F) -> Result<ModelCompletion>
+    where
+        F: FnMut(&'a dyn LanguageModel) -> BoxFuture<'a, Result<ModelCompletion>>,
+    {
+        let first = self.select();
+        let mut last_error = None;
+        for offset in 0..self.backends.len() {
+            let index = (first + offset) % self.backends.len();
+            if offset > 0 {
+                self.touch(index);
+            }
+            match call(self.backends[index].as_ref()).await {
+                Ok(completion) => return Ok(completion),
+                Err(err) if err.is_retryable() => {
+                    tracing::warn!(backend = index, error = %err, "model backend failed; trying the next one");
+                    last_error = Some(err);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        Err(last_error.expect("at least one backend was tried"))
+    }
+}
+
+#[async_trait]
+impl LanguageModel for LoadBalancedModel {
+    async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.with_failover(|model| model.complete_chat(messages, tools, stream))
+            .await
+    }
+
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.with_failover(|model| model.complete_chat_with_format(messages, tools, stream, format))
+            .await
+    }
+
+    async fn complete_chat_streaming(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        format: &OutputFormat,
+        deltas: mpsc::UnboundedSender<ToolCallDelta>,
+    ) -> Result<ModelCompletion> {
+        self.with_failover(|model| {
+            model.complete_chat_streaming(messages, tools, format, deltas.clone())
+        })
+        .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        self.with_failover(|model| {
+            model.complete_chat_with_options(
+                messages,
+                tools,
+                stream,
+                format,
+                options,
+                deltas.clone(),
+            )
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Answers with its own name, or fails with `error` when set.
+    struct Backend {
+        name: &'static str,
+        error: Option<fn() -> AgnoError>,
+        calls: AtomicUsize,
+    }
+
+    impl Backend {
+        fn healthy(name: &'static str) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                error: None,
+                calls: AtomicUsize::new(0),
+            })
+        }
+
+        fn failing(name: &'static str, error: fn() -> AgnoError) -> Arc<Self> {
+            Arc::new(Self {
+                name,
+                error: Some(error),
+                calls: AtomicUsize::new(0),
+            })
+        }
+    }
+
+    #[async_trait]
+    impl LanguageModel for Backend {
+        async fn complete_chat(
+            &self,
+            _messages: &[Message],
+            _tools: &[ToolDescription],
+            _stream: bool,
+        ) -> Result<ModelCompletion> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            match self.error {
+                Some(error) => Err(error()),
+                None => Ok(ModelCompletion {
+                    content: Some(self.name.to_string()),
+                    tool_calls: Vec::new(),
+                    usage: None,
+                }),
+            }
+        }
+    }
+
+    async fn answers(model: &LoadBalancedModel, count: usize) -> Vec<String> {
+        let mut answers = Vec::new();
+        for _ in 0..count {
+            let completion = model.complete_chat(&[], &[], false).await.unwrap();
+            answers.push(completion.content.unwrap());
+        }
+        answers
+    }
+
+    fn unavailable() -> AgnoError {
+        AgnoError::Transport {
+            provider: "vllm".into(),
+            message: "connection refused".into(),
+        }
+    }
+
+    #[tokio::test]
+    async fn round_robin_spreads_requests_evenly() {
+        let model = LoadBalancedModel::new(
+            vec![
+                Backend::healthy("a"),
+                Backend::healthy("b"),
+                Backend::healthy("c"),
+            ],
+            BalanceStrategy::RoundRobin,
+        )
+        .unwrap();
+        assert_eq!(answers(&model, 6).await, ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn weighted_strategy_interleaves_by_weight() {
+        let model = LoadBalancedModel::new(
+            vec![Backend::healthy("a"), Backend::healthy("b")],
+            BalanceStrategy::Weighted(vec![2, 1]),
+        )
+        .unwrap();
+        assert_eq!(answers(&model, 6).await, ["a", "b", "a", "a", "b", "a"]);
+    }
+
+    #[tokio::test]
+    async fn fails_over_on_retryable_errors_only() {
+        let down = Backend::failing("down", unavailable);
+        let up = Backend::healthy("up");
+        let model = LoadBalancedModel::new(
+            vec![down.clone(), up.clone()],
+            BalanceStrategy::LeastRecentlyUsed,
+        )
+        .unwrap();
+        // The first request lands on the failing backend and is retried on the other one;
+        // the second goes to whichever was used least recently.
+        assert_eq!(answers(&model, 2).await, ["up", "up"]);
+        assert_eq!(down.calls.load(Ordering::SeqCst), 2);
+        assert_eq!(up.calls.load(Ordering::SeqCst), 2);
+
+        let rejected = Backend::failing("rejected", || AgnoError::Auth {
+            provider: "vllm".into(),
+            status: 401,
+            message: "bad key".into(),
+        });
+        let spare = Backend::healthy("spare");
+        let model =
+            LoadBalancedModel::new(vec![rejected, spare.clone()], BalanceStrategy::RoundRobin)
+                .unwrap();
+        let err = model.complete_chat(&[], &[], false).await.unwrap_err();
+        assert!(matches!(err, AgnoError::Auth { status: 401, .. }));
+        assert_eq!(spare.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[tokio::test]
+    async fn fails_over_when_a_replica_answers_service_unavailable() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let overloaded = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(503).set_body_string("overloaded"))
+            .mount(&overloaded)
+            .await;
+        let mut config = crate::AppConfig::default().model;
+        config.api_key = Some("sk-test".into());
+        config.base_url = Some(overloaded.uri());
+        let replica: Arc<dyn LanguageModel> =
+            Arc::new(crate::OpenAIClient::from_config(&config).unwrap());
+
+        let up = Backend::healthy("up");
+        let model =
+            LoadBalancedModel::new(vec![replica, up.clone()], BalanceStrategy::RoundRobin).unwrap();
+        let completion = model
+            .complete_chat(&[Message::user("hi")], &[], false)
+            .await
+            .unwrap();
+        assert_eq!(completion.content.as_deref(), Some("up"));
+        assert_eq!(overloaded.received_requests().await.unwrap().len(), 1);
+        assert_eq!(up.calls.load(Ordering::SeqCst), 1);
+    }
+}
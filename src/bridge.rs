@@ -0,0 +1,226 @@
+//! Tool calling for models without native function calling: tools are described in the
+//! prompt and calls come back as JSON directives in the reply text.
+//!
+//! The OpenAI, Azure OpenAI, Anthropic, Gemini, Cohere, Groq, Mistral, Together, Fireworks
+//! and AWS Bedrock clients send tools natively, as does Ollama for models that support
+//! tools. Wrap a model in [`ToolCallBridge`] when it ignores or rejects the `tools` field,
+//! typically Ollama models without tool support or Gemini models served without function
+//! calling.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::json;
+
+use crate::agent::{AgentDirective, DirectiveParser, LenientDirectiveParser};
+use crate::error::Result;
+use crate::llm::{LanguageModel, ModelCompletion};
+use crate::message::{Message, Role, ToolCall};
+use crate::tool::ToolDescription;
+
+/// A [`LanguageModel`] that gives `inner` no native tools. Instead it appends an
+/// instruction to emit an exact JSON directive, turns earlier tool calls and results into
+/// plain messages, and reads a directive that makes up the reply, or sits in its only code
+/// fence, as a tool call.
+pub struct ToolCallBridge {
+    inner: Arc<dyn LanguageModel>,
+    parser: Arc<dyn DirectiveParser>,
+}
+
+impl ToolCallBridge {
+    pub fn new(inner: Arc<dyn LanguageModel>) -> Self {
+        Self {
+            inner,
+            parser: Arc::new(LenientDirectiveParser),
+        }
+    }
+
+    /// Read directives with `parser` instead of the [`LenientDirectiveParser`].
+    pub fn with_parser(mut self, parser: Arc<dyn DirectiveParser>) -> Self {
+        self.parser = parser;
+        self
+    }
+}
+
+#[async_trait]
+impl LanguageModel for ToolCallBridge {
+    async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        let bridged = bridge_messages(messages, tools);
+        let mut completion = self.inner.complete_chat(&bridged, &[], stream).await?;
+        if !completion.tool_calls.is_empty() {
+            return Ok(completion);
+        }
+        if let Some(content) = completion.content.take() {
+            completion.content = match self.parser.parse(&content) {
+                Some(AgentDirective::CallTool { name, arguments }) => {
+                    completion.tool_calls.push(ToolCall {
+                        id: None,
+                        name,
+                        arguments,
+                    });
+                    None
+                }
+                Some(AgentDirective::Respond { content }) => Some(content),
+                None => Some(content),
+            };
+        }
+        Ok(completion)
+    }
+}
+
+/// The instruction telling the model how to call `tools`.
+fn bridge_instructions(tools: &[ToolDescription]) -> String {
+    let mut instructions = String::from(
+        "To call a tool, reply with only this JSON object and nothing else:\n\
+         {\"action\":\"call_tool\",\"name\":\"<tool name>\",\"arguments\":{<arguments>}}\n\
+         To answer the user, reply with:\n\
+         {\"action\":\"respond\",\"content\":\"<answer>\"}\n\
+         Tools you can call:\n",
+    );
+    for tool in tools {
+        instructions.push_str(&format!("- {}: {}", tool.name, tool.description));
+        if let Some(parameters) = &tool.parameters {
+            instructions.push_str(&format!(" (arguments: {parameters})"));
+        }
+        instructions.push('\n');
+    }
+    instructions
+}
+
+/// `messages` without native tool traffic, with the tool instruction appended to the
+/// leading system message (or added as one).
+fn bridge_messages(messages: &[Message], tools: &[ToolDescription]) -> Vec<Message> {
+    let mut bridged: Vec<Message> = messages
+        .iter()
+        .map(|message| {
+            if let Some(call) = &message.tool_call {
+                let directive = json!({
+                    "action": "call_tool",
+                    "name": call.name,
+                    "arguments": call.arguments,
+                });
+                return Message::assistant(directive.to_string());
+            }
+            match (&message.role, &message.tool_result) {
+                (Role::Tool, Some(result)) => Message::user(format!(
+                    "Result of tool `{}`: {}",
+                    result.name, result.output
+                )),
+                (Role::Tool, None) => Message::user(format!("Tool result: {}", message.content)),
+                _ => message.clone(),
+            }
+        })
+        .collect();
+    if tools.is_empty() {
+        return bridged;
+    }
+    let instructions = bridge_instructions(tools);
+    match bridged.first_mut() {
+        Some(first) if first.role == Role::System => {
+            first.content = format!("{}\n\n{instructions}", first.content);
+        }
+        _ => bridged.insert(0, Message::system(instructions)),
+    }
+    bridged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    use serde_json::Value;
+
+    use crate::agent::StrictDirectiveParser;
+    use crate::tool::{Tool, ToolRegistry};
+    use crate::{Agent, StubModel};
+
+    struct EchoTool;
+
+    #[async_trait]
+    impl Tool for EchoTool {
+        fn name(&self) -> &str {
+            "echo"
+        }
+
+        fn description(&self) -> &str {
+            "Echoes the `text` field back"
+        }
+
+        async fn call(&self, input: Value) -> Result<Value> {
+            Ok(input)
+        }
+    }
+
+    /// Records what reaches the underlying model.
+    struct Recording {
+        inner: Arc<StubModel>,
+        requests: Mutex<Vec<(Vec<Message>, usize)>>,
+    }
+
+    #[async_trait]
+    impl LanguageModel for Recording {
+        async fn complete_chat(
+            &self,
+            messages: &[Message],
+            tools: &[ToolDescription],
+            stream: bool,
+        ) -> Result<ModelCompletion> {
+            self.requests
+                .lock()
+                .unwrap()
+                .push((messages.to_vec(), tools.len()));
+            self.inner.complete_chat(messages, tools, stream).await
+        }
+    }
+
+    #[tokio::test]
+    async fn bridges_a_tool_call_written_in_prose() {
+        let recording = Arc::new(Recording {
+            inner: StubModel::new(vec![
+                "Let me check.\n```json\n{\"action\":\"call_tool\",\"name\":\"echo\",\"arguments\":{\"text\":\"ping\"}}\n```".into(),
+                "The echo tool answered ping.".into(),
+            ]),
+            requests: Mutex::new(Vec::new()),
+        });
+        let mut tools = ToolRegistry::new();
+        tools.register(EchoTool);
+        // The strict parser would miss the fenced directive; the bridge must find it.
+        let mut agent = Agent::new(Arc::new(ToolCallBridge::new(recording.clone())))
+            .with_tools(tools)
+            .with_directive_parser(Arc::new(StrictDirectiveParser));
+
+        let reply = agent.respond("say ping").await.unwrap();
+        assert_eq!(reply, "The echo tool answered ping.");
+        assert!(agent
+            .memory()
+            .iter()
+            .any(|message| message.role == Role::Tool));
+
+        let requests = recording.requests.lock().unwrap();
+        assert_eq!(requests.len(), 2);
+        for (messages, tool_count) in requests.iter() {
+            assert_eq!(*tool_count, 0);
+            assert_eq!(messages[0].role, Role::System);
+            assert!(messages[0].content.contains("\"action\":\"call_tool\""));
+            assert!(messages[0].content.contains("- echo: Echoes"));
+        }
+        let (follow_up, _) = &requests[1];
+        assert!(follow_up
+            .iter()
+            .all(|message| message.tool_call.is_none() && message.role != Role::Tool));
+        assert_eq!(
+            follow_up[2].content,
+            r#"{"action":"call_tool","arguments":{"text":"ping"},"name":"echo"}"#
+        );
+        assert_eq!(
+            follow_up[3].content,
+            r#"Result of tool `echo`: {"text":"ping"}"#
+        );
+    }
+}
@@ -12,12 +12,23 @@ pub struct ServerConfig {
     pub port: u16,
     #[serde(default = "default_tls")]
     pub tls_enabled: bool,
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+    /// Highest `max_steps` a chat request may ask for.
+    #[serde(default = "default_max_steps_limit")]
+    pub max_steps_limit: usize,
 }
 
 fn default_tls() -> bool {
     false
 }
 
+fn default_max_steps_limit() -> usize {
+    12
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct SecurityConfig {
     #[serde(default)]
@@ -26,6 +37,9 @@ pub struct SecurityConfig {
     pub allowed_tenants: Vec<String>,
     #[serde(default = "default_encryption_required")]
     pub encryption_required: bool,
+    /// Bearer tokens accepted by the runtime server. Authentication is disabled when empty.
+    #[serde(default)]
+    pub api_keys: Vec<ApiKeyConfig>,
 }
 
 impl Default for SecurityConfig {
@@ -34,10 +48,24 @@ impl Default for SecurityConfig {
             allowed_origins: Vec::new(),
             allowed_tenants: Vec::new(),
             encryption_required: default_encryption_required(),
+            api_keys: Vec::new(),
         }
     }
 }
 
+/// An API key and the principal it authenticates as.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    pub principal_id: String,
+    #[serde(default = "default_api_key_role")]
+    pub role: String,
+}
+
+fn default_api_key_role() -> String {
+    "user".into()
+}
+
 fn default_encryption_required() -> bool {
     true
 }
@@ -121,6 +149,8 @@ pub struct ModelConfig {
     pub gemini: ProviderConfig,
     #[serde(default)]
     pub cohere: ProviderConfig,
+    #[serde(default)]
+    pub azure: ProviderConfig,
     #[cfg(feature = "aws")]
     #[serde(default)]
     pub bedrock: ProviderConfig,
@@ -135,6 +165,12 @@ pub struct ProviderConfig {
     pub endpoint: Option<String>,
     #[serde(default)]
     pub organization: Option<String>,
+    /// Azure OpenAI deployment name.
+    #[serde(default)]
+    pub deployment: Option<String>,
+    /// Azure OpenAI `api-version` query parameter.
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 #[cfg(not(feature = "persistence"))]
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Default)]
@@ -145,6 +181,12 @@ pub struct ProviderConfig {
     pub endpoint: Option<String>,
     #[serde(default)]
     pub organization: Option<String>,
+    /// Azure OpenAI deployment name.
+    #[serde(default)]
+    pub deployment: Option<String>,
+    /// Azure OpenAI `api-version` query parameter.
+    #[serde(default)]
+    pub api_version: Option<String>,
 }
 
 #[cfg(feature = "persistence")]
@@ -211,11 +253,15 @@ impl Default for AppConfig {
                 host: "0.0.0.0".into(),
                 port: 8080,
                 tls_enabled: default_tls(),
+                tls_cert_path: None,
+                tls_key_path: None,
+                max_steps_limit: default_max_steps_limit(),
             },
             security: SecurityConfig {
                 allowed_origins: vec![],
                 allowed_tenants: vec![],
                 encryption_required: default_encryption_required(),
+                api_keys: vec![],
             },
             telemetry: TelemetryConfig {
                 sample_rate: default_sample_rate(),
@@ -239,6 +285,7 @@ impl Default for AppConfig {
                 anthropic: ProviderConfig::default(),
                 gemini: ProviderConfig::default(),
                 cohere: ProviderConfig::default(),
+                azure: ProviderConfig::default(),
                 #[cfg(feature = "aws")]
                 bedrock: ProviderConfig::default(),
             },
@@ -249,11 +296,36 @@ impl Default for AppConfig {
 }
 
 impl AppConfig {
+    /// Load configuration, choosing the format from the file extension (`.toml`,
+    /// `.yaml`/`.yml` or `.json`). Files without an extension are parsed as TOML.
+    ///
+    /// String values may reference the environment with `${VAR}` or `${VAR:-default}`.
     pub fn from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_ascii_lowercase());
         let raw = fs::read_to_string(path)?;
-        let cfg: Self = toml::from_str(&raw)
+        let parsed = match extension.as_deref() {
+            None | Some("toml") => toml::from_str(&raw).map_err(|err| err.to_string()),
+            Some("yaml") | Some("yml") => serde_yaml::from_str(&raw).map_err(|err| err.to_string()),
+            Some("json") => serde_json::from_str(&raw).map_err(|err| err.to_string()),
+            Some(other) => {
+                return Err(AgnoError::Protocol(format!(
+                    "Unsupported configuration format `.{other}` (expected toml, yaml or json)"
+                )))
+            }
+        };
+        let cfg: Self = parsed
             .map_err(|err| AgnoError::Protocol(format!("Failed to parse configuration: {err}")))?;
-        Ok(cfg)
+        cfg.expand_env()
+    }
+
+    /// Substitute `${VAR}` and `${VAR:-default}` references in every string value.
+    fn expand_env(self) -> Result<Self> {
+        let mut value = serde_json::to_value(&self)?;
+        expand_env_in_value(&mut value)?;
+        Ok(serde_json::from_value(value)?)
     }
 
     pub fn from_env_or_file(path: impl AsRef<Path>) -> Result<Self> {
@@ -296,6 +368,15 @@ impl AppConfig {
         if let Ok(cohere_endpoint) = env::var("AGNO_COHERE_ENDPOINT") {
             cfg.model.cohere.endpoint = Some(cohere_endpoint);
         }
+        if let Ok(azure_key) = env::var("AGNO_AZURE_OPENAI_API_KEY") {
+            cfg.model.azure.api_key = Some(azure_key);
+        }
+        if let Ok(azure_endpoint) = env::var("AGNO_AZURE_OPENAI_ENDPOINT") {
+            cfg.model.azure.endpoint = Some(azure_endpoint);
+        }
+        if let Ok(azure_deployment) = env::var("AGNO_AZURE_OPENAI_DEPLOYMENT") {
+            cfg.model.azure.deployment = Some(azure_deployment);
+        }
         if let Ok(stream) = env::var("AGNO_STREAMING") {
             if let Ok(parsed) = stream.parse::<bool>() {
                 cfg.model.stream = parsed;
@@ -327,6 +408,55 @@ impl AppConfig {
     }
 }
 
+fn expand_env_in_value(value: &mut serde_json::Value) -> Result<()> {
+    match value {
+        serde_json::Value::String(text) => *text = expand_env_vars(text)?,
+        serde_json::Value::Array(items) => {
+            for item in items {
+                expand_env_in_value(item)?;
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for item in map.values_mut() {
+                expand_env_in_value(item)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand_env_vars(input: &str) -> Result<String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+    while let Some(start) = rest.find("${") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let end = after.find('}').ok_or_else(|| {
+            AgnoError::Protocol(format!(
+                "Unterminated `${{` in configuration value `{input}`"
+            ))
+        })?;
+        let expr = &after[..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        match (env::var(name), default) {
+            (Ok(value), _) => output.push_str(&value),
+            (Err(_), Some(default)) => output.push_str(default),
+            (Err(_), None) => {
+                return Err(AgnoError::Protocol(format!(
+                    "Environment variable `{name}` referenced in configuration is not set"
+                )))
+            }
+        }
+        rest = &after[end + 1..];
+    }
+    output.push_str(rest);
+    Ok(output)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -352,6 +482,71 @@ mod tests {
         env::remove_var("AGNO_PORT");
     }
 
+    fn config_file(suffix: &str, contents: &str) -> NamedTempFile {
+        let mut file = tempfile::Builder::new().suffix(suffix).tempfile().unwrap();
+        write!(file, "{contents}").unwrap();
+        file
+    }
+
+    #[test]
+    fn loads_same_config_from_toml_yaml_and_json() {
+        let toml = config_file(
+            ".toml",
+            "[server]\nhost='127.0.0.1'\nport=9000\n[model]\nprovider='openai'\nmodel='gpt-4'\n[deployment]\nreplicas=3",
+        );
+        let yaml = config_file(
+            ".yaml",
+            "server:\n  host: 127.0.0.1\n  port: 9000\nmodel:\n  provider: openai\n  model: gpt-4\ndeployment:\n  replicas: 3\n",
+        );
+        let json = config_file(
+            ".json",
+            r#"{"server":{"host":"127.0.0.1","port":9000},"model":{"provider":"openai","model":"gpt-4"},"deployment":{"replicas":3}}"#,
+        );
+
+        let from_toml = AppConfig::from_file(toml.path()).unwrap();
+        assert_eq!(from_toml.deployment.replicas, 3);
+        assert_eq!(from_toml, AppConfig::from_file(yaml.path()).unwrap());
+        assert_eq!(from_toml, AppConfig::from_file(json.path()).unwrap());
+    }
+
+    #[test]
+    fn expands_env_vars_in_nested_values() {
+        env::set_var("SAYR_TEST_OPENAI_KEY", "sk-from-env");
+        let file = config_file(
+            ".toml",
+            "[server]\nhost='${SAYR_TEST_HOST:-127.0.0.1}'\nport=9000\n[model]\nprovider='openai'\nmodel='gpt-4'\n[model.openai]\napi_key='${SAYR_TEST_OPENAI_KEY}'\n[model.cohere]\nendpoint='https://${SAYR_TEST_COHERE_HOST:-api.cohere.ai}/v1'",
+        );
+
+        let cfg = AppConfig::from_file(file.path()).unwrap();
+        assert_eq!(cfg.model.openai.api_key.as_deref(), Some("sk-from-env"));
+        assert_eq!(cfg.server.host, "127.0.0.1");
+        assert_eq!(
+            cfg.model.cohere.endpoint.as_deref(),
+            Some("https://api.cohere.ai/v1")
+        );
+        env::remove_var("SAYR_TEST_OPENAI_KEY");
+    }
+
+    #[test]
+    fn errors_on_unset_env_var_without_default() {
+        let file = config_file(
+            ".yaml",
+            "server:\n  host: 127.0.0.1\n  port: 9000\nmodel:\n  provider: openai\n  model: gpt-4\n  api_key: ${SAYR_TEST_MISSING_KEY}\n",
+        );
+
+        let err = AppConfig::from_file(file.path()).unwrap_err();
+        assert!(err.to_string().contains("SAYR_TEST_MISSING_KEY"));
+    }
+
+    #[test]
+    fn rejects_unknown_config_extension() {
+        let file = config_file(".ini", "host=127.0.0.1");
+        let err = AppConfig::from_file(file.path()).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("Unsupported configuration format `.ini`"));
+    }
+
     #[test]
     #[cfg(feature = "persistence")]
     fn overrides_storage_backend() {
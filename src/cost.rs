@@ -0,0 +1,100 @@
+//! Token pricing and per-run cost accounting.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::llm::TokenUsage;
+
+/// Model that matches every model of a provider in a [`CostModel`] price table.
+pub const ANY_MODEL: &str = "*";
+
+/// Token prices in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input_per_million: f64,
+    pub output_per_million: f64,
+}
+
+impl ModelPrice {
+    pub fn new(input_per_million: f64, output_per_million: f64) -> Self {
+        Self {
+            input_per_million,
+            output_per_million,
+        }
+    }
+
+    pub fn cost(&self, usage: &TokenUsage) -> f64 {
+        (usage.input_tokens as f64 * self.input_per_million
+            + usage.output_tokens as f64 * self.output_per_million)
+            / 1_000_000.0
+    }
+}
+
+/// Price table keyed by provider and model, used to turn reported token usage into cost.
+#[derive(Debug, Clone, Default)]
+pub struct CostModel {
+    prices: HashMap<(String, String), ModelPrice>,
+}
+
+impl CostModel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the price for `model` on `provider`. Use [`ANY_MODEL`] as the model for a
+    /// provider-wide fallback.
+    pub fn with_price(
+        mut self,
+        provider: impl Into<String>,
+        model: impl Into<String>,
+        price: ModelPrice,
+    ) -> Self {
+        self.prices.insert((provider.into(), model.into()), price);
+        self
+    }
+
+    /// The exact model price, falling back to the provider-wide entry.
+    pub fn price(&self, provider: &str, model: &str) -> Option<ModelPrice> {
+        self.prices
+            .get(&(provider.to_string(), model.to_string()))
+            .or_else(|| {
+                self.prices
+                    .get(&(provider.to_string(), ANY_MODEL.to_string()))
+            })
+            .copied()
+    }
+
+    /// Cost in USD of `usage`, or `None` when the model has no configured price.
+    pub fn cost(&self, usage: &TokenUsage) -> Option<f64> {
+        self.price(&usage.provider, &usage.model)
+            .map(|price| price.cost(usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn usage(model: &str, input_tokens: u64, output_tokens: u64) -> TokenUsage {
+        TokenUsage {
+            provider: "openai".into(),
+            model: model.into(),
+            input_tokens,
+            output_tokens,
+        }
+    }
+
+    #[test]
+    fn prices_usage_by_model_with_provider_fallback() {
+        let costs = CostModel::new()
+            .with_price("openai", "gpt-4o", ModelPrice::new(2.5, 10.0))
+            .with_price("openai", ANY_MODEL, ModelPrice::new(1.0, 1.0));
+
+        let cost = costs.cost(&usage("gpt-4o", 1_000, 500)).unwrap();
+        assert!((cost - 0.0075).abs() < 1e-12);
+        let fallback = costs.cost(&usage("gpt-4o-mini", 2_000_000, 0)).unwrap();
+        assert!((fallback - 2.0).abs() < 1e-12);
+        assert!(CostModel::new().cost(&usage("gpt-4o", 1, 1)).is_none());
+    }
+}
@@ -1,7 +1,20 @@
 use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
 
 use crate::config::AppConfig;
 
+const DEFAULT_IMAGE: &str = "ghcr.io/YASSERRMD/sayr-engine:latest";
+
+/// CPU utilization the generated HorizontalPodAutoscaler aims for.
+const HPA_TARGET_CPU_UTILIZATION: u32 = 70;
+
+/// How far past the configured replica count the autoscaler may grow.
+const HPA_MAX_REPLICA_FACTOR: u16 = 4;
+
+/// CPU each pod requests. The autoscaler measures utilization against this, so without
+/// it the HorizontalPodAutoscaler never scales.
+const CONTAINER_CPU_REQUEST: &str = "250m";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeploymentPlan {
     pub name: String,
@@ -10,22 +23,154 @@ pub struct DeploymentPlan {
 
 impl DeploymentPlan {
     pub fn render_compose(&self) -> String {
-        format!(
-            "version: '3'\nservices:\n  agno:\n    image: {}\n    ports:\n      - \"{}:{}\"\n    environment:\n      - AGNO_HOST={}\n      - AGNO_PORT={}\n      - AGNO_TELEMETRY_SAMPLE={}\n",
-            self.config
-                .deployment
-                .container_image
-                .clone()
-                .unwrap_or_else(|| "ghcr.io/YASSERRMD/sayr-engine:latest".into()),
-            self.config.server.port,
-            self.config.server.port,
-            self.config.server.host,
-            self.config.server.port,
-            self.config.telemetry.sample_rate
-        )
+        self.to_docker_compose_yaml("agno")
+    }
+
+    /// Docker Compose file running `app_name` with the configured image, replica count
+    /// and server settings. A single replica publishes the server port on the same host
+    /// port; several replicas each get an ephemeral host port, since they cannot all bind
+    /// one.
+    pub fn to_docker_compose_yaml(&self, app_name: &str) -> String {
+        let port = self.config.server.port;
+        let replicas = self.config.deployment.replicas;
+        let published = if replicas > 1 {
+            port.to_string()
+        } else {
+            format!("{port}:{port}")
+        };
+        let mut services = serde_json::Map::new();
+        services.insert(
+            app_name.to_string(),
+            json!({
+                "image": self.image(),
+                "ports": [published],
+                "environment": self.environment(),
+                "deploy": { "replicas": replicas },
+            }),
+        );
+        to_yaml(&json!({ "services": services }))
+    }
+
+    /// Kubernetes manifests for `app_name`: a Deployment, a Service in front of it and,
+    /// when `deployment.autoscale` is set, a HorizontalPodAutoscaler. The autoscaler
+    /// scales on CPU between the configured replica count and four times that, and
+    /// records `max_concurrency` as an annotation since each pod caps its own concurrent
+    /// runs at that value.
+    pub fn to_kubernetes_yaml(&self, app_name: &str) -> String {
+        let deployment = &self.config.deployment;
+        let port = self.config.server.port;
+        let labels = json!({ "app": app_name });
+        let env: Vec<Value> = self
+            .environment()
+            .into_iter()
+            .map(|entry| {
+                let (name, value) = entry.split_once('=').unwrap_or((&entry, ""));
+                json!({ "name": name, "value": value })
+            })
+            .collect();
+        let probe = json!({ "httpGet": { "path": "/health", "port": port } });
+
+        let mut documents = vec![
+            json!({
+                "apiVersion": "apps/v1",
+                "kind": "Deployment",
+                "metadata": { "name": app_name, "labels": labels },
+                "spec": {
+                    "replicas": deployment.replicas,
+                    "selector": { "matchLabels": labels },
+                    "template": {
+                        "metadata": { "labels": labels },
+                        "spec": {
+                            "containers": [{
+                                "name": app_name,
+                                "image": self.image(),
+                                "ports": [{ "containerPort": port }],
+                                "env": env,
+                                "resources": {
+                                    "requests": { "cpu": CONTAINER_CPU_REQUEST },
+                                },
+                                "readinessProbe": probe,
+                                "livenessProbe": probe,
+                            }],
+                        },
+                    },
+                },
+            }),
+            json!({
+                "apiVersion": "v1",
+                "kind": "Service",
+                "metadata": { "name": app_name, "labels": labels },
+                "spec": {
+                    "selector": labels,
+                    "ports": [{ "port": port, "targetPort": port }],
+                },
+            }),
+        ];
+        if deployment.autoscale {
+            let min_replicas = deployment.replicas.max(1);
+            documents.push(json!({
+                "apiVersion": "autoscaling/v2",
+                "kind": "HorizontalPodAutoscaler",
+                "metadata": {
+                    "name": app_name,
+                    "labels": labels,
+                    "annotations": {
+                        "sayr-engine/max-concurrency": deployment.max_concurrency.to_string(),
+                    },
+                },
+                "spec": {
+                    "scaleTargetRef": {
+                        "apiVersion": "apps/v1",
+                        "kind": "Deployment",
+                        "name": app_name,
+                    },
+                    "minReplicas": min_replicas,
+                    "maxReplicas": min_replicas.saturating_mul(HPA_MAX_REPLICA_FACTOR),
+                    "metrics": [{
+                        "type": "Resource",
+                        "resource": {
+                            "name": "cpu",
+                            "target": {
+                                "type": "Utilization",
+                                "averageUtilization": HPA_TARGET_CPU_UTILIZATION,
+                            },
+                        },
+                    }],
+                },
+            }));
+        }
+
+        documents
+            .iter()
+            .map(to_yaml)
+            .collect::<Vec<_>>()
+            .join("---\n")
+    }
+
+    fn image(&self) -> String {
+        self.config
+            .deployment
+            .container_image
+            .clone()
+            .unwrap_or_else(|| DEFAULT_IMAGE.into())
+    }
+
+    fn environment(&self) -> Vec<String> {
+        vec![
+            format!("AGNO_HOST={}", self.config.server.host),
+            format!("AGNO_PORT={}", self.config.server.port),
+            format!(
+                "AGNO_TELEMETRY_SAMPLE={}",
+                self.config.telemetry.sample_rate
+            ),
+        ]
     }
 }
 
+fn to_yaml(document: &Value) -> String {
+    serde_yaml::to_string(document).expect("JSON values always serialize to YAML")
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -40,4 +185,83 @@ mod tests {
         assert!(rendered.contains("services:"));
         assert!(rendered.contains("agno"));
     }
+
+    fn plan(autoscale: bool) -> DeploymentPlan {
+        let mut config = AppConfig::default();
+        config.deployment.replicas = 3;
+        config.deployment.autoscale = autoscale;
+        config.deployment.container_image = Some("registry.example/agents:1.2".into());
+        DeploymentPlan {
+            name: "demo".into(),
+            config,
+        }
+    }
+
+    fn parse_all(rendered: &str) -> Vec<serde_yaml::Value> {
+        serde_yaml::Deserializer::from_str(rendered)
+            .map(|document| serde_yaml::Value::deserialize(document).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn renders_kubernetes_manifests_with_optional_autoscaler() {
+        let documents = parse_all(&plan(false).to_kubernetes_yaml("support-bot"));
+        let kinds: Vec<&str> = documents
+            .iter()
+            .map(|doc| doc["kind"].as_str().unwrap())
+            .collect();
+        assert_eq!(kinds, ["Deployment", "Service"]);
+        let deployment = &documents[0];
+        assert_eq!(deployment["metadata"]["name"].as_str(), Some("support-bot"));
+        assert_eq!(deployment["spec"]["replicas"].as_u64(), Some(3));
+        let container = &deployment["spec"]["template"]["spec"]["containers"][0];
+        assert_eq!(
+            container["image"].as_str(),
+            Some("registry.example/agents:1.2")
+        );
+        assert_eq!(container["ports"][0]["containerPort"].as_u64(), Some(8080));
+        assert_eq!(
+            container["resources"]["requests"]["cpu"].as_str(),
+            Some(CONTAINER_CPU_REQUEST)
+        );
+        assert_eq!(
+            documents[1]["spec"]["selector"]["app"].as_str(),
+            Some("support-bot")
+        );
+
+        let documents = parse_all(&plan(true).to_kubernetes_yaml("support-bot"));
+        assert_eq!(documents.len(), 3);
+        let hpa = &documents[2];
+        assert_eq!(hpa["kind"].as_str(), Some("HorizontalPodAutoscaler"));
+        assert_eq!(hpa["spec"]["minReplicas"].as_u64(), Some(3));
+        assert_eq!(hpa["spec"]["maxReplicas"].as_u64(), Some(12));
+        assert_eq!(
+            hpa["metadata"]["annotations"]["sayr-engine/max-concurrency"].as_str(),
+            Some("32")
+        );
+    }
+
+    #[test]
+    fn renders_docker_compose_with_replicas_and_image() {
+        let rendered = plan(false).to_docker_compose_yaml("support-bot");
+        let compose: serde_yaml::Value = serde_yaml::from_str(&rendered).unwrap();
+        let service = &compose["services"]["support-bot"];
+        assert_eq!(
+            service["image"].as_str(),
+            Some("registry.example/agents:1.2")
+        );
+        assert_eq!(service["deploy"]["replicas"].as_u64(), Some(3));
+        // Replicas cannot share a host port, so each gets an ephemeral one.
+        assert_eq!(service["ports"][0].as_str(), Some("8080"));
+        assert_eq!(service["environment"][1].as_str(), Some("AGNO_PORT=8080"));
+
+        let mut single = plan(false);
+        single.config.deployment.replicas = 1;
+        let rendered = single.to_docker_compose_yaml("support-bot");
+        let compose: serde_yaml::Value = serde_yaml::from_str(&rendered).unwrap();
+        assert_eq!(
+            compose["services"]["support-bot"]["ports"][0].as_str(),
+            Some("8080:8080")
+        );
+    }
 }
@@ -7,13 +7,77 @@ pub enum AgnoError {
     #[error("tool `{0}` not found")]
     ToolNotFound(String),
 
-    #[error("tool `{name}` invocation failed: {source}")]
-    ToolInvocation {
-        name: String,
+    #[error("tool `{tool}` invocation failed: {source}")]
+    ToolExecution {
+        tool: String,
         #[source]
         source: Box<dyn std::error::Error + Send + Sync>,
     },
 
+    #[error("tool `{name}` timed out after {timeout:?}")]
+    ToolTimeout {
+        name: String,
+        timeout: std::time::Duration,
+    },
+
+    /// An agent run went past the deadline set with `Agent::with_timeout`. Not retryable:
+    /// the same run would hit the same limit again.
+    #[error("agent run exceeded its {limit:?} time limit")]
+    RunTimeout { limit: std::time::Duration },
+
+    /// The arguments did not match the tool's parameter schema, so the tool was not run.
+    #[error("tool `{tool}` called with invalid arguments: {}", .violations.join("; "))]
+    ToolArguments {
+        tool: String,
+        violations: Vec<String>,
+    },
+
+    #[error(
+        "{provider} rate limit exceeded{}",
+        .retry_after
+            .map(|delay| format!("; retry after {:.1}s", delay.as_secs_f64()))
+            .unwrap_or_default()
+    )]
+    RateLimited {
+        provider: String,
+        retry_after: Option<std::time::Duration>,
+    },
+
+    /// The provider rejected the credentials (HTTP 401/403).
+    #[error(
+        "language model error: {provider} request failed with {}: {message}",
+        status_line(*.status)
+    )]
+    Auth {
+        provider: String,
+        status: u16,
+        message: String,
+    },
+
+    /// The provider failed to serve the request (HTTP 5xx), e.g. while overloaded or
+    /// restarting.
+    #[error(
+        "language model error: {provider} request failed with {}: {message}",
+        status_line(*.status)
+    )]
+    ServerError {
+        provider: String,
+        status: u16,
+        message: String,
+    },
+
+    /// The request to the provider timed out before a response arrived.
+    #[error("language model error: {provider} request error: {message}")]
+    Timeout { provider: String, message: String },
+
+    /// The request could not be delivered (connection refused, DNS, TLS, ...).
+    #[error("language model error: {provider} request error: {message}")]
+    Transport { provider: String, message: String },
+
+    /// The provider answered, but the body could not be understood.
+    #[error("language model error: {message}")]
+    InvalidResponse { provider: String, message: String },
+
     #[error("language model error: {0}")]
     LanguageModel(String),
 
@@ -23,6 +87,13 @@ pub enum AgnoError {
     #[error("storage error: {0}")]
     Storage(String),
 
+    /// An embedding's length differs from the vectors a store already holds, e.g. after
+    /// switching embedding models mid-corpus.
+    #[error(
+        "embedding dimension mismatch: store holds {expected}-dimensional vectors, got {actual}"
+    )]
+    DimensionMismatch { expected: usize, actual: usize },
+
     #[error(transparent)]
     Serde(#[from] serde_json::Error),
 
@@ -34,5 +105,52 @@ pub enum AgnoError {
 
     #[error("MCP error: {0}")]
     Mcp(String),
+
+    /// The run was cancelled through its cancellation token before it finished.
+    #[error("run cancelled")]
+    Cancelled,
+}
+
+/// Render an HTTP status the way `reqwest::StatusCode` displays it, e.g. `401 Unauthorized`.
+fn status_line(status: u16) -> String {
+    reqwest::StatusCode::from_u16(status)
+        .map(|status| status.to_string())
+        .unwrap_or_else(|_| status.to_string())
+}
+
+impl AgnoError {
+    /// How long the provider asked callers to wait, for rate-limit errors that advertised it.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        match self {
+            AgnoError::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether repeating the same request may succeed: rate limits, server errors, timeouts
+    /// and transport failures are transient; auth, protocol and parse errors are not.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            AgnoError::RateLimited { .. }
+                | AgnoError::ServerError { .. }
+                | AgnoError::Timeout { .. }
+                | AgnoError::Transport { .. }
+                | AgnoError::ToolTimeout { .. }
+        )
+    }
+
+    /// The model provider the error came from, for provider-level failures.
+    pub fn provider(&self) -> Option<&str> {
+        match self {
+            AgnoError::RateLimited { provider, .. }
+            | AgnoError::Auth { provider, .. }
+            | AgnoError::ServerError { provider, .. }
+            | AgnoError::Timeout { provider, .. }
+            | AgnoError::Transport { provider, .. }
+            | AgnoError::InvalidResponse { provider, .. } => Some(provider),
+            _ => None,
+        }
+    }
 }
 
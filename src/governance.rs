@@ -1,8 +1,15 @@
 use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, RwLock};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::SystemTime;
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{AgnoError, Result};
+use crate::message::Message;
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub enum Role {
     Admin,
@@ -18,6 +25,24 @@ pub enum Action {
     ManageDeployment,
 }
 
+impl Action {
+    /// Tool name that matches every tool in `CallTool` grants and denials.
+    pub const ANY_TOOL: &'static str = "*";
+
+    /// `CallTool` action covering all tools.
+    pub fn any_tool() -> Self {
+        Action::CallTool(Self::ANY_TOOL.to_string())
+    }
+
+    /// The wildcard form of this action, for `CallTool` actions on a specific tool.
+    fn wildcard(&self) -> Option<Self> {
+        match self {
+            Action::CallTool(name) if name != Self::ANY_TOOL => Some(Self::any_tool()),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
 pub struct Principal {
     pub id: String,
@@ -25,55 +50,284 @@ pub struct Principal {
     pub tenant: Option<String>,
 }
 
+/// Replaces the value of every object key named `field`, at any depth, with `redaction`.
+/// Applied to tool outputs before they reach agent memory and to transcripts returned by
+/// the server. Rules without a `tenant` apply to every tenant.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct PrivacyRule {
     pub field: String,
     pub redaction: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+}
+
+impl PrivacyRule {
+    pub fn new(field: impl Into<String>, redaction: impl Into<String>) -> Self {
+        Self {
+            field: field.into(),
+            redaction: redaction.into(),
+            tenant: None,
+        }
+    }
+
+    /// Only apply this rule to requests made on behalf of `tenant`.
+    pub fn for_tenant(mut self, tenant: impl Into<String>) -> Self {
+        self.tenant = Some(tenant.into());
+        self
+    }
+
+    fn applies_to(&self, tenant: Option<&str>) -> bool {
+        self.tenant
+            .as_deref()
+            .is_none_or(|scope| Some(scope) == tenant)
+    }
+
+    fn apply(&self, value: &mut serde_json::Value) {
+        match value {
+            serde_json::Value::Object(obj) => {
+                for (key, nested) in obj.iter_mut() {
+                    if *key == self.field {
+                        *nested = serde_json::Value::String(self.redaction.clone());
+                    } else {
+                        self.apply(nested);
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter_mut().for_each(|item| self.apply(item)),
+            _ => {}
+        }
+    }
+}
+
+/// One access-control decision.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AuditRecord {
+    pub principal: Principal,
+    pub action: Action,
+    pub allowed: bool,
+    pub timestamp: SystemTime,
+}
+
+/// Receives every decision made by [`AccessController::authorize`].
+pub trait AuditSink: Send + Sync {
+    fn record(&self, principal: &Principal, action: &Action, allowed: bool);
+}
+
+/// Keeps the audit trail in memory until it is drained.
+#[derive(Default, Clone)]
+pub struct InMemoryAuditSink {
+    records: Arc<Mutex<Vec<AuditRecord>>>,
+}
+
+impl InMemoryAuditSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn entries(&self) -> Vec<AuditRecord> {
+        self.records.lock().unwrap().clone()
+    }
+
+    pub fn drain(&self) -> Vec<AuditRecord> {
+        std::mem::take(&mut *self.records.lock().unwrap())
+    }
+}
+
+impl AuditSink for InMemoryAuditSink {
+    fn record(&self, principal: &Principal, action: &Action, allowed: bool) {
+        self.records.lock().unwrap().push(AuditRecord {
+            principal: principal.clone(),
+            action: action.clone(),
+            allowed,
+            timestamp: SystemTime::now(),
+        });
+    }
+}
+
+/// Appends the audit trail to a JSONL file, one [`AuditRecord`] per line.
+pub struct FileAuditSink {
+    path: PathBuf,
+    lock: Mutex<()>,
+}
+
+impl FileAuditSink {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Read back every record written so far.
+    pub fn entries(&self) -> Result<Vec<AuditRecord>> {
+        let _guard = self.lock.lock().unwrap();
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => {
+                return Err(AgnoError::Storage(format!(
+                    "failed to read audit log `{}`: {err}",
+                    self.path.display()
+                )))
+            }
+        };
+        content
+            .lines()
+            .map(|line| serde_json::from_str(line).map_err(AgnoError::from))
+            .collect()
+    }
+
+    fn append(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)?;
+        line.push('\n');
+        let _guard = self.lock.lock().unwrap();
+        fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .map_err(|err| {
+                AgnoError::Storage(format!(
+                    "failed to write audit log `{}`: {err}",
+                    self.path.display()
+                ))
+            })
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, principal: &Principal, action: &Action, allowed: bool) {
+        let record = AuditRecord {
+            principal: principal.clone(),
+            action: action.clone(),
+            allowed,
+            timestamp: SystemTime::now(),
+        };
+        if let Err(err) = self.append(&record) {
+            tracing::warn!("dropping audit record: {err}");
+        }
+    }
 }
 
 #[derive(Default, Clone)]
 pub struct AccessController {
     rules: Arc<RwLock<HashMap<Role, HashSet<Action>>>>,
+    denials: Arc<RwLock<HashMap<Role, HashSet<Action>>>>,
+    inheritance: Arc<RwLock<HashMap<Role, Vec<Role>>>>,
     privacy: Arc<RwLock<Vec<PrivacyRule>>>,
+    audit: Arc<RwLock<Option<Arc<dyn AuditSink>>>>,
 }
 
 impl AccessController {
+    /// `Admin` inherits the grants of `User`; see [`AccessController::set_inheritance`].
     pub fn new() -> Self {
         let controller = Self::default();
+        controller.set_inheritance(Role::Admin, vec![Role::User]);
         controller.allow(Role::Admin, Action::ManageDeployment);
         controller.allow(Role::Admin, Action::ReadTranscript);
         controller
     }
 
+    /// Report every `authorize` decision to `sink`.
+    pub fn with_audit_sink(self, sink: Arc<dyn AuditSink>) -> Self {
+        self.set_audit_sink(sink);
+        self
+    }
+
+    /// Like [`AccessController::with_audit_sink`], for a controller that is already shared,
+    /// e.g. the one behind `AgentRuntime::access_control`. Every clone reports to `sink`.
+    pub fn set_audit_sink(&self, sink: Arc<dyn AuditSink>) {
+        *self.audit.write().unwrap() = Some(sink);
+    }
+
+    /// Make `role` inherit the grants and denials of `parents` (transitively).
+    pub fn set_inheritance(&self, role: Role, parents: Vec<Role>) {
+        self.inheritance.write().unwrap().insert(role, parents);
+    }
+
+    /// `role` followed by every role it inherits from, each listed once.
+    fn lineage(&self, role: &Role) -> Vec<Role> {
+        let inheritance = self.inheritance.read().unwrap();
+        let mut lineage = vec![role.clone()];
+        let mut index = 0;
+        while index < lineage.len() {
+            for parent in inheritance.get(&lineage[index]).into_iter().flatten() {
+                if !lineage.contains(parent) {
+                    lineage.push(parent.clone());
+                }
+            }
+            index += 1;
+        }
+        lineage
+    }
+
     pub fn allow(&self, role: Role, action: Action) {
         let mut rules = self.rules.write().unwrap();
         rules.entry(role).or_default().insert(action);
     }
 
+    /// Forbid `action` for `role`, overriding any allow grant (including wildcards).
+    pub fn deny(&self, role: Role, action: Action) {
+        let mut denials = self.denials.write().unwrap();
+        denials.entry(role).or_default().insert(action);
+    }
+
+    /// Checks the principal's role and its inherited roles. A denial anywhere in that
+    /// lineage wins over grants; `Action::any_tool()` matches every `CallTool` action.
     pub fn authorize(&self, principal: &Principal, action: &Action) -> bool {
-        let rules = self.rules.read().unwrap();
-        if let Some(actions) = rules.get(&principal.role) {
-            actions.contains(action)
-        } else {
-            false
+        let allowed = self.decide(principal, action);
+        let sink = self.audit.read().unwrap().clone();
+        if let Some(sink) = sink {
+            sink.record(principal, action, allowed);
         }
+        allowed
     }
 
-    pub fn add_privacy_rule(&mut self, rule: PrivacyRule) {
+    fn decide(&self, principal: &Principal, action: &Action) -> bool {
+        let wildcard = action.wildcard();
+        let matches = |actions: &HashSet<Action>| {
+            actions.contains(action) || wildcard.as_ref().is_some_and(|w| actions.contains(w))
+        };
+
+        let lineage = self.lineage(&principal.role);
+
+        let denials = self.denials.read().unwrap();
+        if lineage
+            .iter()
+            .any(|role| denials.get(role).is_some_and(matches))
+        {
+            return false;
+        }
+        let rules = self.rules.read().unwrap();
+        lineage
+            .iter()
+            .any(|role| rules.get(role).is_some_and(matches))
+    }
+
+    pub fn add_privacy_rule(&self, rule: PrivacyRule) {
         self.privacy.write().unwrap().push(rule);
     }
 
+    /// Apply the rules that are not scoped to a tenant.
     pub fn scrub_payload(&self, payload: &mut serde_json::Value) {
+        self.scrub_for_tenant(None, payload);
+    }
+
+    /// Apply the global rules plus those scoped to `tenant`.
+    pub fn scrub_for_tenant(&self, tenant: Option<&str>, payload: &mut serde_json::Value) {
         let rules = self.privacy.read().unwrap();
-        for rule in rules.iter() {
-            if let Some(obj) = payload.as_object_mut() {
-                if obj.contains_key(&rule.field) {
-                    obj.insert(
-                        rule.field.clone(),
-                        serde_json::Value::String(rule.redaction.clone()),
-                    );
-                }
-            }
+        for rule in rules.iter().filter(|rule| rule.applies_to(tenant)) {
+            rule.apply(payload);
+        }
+    }
+
+    /// Scrub the tool call arguments and tool result output carried by `message`.
+    pub fn scrub_message(&self, tenant: Option<&str>, message: &mut Message) {
+        if let Some(call) = message.tool_call.as_mut() {
+            self.scrub_for_tenant(tenant, &mut call.arguments);
+        }
+        if let Some(result) = message.tool_result.as_mut() {
+            self.scrub_for_tenant(tenant, &mut result.output);
         }
     }
 }
@@ -93,15 +347,129 @@ mod tests {
         assert!(!controller.authorize(&user, &Action::ManageDeployment));
     }
 
+    #[test]
+    fn wildcard_tool_grant_with_specific_deny() {
+        let controller = AccessController::new();
+        controller.allow(Role::User, Action::any_tool());
+        controller.deny(Role::User, Action::CallTool("shell".into()));
+        let user = Principal {
+            id: "user1".into(),
+            role: Role::User,
+            tenant: None,
+        };
+
+        assert!(controller.authorize(&user, &Action::CallTool("search".into())));
+        assert!(!controller.authorize(&user, &Action::CallTool("shell".into())));
+        assert!(!controller.authorize(&user, &Action::SendMessage));
+    }
+
+    #[test]
+    fn deny_overrides_allow() {
+        let controller = AccessController::new();
+        let admin = Principal {
+            id: "admin".into(),
+            role: Role::Admin,
+            tenant: None,
+        };
+        assert!(controller.authorize(&admin, &Action::ManageDeployment));
+
+        controller.deny(Role::Admin, Action::ManageDeployment);
+        assert!(!controller.authorize(&admin, &Action::ManageDeployment));
+
+        controller.allow(Role::Admin, Action::CallTool("shell".into()));
+        controller.deny(Role::Admin, Action::any_tool());
+        assert!(!controller.authorize(&admin, &Action::CallTool("shell".into())));
+    }
+
+    #[test]
+    fn admin_inherits_user_grants_but_not_admin_denials() {
+        let controller = AccessController::new();
+        let user = Principal {
+            id: "user1".into(),
+            role: Role::User,
+            tenant: None,
+        };
+        let admin = Principal {
+            id: "admin".into(),
+            role: Role::Admin,
+            tenant: None,
+        };
+        let search = Action::CallTool("search".into());
+
+        controller.allow(Role::User, search.clone());
+        assert!(controller.authorize(&admin, &search));
+
+        controller.deny(Role::Admin, search.clone());
+        assert!(!controller.authorize(&admin, &search));
+        assert!(controller.authorize(&user, &search));
+
+        controller.deny(Role::User, Action::SendMessage);
+        controller.allow(Role::Admin, Action::SendMessage);
+        assert!(!controller.authorize(&admin, &Action::SendMessage));
+    }
+
+    #[test]
+    fn inheritance_cycles_terminate() {
+        let controller = AccessController::default();
+        controller.set_inheritance(Role::User, vec![Role::Service]);
+        controller.set_inheritance(Role::Service, vec![Role::User]);
+        controller.allow(Role::Service, Action::SendMessage);
+        let user = Principal {
+            id: "user1".into(),
+            role: Role::User,
+            tenant: None,
+        };
+        assert!(controller.authorize(&user, &Action::SendMessage));
+        assert!(!controller.authorize(&user, &Action::ManageDeployment));
+    }
+
+    #[test]
+    fn file_audit_sink_persists_decisions() {
+        let dir = tempfile::tempdir().unwrap();
+        let sink = Arc::new(FileAuditSink::new(dir.path().join("audit.jsonl")));
+        let controller = AccessController::new().with_audit_sink(sink.clone());
+        let user = Principal {
+            id: "user1".into(),
+            role: Role::User,
+            tenant: None,
+        };
+
+        controller.allow(Role::User, Action::SendMessage);
+        assert!(controller.authorize(&user, &Action::SendMessage));
+        assert!(!controller.authorize(&user, &Action::ReadTranscript));
+
+        let entries = sink.entries().unwrap();
+        assert_eq!(entries.len(), 2);
+        assert!(entries[0].allowed);
+        assert_eq!(entries[1].action, Action::ReadTranscript);
+        assert!(!entries[1].allowed);
+    }
+
     #[test]
     fn scrubs_fields() {
-        let mut controller = AccessController::new();
+        let controller = AccessController::new();
         controller.add_privacy_rule(PrivacyRule {
             field: "secret".into(),
             redaction: "***".into(),
+            tenant: None,
         });
         let mut payload = serde_json::json!({"secret": "value", "other": "ok"});
         controller.scrub_payload(&mut payload);
         assert_eq!(payload["secret"], "***");
     }
+
+    #[test]
+    fn scrubs_nested_fields_for_matching_tenant_only() {
+        let controller = AccessController::new();
+        controller.add_privacy_rule(PrivacyRule::new("ssn", "[redacted]").for_tenant("acme"));
+        let original = serde_json::json!({"people": [{"name": "Ada", "ssn": "123-45-6789"}]});
+
+        let mut payload = original.clone();
+        controller.scrub_for_tenant(Some("globex"), &mut payload);
+        assert_eq!(payload, original);
+
+        controller.scrub_for_tenant(Some("acme"), &mut payload);
+        assert_eq!(payload["people"][0]["ssn"], "[redacted]");
+        assert_eq!(payload["people"][0]["name"], "Ada");
+    }
 }
@@ -1,4 +1,5 @@
 use async_trait::async_trait;
+use serde_json::Value;
 
 use crate::error::Result;
 use crate::message::{Message, ToolCall, ToolResult};
@@ -20,9 +21,49 @@ pub trait AgentHook: Send + Sync {
     async fn after_tool_result(&self, _result: &ToolResult) -> Result<()> {
         Ok(())
     }
+
+    /// Inspect or rewrite the final reply before it is stored and returned.
+    async fn after_response(&self, _reply: &mut String) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[async_trait]
 pub trait ConfirmationHandler: Send + Sync {
     async fn confirm_tool_call(&self, call: &ToolCall) -> Result<bool>;
 }
+
+/// Outcome of reviewing a tool call before it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfirmationDecision {
+    Approve,
+    Reject,
+    /// Run the tool with these arguments instead of the ones the model chose.
+    Modify(Value),
+}
+
+impl From<bool> for ConfirmationDecision {
+    fn from(approved: bool) -> Self {
+        if approved {
+            Self::Approve
+        } else {
+            Self::Reject
+        }
+    }
+}
+
+/// Reviews tool calls before execution and may rewrite their arguments. Every
+/// [`ConfirmationHandler`] is a reviewer that only approves or rejects.
+#[async_trait]
+pub trait ToolCallReviewer: Send + Sync {
+    async fn review_tool_call(&self, call: &ToolCall) -> Result<ConfirmationDecision>;
+}
+
+#[async_trait]
+impl<H: ConfirmationHandler + ?Sized> ToolCallReviewer for H {
+    async fn review_tool_call(&self, call: &ToolCall) -> Result<ConfirmationDecision> {
+        self.confirm_tool_call(call)
+            .await
+            .map(ConfirmationDecision::from)
+    }
+}
@@ -1,14 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::hash::{Hash, Hasher};
-use std::sync::Arc;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, OnceLock};
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use tokio::sync::RwLock;
 
-use crate::error::Result;
+use crate::error::{AgnoError, Result};
+use crate::metrics::{EvaluationReport, QueryRetrievalMetrics, RetrievalMetrics};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Document {
     pub id: String,
     pub text: String,
@@ -21,6 +25,22 @@ pub struct ScoredDocument {
     pub score: f32,
 }
 
+/// Source id and relevance score of a retrieved document, for citing grounded answers.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    pub id: String,
+    pub score: f32,
+}
+
+impl From<&ScoredDocument> for Citation {
+    fn from(scored: &ScoredDocument) -> Self {
+        Self {
+            id: scored.document.id.clone(),
+            score: scored.score,
+        }
+    }
+}
+
 #[async_trait]
 pub trait Embedder: Send + Sync {
     async fn embed(&self, text: &str) -> Result<Vec<f32>>;
@@ -82,6 +102,95 @@ where
     }
 }
 
+/// Hit and miss counters for a [`CachingEmbedder`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EmbeddingCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// Embedder decorator that remembers vectors for previously seen text.
+///
+/// Entries are keyed by a stable 64-bit FNV-1a hash of the text, so a cache written with
+/// [`CachingEmbedder::save`] stays valid across processes and toolchain upgrades.
+pub struct CachingEmbedder<E> {
+    inner: Arc<E>,
+    cache: RwLock<HashMap<u64, Vec<f32>>>,
+    path: Option<std::path::PathBuf>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl<E> CachingEmbedder<E> {
+    pub fn new(inner: Arc<E>) -> Self {
+        Self {
+            inner,
+            cache: RwLock::new(HashMap::new()),
+            path: None,
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Back the cache with `path`, loading any entries a previous [`CachingEmbedder::save`]
+    /// left there.
+    pub async fn with_file(mut self, path: impl Into<std::path::PathBuf>) -> Result<Self> {
+        let path = path.into();
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => *self.cache.get_mut() = serde_json::from_slice(&bytes)?,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        self.path = Some(path);
+        Ok(self)
+    }
+
+    /// Write the cache to the file configured with [`CachingEmbedder::with_file`], if any.
+    pub async fn save(&self) -> Result<()> {
+        if let Some(path) = &self.path {
+            let bytes = serde_json::to_vec(&*self.cache.read().await)?;
+            tokio::fs::write(path, bytes).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn stats(&self) -> EmbeddingCacheStats {
+        EmbeddingCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.cache.read().await.len(),
+        }
+    }
+
+    fn key(text: &str) -> u64 {
+        fnv1a(text.as_bytes())
+    }
+}
+
+/// 64-bit FNV-1a hash of `bytes`. Unlike the std hasher it is stable across processes and
+/// Rust versions, so it can key persisted data and cross-process decisions.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+#[async_trait]
+impl<E: Embedder> Embedder for CachingEmbedder<E> {
+    async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let key = Self::key(text);
+        if let Some(vector) = self.cache.read().await.get(&key) {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+            return Ok(vector.clone());
+        }
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let vector = self.inner.embed(text).await?;
+        self.cache.write().await.insert(key, vector.clone());
+        Ok(vector)
+    }
+}
+
 #[async_trait]
 pub trait VectorStore: Send + Sync {
     async fn add(&self, document: Document, embedding: Vec<f32>) -> Result<()>;
@@ -130,10 +239,55 @@ pub struct InMemoryVectorStore {
     entries: RwLock<Vec<(Document, Vec<f32>)>>,
 }
 
+#[derive(Serialize, Deserialize)]
+struct StoredEntry {
+    document: Document,
+    embedding: Vec<f32>,
+}
+
+impl InMemoryVectorStore {
+    /// Write every document and its embedding to `path` as JSON, so a corpus can be reloaded
+    /// without re-embedding it.
+    pub async fn save_to_file(&self, path: impl AsRef<Path>) -> Result<()> {
+        let entries: Vec<StoredEntry> = self
+            .entries
+            .read()
+            .await
+            .iter()
+            .map(|(document, embedding)| StoredEntry {
+                document: document.clone(),
+                embedding: embedding.clone(),
+            })
+            .collect();
+        tokio::fs::write(path, serde_json::to_vec(&entries)?).await?;
+        Ok(())
+    }
+
+    /// Load a store previously written by [`InMemoryVectorStore::save_to_file`].
+    pub async fn load_from_file(path: impl AsRef<Path>) -> Result<Self> {
+        let bytes = tokio::fs::read(path).await?;
+        let entries: Vec<StoredEntry> = serde_json::from_slice(&bytes)?;
+        Ok(Self {
+            entries: RwLock::new(
+                entries
+                    .into_iter()
+                    .map(|entry| (entry.document, entry.embedding))
+                    .collect(),
+            ),
+        })
+    }
+}
+
 #[async_trait]
 impl VectorStore for InMemoryVectorStore {
+    /// The first embedding added fixes the store's dimension; later adds and searches with
+    /// another length fail with [`AgnoError::DimensionMismatch`].
     async fn add(&self, document: Document, embedding: Vec<f32>) -> Result<()> {
-        self.entries.write().await.push((document, embedding));
+        let mut entries = self.entries.write().await;
+        if let Some((_, first)) = entries.first() {
+            check_dimension(first.len(), &embedding)?;
+        }
+        entries.push((document, embedding));
         Ok(())
     }
 
@@ -143,8 +297,12 @@ impl VectorStore for InMemoryVectorStore {
         params: SearchParams,
     ) -> Result<Vec<ScoredDocument>> {
         let entries = self.entries.read().await;
+        if let Some((_, first)) = entries.first() {
+            check_dimension(first.len(), &embedding)?;
+        }
         let mut scored: Vec<ScoredDocument> = entries
             .iter()
+            .filter(|(doc, _)| matches_metadata_filter(&doc.metadata, &params.metadata_filter))
             .map(|(doc, stored)| ScoredDocument {
                 document: doc.clone(),
                 score: similarity(stored, &embedding, params.similarity),
@@ -161,6 +319,39 @@ impl VectorStore for InMemoryVectorStore {
     }
 }
 
+fn check_dimension(expected: usize, embedding: &[f32]) -> Result<()> {
+    if embedding.len() == expected {
+        Ok(())
+    } else {
+        Err(AgnoError::DimensionMismatch {
+            expected,
+            actual: embedding.len(),
+        })
+    }
+}
+
+/// Embedding length a remote store adapter expects: preset, or taken from the first
+/// vector added.
+#[derive(Default)]
+struct ExpectedDimension(OnceLock<usize>);
+
+impl ExpectedDimension {
+    fn preset(dimension: usize) -> Self {
+        Self(OnceLock::from(dimension))
+    }
+
+    fn check_add(&self, embedding: &[f32]) -> Result<()> {
+        check_dimension(*self.0.get_or_init(|| embedding.len()), embedding)
+    }
+
+    fn check_search(&self, embedding: &[f32]) -> Result<()> {
+        match self.0.get() {
+            Some(expected) => check_dimension(*expected, embedding),
+            None => Ok(()),
+        }
+    }
+}
+
 fn similarity(a: &[f32], b: &[f32], metric: SimilarityMetric) -> f32 {
     let (mut dot, mut norm_a, mut norm_b) = (0.0, 0.0, 0.0);
     for (x, y) in a.iter().zip(b.iter()) {
@@ -201,6 +392,9 @@ pub enum SimilarityMetric {
 pub struct SearchParams {
     pub top_k: usize,
     pub similarity: SimilarityMetric,
+    /// Exact-match conditions on `Document.metadata`; a document is only returned when
+    /// every key is present with an equal value. Empty means no filtering.
+    pub metadata_filter: HashMap<String, Value>,
 }
 
 impl Default for SearchParams {
@@ -208,10 +402,17 @@ impl Default for SearchParams {
         Self {
             top_k: 5,
             similarity: SimilarityMetric::Cosine,
+            metadata_filter: HashMap::new(),
         }
     }
 }
 
+fn matches_metadata_filter(metadata: &Value, filter: &HashMap<String, Value>) -> bool {
+    filter
+        .iter()
+        .all(|(key, expected)| metadata.get(key) == Some(expected))
+}
+
 #[async_trait]
 pub trait PgVectorClient: Send + Sync {
     async fn upsert(&self, document: &Document, embedding: &[f32]) -> Result<()>;
@@ -221,11 +422,23 @@ pub trait PgVectorClient: Send + Sync {
 /// Adapter for Postgres/pgvector style databases.
 pub struct PgVectorStore<C> {
     client: Arc<C>,
+    dimension: ExpectedDimension,
 }
 
 impl<C> PgVectorStore<C> {
+    /// The first embedding added fixes the expected dimension; use
+    /// [`PgVectorStore::with_dimension`] when the backing store already holds vectors.
     pub fn new(client: Arc<C>) -> Self {
-        Self { client }
+        Self {
+            client,
+            dimension: ExpectedDimension::default(),
+        }
+    }
+
+    /// Reject embeddings whose length is not `dimension` before they reach the database.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = ExpectedDimension::preset(dimension);
+        self
     }
 }
 
@@ -235,6 +448,7 @@ where
     C: PgVectorClient,
 {
     async fn add(&self, document: Document, embedding: Vec<f32>) -> Result<()> {
+        self.dimension.check_add(&embedding)?;
         self.client.upsert(&document, &embedding).await
     }
 
@@ -243,10 +457,171 @@ where
         embedding: Vec<f32>,
         params: SearchParams,
     ) -> Result<Vec<ScoredDocument>> {
+        self.dimension.check_search(&embedding)?;
         self.client.query(&embedding, params).await
     }
 }
 
+/// [`PgVectorClient`] backed by a sqlx Postgres pool with the `vector` extension. Each
+/// document is a row of `(id, text, metadata jsonb, embedding vector)`.
+#[cfg(feature = "pgvector")]
+#[derive(Clone)]
+pub struct SqlxPgVectorClient {
+    pool: sqlx::PgPool,
+    table: String,
+    dimension: usize,
+}
+
+#[cfg(feature = "pgvector")]
+impl SqlxPgVectorClient {
+    /// Use `table` in `pool`, creating the extension, the table and an HNSW index for
+    /// `metric` if they are missing. Searches with another metric still work, just
+    /// without the index.
+    pub async fn new(
+        pool: sqlx::PgPool,
+        table: impl Into<String>,
+        dimension: usize,
+        metric: SimilarityMetric,
+    ) -> Result<Self> {
+        let table = table.into();
+        let valid = table.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+            && table.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid {
+            return Err(AgnoError::Storage(format!(
+                "invalid pgvector table name `{table}`"
+            )));
+        }
+        let opclass = match metric {
+            SimilarityMetric::Cosine => "vector_cosine_ops",
+            SimilarityMetric::DotProduct => "vector_ip_ops",
+            SimilarityMetric::Euclidean => "vector_l2_ops",
+        };
+        let statements = [
+            "CREATE EXTENSION IF NOT EXISTS vector".to_string(),
+            format!(
+                "CREATE TABLE IF NOT EXISTS {table} (
+                    id TEXT PRIMARY KEY,
+                    text TEXT NOT NULL,
+                    metadata JSONB NOT NULL DEFAULT '{{}}',
+                    embedding vector({dimension}) NOT NULL
+                )"
+            ),
+            format!(
+                "CREATE INDEX IF NOT EXISTS {table}_embedding_idx ON {table} \
+                 USING hnsw (embedding {opclass})"
+            ),
+        ];
+        for statement in statements {
+            sqlx::query(&statement)
+                .execute(&pool)
+                .await
+                .map_err(|err| {
+                    AgnoError::Storage(format!("failed initializing pgvector schema: {err}"))
+                })?;
+        }
+        Ok(Self {
+            pool,
+            table,
+            dimension,
+        })
+    }
+}
+
+/// pgvector's text input format, e.g. `[0.1,0.2]`.
+#[cfg(feature = "pgvector")]
+fn pgvector_literal(embedding: &[f32]) -> String {
+    let values: Vec<String> = embedding.iter().map(f32::to_string).collect();
+    format!("[{}]", values.join(","))
+}
+
+#[cfg(feature = "pgvector")]
+fn pgvector_operator(metric: SimilarityMetric) -> &'static str {
+    match metric {
+        SimilarityMetric::Cosine => "<=>",
+        SimilarityMetric::DotProduct => "<#>",
+        SimilarityMetric::Euclidean => "<->",
+    }
+}
+
+/// Turn a pgvector distance into the same higher-is-better score the in-memory store
+/// reports for `metric`.
+#[cfg(feature = "pgvector")]
+fn pgvector_score(distance: f64, metric: SimilarityMetric) -> f32 {
+    let score = match metric {
+        SimilarityMetric::Cosine => 1.0 - distance,
+        // `<#>` is the negated inner product.
+        SimilarityMetric::DotProduct => -distance,
+        SimilarityMetric::Euclidean => 1.0 / (1.0 + distance),
+    };
+    score as f32
+}
+
+#[cfg(feature = "pgvector")]
+#[async_trait]
+impl PgVectorClient for SqlxPgVectorClient {
+    async fn upsert(&self, document: &Document, embedding: &[f32]) -> Result<()> {
+        check_dimension(self.dimension, embedding)?;
+        let statement = format!(
+            "INSERT INTO {} (id, text, metadata, embedding) VALUES ($1, $2, $3::jsonb, $4::vector)
+             ON CONFLICT (id) DO UPDATE SET text = EXCLUDED.text,
+                 metadata = EXCLUDED.metadata, embedding = EXCLUDED.embedding",
+            self.table
+        );
+        let metadata = match &document.metadata {
+            Value::Null => "{}".to_string(),
+            metadata => metadata.to_string(),
+        };
+        sqlx::query(&statement)
+            .bind(&document.id)
+            .bind(&document.text)
+            .bind(metadata)
+            .bind(pgvector_literal(embedding))
+            .execute(&self.pool)
+            .await
+            .map(|_| ())
+            .map_err(|err| AgnoError::Storage(format!("failed upserting document: {err}")))
+    }
+
+    async fn query(&self, embedding: &[f32], params: SearchParams) -> Result<Vec<ScoredDocument>> {
+        use sqlx::Row;
+
+        check_dimension(self.dimension, embedding)?;
+        // Order by the bare operator expression, not the cast alias, so the index is used.
+        let operator = pgvector_operator(params.similarity);
+        let statement = format!(
+            "SELECT id, text, metadata::text AS metadata, (embedding {operator} $1::vector)::float8 AS distance
+             FROM {} WHERE metadata @> $2::jsonb ORDER BY embedding {operator} $1::vector LIMIT $3",
+            self.table
+        );
+        let filter = Value::Object(params.metadata_filter.into_iter().collect());
+        let rows = sqlx::query(&statement)
+            .bind(pgvector_literal(embedding))
+            .bind(filter.to_string())
+            .bind(params.top_k as i64)
+            .fetch_all(&self.pool)
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed searching documents: {err}")))?;
+
+        rows.into_iter()
+            .map(|row| {
+                let decode = |err: sqlx::Error| {
+                    AgnoError::Storage(format!("failed decoding document row: {err}"))
+                };
+                let metadata: String = row.try_get("metadata").map_err(decode)?;
+                let distance: f64 = row.try_get("distance").map_err(decode)?;
+                Ok(ScoredDocument {
+                    document: Document {
+                        id: row.try_get("id").map_err(decode)?,
+                        text: row.try_get("text").map_err(decode)?,
+                        metadata: serde_json::from_str(&metadata)?,
+                    },
+                    score: pgvector_score(distance, params.similarity),
+                })
+            })
+            .collect()
+    }
+}
+
 #[async_trait]
 pub trait QdrantClient: Send + Sync {
     async fn upsert(&self, document: &Document, embedding: &[f32]) -> Result<()>;
@@ -256,11 +631,23 @@ pub trait QdrantClient: Send + Sync {
 /// Adapter for Qdrant (or other HTTP/gRPC vector databases).
 pub struct QdrantStore<C> {
     client: Arc<C>,
+    dimension: ExpectedDimension,
 }
 
 impl<C> QdrantStore<C> {
+    /// The first embedding added fixes the expected dimension; use
+    /// [`QdrantStore::with_dimension`] when the backing store already holds vectors.
     pub fn new(client: Arc<C>) -> Self {
-        Self { client }
+        Self {
+            client,
+            dimension: ExpectedDimension::default(),
+        }
+    }
+
+    /// Reject embeddings whose length is not `dimension` before they reach the database.
+    pub fn with_dimension(mut self, dimension: usize) -> Self {
+        self.dimension = ExpectedDimension::preset(dimension);
+        self
     }
 }
 
@@ -270,6 +657,7 @@ where
     C: QdrantClient,
 {
     async fn add(&self, document: Document, embedding: Vec<f32>) -> Result<()> {
+        self.dimension.check_add(&embedding)?;
         self.client.upsert(&document, &embedding).await
     }
 
@@ -278,14 +666,254 @@ where
         embedding: Vec<f32>,
         params: SearchParams,
     ) -> Result<Vec<ScoredDocument>> {
+        self.dimension.check_search(&embedding)?;
         self.client.query(&embedding, params).await
     }
 }
 
+/// [`QdrantClient`] over Qdrant's REST API. Point ids are derived from the document id
+/// (Qdrant only accepts UUIDs and integers); the original id, text and metadata are kept
+/// in the payload so documents come back intact.
+#[cfg(feature = "qdrant")]
+#[derive(Clone)]
+pub struct HttpQdrantClient {
+    http: reqwest::Client,
+    base_url: String,
+    collection: String,
+    api_key: Option<String>,
+}
+
+#[cfg(feature = "qdrant")]
+impl HttpQdrantClient {
+    pub fn new(base_url: impl Into<String>, collection: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .expect("failed to build http client"),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            collection: collection.into(),
+            api_key: None,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Create the collection for `dimension`-sized vectors compared with `metric`, unless
+    /// it already exists. Searches should use the metric the collection was created with.
+    pub async fn ensure_collection(
+        &self,
+        dimension: usize,
+        metric: SimilarityMetric,
+    ) -> Result<()> {
+        let url = self.collection_url("");
+        let existing = self
+            .authorized(self.http.get(&url))
+            .send()
+            .await
+            .map_err(|err| AgnoError::Transport {
+                provider: "qdrant".into(),
+                message: err.to_string(),
+            })?;
+        if existing.status().is_success() {
+            return Ok(());
+        }
+        let body = json!({
+            "vectors": { "size": dimension, "distance": qdrant_distance(metric) },
+        });
+        let request = self.authorized(self.http.put(&url)).json(&body);
+        crate::llm::send_request("qdrant", request).await?;
+        Ok(())
+    }
+
+    fn collection_url(&self, path: &str) -> String {
+        format!(
+            "{}/collections/{}{path}",
+            self.base_url,
+            urlencoding::encode(&self.collection)
+        )
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.header("api-key", key),
+            None => request,
+        }
+    }
+}
+
+#[cfg(feature = "qdrant")]
+fn qdrant_distance(metric: SimilarityMetric) -> &'static str {
+    match metric {
+        SimilarityMetric::Cosine => "Cosine",
+        SimilarityMetric::DotProduct => "Dot",
+        SimilarityMetric::Euclidean => "Euclid",
+    }
+}
+
+/// Stable point id for a document id, so re-adding a document overwrites its point.
+#[cfg(feature = "qdrant")]
+fn qdrant_point_id(document_id: &str) -> uuid::Uuid {
+    uuid::Uuid::new_v5(&uuid::Uuid::NAMESPACE_OID, document_id.as_bytes())
+}
+
+/// Translate an exact-match metadata filter into Qdrant `must` conditions on the
+/// `metadata` payload field.
+#[cfg(feature = "qdrant")]
+fn qdrant_filter(filter: &HashMap<String, Value>) -> Result<Option<Value>> {
+    if filter.is_empty() {
+        return Ok(None);
+    }
+    let mut keys: Vec<&String> = filter.keys().collect();
+    keys.sort();
+    let must = keys
+        .into_iter()
+        .map(|key| {
+            let field = format!("metadata.{key}");
+            match &filter[key] {
+                Value::Null => Ok(json!({ "is_null": { "key": field } })),
+                // Qdrant only matches keywords, integers and booleans exactly.
+                Value::Number(n) if n.is_f64() => {
+                    Ok(json!({ "key": field, "range": { "gte": n, "lte": n } }))
+                }
+                value @ (Value::String(_) | Value::Number(_) | Value::Bool(_)) => {
+                    Ok(json!({ "key": field, "match": { "value": value } }))
+                }
+                other => Err(AgnoError::Storage(format!(
+                    "Qdrant cannot filter `{key}` on non-scalar value {other}"
+                ))),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(Some(json!({ "must": must })))
+}
+
+#[cfg(feature = "qdrant")]
+#[derive(Deserialize)]
+struct QdrantSearchResponse {
+    result: Vec<QdrantScoredPoint>,
+}
+
+#[cfg(feature = "qdrant")]
+#[derive(Deserialize)]
+struct QdrantScoredPoint {
+    score: f32,
+    #[serde(default)]
+    payload: Option<QdrantPayload>,
+}
+
+#[cfg(feature = "qdrant")]
+#[derive(Deserialize)]
+struct QdrantPayload {
+    document_id: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    metadata: Value,
+}
+
+#[cfg(feature = "qdrant")]
+#[async_trait]
+impl QdrantClient for HttpQdrantClient {
+    async fn upsert(&self, document: &Document, embedding: &[f32]) -> Result<()> {
+        let body = json!({
+            "points": [{
+                "id": qdrant_point_id(&document.id),
+                "vector": embedding,
+                "payload": {
+                    "document_id": document.id,
+                    "text": document.text,
+                    "metadata": document.metadata,
+                },
+            }],
+        });
+        let request = self
+            .http
+            .put(self.collection_url("/points?wait=true"))
+            .json(&body);
+        crate::llm::send_request("qdrant", self.authorized(request)).await?;
+        Ok(())
+    }
+
+    async fn query(&self, embedding: &[f32], params: SearchParams) -> Result<Vec<ScoredDocument>> {
+        let mut body = json!({
+            "vector": embedding,
+            "limit": params.top_k,
+            "with_payload": true,
+        });
+        if let Some(filter) = qdrant_filter(&params.metadata_filter)? {
+            body["filter"] = filter;
+        }
+        let request = self
+            .http
+            .post(self.collection_url("/points/search"))
+            .json(&body);
+        let resp = crate::llm::send_request("qdrant", self.authorized(request)).await?;
+        let parsed: QdrantSearchResponse =
+            resp.json()
+                .await
+                .map_err(|err| AgnoError::InvalidResponse {
+                    provider: "qdrant".into(),
+                    message: format!("Qdrant search response parse error: {err}"),
+                })?;
+        Ok(parsed
+            .result
+            .into_iter()
+            .filter_map(|point| {
+                let payload = point.payload?;
+                // Qdrant reports Euclid scores as distances; flip them so higher is better
+                // like the other stores.
+                let score = match params.similarity {
+                    SimilarityMetric::Euclidean => 1.0 / (1.0 + point.score),
+                    _ => point.score,
+                };
+                Some(ScoredDocument {
+                    document: Document {
+                        id: payload.document_id,
+                        text: payload.text,
+                        metadata: payload.metadata,
+                    },
+                    score,
+                })
+            })
+            .collect())
+    }
+}
+
 pub trait DocumentChunker: Send + Sync {
     fn chunk(&self, document: &Document) -> Vec<Document>;
 }
 
+/// Build the `chunk_index`-th chunk of `document`, tagging it with `chunk_index` and
+/// `source_id` metadata.
+fn chunk_of(document: &Document, chunk_index: usize, text: String) -> Document {
+    let mut metadata = document.metadata.clone();
+
+    if let Value::Object(map) = &mut metadata {
+        map.insert("chunk_index".to_string(), Value::from(chunk_index as u64));
+        map.insert("source_id".to_string(), Value::from(document.id.clone()));
+    } else {
+        metadata = json!({
+            "chunk_index": chunk_index,
+            "source_id": document.id
+        });
+    }
+
+    Document {
+        id: format!("{}::{}", document.id, chunk_index),
+        text,
+        metadata,
+    }
+}
+
 /// Token (word) based chunker with sliding window overlap.
 pub struct SlidingWindowChunker {
     pub max_tokens: usize,
@@ -319,23 +947,7 @@ impl DocumentChunker for SlidingWindowChunker {
         while start < tokens.len() {
             let end = usize::min(start + self.max_tokens, tokens.len());
             let text = tokens[start..end].join(" ");
-            let mut metadata = document.metadata.clone();
-
-            if let Value::Object(map) = &mut metadata {
-                map.insert("chunk_index".to_string(), Value::from(chunk_index as u64));
-                map.insert("source_id".to_string(), Value::from(document.id.clone()));
-            } else {
-                metadata = json!({
-                    "chunk_index": chunk_index,
-                    "source_id": document.id
-                });
-            }
-
-            chunks.push(Document {
-                id: format!("{}::{}", document.id, chunk_index),
-                text,
-                metadata,
-            });
+            chunks.push(chunk_of(document, chunk_index, text));
 
             if end == tokens.len() {
                 break;
@@ -349,7 +961,383 @@ impl DocumentChunker for SlidingWindowChunker {
     }
 }
 
-pub type Reranker = Arc<dyn Fn(&ScoredDocument) -> f32 + Send + Sync>;
+/// Splits Markdown along its heading hierarchy, recording the heading path of each chunk as
+/// `metadata.section` (e.g. `"Installation > Linux"`). Fenced code blocks are never split;
+/// sections longer than `max_tokens` are packed paragraph by paragraph, and only prose
+/// paragraphs that are themselves too long fall back to a sliding window.
+pub struct MarkdownChunker {
+    pub max_tokens: usize,
+    pub overlap: usize,
+}
+
+impl Default for MarkdownChunker {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            overlap: 32,
+        }
+    }
+}
+
+struct MarkdownSection {
+    path: String,
+    blocks: Vec<String>,
+}
+
+impl MarkdownChunker {
+    fn sections(text: &str) -> Vec<MarkdownSection> {
+        fn flush(block: &mut String, sections: &mut [MarkdownSection]) {
+            if !block.trim().is_empty() {
+                let section = sections.last_mut().expect("at least one section");
+                section.blocks.push(block.trim_end().to_string());
+            }
+            block.clear();
+        }
+
+        let mut sections = vec![MarkdownSection {
+            path: String::new(),
+            blocks: Vec::new(),
+        }];
+        let mut headings: Vec<(usize, String)> = Vec::new();
+        let mut block = String::new();
+        let mut fence: Option<&str> = None;
+
+        for line in text.lines() {
+            let trimmed = line.trim_start();
+            if let Some(marker) = fence {
+                block.push_str(line);
+                block.push('\n');
+                if trimmed.starts_with(marker) {
+                    fence = None;
+                    flush(&mut block, &mut sections);
+                }
+                continue;
+            }
+            if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+                flush(&mut block, &mut sections);
+                fence = Some(&trimmed[..3]);
+                block.push_str(line);
+                block.push('\n');
+                continue;
+            }
+            let level = trimmed.chars().take_while(|c| *c == '#').count();
+            if (1..=6).contains(&level) && trimmed[level..].starts_with(' ') {
+                flush(&mut block, &mut sections);
+                headings.retain(|(existing, _)| *existing < level);
+                headings.push((level, trimmed[level..].trim().to_string()));
+                sections.push(MarkdownSection {
+                    path: headings
+                        .iter()
+                        .map(|(_, title)| title.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" > "),
+                    blocks: vec![trimmed.to_string()],
+                });
+                continue;
+            }
+            if trimmed.is_empty() {
+                flush(&mut block, &mut sections);
+            } else {
+                block.push_str(line);
+                block.push('\n');
+            }
+        }
+        flush(&mut block, &mut sections);
+        sections.retain(|section| !section.blocks.is_empty());
+        sections
+    }
+
+    /// Pack a section's blocks into texts of at most `max_tokens` words where possible.
+    fn pack(&self, blocks: &[String]) -> Vec<String> {
+        let window = SlidingWindowChunker {
+            max_tokens: self.max_tokens,
+            overlap: self.overlap,
+        };
+        let mut texts = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        let mut tokens = 0;
+        for block in blocks {
+            let size = block.split_whitespace().count();
+            if !current.is_empty() && tokens + size > self.max_tokens {
+                texts.push(current.join("\n\n"));
+                current.clear();
+                tokens = 0;
+            }
+            let is_fence = block.starts_with("```") || block.starts_with("~~~");
+            if size > self.max_tokens && !is_fence {
+                let paragraph = Document {
+                    id: String::new(),
+                    text: block.clone(),
+                    metadata: Value::Null,
+                };
+                texts.extend(window.chunk(&paragraph).into_iter().map(|c| c.text));
+                continue;
+            }
+            current.push(block);
+            tokens += size;
+        }
+        if !current.is_empty() {
+            texts.push(current.join("\n\n"));
+        }
+        texts
+    }
+}
+
+impl DocumentChunker for MarkdownChunker {
+    fn chunk(&self, document: &Document) -> Vec<Document> {
+        let mut chunks = Vec::new();
+        for section in Self::sections(&document.text) {
+            for text in self.pack(&section.blocks) {
+                let mut chunk = chunk_of(document, chunks.len(), text);
+                if !section.path.is_empty() {
+                    chunk.metadata["section"] = Value::from(section.path.clone());
+                }
+                chunks.push(chunk);
+            }
+        }
+        if chunks.is_empty() {
+            return vec![document.clone()];
+        }
+        chunks
+    }
+}
+
+/// Abbreviations whose trailing period does not end a sentence.
+const ABBREVIATIONS: &[&str] = &[
+    "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "inc", "ltd", "co", "no",
+    "fig", "approx",
+];
+
+/// Packs whole sentences into chunks of up to `max_tokens` words, repeating the last
+/// `overlap_sentences` sentences of each chunk at the start of the next. A sentence longer
+/// than `max_tokens` becomes a chunk of its own rather than being split.
+pub struct SentenceChunker {
+    pub max_tokens: usize,
+    pub overlap_sentences: usize,
+}
+
+impl Default for SentenceChunker {
+    fn default() -> Self {
+        Self {
+            max_tokens: 256,
+            overlap_sentences: 1,
+        }
+    }
+}
+
+impl SentenceChunker {
+    /// Split on `.`, `?` and `!` followed by whitespace, skipping common abbreviations,
+    /// dotted abbreviations such as `e.g.` and single-letter initials.
+    pub fn split_sentences(text: &str) -> Vec<&str> {
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut chars = text.char_indices().peekable();
+        while let Some((idx, ch)) = chars.next() {
+            if !matches!(ch, '.' | '?' | '!') {
+                continue;
+            }
+            // Keep closing quotes and brackets with the sentence they end.
+            let mut end = idx + ch.len_utf8();
+            while let Some(&(next_idx, next)) = chars.peek() {
+                if matches!(next, '.' | '?' | '!' | '"' | '\'' | ')' | ']') {
+                    end = next_idx + next.len_utf8();
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            let at_boundary = chars.peek().is_none_or(|&(_, next)| next.is_whitespace());
+            if !at_boundary || (ch == '.' && Self::is_abbreviation(&text[start..idx])) {
+                continue;
+            }
+            let sentence = text[start..end].trim();
+            if !sentence.is_empty() {
+                sentences.push(sentence);
+            }
+            start = end;
+        }
+        let rest = text[start..].trim();
+        if !rest.is_empty() {
+            sentences.push(rest);
+        }
+        sentences
+    }
+
+    fn is_abbreviation(preceding: &str) -> bool {
+        let word = preceding
+            .rsplit(|c: char| c.is_whitespace() || c == '(')
+            .next()
+            .unwrap_or("")
+            .to_lowercase();
+        let is_initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+        is_initial || word.contains('.') || ABBREVIATIONS.contains(&word.as_str())
+    }
+}
+
+impl DocumentChunker for SentenceChunker {
+    fn chunk(&self, document: &Document) -> Vec<Document> {
+        let sentences = Self::split_sentences(&document.text);
+        let lengths: Vec<usize> = sentences
+            .iter()
+            .map(|s| s.split_whitespace().count())
+            .collect();
+        if lengths.iter().sum::<usize>() <= self.max_tokens {
+            return vec![document.clone()];
+        }
+
+        let mut chunks = Vec::new();
+        let mut start = 0usize;
+        while start < sentences.len() {
+            let mut end = start + 1;
+            let mut tokens = lengths[start];
+            while end < sentences.len() && tokens + lengths[end] <= self.max_tokens {
+                tokens += lengths[end];
+                end += 1;
+            }
+            chunks.push(chunk_of(
+                document,
+                chunks.len(),
+                sentences[start..end].join(" "),
+            ));
+            if end == sentences.len() {
+                break;
+            }
+            // Always advance by at least one sentence so oversized overlaps cannot stall.
+            start = end.saturating_sub(self.overlap_sentences).max(start + 1);
+        }
+        chunks
+    }
+}
+
+/// Reorders retrieved documents for a query. Unlike a per-document score, a reranker sees the
+/// query and every candidate together, so cross-encoders can score (query, document) pairs.
+///
+/// Closures of the form `Fn(&ScoredDocument) -> f32` are rerankers too: each document is
+/// rescored independently and the results are sorted by the new score, highest first.
+#[async_trait]
+pub trait Reranker: Send + Sync {
+    async fn rerank(&self, query: &str, docs: Vec<ScoredDocument>) -> Result<Vec<ScoredDocument>>;
+}
+
+#[async_trait]
+impl<F> Reranker for F
+where
+    F: Fn(&ScoredDocument) -> f32 + Send + Sync,
+{
+    async fn rerank(
+        &self,
+        _query: &str,
+        mut docs: Vec<ScoredDocument>,
+    ) -> Result<Vec<ScoredDocument>> {
+        for doc in docs.iter_mut() {
+            doc.score = self(doc);
+        }
+        docs.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Ok(docs)
+    }
+}
+
+/// Reranks with Cohere's rerank endpoint. Documents come back in Cohere's order with the
+/// relevance score it assigned; with [`CohereReranker::with_top_n`] only the best are kept.
+#[derive(Clone)]
+pub struct CohereReranker {
+    http: reqwest::Client,
+    api_key: String,
+    model: String,
+    endpoint: String,
+    top_n: Option<usize>,
+}
+
+impl CohereReranker {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(std::time::Duration::from_secs(30))
+                .build()
+                .expect("failed to build http client"),
+            api_key: api_key.into(),
+            model: "rerank-v3.5".to_string(),
+            endpoint: "https://api.cohere.com/v2/rerank".to_string(),
+            top_n: None,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn with_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.endpoint = endpoint.into();
+        self
+    }
+
+    pub fn with_top_n(mut self, top_n: usize) -> Self {
+        self.top_n = Some(top_n);
+        self
+    }
+}
+
+#[derive(Deserialize)]
+struct CohereRerankResponse {
+    results: Vec<CohereRerankResult>,
+}
+
+#[derive(Deserialize)]
+struct CohereRerankResult {
+    index: usize,
+    relevance_score: f32,
+}
+
+#[async_trait]
+impl Reranker for CohereReranker {
+    async fn rerank(&self, query: &str, docs: Vec<ScoredDocument>) -> Result<Vec<ScoredDocument>> {
+        if docs.is_empty() {
+            return Ok(docs);
+        }
+        let mut payload = json!({
+            "model": self.model,
+            "query": query,
+            "documents": docs.iter().map(|d| d.document.text.as_str()).collect::<Vec<_>>(),
+        });
+        if let Some(top_n) = self.top_n {
+            payload["top_n"] = json!(top_n);
+        }
+        let request = self
+            .http
+            .post(&self.endpoint)
+            .bearer_auth(&self.api_key)
+            .json(&payload);
+        let resp = crate::llm::send_request("cohere", request).await?;
+        let body: CohereRerankResponse =
+            resp.json()
+                .await
+                .map_err(|err| AgnoError::InvalidResponse {
+                    provider: "cohere".into(),
+                    message: format!("Cohere rerank response parse error: {err}"),
+                })?;
+        Ok(apply_rerank_results(docs, body.results))
+    }
+}
+
+fn apply_rerank_results(
+    docs: Vec<ScoredDocument>,
+    results: Vec<CohereRerankResult>,
+) -> Vec<ScoredDocument> {
+    let mut docs: Vec<Option<ScoredDocument>> = docs.into_iter().map(Some).collect();
+    results
+        .into_iter()
+        .filter_map(|result| {
+            let mut doc = docs.get_mut(result.index)?.take()?;
+            doc.score = result.relevance_score;
+            Some(doc)
+        })
+        .collect()
+}
 
 pub struct KnowledgeBase<E: Embedder, S: VectorStore> {
     embedder: Arc<E>,
@@ -368,7 +1356,7 @@ impl<E: Embedder, S: VectorStore> KnowledgeBase<E, S> {
         }
     }
 
-    pub fn with_reranker(mut self, reranker: Reranker) -> Self {
+    pub fn with_reranker(mut self, reranker: Arc<dyn Reranker>) -> Self {
         self.config.reranker = Some(reranker);
         self
     }
@@ -419,21 +1407,14 @@ impl<E: Embedder, S: VectorStore> KnowledgeBase<E, S> {
         let params = SearchParams {
             top_k: overrides.top_k.unwrap_or(self.config.top_k),
             similarity: overrides.similarity.unwrap_or(self.config.similarity),
+            metadata_filter: overrides.metadata_filter,
         };
-        let mut scored = self.store.search(embedding, params).await?;
+        let scored = self.store.search(embedding, params).await?;
 
-        if let Some(reranker) = overrides.reranker.or_else(|| self.config.reranker.clone()) {
-            for doc in scored.iter_mut() {
-                doc.score = reranker(doc);
-            }
-            scored.sort_by(|a, b| {
-                b.score
-                    .partial_cmp(&a.score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+        match overrides.reranker.or_else(|| self.config.reranker.clone()) {
+            Some(reranker) => reranker.rerank(query, scored).await,
+            None => Ok(scored),
         }
-
-        Ok(scored)
     }
 
     pub async fn evaluate(
@@ -465,11 +1446,108 @@ impl<E: Embedder, S: VectorStore> KnowledgeBase<E, S> {
             recall,
         })
     }
+
+    /// Evaluate every `(query, relevant_ids)` case and aggregate mean precision, mean
+    /// recall, MRR and nDCG@k, where `k` is the effective top-k, into a report with a
+    /// per-query breakdown.
+    pub async fn evaluate_batch(
+        &self,
+        cases: &[(String, Vec<String>)],
+        overrides: RetrievalOverrides,
+    ) -> Result<EvaluationReport> {
+        let started = std::time::Instant::now();
+        let k = overrides.top_k.unwrap_or(self.config.top_k);
+        let mut queries = Vec::with_capacity(cases.len());
+        for (query, relevant_ids) in cases {
+            let evaluation = self
+                .evaluate(query, relevant_ids, overrides.clone())
+                .await?;
+            let retrieved_ids: Vec<String> = evaluation
+                .retrieved
+                .iter()
+                .map(|scored| scored.document.id.clone())
+                .collect();
+            let relevant: HashSet<&str> = relevant_ids.iter().map(String::as_str).collect();
+            queries.push(QueryRetrievalMetrics {
+                query: query.clone(),
+                precision: evaluation.precision,
+                recall: evaluation.recall,
+                reciprocal_rank: reciprocal_rank(&retrieved_ids, &relevant),
+                ndcg: ndcg_at_k(&retrieved_ids, &relevant, k),
+                retrieved_ids,
+            });
+        }
+
+        let mean = |metric: fn(&QueryRetrievalMetrics) -> f32| {
+            if queries.is_empty() {
+                0.0
+            } else {
+                queries.iter().map(metric).sum::<f32>() / queries.len() as f32
+            }
+        };
+        let retrieval = RetrievalMetrics {
+            mean_precision: mean(|q| q.precision),
+            mean_recall: mean(|q| q.recall),
+            mrr: mean(|q| q.reciprocal_rank),
+            ndcg: mean(|q| q.ndcg),
+            k,
+            queries,
+        };
+        Ok(EvaluationReport {
+            duration: started.elapsed(),
+            success: true,
+            retrieval: Some(retrieval),
+            ..Default::default()
+        })
+    }
+}
+
+fn reciprocal_rank(retrieved_ids: &[String], relevant: &HashSet<&str>) -> f32 {
+    retrieved_ids
+        .iter()
+        .position(|id| relevant.contains(id.as_str()))
+        .map_or(0.0, |rank| 1.0 / (rank + 1) as f32)
+}
+
+/// Binary-relevance nDCG over the first `k` results.
+fn ndcg_at_k(retrieved_ids: &[String], relevant: &HashSet<&str>, k: usize) -> f32 {
+    let discount = |rank: usize| 1.0 / ((rank + 2) as f32).log2();
+    let dcg: f32 = retrieved_ids
+        .iter()
+        .take(k)
+        .enumerate()
+        .filter(|(_, id)| relevant.contains(id.as_str()))
+        .map(|(rank, _)| discount(rank))
+        .sum();
+    let ideal: f32 = (0..relevant.len().min(k)).map(discount).sum();
+    if ideal == 0.0 {
+        0.0
+    } else {
+        dcg / ideal
+    }
 }
 
 #[async_trait]
 pub trait Retriever: Send + Sync {
     async fn retrieve(&self, query: &str, top_k: usize) -> Result<Vec<String>>;
+
+    /// Retrieved documents with their source ids and scores. The default wraps
+    /// [`Retriever::retrieve`], numbering results by rank with a score of zero.
+    async fn retrieve_scored(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>> {
+        let texts = self.retrieve(query, top_k).await?;
+        Ok(texts
+            .into_iter()
+            .enumerate()
+            .map(|(rank, text)| ScoredDocument {
+                document: Document {
+                    id: rank.to_string(),
+                    text,
+                    metadata: Value::Null,
+                },
+                score: 0.0,
+            })
+            .collect())
+    }
 }
 
 #[async_trait]
@@ -482,13 +1560,17 @@ where
         let docs = KnowledgeBase::retrieve(self, query, top_k).await?;
         Ok(docs.into_iter().map(|d| d.document.text).collect())
     }
+
+    async fn retrieve_scored(&self, query: &str, top_k: usize) -> Result<Vec<ScoredDocument>> {
+        KnowledgeBase::retrieve(self, query, top_k).await
+    }
 }
 
 #[derive(Clone)]
 pub struct RetrievalConfig {
     pub top_k: usize,
     pub similarity: SimilarityMetric,
-    pub reranker: Option<Reranker>,
+    pub reranker: Option<Arc<dyn Reranker>>,
 }
 
 impl Default for RetrievalConfig {
@@ -505,7 +1587,8 @@ impl Default for RetrievalConfig {
 pub struct RetrievalOverrides {
     pub top_k: Option<usize>,
     pub similarity: Option<SimilarityMetric>,
-    pub reranker: Option<Reranker>,
+    pub reranker: Option<Arc<dyn Reranker>>,
+    pub metadata_filter: HashMap<String, Value>,
 }
 
 pub struct RetrievalEvaluation {
@@ -549,6 +1632,88 @@ mod tests {
         assert_eq!(scored.len(), 2);
     }
 
+    #[tokio::test]
+    async fn applies_reranker_trait_and_closure_adapter() {
+        struct Reverse;
+
+        #[async_trait]
+        impl Reranker for Reverse {
+            async fn rerank(
+                &self,
+                query: &str,
+                mut docs: Vec<ScoredDocument>,
+            ) -> Result<Vec<ScoredDocument>> {
+                assert_eq!(query, "notes");
+                docs.reverse();
+                Ok(docs)
+            }
+        }
+
+        let kb = KnowledgeBase::new(
+            Arc::new(TestEmbedder),
+            Arc::new(InMemoryVectorStore::default()),
+        );
+        for (id, text) in [("a", "go"), ("b", "rust notes"), ("c", "zig")] {
+            kb.add_document(Document {
+                id: id.into(),
+                text: text.into(),
+                metadata: Value::Null,
+            })
+            .await
+            .unwrap();
+        }
+        let ids =
+            |docs: Vec<ScoredDocument>| docs.into_iter().map(|d| d.document.id).collect::<Vec<_>>();
+
+        let plain = ids(kb.retrieve("notes", 3).await.unwrap());
+        let kb = kb.with_reranker(Arc::new(Reverse));
+        let mut reversed = ids(kb.retrieve("notes", 3).await.unwrap());
+        reversed.reverse();
+        assert_eq!(reversed, plain);
+
+        let by_length = RetrievalOverrides {
+            reranker: Some(Arc::new(|doc: &ScoredDocument| {
+                doc.document.text.len() as f32
+            })),
+            ..Default::default()
+        };
+        let ranked = ids(kb
+            .retrieve_with_overrides("notes", by_length)
+            .await
+            .unwrap());
+        assert_eq!(ranked, vec!["b", "c", "a"]);
+    }
+
+    #[test]
+    fn orders_documents_by_cohere_relevance() {
+        let docs = ["a", "b", "c"]
+            .into_iter()
+            .map(|id| ScoredDocument {
+                document: Document {
+                    id: id.into(),
+                    text: id.into(),
+                    metadata: Value::Null,
+                },
+                score: 0.0,
+            })
+            .collect();
+        let body: CohereRerankResponse = serde_json::from_value(json!({
+            "id": "rerank-1",
+            "results": [
+                {"index": 2, "relevance_score": 0.9},
+                {"index": 0, "relevance_score": 0.4},
+            ],
+        }))
+        .unwrap();
+
+        let reranked = apply_rerank_results(docs, body.results);
+        let summary: Vec<_> = reranked
+            .iter()
+            .map(|d| (d.document.id.as_str(), d.score))
+            .collect();
+        assert_eq!(summary, vec![("c", 0.9), ("a", 0.4)]);
+    }
+
     #[tokio::test]
     async fn evaluates_precision_recall() {
         let embedder = Arc::new(TestEmbedder);
@@ -585,4 +1750,538 @@ mod tests {
         assert_eq!(report.recall, 1.0);
         assert_eq!(report.precision, 1.0);
     }
+
+    #[tokio::test]
+    async fn vector_store_round_trips_through_file() {
+        let store = InMemoryVectorStore::default();
+        let vectors = [
+            ("rust", vec![0.9_f32, 0.1, 0.0]),
+            ("python", vec![0.2, 0.8, 0.1]),
+            ("go", vec![0.6, 0.3, 0.123_456_79]),
+        ];
+        for (id, vector) in vectors {
+            store
+                .add(
+                    Document {
+                        id: id.into(),
+                        text: format!("{id} notes"),
+                        metadata: json!({"lang": id}),
+                    },
+                    vector,
+                )
+                .await
+                .unwrap();
+        }
+        let file = tempfile::NamedTempFile::new().unwrap();
+        store.save_to_file(file.path()).await.unwrap();
+
+        let loaded = InMemoryVectorStore::load_from_file(file.path())
+            .await
+            .unwrap();
+        let query = vec![0.7, 0.2, 0.1];
+        let params = SearchParams {
+            top_k: 3,
+            ..Default::default()
+        };
+        let before = store.search(query.clone(), params.clone()).await.unwrap();
+        let after = loaded.search(query, params).await.unwrap();
+
+        let summarize = |scored: &[ScoredDocument]| {
+            scored
+                .iter()
+                .map(|s| (s.document.id.clone(), s.document.metadata.clone(), s.score))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(summarize(&before), summarize(&after));
+        assert_eq!(after[0].document.text, "go notes");
+        assert_eq!(loaded.entries.read().await[2].1[2], 0.123_456_79);
+    }
+
+    #[test]
+    fn splits_sentences_around_abbreviations() {
+        let sentences = SentenceChunker::split_sentences(
+            "Dr. Smith met J. Doe at 3.30 p.m. today! Was it planned? \"Yes.\" Done",
+        );
+        assert_eq!(
+            sentences,
+            vec![
+                "Dr. Smith met J. Doe at 3.30 p.m. today!",
+                "Was it planned?",
+                "\"Yes.\"",
+                "Done",
+            ]
+        );
+    }
+
+    #[test]
+    fn sentence_chunker_keeps_sentences_whole_with_overlap() {
+        let text = "Rust is fast. It has no garbage collector. Memory safety comes from \
+                    ownership. The borrow checker enforces it at compile time. Cargo builds \
+                    the code.";
+        let document = Document {
+            id: "rust".into(),
+            text: text.into(),
+            metadata: json!({"topic": "lang"}),
+        };
+        let chunker = SentenceChunker {
+            max_tokens: 14,
+            overlap_sentences: 1,
+        };
+        let chunks = chunker.chunk(&document);
+        let sentences = SentenceChunker::split_sentences(text);
+
+        assert!(chunks.len() > 1);
+        for chunk in &chunks {
+            let parts = SentenceChunker::split_sentences(&chunk.text);
+            assert!(parts.iter().all(|part| sentences.contains(part)));
+        }
+        for pair in chunks.windows(2) {
+            let previous = SentenceChunker::split_sentences(&pair[0].text);
+            let next = SentenceChunker::split_sentences(&pair[1].text);
+            assert_eq!(previous.last(), next.first());
+        }
+        assert_eq!(chunks[1].metadata["chunk_index"], 1);
+        assert_eq!(chunks[1].metadata["source_id"], "rust");
+        assert_eq!(chunks[1].metadata["topic"], "lang");
+        assert_eq!(chunks[1].id, "rust::1");
+    }
+
+    #[test]
+    fn markdown_chunker_tracks_sections_and_keeps_code_fences() {
+        let text = [
+            "Intro paragraph.",
+            "",
+            "# Installation",
+            "",
+            "Pick your platform.",
+            "",
+            "## Linux",
+            "",
+            "Run the installer:",
+            "",
+            "```sh",
+            "# not a heading",
+            "",
+            "curl -sSf https://example.com/install.sh | sh",
+            "sayr --version",
+            "```",
+            "",
+            "## macOS",
+            "",
+            "Use Homebrew.",
+            "",
+            "# Usage",
+            "",
+            "Start the server.",
+        ]
+        .join("\n");
+        let document = Document {
+            id: "readme".into(),
+            text,
+            metadata: Value::Null,
+        };
+        let chunker = MarkdownChunker {
+            max_tokens: 12,
+            overlap: 0,
+        };
+        let chunks = chunker.chunk(&document);
+
+        let sections: Vec<Option<&str>> = chunks
+            .iter()
+            .map(|c| c.metadata.get("section").and_then(Value::as_str))
+            .collect();
+        assert_eq!(sections[0], None);
+        assert!(sections.contains(&Some("Installation > Linux")));
+        assert!(sections.contains(&Some("Installation > macOS")));
+        assert_eq!(sections.last(), Some(&Some("Usage")));
+
+        let fenced: Vec<&Document> = chunks.iter().filter(|c| c.text.contains("```sh")).collect();
+        assert_eq!(fenced.len(), 1);
+        assert!(fenced[0].text.contains("# not a heading"));
+        assert!(fenced[0].text.trim_end().ends_with("```"));
+        assert_eq!(fenced[0].metadata["section"], "Installation > Linux");
+        assert_eq!(fenced[0].metadata["source_id"], "readme");
+    }
+
+    #[tokio::test]
+    async fn caching_embedder_reuses_vectors_for_identical_text() {
+        struct CountingEmbedder(AtomicU64);
+
+        #[async_trait]
+        impl Embedder for CountingEmbedder {
+            async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+                self.0.fetch_add(1, Ordering::SeqCst);
+                Ok(vec![text.len() as f32])
+            }
+        }
+
+        let inner = Arc::new(CountingEmbedder(AtomicU64::new(0)));
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("embeddings.json");
+        let cached = CachingEmbedder::new(inner.clone())
+            .with_file(&path)
+            .await
+            .unwrap();
+
+        let first = cached.embed("same chunk").await.unwrap();
+        let second = cached.embed("same chunk").await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+        assert_eq!(
+            cached.stats().await,
+            EmbeddingCacheStats {
+                hits: 1,
+                misses: 1,
+                entries: 1
+            }
+        );
+
+        cached.save().await.unwrap();
+        let reloaded = CachingEmbedder::new(inner.clone())
+            .with_file(&path)
+            .await
+            .unwrap();
+        assert_eq!(reloaded.embed("same chunk").await.unwrap(), first);
+        assert_eq!(inner.0.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn fnv1a_matches_the_reference_vectors() {
+        // Persisted cache keys and sampling decisions depend on these exact values.
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[tokio::test]
+    async fn in_memory_search_applies_metadata_filter() {
+        let store = InMemoryVectorStore::default();
+        for (id, lang) in [("a", "en"), ("b", "fr")] {
+            let document = Document {
+                id: id.into(),
+                text: id.into(),
+                metadata: json!({ "lang": lang }),
+            };
+            store.add(document, vec![1.0, 0.0]).await.unwrap();
+        }
+        let params = SearchParams {
+            metadata_filter: HashMap::from([("lang".to_string(), json!("fr"))]),
+            ..Default::default()
+        };
+        let hits = store.search(vec![1.0, 0.0], params).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.id, "b");
+    }
+
+    #[tokio::test]
+    async fn rejects_embeddings_of_another_dimension() {
+        let document = |id: &str| Document {
+            id: id.into(),
+            text: id.into(),
+            metadata: Value::Null,
+        };
+        let store = InMemoryVectorStore::default();
+        store.add(document("a"), vec![0.5; 32]).await.unwrap();
+        let err = store.add(document("b"), vec![0.5; 16]).await.unwrap_err();
+        assert!(matches!(
+            err,
+            AgnoError::DimensionMismatch {
+                expected: 32,
+                actual: 16
+            }
+        ));
+        assert_eq!(
+            err.to_string(),
+            "embedding dimension mismatch: store holds 32-dimensional vectors, got 16"
+        );
+        let err = store
+            .search(vec![0.5; 16], SearchParams::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, AgnoError::DimensionMismatch { .. }));
+        assert_eq!(
+            store
+                .search(vec![0.5; 32], SearchParams::default())
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        struct Unreachable;
+
+        #[async_trait]
+        impl QdrantClient for Unreachable {
+            async fn upsert(&self, _document: &Document, _embedding: &[f32]) -> Result<()> {
+                Ok(())
+            }
+
+            async fn query(
+                &self,
+                _embedding: &[f32],
+                _params: SearchParams,
+            ) -> Result<Vec<ScoredDocument>> {
+                Ok(Vec::new())
+            }
+        }
+
+        let remote = QdrantStore::new(Arc::new(Unreachable)).with_dimension(32);
+        assert!(matches!(
+            remote.search(vec![0.5; 16], SearchParams::default()).await,
+            Err(AgnoError::DimensionMismatch { .. })
+        ));
+        remote.add(document("a"), vec![0.5; 32]).await.unwrap();
+        assert!(remote.add(document("b"), vec![0.5; 16]).await.is_err());
+    }
+
+    #[cfg(feature = "qdrant")]
+    #[tokio::test]
+    async fn qdrant_client_round_trips_payload_through_rest_api() {
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+        use tokio::net::TcpListener;
+
+        // Minimal stand-in for Qdrant: remembers upserted points and answers searches with
+        // them, recording every request line, api key and body.
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let requests = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            let mut points = Vec::new();
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut reader = BufReader::new(socket);
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).await.unwrap();
+                let (mut length, mut api_key) = (0, String::new());
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).await.unwrap();
+                    let Some((name, value)) = header.trim_end().split_once(": ") else {
+                        break;
+                    };
+                    match name.to_ascii_lowercase().as_str() {
+                        "content-length" => length = value.parse().unwrap(),
+                        "api-key" => api_key = value.to_string(),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0u8; length];
+                reader.read_exact(&mut body).await.unwrap();
+                let body: Value = serde_json::from_slice(&body).unwrap();
+
+                let response = if request_line.starts_with("PUT ") {
+                    points.extend(body["points"].as_array().unwrap().iter().cloned());
+                    json!({ "result": { "status": "completed" }, "status": "ok" })
+                } else {
+                    let result: Vec<Value> = points
+                        .iter()
+                        .map(|point| json!({ "id": point["id"], "score": 0.9, "payload": point["payload"] }))
+                        .collect();
+                    json!({ "result": result, "status": "ok" })
+                }
+                .to_string();
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((request_line.trim_end().to_string(), api_key, body));
+                let mut socket = reader.into_inner();
+                let reply = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{response}",
+                    response.len()
+                );
+                socket.write_all(reply.as_bytes()).await.unwrap();
+            }
+        });
+
+        let client = HttpQdrantClient::new(format!("http://{addr}"), "docs").with_api_key("secret");
+        let store = QdrantStore::new(Arc::new(client));
+        let document = Document {
+            id: "guide-1".into(),
+            text: "Install with cargo".into(),
+            metadata: json!({ "lang": "en", "tags": ["setup"], "version": 2 }),
+        };
+        store.add(document, vec![0.1, 0.2, 0.3]).await.unwrap();
+        let params = SearchParams {
+            top_k: 3,
+            metadata_filter: HashMap::from([
+                ("lang".to_string(), json!("en")),
+                ("version".to_string(), json!(2)),
+            ]),
+            ..Default::default()
+        };
+        let hits = store.search(vec![0.1, 0.2, 0.3], params).await.unwrap();
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document.id, "guide-1");
+        assert_eq!(hits[0].document.text, "Install with cargo");
+        assert_eq!(
+            hits[0].document.metadata,
+            json!({ "lang": "en", "tags": ["setup"], "version": 2 })
+        );
+        assert!((hits[0].score - 0.9).abs() < f32::EPSILON);
+
+        let requests = requests.lock().unwrap();
+        assert_eq!(
+            requests[0].0,
+            "PUT /collections/docs/points?wait=true HTTP/1.1"
+        );
+        assert_eq!(
+            requests[0].2["points"][0]["id"],
+            json!(qdrant_point_id("guide-1"))
+        );
+        assert_eq!(
+            requests[1].0,
+            "POST /collections/docs/points/search HTTP/1.1"
+        );
+        assert!(requests.iter().all(|(_, key, _)| key == "secret"));
+        assert_eq!(requests[1].2["limit"], 3);
+        assert_eq!(
+            requests[1].2["filter"],
+            json!({ "must": [
+                { "key": "metadata.lang", "match": { "value": "en" } },
+                { "key": "metadata.version", "match": { "value": 2 } },
+            ] })
+        );
+    }
+
+    #[cfg(feature = "pgvector")]
+    #[tokio::test]
+    async fn pgvector_client_rejects_unsafe_table_names_before_connecting() {
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+            .unwrap();
+        let err =
+            SqlxPgVectorClient::new(pool, "docs; DROP TABLE users", 3, SimilarityMetric::Cosine)
+                .await
+                .err()
+                .unwrap();
+        assert!(err.to_string().contains("invalid pgvector table name"));
+
+        assert!((pgvector_score(0.25, SimilarityMetric::Cosine) - 0.75).abs() < 1e-6);
+        assert!((pgvector_score(-3.0, SimilarityMetric::DotProduct) - 3.0).abs() < 1e-6);
+        assert!((pgvector_score(1.0, SimilarityMetric::Euclidean) - 0.5).abs() < 1e-6);
+    }
+
+    #[cfg(feature = "pgvector")]
+    #[tokio::test]
+    #[ignore = "needs a Postgres server with pgvector at DATABASE_URL"]
+    async fn pgvector_client_upserts_and_searches_with_metadata_filter() {
+        let url = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+        let pool = sqlx::postgres::PgPoolOptions::new()
+            .max_connections(2)
+            .connect(&url)
+            .await
+            .unwrap();
+        let table = format!("sayr_docs_{}", uuid::Uuid::new_v4().simple());
+        let client = SqlxPgVectorClient::new(pool.clone(), &table, 2, SimilarityMetric::Cosine)
+            .await
+            .unwrap();
+        let store = PgVectorStore::new(Arc::new(client));
+        let docs = [
+            ("a", [1.0, 0.0], json!({ "lang": "en", "tier": 1 })),
+            ("b", [0.9, 0.1], json!({ "lang": "fr", "tier": 1 })),
+            ("c", [0.0, 1.0], json!({ "lang": "fr", "tier": 2 })),
+        ];
+        for (id, embedding, metadata) in docs {
+            let document = Document {
+                id: id.into(),
+                text: format!("doc {id}"),
+                metadata,
+            };
+            store.add(document, embedding.to_vec()).await.unwrap();
+        }
+        // Upserting again replaces the row rather than duplicating it.
+        let updated = Document {
+            id: "b".into(),
+            text: "doc b v2".into(),
+            metadata: json!({ "lang": "fr", "tier": 1 }),
+        };
+        store.add(updated, vec![0.9, 0.1]).await.unwrap();
+
+        let params = SearchParams {
+            top_k: 2,
+            metadata_filter: HashMap::from([("lang".to_string(), json!("fr"))]),
+            ..Default::default()
+        };
+        let hits = store.search(vec![1.0, 0.0], params).await.unwrap();
+        sqlx::query(&format!("DROP TABLE {table}"))
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let ids: Vec<&str> = hits.iter().map(|hit| hit.document.id.as_str()).collect();
+        assert_eq!(ids, ["b", "c"]);
+        assert_eq!(hits[0].document.text, "doc b v2");
+        assert_eq!(
+            hits[0].document.metadata,
+            json!({ "lang": "fr", "tier": 1 })
+        );
+        assert!(hits[0].score > 0.9 && hits[0].score <= 1.0);
+        assert!(hits[1].score.abs() < 1e-6);
+    }
+
+    #[tokio::test]
+    async fn evaluates_batch_with_mrr_and_ndcg() {
+        struct AxisEmbedder;
+
+        #[async_trait]
+        impl Embedder for AxisEmbedder {
+            async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+                Ok(match text {
+                    "alpha" => vec![3.0],
+                    "beta" => vec![2.0],
+                    "gamma" => vec![1.0],
+                    "forward" => vec![1.0],
+                    _ => vec![-1.0],
+                })
+            }
+        }
+
+        let kb = KnowledgeBase::new(
+            Arc::new(AxisEmbedder),
+            Arc::new(InMemoryVectorStore::default()),
+        )
+        .with_config(RetrievalConfig {
+            top_k: 2,
+            similarity: SimilarityMetric::DotProduct,
+            reranker: None,
+        });
+        for (id, text) in [("a", "alpha"), ("b", "beta"), ("c", "gamma")] {
+            kb.add_document(Document {
+                id: id.into(),
+                text: text.into(),
+                metadata: Value::Null,
+            })
+            .await
+            .unwrap();
+        }
+
+        // "forward" ranks a, b, c; "backward" ranks c, b, a.
+        let cases = vec![
+            ("forward".to_string(), vec!["b".to_string()]),
+            (
+                "backward".to_string(),
+                vec!["a".to_string(), "c".to_string()],
+            ),
+            ("forward".to_string(), vec!["missing".to_string()]),
+        ];
+        let report = kb
+            .evaluate_batch(&cases, RetrievalOverrides::default())
+            .await
+            .unwrap();
+        let metrics = report.retrieval.unwrap();
+
+        assert_eq!(metrics.k, 2);
+        assert_eq!(metrics.queries[0].retrieved_ids, ["a", "b"]);
+        assert_eq!(metrics.queries[1].retrieved_ids, ["c", "b"]);
+        // Reciprocal ranks 1/2, 1 and 0; recalls 1, 1/2 and 0.
+        assert!((metrics.mrr - 0.5).abs() < 1e-6);
+        assert!((metrics.mean_recall - 0.5).abs() < 1e-6);
+        assert!((metrics.mean_precision - 1.0 / 3.0).abs() < 1e-6);
+        let discount = 1.0 / 3f32.log2();
+        assert!((metrics.queries[0].ndcg - discount).abs() < 1e-6);
+        assert!((metrics.queries[1].ndcg - 1.0 / (1.0 + discount)).abs() < 1e-6);
+        assert_eq!(metrics.queries[2].ndcg, 0.0);
+    }
 }
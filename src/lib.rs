@@ -6,7 +6,10 @@
 //! - An `Agent` that loops between the model and tools using structured JSON directives.
 
 mod agent;
+mod balancer;
+mod bridge;
 mod config;
+mod cost;
 mod deployment;
 mod error;
 mod governance;
@@ -19,6 +22,7 @@ mod memory;
 mod message;
 mod metrics;
 pub mod reasoning;
+mod retry;
 #[cfg(feature = "server")]
 mod server;
 #[cfg(feature = "persistence")]
@@ -32,27 +36,43 @@ pub mod tools;
 mod workflow;
 
 
-pub use agent::{Agent, AgentDirective};
+pub use agent::{
+    Agent, AgentDirective, AgentEvent, DirectiveParser, EmptyCompletionPolicy,
+    LenientDirectiveParser, StrictDirectiveParser,
+};
+pub use balancer::{BalanceStrategy, LoadBalancedModel};
+pub use bridge::ToolCallBridge;
 pub use config::{
-    AppConfig, DeploymentConfig, ModelConfig, ProviderConfig, SecurityConfig, ServerConfig,
-    TelemetryConfig,
+    ApiKeyConfig, AppConfig, DeploymentConfig, ModelConfig, ProviderConfig, SecurityConfig,
+    ServerConfig, TelemetryConfig,
 };
+pub use cost::{CostModel, ModelPrice, ANY_MODEL};
 pub use deployment::DeploymentPlan;
 pub use error::{AgnoError, Result};
-pub use governance::{AccessController, Action, Principal, PrivacyRule, Role as GovernanceRole};
-pub use hooks::{AgentHook, ConfirmationHandler};
+pub use governance::{
+    AccessController, Action, AuditRecord, AuditSink, FileAuditSink, InMemoryAuditSink, Principal,
+    PrivacyRule, Role as GovernanceRole,
+};
+pub use hooks::{AgentHook, ConfirmationDecision, ConfirmationHandler, ToolCallReviewer};
 pub use knowledge::{
-    Document, DocumentChunker, Embedder, InMemoryVectorStore, KnowledgeBase, OpenAiEmbedder,
-    OpenAiEmbeddingClient, PgVectorClient, PgVectorStore, QdrantClient, QdrantStore,
+    CachingEmbedder, Citation, CohereReranker, Document, DocumentChunker, Embedder,
+    EmbeddingCacheStats, InMemoryVectorStore, KnowledgeBase, MarkdownChunker, OpenAiEmbedder,
+    OpenAiEmbeddingClient, PgVectorClient, PgVectorStore, QdrantClient, QdrantStore, Reranker,
     RetrievalConfig, RetrievalEvaluation, RetrievalOverrides, Retriever, ScoredDocument,
-    SearchParams, SimilarityMetric, SlidingWindowChunker, TransformerClient, TransformerEmbedder,
-    VectorStore, WhitespaceEmbedder,
+    SearchParams, SentenceChunker, SimilarityMetric, SlidingWindowChunker, TransformerClient,
+    TransformerEmbedder, VectorStore, WhitespaceEmbedder,
 };
+#[cfg(feature = "pgvector")]
+pub use knowledge::SqlxPgVectorClient;
+#[cfg(feature = "qdrant")]
+pub use knowledge::HttpQdrantClient;
 #[cfg(feature = "aws")]
 pub use llm::AwsBedrockClient;
 pub use llm::{
-    AzureOpenAIClient, CohereClient, FireworksClient, GroqClient, LanguageModel,
-    MistralClient, ModelCompletion, OllamaClient, OpenAIClient, StubModel, TogetherClient,
+    AnthropicClient, AzureOpenAIClient, CohereClient, CompletionOptions, FireworksClient,
+    GeminiClient, GroqClient, HttpSettings, LanguageModel, MistralClient, ModelCompletion,
+    OllamaClient, OpenAIClient, OutputFormat, ReasoningEffort, StubModel, TogetherClient,
+    TokenUsage, ToolCallDelta, ToolChoice,
 };
 pub use memory::{
     ConversationMemory, FullMemoryStrategy, MemoryStrategy, 
@@ -62,21 +82,32 @@ pub use memory::{
 pub use memory::PersistentConversationMemory;
 
 pub use message::{Attachment, AttachmentKind, Message, Role, ToolCall, ToolResult};
-pub use metrics::EvaluationReport;
+pub use metrics::{EvaluationReport, QueryRetrievalMetrics, RetrievalMetrics};
 #[cfg(feature = "telemetry")]
-pub use metrics::MetricsTracker;
+pub use metrics::{LatencyHistogram, MetricsSnapshot, MetricsSummary, MetricsTracker};
+pub use retry::{RetryDecision, RetryPolicy};
 #[cfg(feature = "server")]
 pub use server::AgentRuntime;
 #[cfg(feature = "persistence")]
-pub use storage::{ConversationStore, FileConversationStore, SqlConversationStore};
-pub use team::{Team, TeamEvent};
+pub use storage::{
+    ConversationStore, FileCheckpointer, FileConversationStore, SqlConversationStore,
+};
+#[cfg(feature = "redis")]
+pub use storage::{RedisConversationStore, DEFAULT_REDIS_PREFIX};
+pub use team::{
+    AgentProfile, Aggregator, KeywordRouter, LlmJudge, MajorityVote, ModelRouter, RoundRobin,
+    RoutingStrategy, SharedMemory, Team, TeamEvent,
+};
 #[cfg(feature = "telemetry")]
 pub use telemetry::{
     current_span_attributes, flush_tracer, init_tracing, span_with_labels, FallbackChain,
-    RetryPolicy, TelemetryCollector, TelemetryLabels, TelemetrySink,
+    TelemetryCollector, TelemetryLabels, TelemetrySink,
 };
+pub use tokio_util::sync::CancellationToken;
 pub use tool::{Tool, ToolDescription, ToolRegistry};
 pub use toolkit::basic_toolkit;
 pub use workflow::{
-    AgentTask, FunctionTask, Workflow, WorkflowContext, WorkflowNode, WorkflowTask,
+    AgentTask, Checkpointer, Condition, EdgeSelector, ErrorPolicy, FunctionTask, ParallelNode,
+    ResilientNode, Workflow, WorkflowCheckpoint, WorkflowContext, WorkflowEdge, WorkflowGraph,
+    WorkflowNode, WorkflowTask,
 };
@@ -1,25 +1,190 @@
 //! Language model implementations and abstractions.
 #![allow(dead_code)]
 
-use std::collections::{HashMap, VecDeque};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, VecDeque};
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
 
 use async_trait::async_trait;
+use base64::Engine;
 use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use tokio::sync::mpsc;
 
 use crate::config::ModelConfig;
 use crate::error::{AgnoError, Result};
-use crate::message::{Message, Role, ToolCall};
+use crate::message::{Attachment, AttachmentKind, Message, Role, ToolCall};
+use crate::retry::{RetryDecision, RetryPolicy};
+#[cfg(feature = "telemetry")]
+use crate::telemetry::{TelemetryCollector, TelemetryLabels};
 use crate::tool::ToolDescription;
+use crate::tools::FsSandbox;
 
 /// Result of a chat completion request.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct ModelCompletion {
     pub content: Option<String>,
     pub tool_calls: Vec<ToolCall>,
+    /// Token counts reported by the provider, when it returns them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<TokenUsage>,
+}
+
+/// Tool-call progress reported while a streamed completion is still arriving. Calls are
+/// identified by their position in the reply, since providers may send the id only once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ToolCallDelta {
+    /// The model began a call; its arguments may still be incomplete.
+    Started {
+        index: usize,
+        id: Option<String>,
+        name: String,
+    },
+    /// The next fragment of a call's JSON arguments.
+    Arguments { index: usize, fragment: String },
+}
+
+/// Tokens consumed by one completion request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub provider: String,
+    pub model: String,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+}
+
+/// Shape the model is asked to reply in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum OutputFormat {
+    #[default]
+    Text,
+    /// Any syntactically valid JSON object.
+    JsonObject,
+    /// JSON conforming to the given schema. Either a bare JSON schema, or an object with
+    /// `name` and `schema` (plus optional `strict`) as expected by OpenAI's `json_schema`.
+    JsonSchema(Value),
+}
+
+impl OutputFormat {
+    /// A bare JSON `schema`, sent under the name `response`. `strict` asks OpenAI to
+    /// enforce it exactly, which requires every property to be listed as required.
+    pub fn json_schema(schema: Value, strict: bool) -> Self {
+        OutputFormat::JsonSchema(json!({ "name": "response", "schema": schema, "strict": strict }))
+    }
+
+    /// The OpenAI-style `response_format` request field, or `None` for plain text.
+    pub fn response_format(&self) -> Option<Value> {
+        match self {
+            OutputFormat::Text => None,
+            OutputFormat::JsonObject => Some(json!({ "type": "json_object" })),
+            OutputFormat::JsonSchema(schema) => {
+                let json_schema = if schema.get("schema").is_some() && schema.get("name").is_some()
+                {
+                    schema.clone()
+                } else {
+                    json!({ "name": "response", "schema": schema, "strict": true })
+                };
+                Some(json!({ "type": "json_schema", "json_schema": json_schema }))
+            }
+        }
+    }
+}
+
+/// The format a request asks for: `requested`, unless that is plain text, which leaves the
+/// format the client was configured with via `with_output_format` in place.
+fn requested_format<'a>(
+    requested: &'a OutputFormat,
+    configured: &'a OutputFormat,
+) -> &'a OutputFormat {
+    match requested {
+        OutputFormat::Text => configured,
+        _ => requested,
+    }
+}
+
+/// Whether, and which, tool the model may call in one request.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum ToolChoice {
+    /// The model decides.
+    #[default]
+    Auto,
+    /// The model must reply in text.
+    None,
+    /// The model must call the named tool.
+    Function(String),
+}
+
+impl ToolChoice {
+    /// The OpenAI-style `tool_choice` request field.
+    pub fn openai_value(&self) -> Value {
+        match self {
+            ToolChoice::Auto => json!("auto"),
+            ToolChoice::None => json!("none"),
+            ToolChoice::Function(name) => {
+                json!({ "type": "function", "function": { "name": name } })
+            }
+        }
+    }
+
+    /// The Anthropic Messages `tool_choice` request field.
+    pub fn anthropic_value(&self) -> Value {
+        match self {
+            ToolChoice::Auto => json!({ "type": "auto" }),
+            ToolChoice::None => json!({ "type": "none" }),
+            ToolChoice::Function(name) => json!({ "type": "tool", "name": name }),
+        }
+    }
+}
+
+/// How long a reasoning model may think before answering: an effort level, or an explicit
+/// budget of thinking tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReasoningEffort {
+    Low,
+    Medium,
+    High,
+    Budget(u32),
+}
+
+impl ReasoningEffort {
+    /// The OpenAI `reasoning_effort` field. OpenAI takes no token count, so budgets map
+    /// to the level whose [`budget_tokens`](Self::budget_tokens) they reach.
+    pub fn openai_value(&self) -> &'static str {
+        match self {
+            ReasoningEffort::Low => "low",
+            ReasoningEffort::Medium => "medium",
+            ReasoningEffort::High => "high",
+            ReasoningEffort::Budget(tokens) if *tokens >= 16_384 => "high",
+            ReasoningEffort::Budget(tokens) if *tokens >= 4_096 => "medium",
+            ReasoningEffort::Budget(_) => "low",
+        }
+    }
+
+    /// Thinking tokens for Anthropic extended thinking, never below its 1024-token minimum.
+    pub fn budget_tokens(&self) -> u32 {
+        match self {
+            ReasoningEffort::Low => 1_024,
+            ReasoningEffort::Medium => 4_096,
+            ReasoningEffort::High => 16_384,
+            ReasoningEffort::Budget(tokens) => (*tokens).max(1_024),
+        }
+    }
+}
+
+/// Tokens Anthropic may spend on the answer, on top of any thinking budget.
+const ANTHROPIC_ANSWER_TOKENS: u32 = 4_096;
+
+/// Default cap on `max_tokens` for Anthropic requests, the output limit of current models.
+const ANTHROPIC_MAX_OUTPUT_TOKENS: u32 = 64_000;
+
+/// Per-request settings beyond the messages, tools and reply format.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompletionOptions {
+    pub tool_choice: ToolChoice,
+    /// Sequences at which the model stops generating.
+    pub stop: Vec<String>,
 }
 
 /// Minimal abstraction around a chat completion provider.
@@ -31,19 +196,357 @@ pub trait LanguageModel: Send + Sync {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion>;
+
+    /// Like [`LanguageModel::complete_chat`], but asks for a reply in `format`. Providers
+    /// without native support log a warning and fall back to free-form text.
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.complete_chat(messages, tools, stream).await
+    }
+
+    /// Stream a completion in `format`, sending tool-call progress to `deltas` as it arrives.
+    /// The default streams through [`LanguageModel::complete_chat_with_format`] and reports
+    /// nothing until the completion is done.
+    async fn complete_chat_streaming(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        format: &OutputFormat,
+        _deltas: mpsc::UnboundedSender<ToolCallDelta>,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_format(messages, tools, true, format)
+            .await
+    }
+
+    /// Like [`LanguageModel::complete_chat_with_format`], or
+    /// [`LanguageModel::complete_chat_streaming`] when `deltas` is set, with per-request
+    /// `options`. Providers that cannot send a tool choice get only the tools it allows,
+    /// and stop sequences are ignored with a warning.
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        if !options.stop.is_empty() {
+            tracing::warn!("model does not support stop sequences; ignoring them");
+        }
+        let allowed: Vec<ToolDescription>;
+        let tools = match &options.tool_choice {
+            ToolChoice::Auto => tools,
+            ToolChoice::None => &[],
+            ToolChoice::Function(name) => {
+                allowed = tools
+                    .iter()
+                    .filter(|tool| &tool.name == name)
+                    .cloned()
+                    .collect();
+                &allowed
+            }
+        };
+        match deltas {
+            Some(deltas) => {
+                self.complete_chat_streaming(messages, tools, format, deltas)
+                    .await
+            }
+            None => {
+                self.complete_chat_with_format(messages, tools, stream, format)
+                    .await
+            }
+        }
+    }
 }
 
-fn coalesce_error(status: reqwest::StatusCode, body: &str, provider: &str) -> AgnoError {
+fn coalesce_error(
+    status: reqwest::StatusCode,
+    headers: &reqwest::header::HeaderMap,
+    body: &str,
+    provider: &str,
+) -> AgnoError {
     if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
-        return AgnoError::LanguageModel(format!("{provider} rate limit exceeded: {body}"));
+        tracing::warn!("{provider} rate limit exceeded: {body}");
+        return AgnoError::RateLimited {
+            provider: provider.to_string(),
+            retry_after: parse_retry_after(headers),
+        };
+    }
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        return AgnoError::Auth {
+            provider: provider.to_string(),
+            status: status.as_u16(),
+            message: body.to_string(),
+        };
+    }
+    if status.is_server_error() {
+        return AgnoError::ServerError {
+            provider: provider.to_string(),
+            status: status.as_u16(),
+            message: body.to_string(),
+        };
     }
     AgnoError::LanguageModel(format!("{provider} request failed with {}: {body}", status))
 }
 
+/// Optional retry configuration shared by the HTTP-backed clients.
+#[derive(Clone, Default)]
+struct HttpRetry {
+    policy: Option<RetryPolicy>,
+    #[cfg(feature = "telemetry")]
+    telemetry: Option<TelemetryCollector>,
+}
+
+/// A failed request attempt, classified for the retry loop.
+struct FailedAttempt {
+    error: AgnoError,
+    retryable: bool,
+}
+
+/// Longest wait accepted from a rate-limit header; longer hints are clamped to it.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
+/// How long the provider asks us to wait, read from `Retry-After` (seconds),
+/// `retry-after-ms`, or the `x-ratelimit-reset*` headers (seconds or durations such as
+/// `1s`, `6m0s`, `20ms`). Only the delay-seconds form of `Retry-After` is understood; an
+/// HTTP-date value is ignored in favour of the reset headers.
+fn parse_retry_after(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    if let Some(millis) = header("retry-after-ms").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return wait_from_secs(millis / 1000.0);
+    }
+    if let Some(secs) = header("retry-after").and_then(|v| v.trim().parse::<f64>().ok()) {
+        return wait_from_secs(secs);
+    }
+    [
+        "x-ratelimit-reset",
+        "x-ratelimit-reset-requests",
+        "x-ratelimit-reset-tokens",
+    ]
+    .iter()
+    .filter_map(|name| header(name).and_then(parse_reset_duration))
+    .max()
+}
+
+fn parse_reset_duration(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<f64>() {
+        return wait_from_secs(secs);
+    }
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => amount * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                amount / 1000.0
+            }
+            'm' => amount * 60.0,
+            's' => amount,
+            _ => return None,
+        };
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    wait_from_secs(total)
+}
+
+/// `secs` as a wait of at most [`MAX_RATE_LIMIT_WAIT`], or `None` when it is negative or
+/// not a finite number.
+fn wait_from_secs(secs: f64) -> Option<Duration> {
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    Some(Duration::from_secs_f64(
+        secs.min(MAX_RATE_LIMIT_WAIT.as_secs_f64()),
+    ))
+}
+
+async fn send_once(
+    provider: &str,
+    request: reqwest::RequestBuilder,
+) -> std::result::Result<reqwest::Response, FailedAttempt> {
+    let resp = request.send().await.map_err(|err| FailedAttempt {
+        error: if err.is_timeout() {
+            AgnoError::Timeout {
+                provider: provider.to_string(),
+                message: err.to_string(),
+            }
+        } else {
+            AgnoError::Transport {
+                provider: provider.to_string(),
+                message: err.to_string(),
+            }
+        },
+        retryable: true,
+    })?;
+    let status = resp.status();
+    if status.is_success() {
+        return Ok(resp);
+    }
+    let headers = resp.headers().clone();
+    let body = resp.text().await.unwrap_or_default();
+    let error = coalesce_error(status, &headers, &body, provider);
+    Err(FailedAttempt {
+        retryable: error.is_retryable(),
+        error,
+    })
+}
+
+/// Send `request` once, classifying failures like the model clients do.
+pub(crate) async fn send_request(
+    provider: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    send_once(provider, request)
+        .await
+        .map_err(|failed| failed.error)
+}
+
+/// Send `request`, retrying 429s, 5xx responses and transport errors when a retry policy
+/// is configured. Other client errors fail immediately.
+async fn send_with_retry(
+    retry: &HttpRetry,
+    provider: &str,
+    request: reqwest::RequestBuilder,
+) -> Result<reqwest::Response> {
+    if let Some(policy) = &retry.policy {
+        let attempt = |_| {
+            let attempt = request.try_clone();
+            async move {
+                let Some(attempt) = attempt else {
+                    return Err((
+                        AgnoError::LanguageModel(format!(
+                            "{provider} request body cannot be retried"
+                        )),
+                        RetryDecision::Abort,
+                    ));
+                };
+                send_once(provider, attempt).await.map_err(|failed| {
+                    let decision = if failed.retryable {
+                        RetryDecision::Retry
+                    } else {
+                        RetryDecision::Abort
+                    };
+                    (failed.error, decision)
+                })
+            }
+        };
+        #[cfg(feature = "telemetry")]
+        return policy
+            .retry_classified(
+                attempt,
+                retry.telemetry.as_ref(),
+                TelemetryLabels::default().with_tool(provider),
+            )
+            .await;
+        #[cfg(not(feature = "telemetry"))]
+        return policy
+            .run(attempt, |attempt, err| {
+                tracing::warn!("{provider} attempt {attempt} failed: {err}")
+            })
+            .await;
+    }
+    send_once(provider, request)
+        .await
+        .map_err(|failed| failed.error)
+}
+
 fn serialize_tool_arguments(args: &Value) -> String {
     serde_json::to_string(args).unwrap_or_else(|_| args.to_string())
 }
 
+/// Transport settings for the HTTP model clients, for networks that need a proxy or an
+/// internal TLS root. Build one `reqwest::Client` from them and hand it to each client
+/// with `with_http_client`; clients left alone keep their built-in timeouts.
+#[derive(Debug, Clone, Default)]
+pub struct HttpSettings {
+    /// Proxy URL used for every request, e.g. `http://proxy.internal:3128`.
+    pub proxy: Option<String>,
+    /// PEM-encoded certificates (or bundles) trusted in addition to the built-in roots.
+    pub root_certificates: Vec<Vec<u8>>,
+    pub connect_timeout: Option<Duration>,
+    /// Limit on a whole request, including reading the body. `None` means no limit.
+    pub timeout: Option<Duration>,
+    pub user_agent: Option<String>,
+}
+
+impl HttpSettings {
+    pub fn with_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxy = Some(url.into());
+        self
+    }
+
+    pub fn with_root_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Build a client applying these settings. It is cheap to clone and can be shared.
+    pub fn build(&self) -> Result<reqwest::Client> {
+        let invalid =
+            |err: reqwest::Error| AgnoError::LanguageModel(format!("http client error: {err}"));
+        let mut builder = reqwest::Client::builder();
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy.as_str()).map_err(invalid)?);
+        }
+        for pem in &self.root_certificates {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem).map_err(invalid)?;
+            if certificates.is_empty() {
+                return Err(AgnoError::LanguageModel(
+                    "http client error: no PEM certificate found in root certificate".into(),
+                ));
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent.as_str());
+        }
+        builder.build().map_err(invalid)
+    }
+}
+
 #[derive(Clone)]
 pub struct OpenAIClient {
     http: reqwest::Client,
@@ -51,6 +554,10 @@ pub struct OpenAIClient {
     api_key: String,
     base_url: String,
     organization: Option<String>,
+    retry: HttpRetry,
+    output_format: OutputFormat,
+    images: ImageInlining,
+    reasoning_effort: Option<ReasoningEffort>,
 }
 
 impl OpenAIClient {
@@ -61,6 +568,10 @@ impl OpenAIClient {
             api_key: api_key.into(),
             base_url: "https://api.openai.com/v1".to_string(),
             organization: None,
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
+            images: ImageInlining::default(),
+            reasoning_effort: None,
         }
     }
 
@@ -103,75 +614,57 @@ impl OpenAIClient {
                 .organization
                 .clone()
                 .or_else(|| cfg.organization.clone()),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
+            images: ImageInlining::default(),
+            reasoning_effort: None,
         })
     }
 
-    fn to_openai_messages(&self, messages: &[Message]) -> Vec<OpenAiMessage> {
-        let mut built = Vec::new();
-        for message in messages {
-            let role = match message.role {
-                Role::System => "system",
-                Role::User => "user",
-                Role::Assistant => "assistant",
-                Role::Tool => "tool",
-            }
-            .to_string();
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
 
-            let mut tool_calls = None;
-            if let Some(call) = &message.tool_call {
-                tool_calls = Some(vec![OpenAiToolCall {
-                    id: call.id.clone(),
-                    r#type: "function".to_string(),
-                    function: OpenAiFunctionCall {
-                        name: call.name.clone(),
-                        arguments: serialize_tool_arguments(&call.arguments),
-                    },
-                }]);
-            }
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
 
-            let content = if message.role == Role::Tool {
-                message
-                    .tool_result
-                    .as_ref()
-                    .map(|result| serialize_tool_arguments(&result.output))
-                    .or_else(|| Some(message.content.clone()))
-            } else {
-                Some(message.content.clone())
-            };
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
 
-            let tool_call_id = message
-                .tool_result
-                .as_ref()
-                .and_then(|result| result.tool_call_id.clone());
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
 
-            built.push(OpenAiMessage {
-                role,
-                content,
-                tool_call_id,
-                tool_calls,
-            });
-        }
-        built
+    /// Download remote image attachments and send them inline as base64, for endpoints
+    /// that cannot fetch URLs themselves.
+    pub fn with_inline_remote_images(mut self, inline: bool) -> Self {
+        self.images.remote = inline;
+        self
     }
 
-    fn to_openai_tools(&self, tools: &[ToolDescription]) -> Option<Vec<OpenAiTool>> {
-        if tools.is_empty() {
-            return None;
-        }
+    /// Inline `file:` image attachments read from inside `sandbox`. Without a sandbox they
+    /// are never read, since their bytes would be sent to the provider.
+    pub fn with_local_images(mut self, sandbox: FsSandbox) -> Self {
+        self.images.local = Some(sandbox);
+        self
+    }
 
-        Some(
-            tools
-                .iter()
-                .map(|tool| OpenAiTool {
-                    r#type: "function".to_string(),
-                    function: OpenAiFunction {
-                        name: tool.name.clone(),
-                        description: Some(tool.description.clone()),
-                        parameters: tool.parameters.clone(),
-                    },
-                })
-                .collect(),
-        )
+    /// Send `reasoning_effort` with every request, for reasoning models such as o3.
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
     }
 }
 
@@ -183,13 +676,74 @@ impl LanguageModel for OpenAIClient {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion> {
-        let payload = json!({
-            "model": self.model,
-            "messages": self.to_openai_messages(messages),
-            "tools": self.to_openai_tools(tools),
-            "tool_choice": if tools.is_empty() { Value::Null } else { Value::String("auto".to_string()) },
-            "stream": stream,
-        });
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
+
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
+
+    async fn complete_chat_streaming(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        format: &OutputFormat,
+        deltas: mpsc::UnboundedSender<ToolCallDelta>,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(
+            messages,
+            tools,
+            true,
+            format,
+            &CompletionOptions::default(),
+            Some(&deltas),
+        )
+        .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        self.send_chat(messages, tools, stream, format, options, deltas.as_ref())
+            .await
+    }
+}
+
+impl OpenAIClient {
+    async fn send_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<&mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let messages = self.images.apply(messages).await;
+        let format = requested_format(format, &self.output_format);
+        let payload = self.chat_payload(&messages, tools, stream, format, options);
 
         let mut builder = self
             .http
@@ -201,119 +755,304 @@ impl LanguageModel for OpenAIClient {
         if let Some(org) = &self.organization {
             builder = builder.header("OpenAI-Organization", org);
         }
-        let resp = builder
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|err| AgnoError::LanguageModel(format!("OpenAI request error: {err}")))?;
+        let resp = send_with_retry(&self.retry, "openai", builder.json(&payload)).await?;
+        read_openai_completion(resp, stream, "openai", "OpenAI", &self.model, deltas).await
+    }
 
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "openai"));
+    fn chat_payload(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+    ) -> Value {
+        let mut payload = openai_chat_payload(messages, tools, stream, format, options);
+        payload["model"] = json!(self.model);
+        if let Some(effort) = self.reasoning_effort {
+            payload["reasoning_effort"] = json!(effort.openai_value());
         }
+        payload
+    }
+}
+
+fn to_openai_messages(messages: &[Message]) -> Vec<OpenAiMessage> {
+    let mut built = Vec::new();
+    for message in messages {
+        let role = match message.role {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+        }
+        .to_string();
+
+        let mut tool_calls = None;
+        if let Some(call) = &message.tool_call {
+            tool_calls = Some(vec![OpenAiToolCall {
+                id: call.id.clone(),
+                r#type: "function".to_string(),
+                function: OpenAiFunctionCall {
+                    name: call.name.clone(),
+                    arguments: serialize_tool_arguments(&call.arguments),
+                },
+            }]);
+        }
+
+        let content = if message.role == Role::Tool {
+            message
+                .tool_result
+                .as_ref()
+                .map(|result| serialize_tool_arguments(&result.output))
+                .or_else(|| Some(message.content.clone()))
+                .map(OpenAiContent::Text)
+        } else {
+            Some(openai_content_with_images(message))
+        };
+
+        let tool_call_id = message
+            .tool_result
+            .as_ref()
+            .and_then(|result| result.tool_call_id.clone());
+
+        built.push(OpenAiMessage {
+            role,
+            content,
+            tool_call_id,
+            tool_calls,
+        });
+    }
+    built
+}
+
+fn to_openai_tools(tools: &[ToolDescription]) -> Option<Vec<OpenAiTool>> {
+    if tools.is_empty() {
+        return None;
+    }
+
+    Some(
+        tools
+            .iter()
+            .map(|tool| OpenAiTool {
+                r#type: "function".to_string(),
+                function: OpenAiFunction {
+                    name: tool.name.clone(),
+                    description: Some(tool.description.clone()),
+                    parameters: tool.parameters.clone(),
+                },
+            })
+            .collect(),
+    )
+}
+
+/// Chat completions body shared by OpenAI and Azure OpenAI. Azure routes by deployment,
+/// so callers add `model` themselves where the endpoint needs it.
+fn openai_chat_payload(
+    messages: &[Message],
+    tools: &[ToolDescription],
+    stream: bool,
+    format: &OutputFormat,
+    options: &CompletionOptions,
+) -> Value {
+    let mut payload = json!({
+        "messages": to_openai_messages(messages),
+        "tools": to_openai_tools(tools),
+        "tool_choice": if tools.is_empty() { Value::Null } else { options.tool_choice.openai_value() },
+        "stream": stream,
+    });
+    if let Some(response_format) = format.response_format() {
+        payload["response_format"] = response_format;
+    }
+    if !options.stop.is_empty() {
+        payload["stop"] = json!(options.stop);
+    }
+    payload
+}
+
+/// Read a chat completions response, accumulating server-sent deltas when `stream` is set.
+async fn read_openai_completion(
+    resp: reqwest::Response,
+    stream: bool,
+    provider: &str,
+    label: &str,
+    model: &str,
+    deltas: Option<&mpsc::UnboundedSender<ToolCallDelta>>,
+) -> Result<ModelCompletion> {
+    if stream {
+        return read_openai_stream(resp.bytes_stream(), label, deltas).await;
+    }
+
+    let body: OpenAiResponse = resp
+        .json()
+        .await
+        .map_err(|err| AgnoError::InvalidResponse {
+            provider: provider.into(),
+            message: format!("{label} response parse error: {err}"),
+        })?;
+
+    let usage = body.usage.as_ref().map(|usage| TokenUsage {
+        provider: provider.into(),
+        model: model.to_string(),
+        input_tokens: usage.prompt_tokens,
+        output_tokens: usage.completion_tokens,
+    });
+    let first = body
+        .choices
+        .into_iter()
+        .next()
+        .ok_or_else(|| AgnoError::InvalidResponse {
+            provider: provider.into(),
+            message: format!("{label} returned no choices"),
+        })?;
+
+    let mut tool_calls = Vec::new();
+    if let Some(calls) = first.message.tool_calls {
+        for call in calls {
+            let args = serde_json::from_str(&call.function.arguments)
+                .unwrap_or_else(|_| Value::String(call.function.arguments.clone()));
+            tool_calls.push(ToolCall {
+                id: call.id,
+                name: call.function.name,
+                arguments: args,
+            });
+        }
+    }
+
+    Ok(ModelCompletion {
+        content: first.message.content,
+        tool_calls,
+        usage,
+    })
+}
+
+/// Splits a server-sent event stream into `data:` payloads. Events can be split across
+/// network chunks, so bytes after the last newline are held until a later chunk
+/// completes the line.
+#[derive(Default)]
+struct SseBuffer {
+    pending: Vec<u8>,
+}
+
+impl SseBuffer {
+    /// Append `chunk` and return the payloads of the `data:` lines it completed.
+    fn feed(&mut self, chunk: &[u8]) -> Vec<String> {
+        self.pending.extend_from_slice(chunk);
+        let mut payloads = Vec::new();
+        while let Some(end) = self.pending.iter().position(|byte| *byte == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            let line = String::from_utf8_lossy(&line);
+            if let Some(data) = line.trim().strip_prefix("data:") {
+                let data = data.trim();
+                if !data.is_empty() && data != "[DONE]" {
+                    payloads.push(data.to_string());
+                }
+            }
+        }
+        payloads
+    }
+
+    /// Return the payload of a final line that ended without a newline.
+    fn finish(&mut self) -> Vec<String> {
+        self.feed(b"\n")
+    }
+}
 
-        if stream {
-            let mut content = String::new();
-            let mut tool_calls: HashMap<String, OpenAiToolCallState> = HashMap::new();
-            let mut stream = resp.bytes_stream();
-            while let Some(chunk) = stream.next().await {
+/// Assemble a completion from OpenAI-style server-sent events, reporting tool calls to
+/// `deltas` as their names and argument fragments arrive.
+async fn read_openai_stream<S, B, E>(
+    mut stream: S,
+    label: &str,
+    deltas: Option<&mpsc::UnboundedSender<ToolCallDelta>>,
+) -> Result<ModelCompletion>
+where
+    S: futures::Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut content = String::new();
+    let mut tool_calls: BTreeMap<usize, OpenAiToolCallState> = BTreeMap::new();
+    let mut buffer = SseBuffer::default();
+    let mut finished = false;
+    while !finished {
+        let payloads = match stream.next().await {
+            Some(chunk) => {
                 let chunk = chunk.map_err(|err| {
-                    AgnoError::LanguageModel(format!("OpenAI stream error: {err}"))
+                    AgnoError::LanguageModel(format!("{label} stream error: {err}"))
                 })?;
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    if !line.starts_with("data: ") {
-                        continue;
+                buffer.feed(chunk.as_ref())
+            }
+            None => {
+                finished = true;
+                buffer.finish()
+            }
+        };
+        for data in payloads {
+            let parsed: OpenAiStreamChunk = serde_json::from_str(&data).map_err(|err| {
+                AgnoError::LanguageModel(format!("{label} stream parse error `{data}`: {err}"))
+            })?;
+
+            for choice in parsed.choices {
+                if let Some(delta_content) = choice.delta.content {
+                    content.push_str(&delta_content);
+                }
+                for delta_call in choice.delta.tool_calls.unwrap_or_default() {
+                    let index = delta_call.index.unwrap_or(tool_calls.len());
+                    let state = tool_calls.entry(index).or_default();
+                    if delta_call.id.is_some() {
+                        state.id = delta_call.id;
                     }
-                    let data = line.trim_start_matches("data: ").trim();
-                    if data == "[DONE]" {
+                    let Some(function) = delta_call.function else {
                         continue;
-                    }
-                    let parsed: OpenAiStreamChunk = serde_json::from_str(data).map_err(|err| {
-                        AgnoError::LanguageModel(format!(
-                            "OpenAI stream parse error `{data}`: {err}"
-                        ))
-                    })?;
-
-                    for choice in parsed.choices {
-                        if let Some(delta_content) = choice.delta.content {
-                            content.push_str(&delta_content);
-                        }
-                        if let Some(calls) = choice.delta.tool_calls {
-                            for delta_call in calls {
-                                let id = delta_call
-                                    .id
-                                    .clone()
-                                    .unwrap_or_else(|| format!("call_{}", tool_calls.len()));
-                                let state = tool_calls.entry(id.clone()).or_default();
-                                if let Some(function) = delta_call.function {
-                                    if let Some(name) = function.name {
-                                        state.name = Some(name);
-                                    }
-                                    if let Some(args) = function.arguments {
-                                        state.arguments.push_str(&args);
-                                    }
-                                }
-                                state.id = Some(id);
+                    };
+                    if let Some(name) = function.name {
+                        if state.name.is_none() {
+                            if let Some(deltas) = deltas {
+                                let _ = deltas.send(ToolCallDelta::Started {
+                                    index,
+                                    id: state.id.clone(),
+                                    name: name.clone(),
+                                });
                             }
                         }
+                        state.name = Some(name);
+                    }
+                    if let Some(args) = function.arguments.filter(|args| !args.is_empty()) {
+                        state.arguments.push_str(&args);
+                        if let Some(deltas) = deltas {
+                            let _ = deltas.send(ToolCallDelta::Arguments {
+                                index,
+                                fragment: args,
+                            });
+                        }
                     }
                 }
             }
-
-            let calls: Vec<ToolCall> = tool_calls
-                .into_values()
-                .filter_map(|state| {
-                    let name = state.name?;
-                    let args = serde_json::from_str(&state.arguments)
-                        .unwrap_or_else(|_| Value::String(state.arguments.clone()));
-                    Some(ToolCall {
-                        id: state.id,
-                        name,
-                        arguments: args,
-                    })
-                })
-                .collect();
-
-            return Ok(ModelCompletion {
-                content: if content.is_empty() {
-                    None
-                } else {
-                    Some(content)
-                },
-                tool_calls: calls,
-            });
-        }
-
-        let body: OpenAiResponse = resp.json().await.map_err(|err| {
-            AgnoError::LanguageModel(format!("OpenAI response parse error: {err}"))
-        })?;
-
-        let first = body
-            .choices
-            .into_iter()
-            .next()
-            .ok_or_else(|| AgnoError::LanguageModel("OpenAI returned no choices".into()))?;
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = first.message.tool_calls {
-            for call in calls {
-                let args = serde_json::from_str(&call.function.arguments)
-                    .unwrap_or_else(|_| Value::String(call.function.arguments.clone()));
-                tool_calls.push(ToolCall {
-                    id: call.id,
-                    name: call.function.name,
-                    arguments: args,
-                });
-            }
         }
+    }
 
-        Ok(ModelCompletion {
-            content: first.message.content,
-            tool_calls,
+    let calls: Vec<ToolCall> = tool_calls
+        .into_values()
+        .filter_map(|state| {
+            let name = state.name?;
+            let args = serde_json::from_str(&state.arguments)
+                .unwrap_or_else(|_| Value::String(state.arguments.clone()));
+            Some(ToolCall {
+                id: state.id,
+                name,
+                arguments: args,
+            })
         })
-    }
+        .collect();
+
+    Ok(ModelCompletion {
+        content: if content.is_empty() {
+            None
+        } else {
+            Some(content)
+        },
+        tool_calls: calls,
+        usage: None,
+    })
 }
 
 #[derive(Clone)]
@@ -322,6 +1061,11 @@ pub struct AnthropicClient {
     model: String,
     api_key: String,
     endpoint: String,
+    retry: HttpRetry,
+    images: ImageInlining,
+    reasoning_effort: Option<ReasoningEffort>,
+    surface_thinking: bool,
+    max_output_tokens: u32,
 }
 
 impl AnthropicClient {
@@ -347,28 +1091,33 @@ impl AnthropicClient {
             model: cfg.model.clone(),
             api_key,
             endpoint,
+            retry: HttpRetry::default(),
+            images: ImageInlining::default(),
+            reasoning_effort: None,
+            surface_thinking: false,
+            max_output_tokens: ANTHROPIC_MAX_OUTPUT_TOKENS,
         })
     }
 
+    /// Native tool calls (those carrying an id) go out as `tool_use` blocks and their
+    /// results as `tool_result` blocks in a user turn, as the Messages API requires.
     fn to_messages(&self, messages: &[Message]) -> Vec<AnthropicMessage> {
         messages
             .iter()
-            .filter_map(|message| match message.role {
-                Role::System => None,
-                Role::User | Role::Assistant | Role::Tool => Some(AnthropicMessage {
-                    role: match message.role {
-                        Role::User => "user",
-                        Role::Assistant | Role::Tool => "assistant",
-                        Role::System => unreachable!(),
-                    }
-                    .to_string(),
-                    content: vec![AnthropicContentBlock {
-                        r#type: "text".to_string(),
-                        text: Some(message.content.clone()),
-                        name: None,
-                        input_schema: None,
-                    }],
-                }),
+            .filter_map(|message| {
+                let (role, content) = match message.role {
+                    Role::System => return None,
+                    Role::User => ("user", anthropic_content_with_images(message)),
+                    Role::Assistant => ("assistant", anthropic_assistant_content(message)),
+                    Role::Tool => match anthropic_tool_result(message) {
+                        Some(block) => ("user", vec![block]),
+                        None => ("assistant", anthropic_content_with_images(message)),
+                    },
+                };
+                Some(AnthropicMessage {
+                    role: role.to_string(),
+                    content,
+                })
             })
             .collect()
     }
@@ -391,102 +1140,324 @@ impl AnthropicClient {
                 .collect(),
         )
     }
-}
 
-#[async_trait]
-impl LanguageModel for AnthropicClient {
-    async fn complete_chat(
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Download remote image attachments and send them as base64 `image` blocks instead
+    /// of URL sources.
+    pub fn with_inline_remote_images(mut self, inline: bool) -> Self {
+        self.images.remote = inline;
+        self
+    }
+
+    /// Send `file:` image attachments read from inside `sandbox` as base64 `image` blocks.
+    /// Without a sandbox they are never read.
+    pub fn with_local_images(mut self, sandbox: FsSandbox) -> Self {
+        self.images.local = Some(sandbox);
+        self
+    }
+
+    /// Enable extended thinking with the effort's token budget. `max_tokens` grows to
+    /// leave room for the answer after the thinking, up to the output limit.
+    pub fn with_reasoning_effort(mut self, effort: ReasoningEffort) -> Self {
+        self.reasoning_effort = Some(effort);
+        self
+    }
+
+    /// Put the model's thinking at the start of replies inside `<thinking>` tags, where an
+    /// agent using the default [`HiddenReasoning`](crate::reasoning::HiddenReasoning)
+    /// reports it as reasoning and keeps it out of the reply. Otherwise thinking is
+    /// dropped.
+    pub fn with_surfaced_thinking(mut self, surface: bool) -> Self {
+        self.surface_thinking = surface;
+        self
+    }
+
+    /// Cap `max_tokens` at `limit`, the model's output limit. Defaults to 64,000; older
+    /// models such as Claude 3.5 allow 8,192.
+    pub fn with_max_output_tokens(mut self, limit: u32) -> Self {
+        self.max_output_tokens = limit;
+        self
+    }
+
+    /// Anthropic rejects a forced tool choice while extended thinking is on, so that
+    /// combination is an error here rather than at the API.
+    fn payload(
         &self,
         messages: &[Message],
         tools: &[ToolDescription],
         stream: bool,
-    ) -> Result<ModelCompletion> {
+        options: &CompletionOptions,
+    ) -> Result<Value> {
+        if let (Some(_), ToolChoice::Function(name)) = (self.reasoning_effort, &options.tool_choice)
+        {
+            return Err(AgnoError::LanguageModel(format!(
+                "Anthropic cannot force a call to tool `{name}` while extended thinking is enabled"
+            )));
+        }
         let system = messages
             .iter()
             .find(|m| m.role == Role::System)
             .map(|m| m.content.clone());
-        let payload = json!({
+        let mut payload = json!({
             "model": self.model,
             "system": system,
             "messages": self.to_messages(messages),
             "tools": self.to_tools(tools),
             "stream": stream,
+            "max_tokens": ANTHROPIC_ANSWER_TOKENS.min(self.max_output_tokens),
         });
+        if !tools.is_empty() {
+            payload["tool_choice"] = options.tool_choice.anthropic_value();
+        }
+        if !options.stop.is_empty() {
+            payload["stop_sequences"] = json!(options.stop);
+        }
+        if let Some(effort) = self.reasoning_effort {
+            let max_tokens = effort
+                .budget_tokens()
+                .saturating_add(ANTHROPIC_ANSWER_TOKENS)
+                .min(self.max_output_tokens);
+            let budget = max_tokens
+                .saturating_sub(ANTHROPIC_ANSWER_TOKENS)
+                .max(1_024);
+            payload["thinking"] = json!({"type": "enabled", "budget_tokens": budget});
+            payload["max_tokens"] = json!(max_tokens);
+        }
+        Ok(payload)
+    }
+
+    /// The reply text, led by the thinking when it is surfaced.
+    fn reply(&self, thinking: String, text: String) -> Option<String> {
+        let content = if self.surface_thinking && !thinking.is_empty() {
+            format!("<thinking>{thinking}</thinking>\n{text}")
+        } else {
+            text
+        };
+        (!content.is_empty()).then_some(content)
+    }
+}
+
+#[async_trait]
+impl LanguageModel for AnthropicClient {
+    async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(messages, tools, stream, &CompletionOptions::default(), None)
+            .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.send_chat(
+            messages,
+            tools,
+            stream || deltas.is_some(),
+            options,
+            deltas.as_ref(),
+        )
+        .await
+    }
+}
+
+impl AnthropicClient {
+    async fn send_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        options: &CompletionOptions,
+        deltas: Option<&mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let messages = self.images.apply(messages).await;
+        let payload = self.payload(&messages, tools, stream, options)?;
 
-        let resp = self
+        let request = self
             .http
             .post(&self.endpoint)
             .header("x-api-key", &self.api_key)
             .header("anthropic-version", "2023-06-01")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|err| AgnoError::LanguageModel(format!("Anthropic request error: {err}")))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "anthropic"));
-        }
+            .json(&payload);
+        let resp = send_with_retry(&self.retry, "anthropic", request).await?;
 
         if stream {
-            let mut content = String::new();
-            let mut stream = resp.bytes_stream();
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|err| {
-                    AgnoError::LanguageModel(format!("Anthropic stream error: {err}"))
-                })?;
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    if !line.starts_with("data: ") {
-                        continue;
-                    }
-                    let data = line.trim_start_matches("data: ").trim();
-                    if data == "[DONE]" || data.is_empty() {
-                        continue;
-                    }
-                    let parsed: AnthropicStreamChunk =
-                        serde_json::from_str(data).map_err(|err| {
-                            AgnoError::LanguageModel(format!(
-                                "Anthropic stream parse error `{data}`: {err}"
-                            ))
-                        })?;
-                    if let Some(text) = parsed.delta.text {
-                        content.push_str(&text);
-                    }
-                }
-            }
-
+            let (thinking, text, tool_calls) =
+                read_anthropic_stream(resp.bytes_stream(), deltas).await?;
             return Ok(ModelCompletion {
-                content: if content.is_empty() {
-                    None
-                } else {
-                    Some(content)
-                },
-                tool_calls: Vec::new(),
+                content: self.reply(thinking, text),
+                tool_calls,
+                usage: None,
             });
         }
 
         let parsed: AnthropicResponse = resp.json().await.map_err(|err| {
-            AgnoError::LanguageModel(format!("Anthropic response parse error: {err}"))
+            AgnoError::InvalidResponse {
+                provider: "anthropic".into(),
+                message: format!("Anthropic response parse error: {err}"),
+            }
         })?;
 
+        let thinking = parsed
+            .content
+            .iter()
+            .filter_map(|block| block.thinking.clone())
+            .collect::<Vec<String>>()
+            .join("\n\n");
         let content = parsed
             .content
             .iter()
             .filter_map(|block| block.text.clone())
             .collect::<Vec<String>>()
             .join("");
+        let usage = parsed.usage.map(|usage| TokenUsage {
+            provider: "anthropic".into(),
+            model: self.model.clone(),
+            input_tokens: usage.input_tokens,
+            output_tokens: usage.output_tokens,
+        });
+
+        let tool_calls = parsed
+            .content
+            .iter()
+            .filter(|block| block.r#type == "tool_use")
+            .map(|block| ToolCall {
+                id: block.id.clone(),
+                name: block.name.clone().unwrap_or_default(),
+                arguments: block.input.clone().unwrap_or_else(|| json!({})),
+            })
+            .collect();
 
         Ok(ModelCompletion {
-            content: if content.is_empty() {
-                None
+            content: self.reply(thinking, content),
+            tool_calls,
+            usage,
+        })
+    }
+}
+
+/// Collect the thinking, text and `tool_use` blocks of an Anthropic server-sent event
+/// stream, reporting tool calls to `deltas` as their names and input fragments arrive.
+async fn read_anthropic_stream<S, B, E>(
+    mut stream: S,
+    deltas: Option<&mpsc::UnboundedSender<ToolCallDelta>>,
+) -> Result<(String, String, Vec<ToolCall>)>
+where
+    S: futures::Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut thinking = String::new();
+    let mut content = String::new();
+    let mut tool_calls: BTreeMap<usize, OpenAiToolCallState> = BTreeMap::new();
+    let mut buffer = SseBuffer::default();
+    let mut finished = false;
+    while !finished {
+        let payloads = match stream.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(|err| {
+                    AgnoError::LanguageModel(format!("Anthropic stream error: {err}"))
+                })?;
+                buffer.feed(chunk.as_ref())
+            }
+            None => {
+                finished = true;
+                buffer.finish()
+            }
+        };
+        for data in payloads {
+            let parsed: AnthropicStreamChunk = serde_json::from_str(&data).map_err(|err| {
+                AgnoError::LanguageModel(format!("Anthropic stream parse error `{data}`: {err}"))
+            })?;
+            if let Some(block) = parsed
+                .content_block
+                .filter(|block| block.r#type == "tool_use")
+            {
+                let name = block.name.unwrap_or_default();
+                if let Some(deltas) = deltas {
+                    let _ = deltas.send(ToolCallDelta::Started {
+                        index: parsed.index,
+                        id: block.id.clone(),
+                        name: name.clone(),
+                    });
+                }
+                tool_calls.insert(
+                    parsed.index,
+                    OpenAiToolCallState {
+                        id: block.id,
+                        name: Some(name),
+                        arguments: String::new(),
+                    },
+                );
+            }
+            let Some(delta) = parsed.delta else {
+                continue;
+            };
+            if let Some(text) = delta.text {
+                content.push_str(&text);
+            }
+            if let Some(text) = delta.thinking {
+                thinking.push_str(&text);
+            }
+            if let Some(fragment) = delta.partial_json.filter(|json| !json.is_empty()) {
+                if let Some(state) = tool_calls.get_mut(&parsed.index) {
+                    state.arguments.push_str(&fragment);
+                    if let Some(deltas) = deltas {
+                        let _ = deltas.send(ToolCallDelta::Arguments {
+                            index: parsed.index,
+                            fragment,
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    // A call without input fragments was made with no arguments.
+    let calls = tool_calls
+        .into_values()
+        .map(|state| ToolCall {
+            id: state.id,
+            name: state.name.unwrap_or_default(),
+            arguments: if state.arguments.is_empty() {
+                json!({})
             } else {
-                Some(content)
+                serde_json::from_str(&state.arguments)
+                    .unwrap_or_else(|_| Value::String(state.arguments.clone()))
             },
-            tool_calls: Vec::new(),
         })
-    }
+        .collect();
+    Ok((thinking, content, calls))
 }
 
 #[derive(Clone)]
@@ -495,52 +1466,169 @@ pub struct GeminiClient {
     model: String,
     api_key: String,
     endpoint: String,
+    retry: HttpRetry,
 }
 
-impl GeminiClient {
-    pub fn from_config(cfg: &ModelConfig) -> Result<Self> {
-        let api_key = cfg
-            .gemini
-            .api_key
-            .clone()
-            .or_else(|| cfg.api_key.clone())
-            .ok_or_else(|| {
-                AgnoError::LanguageModel("missing Gemini API key in model config".into())
-            })?;
-        let endpoint = cfg
-            .gemini
-            .endpoint
-            .clone()
-            .unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1beta".to_string());
-        Ok(Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(60))
-                .build()
-                .map_err(|err| AgnoError::LanguageModel(format!("http client error: {err}")))?,
-            model: cfg.model.clone(),
-            api_key,
-            endpoint,
-        })
+impl GeminiClient {
+    pub fn from_config(cfg: &ModelConfig) -> Result<Self> {
+        let api_key = cfg
+            .gemini
+            .api_key
+            .clone()
+            .or_else(|| cfg.api_key.clone())
+            .ok_or_else(|| {
+                AgnoError::LanguageModel("missing Gemini API key in model config".into())
+            })?;
+        let endpoint = cfg
+            .gemini
+            .endpoint
+            .clone()
+            .unwrap_or_else(|| "https://generativelanguage.googleapis.com/v1beta".to_string());
+        Ok(Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .map_err(|err| AgnoError::LanguageModel(format!("http client error: {err}")))?,
+            model: cfg.model.clone(),
+            api_key,
+            endpoint,
+            retry: HttpRetry::default(),
+        })
+    }
+
+    /// Gemini only accepts `user` and `model` turns; system prompts are sent separately via
+    /// [`GeminiClient::system_instruction`].
+    fn to_contents(&self, messages: &[Message]) -> Vec<GeminiMessage> {
+        messages
+            .iter()
+            .filter_map(|message| {
+                let (role, part) = match message.role {
+                    Role::System => return None,
+                    Role::User => ("user", GeminiPart::text(&message.content)),
+                    Role::Assistant => match &message.tool_call {
+                        Some(call) => ("model", GeminiPart::function_call(call)),
+                        None => ("model", GeminiPart::text(&message.content)),
+                    },
+                    Role::Tool => ("user", GeminiPart::function_response(message)),
+                };
+                Some(GeminiMessage {
+                    role: role.to_string(),
+                    parts: vec![part],
+                })
+            })
+            .collect()
+    }
+
+    fn system_instruction(&self, messages: &[Message]) -> Option<GeminiSystemInstruction> {
+        let system: Vec<&str> = messages
+            .iter()
+            .filter(|m| m.role == Role::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        if system.is_empty() {
+            return None;
+        }
+        Some(GeminiSystemInstruction {
+            parts: vec![GeminiPart::text(system.join("\n\n"))],
+        })
+    }
+
+    fn to_tools(&self, tools: &[ToolDescription]) -> Option<Value> {
+        if tools.is_empty() {
+            return None;
+        }
+        let declarations: Vec<Value> = tools
+            .iter()
+            .map(|tool| {
+                let mut declaration = json!({
+                    "name": tool.name,
+                    "description": tool.description,
+                });
+                if let Some(parameters) = &tool.parameters {
+                    declaration["parameters"] = parameters.clone();
+                }
+                declaration
+            })
+            .collect();
+        Some(json!([{ "functionDeclarations": declarations }]))
+    }
+
+    fn build_payload(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        options: &CompletionOptions,
+    ) -> Value {
+        let mut payload = json!({
+            "contents": self.to_contents(messages),
+        });
+        if let Some(instruction) = self.system_instruction(messages) {
+            payload["systemInstruction"] = json!(instruction);
+        }
+        if let Some(tools) = self.to_tools(tools) {
+            payload["tools"] = tools;
+            payload["toolConfig"] = json!({
+                "functionCallingConfig": match &options.tool_choice {
+                    ToolChoice::Auto => json!({ "mode": "AUTO" }),
+                    ToolChoice::None => json!({ "mode": "NONE" }),
+                    ToolChoice::Function(name) => {
+                        json!({ "mode": "ANY", "allowedFunctionNames": [name] })
+                    }
+                }
+            });
+        }
+        if !options.stop.is_empty() {
+            payload["generationConfig"] = json!({ "stopSequences": options.stop });
+        }
+        payload
+    }
+
+    fn parse_completion(response: GeminiResponse) -> ModelCompletion {
+        let mut content = String::new();
+        let mut tool_calls = Vec::new();
+        if let Some(candidate) = response.candidates.into_iter().next() {
+            for part in candidate.content.parts {
+                if let Some(text) = part.text {
+                    content.push_str(&text);
+                }
+                if let Some(call) = part.function_call {
+                    tool_calls.push(ToolCall {
+                        id: None,
+                        name: call.name,
+                        arguments: call.args,
+                    });
+                }
+            }
+        }
+        ModelCompletion {
+            content: if content.is_empty() {
+                None
+            } else {
+                Some(content)
+            },
+            tool_calls,
+            usage: None,
+        }
+    }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
     }
 
-    fn to_contents(&self, messages: &[Message]) -> Vec<GeminiMessage> {
-        messages
-            .iter()
-            .filter_map(|message| {
-                let role = match message.role {
-                    Role::User => "user",
-                    Role::Assistant => "model",
-                    Role::System => "system",
-                    Role::Tool => "user",
-                };
-                Some(GeminiMessage {
-                    role: role.to_string(),
-                    parts: vec![GeminiPart {
-                        text: message.content.clone(),
-                    }],
-                })
-            })
-            .collect()
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
     }
 }
 
@@ -549,47 +1637,64 @@ impl LanguageModel for GeminiClient {
     async fn complete_chat(
         &self,
         messages: &[Message],
-        _tools: &[ToolDescription],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(messages, tools, stream, &CompletionOptions::default())
+            .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.send_chat(messages, tools, stream || deltas.is_some(), options)
+            .await
+    }
+}
+
+impl GeminiClient {
+    async fn send_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
         _stream: bool,
+        options: &CompletionOptions,
     ) -> Result<ModelCompletion> {
-        let payload = json!({
-            "contents": self.to_contents(messages),
-        });
-        let resp = self
+        let payload = self.build_payload(messages, tools, options);
+        let request = self
             .http
             .post(format!(
                 "{}/models/{}:generateContent?key={}",
                 self.endpoint, self.model, self.api_key
             ))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|err| AgnoError::LanguageModel(format!("Gemini request error: {err}")))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "gemini"));
-        }
+            .json(&payload);
+        let resp = send_with_retry(&self.retry, "gemini", request).await?;
 
         let parsed: GeminiResponse = resp.json().await.map_err(|err| {
-            AgnoError::LanguageModel(format!("Gemini response parse error: {err}"))
+            AgnoError::InvalidResponse {
+                provider: "gemini".into(),
+                message: format!("Gemini response parse error: {err}"),
+            }
         })?;
 
-        let content = parsed
-            .candidates
-            .get(0)
-            .and_then(|cand| cand.content.parts.get(0))
-            .map(|part| part.text.clone())
-            .unwrap_or_default();
-
+        let usage = parsed.usage_metadata.as_ref().map(|usage| TokenUsage {
+            provider: "gemini".into(),
+            model: self.model.clone(),
+            input_tokens: usage.prompt_token_count,
+            output_tokens: usage.candidates_token_count,
+        });
         Ok(ModelCompletion {
-            content: if content.is_empty() {
-                None
-            } else {
-                Some(content)
-            },
-            tool_calls: Vec::new(),
+            usage,
+            ..Self::parse_completion(parsed)
         })
     }
 }
@@ -600,6 +1705,7 @@ pub struct CohereClient {
     model: String,
     api_key: String,
     endpoint: String,
+    retry: HttpRetry,
 }
 
 impl CohereClient {
@@ -612,6 +1718,7 @@ impl CohereClient {
             model: "command-a-03-2025".to_string(),
             api_key: api_key.into(),
             endpoint: "https://api.cohere.ai/v2/chat".to_string(),
+            retry: HttpRetry::default(),
         }
     }
 
@@ -642,6 +1749,7 @@ impl CohereClient {
             model: cfg.model.clone(),
             api_key,
             endpoint,
+            retry: HttpRetry::default(),
         })
     }
 
@@ -681,6 +1789,26 @@ impl CohereClient {
                 .collect(),
         )
     }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
 }
 
 #[async_trait]
@@ -691,88 +1819,91 @@ impl LanguageModel for CohereClient {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion> {
-        let payload = json!({
+        self.send_chat(messages, tools, stream, &CompletionOptions::default())
+            .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.send_chat(messages, tools, stream || deltas.is_some(), options)
+            .await
+    }
+}
+
+impl CohereClient {
+    /// Cohere can require a tool call but not name one, so forcing a tool sends only
+    /// that tool and requires a call.
+    fn payload(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        options: &CompletionOptions,
+    ) -> Value {
+        let forced: Vec<ToolDescription>;
+        let (tools, tool_choice) = match &options.tool_choice {
+            ToolChoice::Auto => (tools, None),
+            ToolChoice::None => (tools, Some("NONE")),
+            ToolChoice::Function(name) => {
+                forced = tools
+                    .iter()
+                    .filter(|tool| &tool.name == name)
+                    .cloned()
+                    .collect();
+                (&forced[..], Some("REQUIRED"))
+            }
+        };
+        let mut payload = json!({
             "model": self.model,
             "messages": self.to_messages(messages),
             "tools": self.to_tools(tools),
             "stream": stream,
         });
+        if let Some(choice) = tool_choice.filter(|_| !tools.is_empty()) {
+            payload["tool_choice"] = json!(choice);
+        }
+        if !options.stop.is_empty() {
+            payload["stop_sequences"] = json!(options.stop);
+        }
+        payload
+    }
+
+    async fn send_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        options: &CompletionOptions,
+    ) -> Result<ModelCompletion> {
+        let payload = self.payload(messages, tools, stream, options);
 
-        let resp = self
+        let request = self
             .http
             .post(&self.endpoint)
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|err| AgnoError::LanguageModel(format!("Cohere request error: {err}")))?;
-
-        if !resp.status().is_success() {
-            let status = resp.status();
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "cohere"));
-        }
+            .json(&payload);
+        let resp = send_with_retry(&self.retry, "cohere", request).await?;
 
         if stream {
-            let mut content = String::new();
-            let tool_calls_map: HashMap<String, OpenAiToolCallState> = HashMap::new();
-            let mut stream = resp.bytes_stream();
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|err| {
-                    AgnoError::LanguageModel(format!("Cohere stream error: {err}"))
-                })?;
-                let text = String::from_utf8_lossy(&chunk);
-                for line in text.lines() {
-                    if !line.starts_with("data: ") {
-                        continue;
-                    }
-                    let data = line.trim_start_matches("data: ").trim();
-                    if data == "[DONE]" || data.is_empty() {
-                        continue;
-                    }
-                    if let Ok(parsed) = serde_json::from_str::<CohereStreamChunk>(data) {
-                        if let Some(delta) = parsed.delta {
-                            if let Some(msg) = delta.message {
-                                if let Some(c) = msg.content {
-                                    if let Some(text_content) = c.get("text") {
-                                        if let Some(t) = text_content.as_str() {
-                                            content.push_str(t);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-
-            let calls: Vec<ToolCall> = tool_calls_map
-                .into_values()
-                .filter_map(|state| {
-                    let name = state.name?;
-                    let args = serde_json::from_str(&state.arguments)
-                        .unwrap_or_else(|_| Value::String(state.arguments.clone()));
-                    Some(ToolCall {
-                        id: state.id,
-                        name,
-                        arguments: args,
-                    })
-                })
-                .collect();
-
-            return Ok(ModelCompletion {
-                content: if content.is_empty() {
-                    None
-                } else {
-                    Some(content)
-                },
-                tool_calls: calls,
-            });
+            return read_cohere_stream(resp.bytes_stream()).await;
         }
 
         let body: CohereResponse = resp.json().await.map_err(|err| {
-            AgnoError::LanguageModel(format!("Cohere response parse error: {err}"))
+            AgnoError::InvalidResponse {
+                provider: "cohere".into(),
+                message: format!("Cohere response parse error: {err}"),
+            }
         })?;
 
         let content = body.message.and_then(|m| {
@@ -793,13 +1924,251 @@ impl LanguageModel for CohereClient {
         });
 
         let mut tool_calls = Vec::new();
-        if let Some(calls) = body.tool_calls {
+        if let Some(calls) = body.tool_calls {
+            for call in calls {
+                let args = serde_json::from_str(&call.function.arguments)
+                    .unwrap_or_else(|_| Value::String(call.function.arguments.clone()));
+                tool_calls.push(ToolCall {
+                    id: call.id,
+                    name: call.function.name,
+                    arguments: args,
+                });
+            }
+        }
+
+        Ok(ModelCompletion {
+            content,
+            tool_calls,
+            usage: None,
+        })
+    }
+}
+
+/// Assemble a completion from Cohere's server-sent events: text from `content-delta`,
+/// and tool calls from `tool-call-start`/`tool-call-delta` keyed by the event index.
+/// `tool-plan-delta` text is not part of the answer and is dropped, as in the
+/// non-streaming path.
+async fn read_cohere_stream<S, B, E>(mut stream: S) -> Result<ModelCompletion>
+where
+    S: futures::Stream<Item = std::result::Result<B, E>> + Unpin,
+    B: AsRef<[u8]>,
+    E: std::fmt::Display,
+{
+    let mut content = String::new();
+    let mut tool_calls: BTreeMap<usize, OpenAiToolCallState> = BTreeMap::new();
+    let mut buffer = SseBuffer::default();
+    let mut finished = false;
+    while !finished {
+        let payloads = match stream.next().await {
+            Some(chunk) => {
+                let chunk = chunk.map_err(|err| {
+                    AgnoError::LanguageModel(format!("Cohere stream error: {err}"))
+                })?;
+                buffer.feed(chunk.as_ref())
+            }
+            None => {
+                finished = true;
+                buffer.finish()
+            }
+        };
+        for data in payloads {
+            let Ok(parsed) = serde_json::from_str::<CohereStreamChunk>(&data) else {
+                continue;
+            };
+            let Some(message) = parsed.delta.and_then(|delta| delta.message) else {
+                continue;
+            };
+            if let Some(text) = message.content.as_ref().and_then(|c| c.get("text")) {
+                if let Some(text) = text.as_str() {
+                    content.push_str(text);
+                }
+            }
+            if let Some(call) = message.tool_calls {
+                let state = tool_calls
+                    .entry(parsed.index.unwrap_or_default())
+                    .or_default();
+                if call.id.is_some() {
+                    state.id = call.id;
+                }
+                if let Some(function) = call.function {
+                    if function.name.is_some() {
+                        state.name = function.name;
+                    }
+                    if let Some(arguments) = function.arguments {
+                        state.arguments.push_str(&arguments);
+                    }
+                }
+            }
+        }
+    }
+
+    let calls: Vec<ToolCall> = tool_calls
+        .into_values()
+        .filter_map(|state| {
+            let name = state.name?;
+            let args = serde_json::from_str(&state.arguments)
+                .unwrap_or_else(|_| Value::String(state.arguments.clone()));
+            Some(ToolCall {
+                id: state.id,
+                name,
+                arguments: args,
+            })
+        })
+        .collect();
+
+    Ok(ModelCompletion {
+        content: if content.is_empty() {
+            None
+        } else {
+            Some(content)
+        },
+        tool_calls: calls,
+        usage: None,
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Groq Client (OpenAI-compatible API)
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Groq client - uses OpenAI-compatible API with Groq's endpoint.
+/// Default model: llama-3.3-70b-versatile
+#[derive(Clone)]
+pub struct GroqClient {
+    http: reqwest::Client,
+    model: String,
+    api_key: String,
+    base_url: String,
+    retry: HttpRetry,
+    output_format: OutputFormat,
+}
+
+impl GroqClient {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(120))
+                .build()
+                .expect("failed to build http client"),
+            model: "llama-3.3-70b-versatile".to_string(),
+            api_key: api_key.into(),
+            base_url: "https://api.groq.com/openai/v1".to_string(),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = std::env::var("GROQ_API_KEY")
+            .map_err(|_| AgnoError::LanguageModel("GROQ_API_KEY not set".into()))?;
+        Ok(Self::new(api_key))
+    }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl LanguageModel for GroqClient {
+    async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
+
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_options(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        let format = requested_format(format, &self.output_format);
+        let body = self.payload(messages, tools, stream, format, options);
+        let request = self
+            .http
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Groq", request).await?;
+        if stream {
+            return read_openai_stream(resp.bytes_stream(), "Groq", deltas.as_ref()).await;
+        }
+
+        let json: Value = resp
+            .json()
+            .await
+            .map_err(|e| AgnoError::InvalidResponse {
+                provider: "Groq".into(),
+                message: format!("Groq parse error: {e}"),
+            })?;
+
+        let choice = &json["choices"][0]["message"];
+        let content = choice["content"].as_str().map(String::from);
+
+        let mut tool_calls = Vec::new();
+        if let Some(calls) = choice["tool_calls"].as_array() {
             for call in calls {
-                let args = serde_json::from_str(&call.function.arguments)
-                    .unwrap_or_else(|_| Value::String(call.function.arguments.clone()));
+                let name = call["function"]["name"].as_str().unwrap_or("").to_string();
+                let args_str = call["function"]["arguments"].as_str().unwrap_or("{}");
+                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
                 tool_calls.push(ToolCall {
-                    id: call.id,
-                    name: call.function.name,
+                    id: call["id"].as_str().map(String::from),
+                    name,
                     arguments: args,
                 });
             }
@@ -808,57 +2177,20 @@ impl LanguageModel for CohereClient {
         Ok(ModelCompletion {
             content,
             tool_calls,
+            usage: None,
         })
     }
 }
 
-// ─────────────────────────────────────────────────────────────────────────────
-// Groq Client (OpenAI-compatible API)
-// ─────────────────────────────────────────────────────────────────────────────
-
-/// Groq client - uses OpenAI-compatible API with Groq's endpoint.
-/// Default model: llama-3.3-70b-versatile
-#[derive(Clone)]
-pub struct GroqClient {
-    http: reqwest::Client,
-    model: String,
-    api_key: String,
-    base_url: String,
-}
-
 impl GroqClient {
-    pub fn new(api_key: impl Into<String>) -> Self {
-        Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(120))
-                .build()
-                .expect("failed to build http client"),
-            model: "llama-3.3-70b-versatile".to_string(),
-            api_key: api_key.into(),
-            base_url: "https://api.groq.com/openai/v1".to_string(),
-        }
-    }
-
-    pub fn with_model(mut self, model: impl Into<String>) -> Self {
-        self.model = model.into();
-        self
-    }
-
-    pub fn from_env() -> Result<Self> {
-        let api_key = std::env::var("GROQ_API_KEY")
-            .map_err(|_| AgnoError::LanguageModel("GROQ_API_KEY not set".into()))?;
-        Ok(Self::new(api_key))
-    }
-}
-
-#[async_trait]
-impl LanguageModel for GroqClient {
-    async fn complete_chat(
+    fn payload(
         &self,
         messages: &[Message],
         tools: &[ToolDescription],
         stream: bool,
-    ) -> Result<ModelCompletion> {
+        format: &OutputFormat,
+        options: &CompletionOptions,
+    ) -> Value {
         // Convert messages to OpenAI format
         let oai_messages: Vec<Value> = messages
             .iter()
@@ -887,6 +2219,9 @@ impl LanguageModel for GroqClient {
             "messages": oai_messages,
             "stream": stream
         });
+        if let Some(response_format) = format.response_format() {
+            body["response_format"] = response_format;
+        }
 
         if !tools.is_empty() {
             let oai_tools: Vec<Value> = tools
@@ -903,47 +2238,12 @@ impl LanguageModel for GroqClient {
                 })
                 .collect();
             body["tools"] = json!(oai_tools);
+            body["tool_choice"] = options.tool_choice.openai_value();
         }
-
-        let resp = self
-            .http
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Groq request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Groq"));
-        }
-
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Groq parse error: {e}")))?;
-
-        let choice = &json["choices"][0]["message"];
-        let content = choice["content"].as_str().map(String::from);
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = choice["tool_calls"].as_array() {
-            for call in calls {
-                let name = call["function"]["name"].as_str().unwrap_or("").to_string();
-                let args_str = call["function"]["arguments"].as_str().unwrap_or("{}");
-                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
-                tool_calls.push(ToolCall {
-                    id: call["id"].as_str().map(String::from),
-                    name,
-                    arguments: args,
-                });
-            }
+        if !options.stop.is_empty() {
+            body["stop"] = json!(options.stop);
         }
-
-        Ok(ModelCompletion { content, tool_calls })
+        body
     }
 }
 
@@ -958,6 +2258,7 @@ pub struct OllamaClient {
     http: reqwest::Client,
     model: String,
     base_url: String,
+    retry: HttpRetry,
 }
 
 impl OllamaClient {
@@ -969,6 +2270,7 @@ impl OllamaClient {
                 .expect("failed to build http client"),
             model: "llama3.1".to_string(),
             base_url: "http://localhost:11434".to_string(),
+            retry: HttpRetry::default(),
         }
     }
 
@@ -992,6 +2294,26 @@ impl OllamaClient {
         }
         client
     }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
 }
 
 impl Default for OllamaClient {
@@ -1006,8 +2328,48 @@ impl LanguageModel for OllamaClient {
         &self,
         messages: &[Message],
         tools: &[ToolDescription],
-        _stream: bool,
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(messages, tools, stream, &CompletionOptions::default())
+            .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
     ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.send_chat(messages, tools, stream || deltas.is_some(), options)
+            .await
+    }
+}
+
+impl OllamaClient {
+    /// Ollama has no `tool_choice`: forbidding tools sends none, and forcing one is an
+    /// error rather than a request the model may ignore.
+    fn payload(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        options: &CompletionOptions,
+    ) -> Result<Value> {
+        let tools = match &options.tool_choice {
+            ToolChoice::Auto => tools,
+            ToolChoice::None => &[],
+            ToolChoice::Function(name) => {
+                return Err(AgnoError::LanguageModel(format!(
+                    "Ollama cannot force a call to tool `{name}`"
+                )))
+            }
+        };
         // Convert messages to Ollama format
         let ollama_messages: Vec<Value> = messages
             .iter()
@@ -1028,7 +2390,7 @@ impl LanguageModel for OllamaClient {
         let mut body = json!({
             "model": self.model,
             "messages": ollama_messages,
-            "stream": false
+            "stream": stream
         });
 
         if !tools.is_empty() {
@@ -1047,95 +2409,311 @@ impl LanguageModel for OllamaClient {
                 .collect();
             body["tools"] = json!(ollama_tools);
         }
+        if !options.stop.is_empty() {
+            body["options"] = json!({ "stop": options.stop });
+        }
+        Ok(body)
+    }
+
+    async fn send_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        options: &CompletionOptions,
+    ) -> Result<ModelCompletion> {
+        let body = self.payload(messages, tools, stream, options)?;
+        let request = self
+            .http
+            .post(format!("{}/api/chat", self.base_url))
+            .header("Content-Type", "application/json")
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Ollama", request).await?;
+
+        if stream {
+            let mut state = OllamaStream::default();
+            let mut chunks = resp.bytes_stream();
+            while let Some(chunk) = chunks.next().await {
+                let chunk = chunk.map_err(|err| {
+                    AgnoError::LanguageModel(format!("Ollama stream error: {err}"))
+                })?;
+                state.feed(&chunk)?;
+            }
+            return state.finish();
+        }
+
+        let json: Value = resp
+            .json()
+            .await
+            .map_err(|e| AgnoError::InvalidResponse {
+                provider: "Ollama".into(),
+                message: format!("Ollama parse error: {e}"),
+            })?;
+
+        let message = &json["message"];
+        let content = message["content"].as_str().map(String::from);
+        let mut tool_calls = Vec::new();
+        push_ollama_tool_calls(message, &mut tool_calls);
+
+        Ok(ModelCompletion {
+            content,
+            tool_calls,
+            usage: None,
+        })
+    }
+}
+
+fn push_ollama_tool_calls(message: &Value, tool_calls: &mut Vec<ToolCall>) {
+    if let Some(calls) = message["tool_calls"].as_array() {
+        for call in calls {
+            let func = &call["function"];
+            let name = func["name"].as_str().unwrap_or("").to_string();
+            let args = func["arguments"].clone();
+            tool_calls.push(ToolCall {
+                id: None,
+                name,
+                arguments: args,
+            });
+        }
+    }
+}
+
+/// Accumulates Ollama's NDJSON stream: one JSON object per line, each carrying a
+/// `message.content` delta and possibly `tool_calls`, until a final `done: true`.
+/// Lines split across HTTP chunks are buffered until their newline arrives.
+#[derive(Default)]
+struct OllamaStream {
+    pending: Vec<u8>,
+    content: String,
+    tool_calls: Vec<ToolCall>,
+}
+
+impl OllamaStream {
+    fn feed(&mut self, chunk: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(chunk);
+        while let Some(end) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=end).collect();
+            self.apply_line(&line)?;
+        }
+        Ok(())
+    }
+
+    fn apply_line(&mut self, line: &[u8]) -> Result<()> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+        let parsed: Value = serde_json::from_str(line).map_err(|err| {
+            AgnoError::InvalidResponse {
+                provider: "Ollama".into(),
+                message: format!("Ollama stream parse error `{line}`: {err}"),
+            }
+        })?;
+        if let Some(error) = parsed["error"].as_str() {
+            return Err(AgnoError::LanguageModel(format!(
+                "Ollama stream error: {error}"
+            )));
+        }
+        let message = &parsed["message"];
+        if let Some(delta) = message["content"].as_str() {
+            self.content.push_str(delta);
+        }
+        push_ollama_tool_calls(message, &mut self.tool_calls);
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<ModelCompletion> {
+        // The last object may arrive without a trailing newline.
+        let rest = std::mem::take(&mut self.pending);
+        self.apply_line(&rest)?;
+        Ok(ModelCompletion {
+            content: if self.content.is_empty() {
+                None
+            } else {
+                Some(self.content)
+            },
+            tool_calls: self.tool_calls,
+            usage: None,
+        })
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Mistral AI Client
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Mistral AI client using their OpenAI-compatible API.
+/// Default model: mistral-large-latest
+#[derive(Clone)]
+pub struct MistralClient {
+    http: reqwest::Client,
+    model: String,
+    api_key: String,
+    base_url: String,
+    retry: HttpRetry,
+    output_format: OutputFormat,
+}
+
+impl MistralClient {
+    pub fn new(api_key: impl Into<String>) -> Self {
+        Self {
+            http: reqwest::Client::builder()
+                .timeout(Duration::from_secs(120))
+                .build()
+                .expect("failed to build http client"),
+            model: "mistral-large-latest".to_string(),
+            api_key: api_key.into(),
+            base_url: "https://api.mistral.ai/v1".to_string(),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
+        }
+    }
+
+    pub fn with_model(mut self, model: impl Into<String>) -> Self {
+        self.model = model.into();
+        self
+    }
+
+    pub fn from_env() -> Result<Self> {
+        let api_key = std::env::var("MISTRAL_API_KEY")
+            .map_err(|_| AgnoError::LanguageModel("MISTRAL_API_KEY not set".into()))?;
+        Ok(Self::new(api_key))
+    }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+}
+
+#[async_trait]
+impl LanguageModel for MistralClient {
+    async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
+
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_options(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
 
-        let resp = self
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        let format = requested_format(format, &self.output_format);
+        let body = self.payload(messages, tools, stream, format, options);
+        let request = self
             .http
-            .post(format!("{}/api/chat", self.base_url))
+            .post(format!("{}/chat/completions", self.base_url))
+            .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Ollama request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Ollama"));
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Mistral", request).await?;
+        if stream {
+            return read_openai_stream(resp.bytes_stream(), "Mistral", deltas.as_ref()).await;
         }
 
+        // Parse response (OpenAI-compatible format)
         let json: Value = resp
             .json()
             .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Ollama parse error: {e}")))?;
+            .map_err(|e| AgnoError::InvalidResponse {
+                provider: "Mistral".into(),
+                message: format!("Mistral parse error: {e}"),
+            })?;
 
-        let message = &json["message"];
+        let choice = json["choices"]
+            .as_array()
+            .and_then(|c| c.first())
+            .ok_or_else(|| AgnoError::InvalidResponse {
+                provider: "Mistral".into(),
+                message: "Mistral returned no choices".into(),
+            })?;
+
+        let message = &choice["message"];
         let content = message["content"].as_str().map(String::from);
 
         let mut tool_calls = Vec::new();
         if let Some(calls) = message["tool_calls"].as_array() {
             for call in calls {
+                let id = call["id"].as_str().map(String::from);
                 let func = &call["function"];
                 let name = func["name"].as_str().unwrap_or("").to_string();
-                let args = func["arguments"].clone();
+                let args_str = func["arguments"].as_str().unwrap_or("{}");
+                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
                 tool_calls.push(ToolCall {
-                    id: None,
+                    id,
                     name,
                     arguments: args,
                 });
             }
         }
 
-        Ok(ModelCompletion { content, tool_calls })
+        Ok(ModelCompletion {
+            content,
+            tool_calls,
+            usage: None,
+        })
     }
 }
 
-// ─────────────────────────────────────────────────────────────────────────────
-// Mistral AI Client
-// ─────────────────────────────────────────────────────────────────────────────
-
-/// Mistral AI client using their OpenAI-compatible API.
-/// Default model: mistral-large-latest
-#[derive(Clone)]
-pub struct MistralClient {
-    http: reqwest::Client,
-    model: String,
-    api_key: String,
-    base_url: String,
-}
-
 impl MistralClient {
-    pub fn new(api_key: impl Into<String>) -> Self {
-        Self {
-            http: reqwest::Client::builder()
-                .timeout(Duration::from_secs(120))
-                .build()
-                .expect("failed to build http client"),
-            model: "mistral-large-latest".to_string(),
-            api_key: api_key.into(),
-            base_url: "https://api.mistral.ai/v1".to_string(),
-        }
-    }
-
-    pub fn with_model(mut self, model: impl Into<String>) -> Self {
-        self.model = model.into();
-        self
-    }
-
-    pub fn from_env() -> Result<Self> {
-        let api_key = std::env::var("MISTRAL_API_KEY")
-            .map_err(|_| AgnoError::LanguageModel("MISTRAL_API_KEY not set".into()))?;
-        Ok(Self::new(api_key))
-    }
-}
-
-#[async_trait]
-impl LanguageModel for MistralClient {
-    async fn complete_chat(
+    fn payload(
         &self,
         messages: &[Message],
         tools: &[ToolDescription],
         stream: bool,
-    ) -> Result<ModelCompletion> {
+        format: &OutputFormat,
+        options: &CompletionOptions,
+    ) -> Value {
         // Convert messages to Mistral format (OpenAI-compatible)
         let mistral_messages: Vec<Value> = messages
             .iter()
@@ -1152,12 +2730,11 @@ impl LanguageModel for MistralClient {
                     "content": m.content.clone()
                 });
 
-                // Add tool_call_id for tool responses
-                if m.role == Role::Tool {
-                    if let Some(ref tc) = m.tool_call {
-                        if let Some(ref id) = tc.id {
-                            msg["tool_call_id"] = json!(id);
-                        }
+                // Tool responses carry the output and the id of the call they answer
+                if let Some(result) = m.tool_result.as_ref().filter(|_| m.role == Role::Tool) {
+                    msg["content"] = json!(serialize_tool_arguments(&result.output));
+                    if let Some(ref id) = result.tool_call_id {
+                        msg["tool_call_id"] = json!(id);
                     }
                 }
 
@@ -1185,6 +2762,9 @@ impl LanguageModel for MistralClient {
             "messages": mistral_messages,
             "stream": stream
         });
+        if let Some(response_format) = format.response_format() {
+            body["response_format"] = response_format;
+        }
 
         if !tools.is_empty() {
             let mistral_tools: Vec<Value> = tools
@@ -1201,56 +2781,12 @@ impl LanguageModel for MistralClient {
                 })
                 .collect();
             body["tools"] = json!(mistral_tools);
-            body["tool_choice"] = json!("auto");
-        }
-
-        let resp = self
-            .http
-            .post(format!("{}/chat/completions", self.base_url))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Mistral request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Mistral"));
+            body["tool_choice"] = options.tool_choice.openai_value();
         }
-
-        // Parse response (OpenAI-compatible format)
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Mistral parse error: {e}")))?;
-
-        let choice = json["choices"]
-            .as_array()
-            .and_then(|c| c.first())
-            .ok_or_else(|| AgnoError::LanguageModel("Mistral returned no choices".into()))?;
-
-        let message = &choice["message"];
-        let content = message["content"].as_str().map(String::from);
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = message["tool_calls"].as_array() {
-            for call in calls {
-                let id = call["id"].as_str().map(String::from);
-                let func = &call["function"];
-                let name = func["name"].as_str().unwrap_or("").to_string();
-                let args_str = func["arguments"].as_str().unwrap_or("{}");
-                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
-                tool_calls.push(ToolCall {
-                    id,
-                    name,
-                    arguments: args,
-                });
-            }
+        if !options.stop.is_empty() {
+            body["stop"] = json!(options.stop);
         }
-
-        Ok(ModelCompletion { content, tool_calls })
+        body
     }
 }
 
@@ -1266,6 +2802,8 @@ pub struct AzureOpenAIClient {
     api_key: String,
     deployment: String,
     api_version: String,
+    retry: HttpRetry,
+    output_format: OutputFormat,
 }
 
 impl AzureOpenAIClient {
@@ -1283,6 +2821,8 @@ impl AzureOpenAIClient {
             api_key: api_key.into(),
             deployment: deployment.into(),
             api_version: "2024-02-01".to_string(),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
         }
     }
 
@@ -1291,6 +2831,37 @@ impl AzureOpenAIClient {
         self
     }
 
+    /// Build from `cfg.azure`, falling back to the top-level API key and base URL. The
+    /// deployment defaults to the configured model name.
+    pub fn from_config(cfg: &ModelConfig) -> Result<Self> {
+        let endpoint = cfg
+            .azure
+            .endpoint
+            .clone()
+            .or_else(|| cfg.base_url.clone())
+            .ok_or_else(|| {
+                AgnoError::LanguageModel("missing Azure OpenAI endpoint in model config".into())
+            })?;
+        let api_key = cfg
+            .azure
+            .api_key
+            .clone()
+            .or_else(|| cfg.api_key.clone())
+            .ok_or_else(|| {
+                AgnoError::LanguageModel("missing Azure OpenAI API key in model config".into())
+            })?;
+        let deployment = cfg
+            .azure
+            .deployment
+            .clone()
+            .unwrap_or_else(|| cfg.model.clone());
+        let mut client = Self::new(endpoint, api_key, deployment);
+        if let Some(version) = &cfg.azure.api_version {
+            client = client.with_api_version(version.clone());
+        }
+        Ok(client)
+    }
+
     pub fn from_env() -> Result<Self> {
         let endpoint = std::env::var("AZURE_OPENAI_ENDPOINT")
             .map_err(|_| AgnoError::LanguageModel("AZURE_OPENAI_ENDPOINT not set".into()))?;
@@ -1300,6 +2871,41 @@ impl AzureOpenAIClient {
             .unwrap_or_else(|_| "gpt-4".to_string());
         Ok(Self::new(endpoint, api_key, deployment))
     }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
+
+    fn chat_completions_url(&self) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint.trim_end_matches('/'),
+            self.deployment,
+            self.api_version
+        )
+    }
 }
 
 #[async_trait]
@@ -1310,122 +2916,56 @@ impl LanguageModel for AzureOpenAIClient {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion> {
-        // Convert messages to OpenAI format
-        let azure_messages: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                let role = match m.role {
-                    Role::System => "system",
-                    Role::User => "user",
-                    Role::Assistant => "assistant",
-                    Role::Tool => "tool",
-                };
-
-                let mut msg = json!({
-                    "role": role,
-                    "content": m.content.clone()
-                });
-
-                if m.role == Role::Tool {
-                    if let Some(ref tc) = m.tool_call {
-                        if let Some(ref id) = tc.id {
-                            msg["tool_call_id"] = json!(id);
-                        }
-                    }
-                }
-
-                if let Some(ref tc) = m.tool_call {
-                    if m.role == Role::Assistant {
-                        msg["tool_calls"] = json!([{
-                            "id": tc.id.clone().unwrap_or_default(),
-                            "type": "function",
-                            "function": {
-                                "name": tc.name,
-                                "arguments": serialize_tool_arguments(&tc.arguments)
-                            }
-                        }]);
-                        msg["content"] = json!(null);
-                    }
-                }
-
-                msg
-            })
-            .collect();
-
-        let mut body = json!({
-            "messages": azure_messages,
-            "stream": stream
-        });
-
-        if !tools.is_empty() {
-            let azure_tools: Vec<Value> = tools
-                .iter()
-                .map(|t| {
-                    json!({
-                        "type": "function",
-                        "function": {
-                            "name": t.name,
-                            "description": t.description,
-                            "parameters": t.parameters.clone().unwrap_or(json!({"type": "object", "properties": {}}))
-                        }
-                    })
-                })
-                .collect();
-            body["tools"] = json!(azure_tools);
-            body["tool_choice"] = json!("auto");
-        }
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
 
-        let url = format!(
-            "{}/openai/deployments/{}/chat/completions?api-version={}",
-            self.endpoint, self.deployment, self.api_version
-        );
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_options(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
 
-        let resp = self
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        let format = requested_format(format, &self.output_format);
+        let body = openai_chat_payload(messages, tools, stream, format, options);
+        let request = self
             .http
-            .post(&url)
+            .post(self.chat_completions_url())
             .header("api-key", &self.api_key)
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Azure OpenAI request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Azure OpenAI"));
-        }
-
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Azure OpenAI parse error: {e}")))?;
-
-        let choice = json["choices"]
-            .as_array()
-            .and_then(|c| c.first())
-            .ok_or_else(|| AgnoError::LanguageModel("Azure OpenAI returned no choices".into()))?;
-
-        let message = &choice["message"];
-        let content = message["content"].as_str().map(String::from);
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = message["tool_calls"].as_array() {
-            for call in calls {
-                let id = call["id"].as_str().map(String::from);
-                let func = &call["function"];
-                let name = func["name"].as_str().unwrap_or("").to_string();
-                let args_str = func["arguments"].as_str().unwrap_or("{}");
-                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
-                tool_calls.push(ToolCall {
-                    id,
-                    name,
-                    arguments: args,
-                });
-            }
-        }
-
-        Ok(ModelCompletion { content, tool_calls })
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Azure OpenAI", request).await?;
+        read_openai_completion(
+            resp,
+            stream,
+            "Azure OpenAI",
+            "Azure OpenAI",
+            &self.deployment,
+            deltas.as_ref(),
+        )
+        .await
     }
 }
 
@@ -1433,16 +2973,21 @@ impl LanguageModel for AzureOpenAIClient {
 // Together AI Client
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Together AI client using their OpenAI-compatible API.
-/// Default model: meta-llama/Llama-3.3-70B-Instruct-Turbo
+/// Together AI client using their OpenAI-compatible API, with the same request shaping,
+/// streaming and tool-call handling as [`OpenAIClient`].
 #[derive(Clone)]
 pub struct TogetherClient {
     http: reqwest::Client,
     model: String,
     api_key: String,
+    base_url: String,
+    retry: HttpRetry,
+    output_format: OutputFormat,
 }
 
 impl TogetherClient {
+    /// Client for `https://api.together.xyz/v1` using the default model,
+    /// `meta-llama/Llama-3.3-70B-Instruct-Turbo`.
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             http: reqwest::Client::builder()
@@ -1451,6 +2996,9 @@ impl TogetherClient {
                 .expect("failed to build http client"),
             model: "meta-llama/Llama-3.3-70B-Instruct-Turbo".to_string(),
             api_key: api_key.into(),
+            base_url: "https://api.together.xyz/v1".to_string(),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
         }
     }
 
@@ -1459,11 +3007,42 @@ impl TogetherClient {
         self
     }
 
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
     pub fn from_env() -> Result<Self> {
         let api_key = std::env::var("TOGETHER_API_KEY")
             .map_err(|_| AgnoError::LanguageModel("TOGETHER_API_KEY not set".into()))?;
         Ok(Self::new(api_key))
     }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
 }
 
 #[async_trait]
@@ -1474,91 +3053,57 @@ impl LanguageModel for TogetherClient {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion> {
-        let together_messages: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                let role = match m.role {
-                    Role::System => "system",
-                    Role::User => "user",
-                    Role::Assistant => "assistant",
-                    Role::Tool => "tool",
-                };
-                json!({
-                    "role": role,
-                    "content": m.content.clone()
-                })
-            })
-            .collect();
-
-        let mut body = json!({
-            "model": self.model,
-            "messages": together_messages,
-            "stream": stream
-        });
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
 
-        if !tools.is_empty() {
-            let together_tools: Vec<Value> = tools
-                .iter()
-                .map(|t| {
-                    json!({
-                        "type": "function",
-                        "function": {
-                            "name": t.name,
-                            "description": t.description,
-                            "parameters": t.parameters.clone().unwrap_or(json!({"type": "object", "properties": {}}))
-                        }
-                    })
-                })
-                .collect();
-            body["tools"] = json!(together_tools);
-        }
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_options(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
 
-        let resp = self
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        let format = requested_format(format, &self.output_format);
+        let mut body = openai_chat_payload(messages, tools, stream, format, options);
+        body["model"] = json!(self.model);
+        let request = self
             .http
-            .post("https://api.together.xyz/v1/chat/completions")
+            .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Together request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Together"));
-        }
-
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Together parse error: {e}")))?;
-
-        let choice = json["choices"]
-            .as_array()
-            .and_then(|c| c.first())
-            .ok_or_else(|| AgnoError::LanguageModel("Together returned no choices".into()))?;
-
-        let message = &choice["message"];
-        let content = message["content"].as_str().map(String::from);
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = message["tool_calls"].as_array() {
-            for call in calls {
-                let id = call["id"].as_str().map(String::from);
-                let func = &call["function"];
-                let name = func["name"].as_str().unwrap_or("").to_string();
-                let args_str = func["arguments"].as_str().unwrap_or("{}");
-                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
-                tool_calls.push(ToolCall {
-                    id,
-                    name,
-                    arguments: args,
-                });
-            }
-        }
-
-        Ok(ModelCompletion { content, tool_calls })
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Together", request).await?;
+        read_openai_completion(
+            resp,
+            stream,
+            "Together",
+            "Together",
+            &self.model,
+            deltas.as_ref(),
+        )
+        .await
     }
 }
 
@@ -1566,16 +3111,21 @@ impl LanguageModel for TogetherClient {
 // Fireworks AI Client
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Fireworks AI client using their OpenAI-compatible API.
-/// Default model: accounts/fireworks/models/llama-v3p1-70b-instruct
+/// Fireworks AI client using their OpenAI-compatible API, with the same request shaping,
+/// streaming and tool-call handling as [`OpenAIClient`].
 #[derive(Clone)]
 pub struct FireworksClient {
     http: reqwest::Client,
     model: String,
     api_key: String,
+    base_url: String,
+    retry: HttpRetry,
+    output_format: OutputFormat,
 }
 
 impl FireworksClient {
+    /// Client for `https://api.fireworks.ai/inference/v1` using the default model,
+    /// `accounts/fireworks/models/llama-v3p1-70b-instruct`.
     pub fn new(api_key: impl Into<String>) -> Self {
         Self {
             http: reqwest::Client::builder()
@@ -1584,6 +3134,9 @@ impl FireworksClient {
                 .expect("failed to build http client"),
             model: "accounts/fireworks/models/llama-v3p1-70b-instruct".to_string(),
             api_key: api_key.into(),
+            base_url: "https://api.fireworks.ai/inference/v1".to_string(),
+            retry: HttpRetry::default(),
+            output_format: OutputFormat::Text,
         }
     }
 
@@ -1592,11 +3145,42 @@ impl FireworksClient {
         self
     }
 
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into();
+        self
+    }
+
     pub fn from_env() -> Result<Self> {
         let api_key = std::env::var("FIREWORKS_API_KEY")
             .map_err(|_| AgnoError::LanguageModel("FIREWORKS_API_KEY not set".into()))?;
         Ok(Self::new(api_key))
     }
+
+    /// Send requests through `http`, e.g. one built from [`HttpSettings`] and shared
+    /// with other clients.
+    pub fn with_http_client(mut self, http: reqwest::Client) -> Self {
+        self.http = http;
+        self
+    }
+
+    /// Retry rate-limited, 5xx and transport failures according to `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry.policy = Some(policy);
+        self
+    }
+
+    /// Record failed request attempts to `telemetry`.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.retry.telemetry = Some(telemetry);
+        self
+    }
+
+    /// Request a structured reply format via `response_format`.
+    pub fn with_output_format(mut self, format: OutputFormat) -> Self {
+        self.output_format = format;
+        self
+    }
 }
 
 #[async_trait]
@@ -1607,91 +3191,57 @@ impl LanguageModel for FireworksClient {
         tools: &[ToolDescription],
         stream: bool,
     ) -> Result<ModelCompletion> {
-        let fireworks_messages: Vec<Value> = messages
-            .iter()
-            .map(|m| {
-                let role = match m.role {
-                    Role::System => "system",
-                    Role::User => "user",
-                    Role::Assistant => "assistant",
-                    Role::Tool => "tool",
-                };
-                json!({
-                    "role": role,
-                    "content": m.content.clone()
-                })
-            })
-            .collect();
-
-        let mut body = json!({
-            "model": self.model,
-            "messages": fireworks_messages,
-            "stream": stream
-        });
+        self.complete_chat_with_format(messages, tools, stream, &self.output_format)
+            .await
+    }
 
-        if !tools.is_empty() {
-            let fireworks_tools: Vec<Value> = tools
-                .iter()
-                .map(|t| {
-                    json!({
-                        "type": "function",
-                        "function": {
-                            "name": t.name,
-                            "description": t.description,
-                            "parameters": t.parameters.clone().unwrap_or(json!({"type": "object", "properties": {}}))
-                        }
-                    })
-                })
-                .collect();
-            body["tools"] = json!(fireworks_tools);
-        }
+    async fn complete_chat_with_format(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+    ) -> Result<ModelCompletion> {
+        self.complete_chat_with_options(
+            messages,
+            tools,
+            stream,
+            format,
+            &CompletionOptions::default(),
+            None,
+        )
+        .await
+    }
 
-        let resp = self
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        let stream = stream || deltas.is_some();
+        let format = requested_format(format, &self.output_format);
+        let mut body = openai_chat_payload(messages, tools, stream, format, options);
+        body["model"] = json!(self.model);
+        let request = self
             .http
-            .post("https://api.fireworks.ai/inference/v1/chat/completions")
+            .post(format!("{}/chat/completions", self.base_url))
             .header("Authorization", format!("Bearer {}", self.api_key))
             .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Fireworks request failed: {e}")))?;
-
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(coalesce_error(status, &body, "Fireworks"));
-        }
-
-        let json: Value = resp
-            .json()
-            .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Fireworks parse error: {e}")))?;
-
-        let choice = json["choices"]
-            .as_array()
-            .and_then(|c| c.first())
-            .ok_or_else(|| AgnoError::LanguageModel("Fireworks returned no choices".into()))?;
-
-        let message = &choice["message"];
-        let content = message["content"].as_str().map(String::from);
-
-        let mut tool_calls = Vec::new();
-        if let Some(calls) = message["tool_calls"].as_array() {
-            for call in calls {
-                let id = call["id"].as_str().map(String::from);
-                let func = &call["function"];
-                let name = func["name"].as_str().unwrap_or("").to_string();
-                let args_str = func["arguments"].as_str().unwrap_or("{}");
-                let args: Value = serde_json::from_str(args_str).unwrap_or(json!({}));
-                tool_calls.push(ToolCall {
-                    id,
-                    name,
-                    arguments: args,
-                });
-            }
-        }
-
-        Ok(ModelCompletion { content, tool_calls })
+            .json(&body);
+        let resp = send_with_retry(&self.retry, "Fireworks", request).await?;
+        read_openai_completion(
+            resp,
+            stream,
+            "Fireworks",
+            "Fireworks",
+            &self.model,
+            deltas.as_ref(),
+        )
+        .await
     }
 }
 
@@ -1734,10 +3284,39 @@ impl AwsBedrockClient {
 #[async_trait]
 impl LanguageModel for AwsBedrockClient {
     async fn complete_chat(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+    ) -> Result<ModelCompletion> {
+        self.send_chat(messages, tools, stream, &CompletionOptions::default())
+            .await
+    }
+
+    async fn complete_chat_with_options(
+        &self,
+        messages: &[Message],
+        tools: &[ToolDescription],
+        stream: bool,
+        format: &OutputFormat,
+        options: &CompletionOptions,
+        _deltas: Option<mpsc::UnboundedSender<ToolCallDelta>>,
+    ) -> Result<ModelCompletion> {
+        if *format != OutputFormat::Text {
+            tracing::warn!("model does not support structured output; falling back to text");
+        }
+        self.send_chat(messages, tools, stream, options).await
+    }
+}
+
+#[cfg(feature = "aws")]
+impl AwsBedrockClient {
+    async fn send_chat(
         &self,
         messages: &[Message],
         tools: &[ToolDescription],
         _stream: bool, // Streaming not implemented for this pass
+        options: &CompletionOptions,
     ) -> Result<ModelCompletion> {
         // Construct Anthropic Messages API payload for Bedrock
         let system_prompt = messages
@@ -1802,6 +3381,10 @@ impl LanguageModel for AwsBedrockClient {
                 })
             }).collect();
             body["tools"] = json!(tool_defs);
+            body["tool_choice"] = options.tool_choice.anthropic_value();
+        }
+        if !options.stop.is_empty() {
+            body["stop_sequences"] = json!(options.stop);
         }
 
         let blob = aws_sdk_bedrockruntime::primitives::Blob::new(serde_json::to_vec(&body).unwrap());
@@ -1812,10 +3395,23 @@ impl LanguageModel for AwsBedrockClient {
             .body(blob)
             .send()
             .await
-            .map_err(|e| AgnoError::LanguageModel(format!("Bedrock invocation failed: {}", e)))?;
+            .map_err(|e| {
+                if e.as_service_error()
+                    .is_some_and(|err| err.is_throttling_exception())
+                {
+                    return AgnoError::RateLimited {
+                        provider: "bedrock".into(),
+                        retry_after: None,
+                    };
+                }
+                AgnoError::LanguageModel(format!("Bedrock invocation failed: {}", e))
+            })?;
 
         let response_body: Value = serde_json::from_slice(output.body.as_ref())
-            .map_err(|e| AgnoError::LanguageModel(format!("Failed to parse Bedrock response: {}", e)))?;
+            .map_err(|e| AgnoError::InvalidResponse {
+                provider: "bedrock".into(),
+                message: format!("Failed to parse Bedrock response: {}", e),
+            })?;
 
         let mut content = None;
         let mut tool_calls = Vec::new();
@@ -1839,13 +3435,19 @@ impl LanguageModel for AwsBedrockClient {
             }
         }
 
-        Ok(ModelCompletion { content, tool_calls })
+        Ok(ModelCompletion {
+            content,
+            tool_calls,
+            usage: None,
+        })
     }
 }
 
 
 pub struct StubModel {
     responses: Mutex<VecDeque<String>>,
+    usage: Option<TokenUsage>,
+    latency: Option<Duration>,
 }
 
 
@@ -1853,6 +3455,26 @@ impl StubModel {
     pub fn new(responses: Vec<String>) -> Arc<Self> {
         Arc::new(Self {
             responses: Mutex::new(responses.into()),
+            usage: None,
+            latency: None,
+        })
+    }
+
+    /// Like [`StubModel::new`], but every completion reports `usage`.
+    pub fn with_usage(responses: Vec<String>, usage: TokenUsage) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(responses.into()),
+            usage: Some(usage),
+            latency: None,
+        })
+    }
+
+    /// Like [`StubModel::new`], but every completion takes `latency` to arrive.
+    pub fn with_latency(responses: Vec<String>, latency: Duration) -> Arc<Self> {
+        Arc::new(Self {
+            responses: Mutex::new(responses.into()),
+            usage: None,
+            latency: Some(latency),
         })
     }
 }
@@ -1872,6 +3494,9 @@ impl LanguageModel for StubModel {
         _tools: &[ToolDescription],
         _stream: bool,
     ) -> Result<ModelCompletion> {
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
         let mut locked = self.responses.lock().expect("stub model poisoned");
         let raw = locked.pop_front().ok_or_else(|| {
             AgnoError::LanguageModel("StubModel ran out of scripted responses".into())
@@ -1881,6 +3506,7 @@ impl LanguageModel for StubModel {
             Ok(StubDirective::Respond { content }) => Ok(ModelCompletion {
                 content: Some(content),
                 tool_calls: Vec::new(),
+                usage: self.usage.clone(),
             }),
             Ok(StubDirective::CallTool { name, arguments }) => Ok(ModelCompletion {
                 content: None,
@@ -1889,20 +3515,161 @@ impl LanguageModel for StubModel {
                     name,
                     arguments,
                 }],
+                usage: self.usage.clone(),
             }),
             Err(_) => Ok(ModelCompletion {
                 content: Some(raw),
                 tool_calls: Vec::new(),
+                usage: self.usage.clone(),
             }),
         }
     }
 }
 
+/// Images the cache of an [`ImageInlining`] holds.
+const INLINED_IMAGE_CACHE_ENTRIES: usize = 16;
+
+/// Which image attachments a client rewrites as base64 `data:` URIs before sending them.
+#[derive(Clone, Default)]
+struct ImageInlining {
+    /// Download `http(s):` images rather than passing their URLs on.
+    remote: bool,
+    /// Read `file:` images from inside this sandbox; without one they are left alone.
+    local: Option<FsSandbox>,
+    /// Recently inlined images as `(uri, data_uri, media_type)`, so the images in a
+    /// transcript are fetched once rather than on every model step.
+    cache: Arc<Mutex<VecDeque<(String, String, String)>>>,
+}
+
+impl ImageInlining {
+    fn should_inline(&self, uri: &str) -> bool {
+        (self.local.is_some() && uri.starts_with("file:"))
+            || (self.remote && (uri.starts_with("http://") || uri.starts_with("https://")))
+    }
+
+    /// `messages` with the selected image attachments fetched through
+    /// [`Attachment::fetch_bytes_in`] and inlined. Images that fail to load are left as
+    /// they are.
+    ///
+    /// [`Attachment::fetch_bytes_in`]: crate::Attachment::fetch_bytes_in
+    async fn apply<'a>(&self, messages: &'a [Message]) -> Cow<'a, [Message]> {
+        let pending = messages.iter().any(|message| {
+            message.attachments.iter().any(|attachment| {
+                attachment.kind == AttachmentKind::Image && self.should_inline(&attachment.uri)
+            })
+        });
+        if !pending {
+            return Cow::Borrowed(messages);
+        }
+
+        let mut inlined = messages.to_vec();
+        for attachment in inlined
+            .iter_mut()
+            .flat_map(|message| message.attachments.iter_mut())
+            .filter(|attachment| attachment.kind == AttachmentKind::Image)
+        {
+            if !self.should_inline(&attachment.uri) {
+                continue;
+            }
+            let cached = self
+                .cache
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|(uri, ..)| *uri == attachment.uri)
+                .map(|(_, data_uri, media_type)| (data_uri.clone(), media_type.clone()));
+            let (data_uri, media_type) = match cached {
+                Some(cached) => cached,
+                None => match self.fetch(attachment).await {
+                    Ok(fetched) => fetched,
+                    Err(err) => {
+                        tracing::warn!("could not inline image `{}`: {err}", attachment.uri);
+                        continue;
+                    }
+                },
+            };
+            attachment.uri = data_uri;
+            attachment.media_type = Some(media_type);
+        }
+        Cow::Owned(inlined)
+    }
+
+    async fn fetch(&self, attachment: &Attachment) -> Result<(String, String)> {
+        let (bytes, media_type) = match &self.local {
+            Some(sandbox) => attachment.fetch_bytes_in(sandbox).await?,
+            None => attachment.fetch_bytes().await?,
+        };
+        let data = base64::engine::general_purpose::STANDARD.encode(bytes);
+        let data_uri = format!("data:{media_type};base64,{data}");
+        let mut cache = self.cache.lock().unwrap();
+        if cache.len() == INLINED_IMAGE_CACHE_ENTRIES {
+            cache.pop_front();
+        }
+        cache.push_back((attachment.uri.clone(), data_uri.clone(), media_type.clone()));
+        Ok((data_uri, media_type))
+    }
+}
+
+/// Plain text content, or content parts when a message carries images. Text-only messages
+/// keep the string form for compatibility with providers that reject arrays.
+fn openai_content_with_images(message: &Message) -> OpenAiContent {
+    let images: Vec<OpenAiContentPart> = message
+        .attachments
+        .iter()
+        .filter(|attachment| attachment.kind == AttachmentKind::Image)
+        .filter_map(|attachment| {
+            let uri = attachment.uri.as_str();
+            if uri.starts_with("http://") || uri.starts_with("https://") || uri.starts_with("data:")
+            {
+                Some(OpenAiContentPart::ImageUrl {
+                    image_url: OpenAiImageUrl {
+                        url: uri.to_string(),
+                    },
+                })
+            } else {
+                tracing::warn!("skipping image attachment with unsupported uri `{uri}`");
+                None
+            }
+        })
+        .collect();
+    if images.is_empty() {
+        return OpenAiContent::Text(message.content.clone());
+    }
+
+    let mut parts = Vec::with_capacity(images.len() + 1);
+    if !message.content.is_empty() {
+        parts.push(OpenAiContentPart::Text {
+            text: message.content.clone(),
+        });
+    }
+    parts.extend(images);
+    OpenAiContent::Parts(parts)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+enum OpenAiContent {
+    Text(String),
+    Parts(Vec<OpenAiContentPart>),
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum OpenAiContentPart {
+    Text { text: String },
+    ImageUrl { image_url: OpenAiImageUrl },
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OpenAiImageUrl {
+    url: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct OpenAiMessage {
     role: String,
     #[serde(skip_serializing_if = "Option::is_none")]
-    content: Option<String>,
+    content: Option<OpenAiContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
     tool_call_id: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -1941,6 +3708,16 @@ struct OpenAiFunction {
 #[derive(Debug, Deserialize)]
 struct OpenAiResponse {
     choices: Vec<OpenAiChoice>,
+    #[serde(default)]
+    usage: Option<OpenAiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAiUsage {
+    #[serde(default)]
+    prompt_tokens: u64,
+    #[serde(default)]
+    completion_tokens: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -1985,6 +3762,8 @@ struct OpenAiDelta {
 
 #[derive(Debug, Deserialize)]
 struct OpenAiToolCallDelta {
+    #[serde(default)]
+    index: Option<usize>,
     id: Option<String>,
     #[serde(default)]
     function: Option<OpenAiFunctionDelta>,
@@ -1998,21 +3777,124 @@ struct OpenAiFunctionDelta {
     arguments: Option<String>,
 }
 
+/// A text block followed by an `image` block per image attachment. Data URIs are sent
+/// inline as base64; remote images by URL.
+fn anthropic_content_with_images(message: &Message) -> Vec<AnthropicContentBlock> {
+    let images = message
+        .attachments
+        .iter()
+        .filter(|attachment| attachment.kind == AttachmentKind::Image)
+        .filter_map(|attachment| {
+            let uri = attachment.uri.as_str();
+            let source = if let Some(data_uri) = uri.strip_prefix("data:") {
+                let Some((media_type, data)) = data_uri.split_once(";base64,") else {
+                    tracing::warn!("skipping image attachment with non-base64 data uri");
+                    return None;
+                };
+                let media_type = if media_type.is_empty() {
+                    attachment.media_type.clone()?
+                } else {
+                    media_type.to_string()
+                };
+                AnthropicImageSource::Base64 {
+                    media_type,
+                    data: data.to_string(),
+                }
+            } else if uri.starts_with("http://") || uri.starts_with("https://") {
+                AnthropicImageSource::Url {
+                    url: uri.to_string(),
+                }
+            } else {
+                tracing::warn!("skipping image attachment with unsupported uri `{uri}`");
+                return None;
+            };
+            Some(AnthropicContentBlock {
+                r#type: "image".to_string(),
+                source: Some(source),
+                ..AnthropicContentBlock::default()
+            })
+        });
+
+    let mut blocks = Vec::with_capacity(message.attachments.len() + 1);
+    blocks.push(AnthropicContentBlock {
+        r#type: "text".to_string(),
+        text: Some(message.content.clone()),
+        ..AnthropicContentBlock::default()
+    });
+    blocks.extend(images);
+    // Anthropic rejects empty text blocks, which a bare image message would otherwise send.
+    if blocks.len() > 1 && message.content.is_empty() {
+        blocks.remove(0);
+    }
+    blocks
+}
+
+/// An assistant turn, ending in a `tool_use` block when it made a native tool call.
+fn anthropic_assistant_content(message: &Message) -> Vec<AnthropicContentBlock> {
+    let mut blocks = anthropic_content_with_images(message);
+    let Some(call) = message.tool_call.as_ref().filter(|call| call.id.is_some()) else {
+        return blocks;
+    };
+    blocks.retain(|block| block.text.as_deref() != Some(""));
+    blocks.push(AnthropicContentBlock {
+        r#type: "tool_use".to_string(),
+        id: call.id.clone(),
+        name: Some(call.name.clone()),
+        input: Some(call.arguments.clone()),
+        ..AnthropicContentBlock::default()
+    });
+    blocks
+}
+
+/// The `tool_result` block answering a native tool call, if `message` answers one.
+fn anthropic_tool_result(message: &Message) -> Option<AnthropicContentBlock> {
+    let result = message.tool_result.as_ref()?;
+    Some(AnthropicContentBlock {
+        r#type: "tool_result".to_string(),
+        tool_use_id: Some(result.tool_call_id.clone()?),
+        content: Some(serialize_tool_arguments(&result.output)),
+        ..AnthropicContentBlock::default()
+    })
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct AnthropicMessage {
     role: String,
     content: Vec<AnthropicContentBlock>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 struct AnthropicContentBlock {
     r#type: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     text: Option<String>,
+    #[serde(default, skip_serializing)]
+    thinking: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     name: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     input_schema: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source: Option<AnthropicImageSource>,
+    /// The call id of a `tool_use` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    /// The arguments of a `tool_use` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input: Option<Value>,
+    /// The call a `tool_result` block answers.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_use_id: Option<String>,
+    /// The tool output of a `tool_result` block.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicImageSource {
+    Base64 { media_type: String, data: String },
+    Url { url: String },
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -2025,17 +3907,38 @@ struct AnthropicTool {
 #[derive(Debug, Deserialize)]
 struct AnthropicResponse {
     content: Vec<AnthropicContentBlock>,
+    #[serde(default)]
+    usage: Option<AnthropicUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicUsage {
+    #[serde(default)]
+    input_tokens: u64,
+    #[serde(default)]
+    output_tokens: u64,
 }
 
+/// One stream event. Only `content_block_start` carries a block and only
+/// `content_block_delta` and `message_delta` carry a delta.
 #[derive(Debug, Deserialize)]
 struct AnthropicStreamChunk {
-    delta: AnthropicDelta,
+    #[serde(default)]
+    index: usize,
+    #[serde(default)]
+    content_block: Option<AnthropicContentBlock>,
+    #[serde(default)]
+    delta: Option<AnthropicDelta>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AnthropicDelta {
     #[serde(default)]
     text: Option<String>,
+    #[serde(default)]
+    thinking: Option<String>,
+    #[serde(default)]
+    partial_json: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -2045,13 +3948,82 @@ struct GeminiMessage {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
+struct GeminiSystemInstruction {
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiPart {
-    text: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    text: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_call: Option<GeminiFunctionCall>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    fn text(text: impl Into<String>) -> Self {
+        Self {
+            text: Some(text.into()),
+            ..Self::default()
+        }
+    }
+
+    fn function_call(call: &ToolCall) -> Self {
+        Self {
+            function_call: Some(GeminiFunctionCall {
+                name: call.name.clone(),
+                args: call.arguments.clone(),
+            }),
+            ..Self::default()
+        }
+    }
+
+    /// Gemini expects the response to be a JSON object, so other values are wrapped.
+    fn function_response(message: &Message) -> Self {
+        let (name, output) = match &message.tool_result {
+            Some(result) => (result.name.clone(), result.output.clone()),
+            None => (String::new(), Value::String(message.content.clone())),
+        };
+        let response = match output {
+            Value::Object(_) => output,
+            other => json!({ "content": other }),
+        };
+        Self {
+            function_response: Some(GeminiFunctionResponse { name, response }),
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionCall {
+    name: String,
+    #[serde(default)]
+    args: Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionResponse {
+    name: String,
+    response: Value,
 }
 
 #[derive(Debug, Deserialize)]
 struct GeminiResponse {
     candidates: Vec<GeminiCandidate>,
+    #[serde(default, rename = "usageMetadata")]
+    usage_metadata: Option<GeminiUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiUsage {
+    #[serde(default, rename = "promptTokenCount")]
+    prompt_token_count: u64,
+    #[serde(default, rename = "candidatesTokenCount")]
+    candidates_token_count: u64,
 }
 
 #[derive(Debug, Deserialize)]
@@ -2115,6 +4087,8 @@ struct CohereFunctionCall {
 
 #[derive(Debug, Deserialize)]
 struct CohereStreamChunk {
+    #[serde(default)]
+    index: Option<usize>,
     #[serde(default)]
     delta: Option<CohereDelta>,
 }
@@ -2122,6 +4096,966 @@ struct CohereStreamChunk {
 #[derive(Debug, Deserialize)]
 struct CohereDelta {
     #[serde(default)]
-    message: Option<CohereResponseMessage>,
+    message: Option<CohereStreamMessage>,
 }
 
+#[derive(Debug, Deserialize)]
+struct CohereStreamMessage {
+    #[serde(default)]
+    content: Option<Value>,
+    /// A single call per event, unlike the array in a full response.
+    #[serde(default)]
+    tool_calls: Option<CohereStreamToolCall>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereStreamToolCall {
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    function: Option<CohereStreamFunction>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereStreamFunction {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    arguments: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderMap, HeaderValue};
+
+    #[test]
+    fn parses_retry_after_into_rate_limited_error() {
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("2"));
+
+        let err = coalesce_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &headers,
+            "slow down",
+            "openai",
+        );
+        assert_eq!(err.retry_after(), Some(Duration::from_secs(2)));
+        assert_eq!(
+            err.to_string(),
+            "openai rate limit exceeded; retry after 2.0s"
+        );
+    }
+
+    #[test]
+    fn classifies_rate_limits_and_server_errors_as_retryable_and_auth_failures_as_fatal() {
+        let rate_limited = coalesce_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &HeaderMap::new(),
+            "slow down",
+            "openai",
+        );
+        assert!(rate_limited.is_retryable());
+        assert_eq!(rate_limited.provider(), Some("openai"));
+
+        let unauthorized = coalesce_error(
+            reqwest::StatusCode::UNAUTHORIZED,
+            &HeaderMap::new(),
+            "invalid api key",
+            "anthropic",
+        );
+        assert!(matches!(unauthorized, AgnoError::Auth { status: 401, .. }));
+        assert!(!unauthorized.is_retryable());
+        assert_eq!(unauthorized.provider(), Some("anthropic"));
+        assert_eq!(
+            unauthorized.to_string(),
+            "language model error: anthropic request failed with 401 Unauthorized: invalid api key"
+        );
+
+        let unavailable = coalesce_error(
+            reqwest::StatusCode::SERVICE_UNAVAILABLE,
+            &HeaderMap::new(),
+            "overloaded",
+            "openai",
+        );
+        assert!(matches!(
+            unavailable,
+            AgnoError::ServerError { status: 503, .. }
+        ));
+        assert!(unavailable.is_retryable());
+        assert_eq!(unavailable.provider(), Some("openai"));
+    }
+
+    #[test]
+    fn parses_rate_limit_reset_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "x-ratelimit-reset-requests",
+            HeaderValue::from_static("1m30s"),
+        );
+        headers.insert(
+            "x-ratelimit-reset-tokens",
+            HeaderValue::from_static("250ms"),
+        );
+        assert_eq!(parse_retry_after(&headers), Some(Duration::from_secs(60)));
+
+        let mut headers = HeaderMap::new();
+        headers.insert("retry-after", HeaderValue::from_static("1e309"));
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert("retry-after", HeaderValue::from_static("-5"));
+        assert_eq!(parse_retry_after(&headers), None);
+        headers.insert("retry-after", HeaderValue::from_static("7200"));
+        assert_eq!(parse_retry_after(&headers), Some(MAX_RATE_LIMIT_WAIT));
+        headers.insert("retry-after", HeaderValue::from_static("2.5"));
+        assert_eq!(
+            parse_retry_after(&headers),
+            Some(Duration::from_millis(2500))
+        );
+        headers.insert(
+            "retry-after",
+            HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"),
+        );
+        assert_eq!(parse_retry_after(&headers), None);
+
+        let err = coalesce_error(
+            reqwest::StatusCode::TOO_MANY_REQUESTS,
+            &HeaderMap::new(),
+            "",
+            "anthropic",
+        );
+        assert_eq!(err.retry_after(), None);
+        assert_eq!(err.to_string(), "anthropic rate limit exceeded");
+    }
+
+    #[test]
+    fn accumulates_ollama_stream_split_across_chunks() {
+        let body = concat!(
+            r#"{"message":{"role":"assistant","content":"Hel"},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":"lo"},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":"","tool_calls":[{"function":{"name":"lookup","arguments":{"q":"rust"}}}]},"done":false}"#,
+            "\n",
+            r#"{"message":{"role":"assistant","content":""},"done":true}"#,
+        );
+        let mut state = OllamaStream::default();
+        for piece in body.as_bytes().chunks(7) {
+            state.feed(piece).unwrap();
+        }
+        let completion = state.finish().unwrap();
+
+        assert_eq!(completion.content.as_deref(), Some("Hello"));
+        assert_eq!(completion.tool_calls.len(), 1);
+        assert_eq!(completion.tool_calls[0].name, "lookup");
+        assert_eq!(completion.tool_calls[0].arguments["q"], "rust");
+    }
+
+    #[test]
+    fn sends_image_attachments_as_content_parts() {
+        let mut message = Message::user("What is in this picture?");
+        message.attachments.push(crate::message::Attachment {
+            kind: AttachmentKind::Image,
+            uri: "https://example.com/cat.png".into(),
+            description: None,
+            media_type: Some("image/png".into()),
+        });
+        message.attachments.push(crate::message::Attachment {
+            kind: AttachmentKind::Image,
+            uri: "data:image/png;base64,iVBORw0KGgo=".into(),
+            description: None,
+            media_type: None,
+        });
+
+        let payload = serde_json::to_value(
+            to_openai_messages(&[Message::system("be brief"), message]),
+        )
+        .unwrap();
+
+        assert_eq!(payload[0]["content"], "be brief");
+        let parts = payload[1]["content"].as_array().unwrap();
+        assert_eq!(
+            parts[0],
+            json!({"type": "text", "text": "What is in this picture?"})
+        );
+        assert_eq!(
+            parts[1],
+            json!({"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}})
+        );
+        assert_eq!(
+            parts[2]["image_url"]["url"],
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+    }
+
+    #[test]
+    fn sends_image_attachments_as_anthropic_image_blocks() {
+        let mut message = Message::user("Describe both.");
+        message.attachments.push(crate::message::Attachment {
+            kind: AttachmentKind::Image,
+            uri: "data:image/jpeg;base64,/9j/4AAQ".into(),
+            description: None,
+            media_type: None,
+        });
+        message.attachments.push(crate::message::Attachment {
+            kind: AttachmentKind::Image,
+            uri: "https://example.com/cat.png".into(),
+            description: None,
+            media_type: Some("image/png".into()),
+        });
+
+        let blocks = serde_json::to_value(anthropic_content_with_images(&message)).unwrap();
+
+        assert_eq!(
+            blocks,
+            json!([
+                {"type": "text", "text": "Describe both."},
+                {"type": "image", "source": {"type": "base64", "media_type": "image/jpeg", "data": "/9j/4AAQ"}},
+                {"type": "image", "source": {"type": "url", "url": "https://example.com/cat.png"}},
+            ])
+        );
+        let plain =
+            serde_json::to_value(anthropic_content_with_images(&Message::user("hi"))).unwrap();
+        assert_eq!(plain, json!([{"type": "text", "text": "hi"}]));
+    }
+
+    #[test]
+    fn sends_reasoning_effort_to_openai() {
+        let messages = [Message::user("Prove it.")];
+        let client = OpenAIClient::new("test-key").with_model("o3");
+        let plain = client.chat_payload(
+            &messages,
+            &[],
+            false,
+            &OutputFormat::Text,
+            &CompletionOptions::default(),
+        );
+        assert!(plain.get("reasoning_effort").is_none());
+
+        let payload = client
+            .with_reasoning_effort(ReasoningEffort::High)
+            .chat_payload(
+                &messages,
+                &[],
+                false,
+                &OutputFormat::Text,
+                &CompletionOptions::default(),
+            );
+        assert_eq!(payload["model"], "o3");
+        assert_eq!(payload["reasoning_effort"], "high");
+        assert_eq!(ReasoningEffort::Budget(6_000).openai_value(), "medium");
+    }
+
+    #[test]
+    fn sends_thinking_budget_to_anthropic_and_surfaces_thinking() {
+        let config: ModelConfig = serde_json::from_value(json!({
+            "provider": "anthropic",
+            "model": "claude-sonnet-4",
+            "api_key": "test-key",
+        }))
+        .unwrap();
+        let client = AnthropicClient::from_config(&config).unwrap();
+        let messages = [Message::user("Prove it.")];
+        let plain = client
+            .payload(&messages, &[], false, &CompletionOptions::default())
+            .unwrap();
+        assert!(plain.get("thinking").is_none());
+        assert_eq!(plain["max_tokens"], ANTHROPIC_ANSWER_TOKENS);
+
+        let client = client.with_reasoning_effort(ReasoningEffort::Budget(8_000));
+        let payload = client
+            .payload(&messages, &[], false, &CompletionOptions::default())
+            .unwrap();
+        assert_eq!(
+            payload["thinking"],
+            json!({"type": "enabled", "budget_tokens": 8000})
+        );
+        assert_eq!(payload["max_tokens"], 8_000 + ANTHROPIC_ANSWER_TOKENS);
+        assert_eq!(ReasoningEffort::Budget(10).budget_tokens(), 1_024);
+
+        // Huge budgets are clamped to the output limit, leaving room for the answer.
+        let capped = client
+            .clone()
+            .with_reasoning_effort(ReasoningEffort::Budget(u32::MAX))
+            .with_max_output_tokens(8_192)
+            .payload(&messages, &[], false, &CompletionOptions::default())
+            .unwrap();
+        assert_eq!(capped["max_tokens"], 8_192);
+        assert_eq!(
+            capped["thinking"]["budget_tokens"],
+            8_192 - ANTHROPIC_ANSWER_TOKENS
+        );
+
+        let response: AnthropicResponse = serde_json::from_value(json!({
+            "content": [
+                {"type": "thinking", "thinking": "2 is even.", "signature": "sig"},
+                {"type": "text", "text": "Done."}
+            ]
+        }))
+        .unwrap();
+        assert_eq!(response.content[0].thinking.as_deref(), Some("2 is even."));
+        assert_eq!(response.content[0].text, None);
+
+        assert_eq!(
+            client.reply("2 is even.".into(), "Done.".into()).as_deref(),
+            Some("Done.")
+        );
+        let surfacing = client.with_surfaced_thinking(true);
+        let reply = surfacing
+            .reply("2 is even.".into(), "Done.".into())
+            .unwrap();
+        let (reasoning, answer) = crate::reasoning::HiddenReasoning::default().split(&reply);
+        assert_eq!(reasoning.as_deref(), Some("2 is even."));
+        assert_eq!(answer, "Done.");
+    }
+
+    #[tokio::test]
+    async fn parses_anthropic_tool_use_and_sends_the_result_back() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "content": [
+                    {"type": "text", "text": "Checking."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {"city": "Oslo"}}
+                ],
+                "usage": {"input_tokens": 12, "output_tokens": 7}
+            })))
+            .mount(&server)
+            .await;
+        let config: ModelConfig = serde_json::from_value(json!({
+            "provider": "anthropic",
+            "model": "claude-sonnet-4",
+            "api_key": "test-key",
+            "anthropic": {"endpoint": server.uri()},
+        }))
+        .unwrap();
+        let client = AnthropicClient::from_config(&config).unwrap();
+
+        let completion = client
+            .complete_chat(&[Message::user("Weather in Oslo?")], &[], false)
+            .await
+            .unwrap();
+        assert_eq!(completion.content.as_deref(), Some("Checking."));
+        let call = ToolCall {
+            id: Some("toolu_1".into()),
+            name: "weather".into(),
+            arguments: json!({"city": "Oslo"}),
+        };
+        assert_eq!(completion.tool_calls, vec![call.clone()]);
+
+        let mut asked = Message::assistant("Checking.");
+        asked.tool_call = Some(call);
+        let answered =
+            Message::tool_with_call("weather", json!({"temp": 4}), Some("toolu_1".into()));
+        let payload = client
+            .payload(
+                &[asked, answered],
+                &[],
+                false,
+                &CompletionOptions::default(),
+            )
+            .unwrap();
+        assert_eq!(
+            payload["messages"],
+            json!([
+                {"role": "assistant", "content": [
+                    {"type": "text", "text": "Checking."},
+                    {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {"city": "Oslo"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "toolu_1", "content": "{\"temp\":4}"}
+                ]}
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn accumulates_anthropic_tool_use_from_stream() {
+        let events = [
+            json!({"type": "message_start", "message": {"id": "msg_1"}}),
+            json!({"type": "content_block_start", "index": 0, "content_block": {"type": "text", "text": ""}}),
+            json!({"type": "content_block_delta", "index": 0, "delta": {"type": "text_delta", "text": "On it."}}),
+            json!({"type": "content_block_stop", "index": 0}),
+            json!({"type": "content_block_start", "index": 1, "content_block": {"type": "tool_use", "id": "toolu_1", "name": "weather", "input": {}}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "{\"city\": "}}),
+            json!({"type": "content_block_delta", "index": 1, "delta": {"type": "input_json_delta", "partial_json": "\"Oslo\"}"}}),
+            json!({"type": "content_block_stop", "index": 1}),
+            json!({"type": "message_delta", "delta": {"stop_reason": "tool_use"}}),
+            json!({"type": "message_stop"}),
+        ];
+        let chunks: Vec<std::result::Result<String, String>> = events
+            .iter()
+            .map(|event| Ok(format!("data: {event}\n\n")))
+            .collect();
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        let (_, content, tool_calls) =
+            read_anthropic_stream(futures::stream::iter(chunks), Some(&sender))
+                .await
+                .unwrap();
+        assert_eq!(content, "On it.");
+        assert_eq!(
+            tool_calls,
+            vec![ToolCall {
+                id: Some("toolu_1".into()),
+                name: "weather".into(),
+                arguments: json!({"city": "Oslo"}),
+            }]
+        );
+        assert!(matches!(
+            receiver.try_recv().unwrap(),
+            ToolCallDelta::Started { index: 1, .. }
+        ));
+    }
+
+    #[test]
+    fn builds_response_format_for_structured_output() {
+        assert_eq!(OutputFormat::Text.response_format(), None);
+        assert_eq!(
+            OutputFormat::JsonObject.response_format(),
+            Some(json!({"type": "json_object"}))
+        );
+
+        let schema = json!({"type": "object", "properties": {"answer": {"type": "string"}}});
+        assert_eq!(
+            OutputFormat::JsonSchema(schema.clone()).response_format(),
+            Some(json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "schema": schema, "strict": true}
+            }))
+        );
+        assert_eq!(
+            OutputFormat::json_schema(schema.clone(), false).response_format(),
+            Some(json!({
+                "type": "json_schema",
+                "json_schema": {"name": "response", "schema": schema, "strict": false}
+            }))
+        );
+
+        // Asking for plain text keeps a format configured on the client.
+        let configured = OutputFormat::JsonObject;
+        assert_eq!(
+            requested_format(&OutputFormat::Text, &configured),
+            &OutputFormat::JsonObject
+        );
+        let schema = OutputFormat::json_schema(schema, true);
+        assert_eq!(requested_format(&schema, &configured), &schema);
+    }
+
+    #[test]
+    fn azure_client_routes_to_deployment_with_api_version() {
+        let config: ModelConfig = serde_json::from_value(json!({
+            "provider": "azure",
+            "model": "gpt-4o",
+            "azure": {
+                "api_key": "test-key",
+                "endpoint": "https://contoso.openai.azure.com/",
+                "deployment": "chat-prod",
+                "api_version": "2024-06-01",
+            },
+        }))
+        .unwrap();
+        let client = AzureOpenAIClient::from_config(&config).unwrap();
+        assert_eq!(
+            client.chat_completions_url(),
+            "https://contoso.openai.azure.com/openai/deployments/chat-prod/chat/completions?api-version=2024-06-01"
+        );
+
+        let mut config = config;
+        config.azure.deployment = None;
+        config.azure.api_version = None;
+        let client = AzureOpenAIClient::from_config(&config).unwrap();
+        assert!(client
+            .chat_completions_url()
+            .ends_with("/deployments/gpt-4o/chat/completions?api-version=2024-02-01"));
+    }
+
+    #[tokio::test]
+    async fn reports_streamed_tool_call_before_its_arguments_complete() {
+        let (chunks, body) = mpsc::unbounded_channel::<std::result::Result<String, String>>();
+        let (deltas, mut progress) = mpsc::unbounded_channel();
+        let reading = tokio::spawn(async move {
+            let body = tokio_stream::wrappers::UnboundedReceiverStream::new(body);
+            read_openai_stream(body, "OpenAI", Some(&deltas)).await
+        });
+
+        chunks
+            .send(Ok(concat!(
+                r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_abc","#,
+                r#""function":{"name":"lookup","arguments":""}}]}}]}"#,
+                "\n\n",
+                r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"#,
+                r#""function":{"arguments":"{\"ci"#,
+            )
+            .into()))
+            .unwrap();
+        let started = tokio::time::timeout(Duration::from_secs(1), progress.recv())
+            .await
+            .unwrap();
+        assert_eq!(
+            started,
+            Some(ToolCallDelta::Started {
+                index: 0,
+                id: Some("call_abc".into()),
+                name: "lookup".into(),
+            })
+        );
+        assert!(!reading.is_finished());
+
+        chunks
+            .send(Ok(concat!(
+                r#"ty\""}}]}}]}"#,
+                "\n\n",
+                r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"#,
+                r#""function":{"arguments":": \"Oslo\"}"}}]}}]}"#,
+                "\n\ndata: [DONE]\n\n",
+            )
+            .into()))
+            .unwrap();
+        drop(chunks);
+
+        let completion = reading.await.unwrap().unwrap();
+        assert_eq!(
+            completion.tool_calls,
+            vec![ToolCall {
+                id: Some("call_abc".into()),
+                name: "lookup".into(),
+                arguments: json!({"city": "Oslo"}),
+            }]
+        );
+        let fragments: Vec<_> = std::iter::from_fn(|| progress.try_recv().ok()).collect();
+        assert_eq!(
+            fragments,
+            vec![
+                ToolCallDelta::Arguments {
+                    index: 0,
+                    fragment: "{\"city\"".into(),
+                },
+                ToolCallDelta::Arguments {
+                    index: 0,
+                    fragment: ": \"Oslo\"}".into(),
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn streams_tool_calls_from_openai_compatible_hosts() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        let events = concat!(
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"id":"call_1","#,
+            r#""function":{"name":"lookup","arguments":"{\"city\":"}}]}}]}"#,
+            "\n\n",
+            r#"data: {"choices":[{"delta":{"tool_calls":[{"index":0,"#,
+            r#""function":{"arguments":" \"Oslo\"}"}}]}}]}"#,
+            "\n\ndata: [DONE]\n\n",
+        );
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_raw(events, "text/event-stream"))
+            .mount(&server)
+            .await;
+
+        let mut groq = GroqClient::new("key");
+        groq.base_url = server.uri();
+        let mut mistral = MistralClient::new("key");
+        mistral.base_url = server.uri();
+        let clients: Vec<(&str, Box<dyn LanguageModel>)> = vec![
+            ("groq", Box::new(groq)),
+            ("mistral", Box::new(mistral)),
+            (
+                "azure",
+                Box::new(AzureOpenAIClient::new(server.uri(), "key", "gpt-4o")),
+            ),
+            (
+                "together",
+                Box::new(TogetherClient::new("key").with_base_url(server.uri())),
+            ),
+            (
+                "fireworks",
+                Box::new(FireworksClient::new("key").with_base_url(server.uri())),
+            ),
+        ];
+        for (name, client) in clients {
+            let (deltas, mut progress) = mpsc::unbounded_channel();
+            let completion = client
+                .complete_chat_with_options(
+                    &[Message::user("Weather in Oslo?")],
+                    &[],
+                    true,
+                    &OutputFormat::Text,
+                    &CompletionOptions::default(),
+                    Some(deltas),
+                )
+                .await
+                .unwrap_or_else(|err| panic!("{name}: {err}"));
+            assert_eq!(
+                completion.tool_calls,
+                vec![ToolCall {
+                    id: Some("call_1".into()),
+                    name: "lookup".into(),
+                    arguments: json!({"city": "Oslo"}),
+                }],
+                "{name}"
+            );
+            assert!(
+                matches!(
+                    progress.try_recv(),
+                    Ok(ToolCallDelta::Started { index: 0, .. })
+                ),
+                "{name}"
+            );
+        }
+        let streamed = server.received_requests().await.unwrap();
+        assert!(streamed
+            .iter()
+            .all(|request| request.body_json::<Value>().unwrap()["stream"] == true));
+    }
+
+    #[tokio::test]
+    async fn sends_requests_through_configured_proxy() {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let proxy = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = proxy.local_addr().unwrap();
+        let received = tokio::spawn(async move {
+            let (mut socket, _) = proxy.accept().await.unwrap();
+            let mut buf = [0u8; 8192];
+            let n = socket.read(&mut buf).await.unwrap();
+            let body = r#"{"choices":[{"message":{"content":"via proxy"}}]}"#;
+            let response = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            );
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+
+        let http = HttpSettings::default()
+            .with_proxy(format!("http://{addr}"))
+            .with_connect_timeout(Duration::from_secs(5))
+            .with_user_agent("sayr-test/1.0")
+            .build()
+            .unwrap();
+        let mut client = OpenAIClient::new("sk-test").with_http_client(http);
+        client.base_url = "http://llm.internal.example/v1".into();
+
+        let completion = client
+            .complete_chat(&[Message::user("hi")], &[], false)
+            .await
+            .unwrap();
+        assert_eq!(completion.content.as_deref(), Some("via proxy"));
+
+        // A proxied plain-HTTP request carries the absolute target URL.
+        let request = received.await.unwrap();
+        assert!(request.starts_with("POST http://llm.internal.example/v1/chat/completions "));
+        assert!(request.to_lowercase().contains("user-agent: sayr-test/1.0"));
+
+        let internal_root = rcgen::generate_simple_self_signed(vec!["llm.internal".into()])
+            .unwrap()
+            .cert
+            .pem();
+        assert!(HttpSettings::default()
+            .with_root_certificate(internal_root)
+            .build()
+            .is_ok());
+        assert!(HttpSettings::default()
+            .with_root_certificate("not a certificate")
+            .build()
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn accumulates_streamed_cohere_tool_call() {
+        let events = [
+            r#"{"type":"message-start","id":"m1","delta":{"message":{"role":"assistant"}}}"#,
+            r#"{"type":"tool-plan-delta","delta":{"message":{"tool_plan":"I will look up the weather."}}}"#,
+            r#"{"type":"tool-call-start","index":0,"delta":{"message":{"tool_calls":{"id":"call_1","type":"function","function":{"name":"weather","arguments":""}}}}}"#,
+            r#"{"type":"tool-call-delta","index":0,"delta":{"message":{"tool_calls":{"function":{"arguments":"{\"city\":"}}}}}"#,
+            r#"{"type":"tool-call-delta","index":0,"delta":{"message":{"tool_calls":{"function":{"arguments":" \"Oslo\"}"}}}}}"#,
+            r#"{"type":"tool-call-end","index":0}"#,
+            r#"{"type":"message-end","delta":{"finish_reason":"TOOL_CALL"}}"#,
+        ];
+        let body: String = events
+            .iter()
+            .map(|event| format!("event: x\ndata: {event}\n\n"))
+            .collect();
+        let chunks = body
+            .as_bytes()
+            .chunks(37)
+            .map(|chunk| Ok::<_, String>(chunk.to_vec()))
+            .collect::<Vec<_>>();
+
+        let completion = read_cohere_stream(futures::stream::iter(chunks))
+            .await
+            .unwrap();
+        assert_eq!(completion.content, None);
+        assert_eq!(
+            completion.tool_calls,
+            vec![ToolCall {
+                id: Some("call_1".into()),
+                name: "weather".into(),
+                arguments: json!({"city": "Oslo"}),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn parses_event_split_across_two_chunks() {
+        let event =
+            "data: {\"type\":\"content_block_delta\",\"delta\":{\"text\":\"Caf\u{e9} ok\"}}\n\n";
+        // Cut inside the JSON and inside the two-byte `é`.
+        let split = event.find('\u{e9}').unwrap() + 1;
+        let chunks = vec![
+            Ok::<_, String>(event.as_bytes()[..split].to_vec()),
+            Ok(event.as_bytes()[split..].to_vec()),
+        ];
+
+        let (_, content, _) = read_anthropic_stream(futures::stream::iter(chunks), None)
+            .await
+            .unwrap();
+        assert_eq!(content, "Caf\u{e9} ok");
+
+        let mut buffer = SseBuffer::default();
+        assert!(buffer.feed(b"data: {\"a\":").is_empty());
+        assert_eq!(buffer.feed(b"1}\r\ndata: [DONE]\n"), vec!["{\"a\":1}"]);
+        assert_eq!(buffer.feed(b"data: {\"b\":2}"), Vec::<String>::new());
+        assert_eq!(buffer.finish(), vec!["{\"b\":2}"]);
+    }
+
+    #[test]
+    fn openai_compatible_hosts_build_from_env_with_defaults() {
+        std::env::set_var("TOGETHER_API_KEY", "together-key");
+        let together = TogetherClient::from_env().unwrap();
+        assert_eq!(together.api_key, "together-key");
+        assert_eq!(together.base_url, "https://api.together.xyz/v1");
+        assert_eq!(together.model, "meta-llama/Llama-3.3-70B-Instruct-Turbo");
+
+        std::env::set_var("FIREWORKS_API_KEY", "fireworks-key");
+        let fireworks = FireworksClient::from_env().unwrap();
+        assert_eq!(fireworks.api_key, "fireworks-key");
+        assert_eq!(fireworks.base_url, "https://api.fireworks.ai/inference/v1");
+        assert_eq!(
+            fireworks.model,
+            "accounts/fireworks/models/llama-v3p1-70b-instruct"
+        );
+    }
+
+    #[test]
+    fn sends_tool_choice_and_stop_sequences_to_each_provider() {
+        let tools = [ToolDescription {
+            name: "weather".into(),
+            description: "Current weather for a city".into(),
+            parameters: None,
+        }];
+        let other = ToolDescription {
+            name: "clock".into(),
+            description: "Current time".into(),
+            parameters: None,
+        };
+        let messages = [Message::user("Weather in Paris?")];
+        let forced = CompletionOptions {
+            tool_choice: ToolChoice::Function("weather".into()),
+            stop: vec!["END".into()],
+        };
+        let text = OutputFormat::Text;
+
+        let anthropic_config: ModelConfig = serde_json::from_value(json!({
+            "provider": "anthropic",
+            "model": "claude-3-5-sonnet-latest",
+            "api_key": "test-key",
+        }))
+        .unwrap();
+        let anthropic_client = AnthropicClient::from_config(&anthropic_config).unwrap();
+        let anthropic = anthropic_client
+            .payload(&messages, &tools, false, &forced)
+            .unwrap();
+        assert_eq!(
+            anthropic["tool_choice"],
+            json!({"type": "tool", "name": "weather"})
+        );
+        assert_eq!(anthropic["stop_sequences"], json!(["END"]));
+        // Extended thinking only allows the model to choose its tools itself.
+        let thinking = anthropic_client.with_reasoning_effort(ReasoningEffort::Low);
+        assert!(thinking.payload(&messages, &tools, false, &forced).is_err());
+
+        let gemini = gemini_client().build_payload(&messages, &tools, &forced);
+        assert_eq!(
+            gemini["toolConfig"]["functionCallingConfig"],
+            json!({"mode": "ANY", "allowedFunctionNames": ["weather"]})
+        );
+        assert_eq!(gemini["generationConfig"]["stopSequences"], json!(["END"]));
+
+        let both = [tools[0].clone(), other];
+        let cohere = CohereClient::new("key").payload(&messages, &both, false, &forced);
+        assert_eq!(cohere["tool_choice"], "REQUIRED");
+        assert_eq!(cohere["tools"].as_array().unwrap().len(), 1);
+        assert_eq!(cohere["stop_sequences"], json!(["END"]));
+
+        let function = json!({"type": "function", "function": {"name": "weather"}});
+        let groq = GroqClient::new("key").payload(&messages, &tools, false, &text, &forced);
+        assert_eq!(groq["tool_choice"], function);
+        assert_eq!(groq["stop"], json!(["END"]));
+        let mistral = MistralClient::new("key").payload(&messages, &tools, false, &text, &forced);
+        assert_eq!(mistral["tool_choice"], function);
+        assert_eq!(mistral["stop"], json!(["END"]));
+
+        let ollama = OllamaClient::new();
+        assert!(ollama.payload(&messages, &tools, false, &forced).is_err());
+        let silent = CompletionOptions {
+            tool_choice: ToolChoice::None,
+            stop: vec!["END".into()],
+        };
+        let body = ollama.payload(&messages, &tools, false, &silent).unwrap();
+        assert!(body.get("tools").is_none());
+        assert_eq!(body["options"]["stop"], json!(["END"]));
+    }
+
+    #[test]
+    fn pairs_mistral_tool_results_with_their_calls() {
+        let mut asked = Message::assistant("Calling tool `weather`");
+        asked.tool_call = Some(ToolCall {
+            id: Some("call_7".into()),
+            name: "weather".into(),
+            arguments: json!({"city": "Paris"}),
+        });
+        let answered =
+            Message::tool_with_call("weather", json!({"temp": 18}), Some("call_7".into()));
+        let payload = MistralClient::new("key").payload(
+            &[asked, answered],
+            &[],
+            false,
+            &OutputFormat::Text,
+            &CompletionOptions::default(),
+        );
+
+        let (call, result) = (&payload["messages"][0], &payload["messages"][1]);
+        assert_eq!(call["tool_calls"][0]["id"], "call_7");
+        assert_eq!(result["tool_call_id"], call["tool_calls"][0]["id"]);
+        assert_eq!(result["content"], r#"{"temp":18}"#);
+    }
+
+    fn gemini_client() -> GeminiClient {
+        let config: ModelConfig = serde_json::from_value(json!({
+            "provider": "gemini",
+            "model": "gemini-1.5-flash",
+            "api_key": "test-key",
+        }))
+        .unwrap();
+        GeminiClient::from_config(&config).unwrap()
+    }
+
+    #[test]
+    fn sends_gemini_system_prompt_as_system_instruction() {
+        let payload = gemini_client().build_payload(
+            &[
+                Message::system("You are terse."),
+                Message::user("Weather in Paris?"),
+                Message::tool("weather", json!("sunny")),
+            ],
+            &[],
+            &CompletionOptions::default(),
+        );
+
+        assert_eq!(
+            payload["systemInstruction"],
+            json!({"parts": [{"text": "You are terse."}]})
+        );
+        let contents = payload["contents"].as_array().unwrap();
+        assert_eq!(contents.len(), 2);
+        assert!(contents.iter().all(|c| c["role"] != "system"));
+        assert_eq!(contents[0]["parts"][0]["text"], "Weather in Paris?");
+        assert_eq!(
+            contents[1]["parts"][0]["functionResponse"],
+            json!({"name": "weather", "response": {"content": "sunny"}})
+        );
+    }
+
+    #[test]
+    fn round_trips_gemini_function_calls() {
+        let tool = ToolDescription {
+            name: "weather".into(),
+            description: "Current weather for a city".into(),
+            parameters: Some(json!({
+                "type": "object",
+                "properties": {"city": {"type": "string"}},
+                "required": ["city"],
+            })),
+        };
+        let call = ToolCall {
+            id: None,
+            name: "weather".into(),
+            arguments: json!({"city": "Paris"}),
+        };
+        let mut assistant = Message::assistant("Calling tool `weather`");
+        assistant.tool_call = Some(call);
+
+        let payload = gemini_client().build_payload(
+            &[
+                Message::user("Weather in Paris?"),
+                assistant,
+                Message::tool("weather", json!({"forecast": "sunny"})),
+            ],
+            &[tool],
+            &CompletionOptions::default(),
+        );
+        let declarations = &payload["tools"][0]["functionDeclarations"];
+        assert_eq!(declarations[0]["name"], "weather");
+        assert_eq!(declarations[0]["parameters"]["required"], json!(["city"]));
+        assert_eq!(
+            payload["contents"][1]["parts"][0]["functionCall"],
+            json!({"name": "weather", "args": {"city": "Paris"}})
+        );
+        assert_eq!(
+            payload["contents"][2]["parts"][0]["functionResponse"]["response"],
+            json!({"forecast": "sunny"})
+        );
+
+        let response: GeminiResponse = serde_json::from_value(json!({
+            "candidates": [{"content": {"role": "model", "parts": [
+                {"functionCall": {"name": "weather", "args": {"city": "Lyon"}}}
+            ]}}]
+        }))
+        .unwrap();
+        let completion = GeminiClient::parse_completion(response);
+        assert!(completion.content.is_none());
+        assert_eq!(completion.tool_calls.len(), 1);
+        assert_eq!(completion.tool_calls[0].name, "weather");
+        assert_eq!(completion.tool_calls[0].arguments, json!({"city": "Lyon"}));
+    }
+
+    #[tokio::test]
+    async fn inlines_local_images_only_from_an_explicit_sandbox() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("cat.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        let mut message = Message::user("what is this?");
+        message.attachments.push(Attachment {
+            kind: AttachmentKind::Image,
+            uri: "file:cat.png".into(),
+            description: None,
+            media_type: None,
+        });
+        let messages = [message];
+
+        let untouched = ImageInlining::default().apply(&messages).await;
+        assert_eq!(untouched[0].attachments[0].uri, "file:cat.png");
+
+        let images = ImageInlining {
+            local: Some(FsSandbox::new(dir.path()).unwrap()),
+            ..ImageInlining::default()
+        };
+        let inlined = images.apply(&messages).await;
+        assert_eq!(
+            inlined[0].attachments[0].uri,
+            "data:image/png;base64,iVBORw0KGgo="
+        );
+        // Later steps reuse the inlined copy instead of reading the file again.
+        std::fs::remove_file(dir.path().join("cat.png")).unwrap();
+        let again = images.apply(&messages).await;
+        assert_eq!(again[0].attachments[0].uri, inlined[0].attachments[0].uri);
+    }
+}
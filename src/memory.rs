@@ -1,20 +1,43 @@
+use std::sync::Arc;
+
 use crate::message::Message;
 #[cfg(feature = "persistence")]
 use crate::storage::ConversationStore;
 
-/// In-memory transcript storage.
-#[derive(Default, Clone, Debug)]
+/// In-memory transcript storage. Messages are shared copy-on-write, so clones and forks
+/// are cheap until one side is modified.
+#[derive(Default, Clone, Debug, PartialEq)]
 pub struct ConversationMemory {
-    messages: Vec<Message>,
+    messages: Arc<Vec<Message>>,
 }
 
 impl ConversationMemory {
     pub fn with_messages(messages: Vec<Message>) -> Self {
-        Self { messages }
+        Self {
+            messages: Arc::new(messages),
+        }
     }
 
     pub fn push(&mut self, message: Message) {
-        self.messages.push(message);
+        Arc::make_mut(&mut self.messages).push(message);
+    }
+
+    /// Snapshot the transcript so it can continue independently of `self`.
+    pub fn fork(&self) -> ConversationMemory {
+        self.clone()
+    }
+
+    /// New memory holding the first `index` messages (all of them if `index` is past the end).
+    pub fn branch_from(&self, index: usize) -> ConversationMemory {
+        let end = index.min(self.messages.len());
+        Self::with_messages(self.messages[..end].to_vec())
+    }
+
+    /// Roll back to the first `len` messages, dropping everything after.
+    pub fn truncate_to(&mut self, len: usize) {
+        if len < self.messages.len() {
+            Arc::make_mut(&mut self.messages).truncate(len);
+        }
     }
 
     pub fn iter(&self) -> impl DoubleEndedIterator<Item = &Message> + '_ {
@@ -28,6 +51,17 @@ impl ConversationMemory {
     pub fn is_empty(&self) -> bool {
         self.messages.is_empty()
     }
+
+    /// Serialize the transcript as a JSON array of messages, including tool calls, tool
+    /// results and attachments.
+    pub fn to_json(&self) -> crate::Result<String> {
+        Ok(serde_json::to_string(self.messages.as_ref())?)
+    }
+
+    /// Rebuild a memory from the output of [`ConversationMemory::to_json`].
+    pub fn from_json(json: &str) -> crate::Result<Self> {
+        Ok(Self::with_messages(serde_json::from_str(json)?))
+    }
 }
 
 #[cfg(feature = "persistence")]
@@ -310,6 +344,64 @@ impl MemoryStrategy for TokenLimitedMemoryStrategy {
 mod tests {
     use super::*;
 
+    #[test]
+    fn round_trips_transcript_through_json() {
+        use crate::message::{Attachment, AttachmentKind, ToolCall};
+
+        let mut memory = ConversationMemory::default();
+        let mut question = Message::user("What's in this picture, and what's the weather?");
+        question.attachments.push(Attachment {
+            kind: AttachmentKind::Image,
+            uri: "https://example.com/cat.png".into(),
+            description: Some("a cat".into()),
+            media_type: None,
+        });
+        memory.push(question);
+        memory.push(Message {
+            tool_call: Some(ToolCall {
+                id: Some("call-1".into()),
+                name: "weather".into(),
+                arguments: serde_json::json!({"city": "Paris"}),
+            }),
+            ..Message::assistant("Calling tool `weather`")
+        });
+        memory.push(Message::tool_with_call(
+            "weather",
+            serde_json::json!({"forecast": "sunny", "high": 24}),
+            Some("call-1".into()),
+        ));
+        memory.push(Message::assistant("A cat, and it's sunny in Paris."));
+
+        let json = memory.to_json().unwrap();
+        let restored = ConversationMemory::from_json(&json).unwrap();
+
+        assert_eq!(restored, memory);
+        assert_eq!(restored.to_json().unwrap(), json);
+        assert!(ConversationMemory::from_json("{\"not\": \"an array\"}").is_err());
+    }
+
+    #[test]
+    fn forks_branches_and_truncates_without_touching_the_original() {
+        let mut memory = ConversationMemory::default();
+        memory.push(Message::user("one"));
+        memory.push(Message::assistant("two"));
+        memory.push(Message::user("three"));
+
+        let mut fork = memory.fork();
+        fork.push(Message::assistant("four"));
+        assert_eq!(memory.len(), 3);
+        assert_eq!(fork.len(), 4);
+
+        let branch = memory.branch_from(1);
+        assert_eq!(branch.len(), 1);
+        assert_eq!(branch.iter().next().unwrap().content, "one");
+        assert_eq!(memory.branch_from(10).len(), 3);
+
+        fork.truncate_to(2);
+        assert_eq!(fork.len(), 2);
+        assert_eq!(memory.len(), 3);
+    }
+
     #[test]
     fn test_windowed_strategy() {
         let messages = vec![
@@ -1,5 +1,24 @@
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use base64::Engine;
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
 
+use crate::error::{AgnoError, Result};
+use crate::tools::FsSandbox;
+
+/// Largest attachment [`Attachment::fetch_bytes`] downloads.
+const MAX_ATTACHMENT_BYTES: usize = 20 * 1024 * 1024;
+
+/// Time allowed for an attachment download, from connecting to the last byte.
+const ATTACHMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redirects followed when downloading an attachment. Every hop is checked like the first.
+const MAX_ATTACHMENT_REDIRECTS: usize = 5;
+
 /// A non-textual payload that can accompany a message.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Attachment {
@@ -11,6 +30,257 @@ pub struct Attachment {
     pub media_type: Option<String>,
 }
 
+impl Attachment {
+    /// Load the attachment's bytes along with their media type. `http(s):` URIs are
+    /// downloaded and `data:` URIs decoded; `file:` URIs are refused, since reading them
+    /// needs a sandbox (see [`Attachment::fetch_bytes_in`]).
+    ///
+    /// Downloads time out after 30 seconds, stop at 20 MiB and only reach public
+    /// addresses, so a URI cannot point the process at loopback, private or link-local
+    /// hosts such as cloud metadata endpoints, directly or through a redirect.
+    ///
+    /// The media type is the one the source declares (the `data:` prefix or the
+    /// `Content-Type` header), else [`Attachment::media_type`], else a guess from the file
+    /// extension and leading bytes, falling back to `application/octet-stream`.
+    pub async fn fetch_bytes(&self) -> Result<(Vec<u8>, String)> {
+        self.fetch(None).await
+    }
+
+    /// Like [`Attachment::fetch_bytes`], but also reads `file:` URIs, which must resolve
+    /// inside `sandbox`.
+    pub async fn fetch_bytes_in(&self, sandbox: &FsSandbox) -> Result<(Vec<u8>, String)> {
+        self.fetch(Some(sandbox)).await
+    }
+
+    async fn fetch(&self, sandbox: Option<&FsSandbox>) -> Result<(Vec<u8>, String)> {
+        let uri = self.uri.as_str();
+        let (bytes, declared) = if let Some(data_uri) = uri.strip_prefix("data:") {
+            decode_data_uri(data_uri)?
+        } else if uri.starts_with("http://") || uri.starts_with("https://") {
+            download(uri).await?
+        } else if let Some(path) = uri.strip_prefix("file:") {
+            let Some(sandbox) = sandbox else {
+                return Err(AgnoError::Protocol(format!(
+                    "attachment `{uri}` is a local file; read it with `fetch_bytes_in` and a sandbox"
+                )));
+            };
+            let path = path.strip_prefix("//").unwrap_or(path);
+            let path = urlencoding::decode(path)
+                .map_err(|err| AgnoError::Protocol(format!("invalid file uri `{uri}`: {err}")))?;
+            let resolved = sandbox.resolve(&path)?;
+            let bytes = tokio::fs::read(&resolved).await?;
+            check_size(uri, bytes.len())?;
+            (bytes, None)
+        } else {
+            return Err(AgnoError::Protocol(format!(
+                "unsupported attachment uri `{uri}`; expected http(s):, data: or file:"
+            )));
+        };
+
+        let media_type = declared
+            .or_else(|| self.media_type.clone())
+            .or_else(|| media_type_from_extension(uri).map(str::to_string))
+            .or_else(|| media_type_from_bytes(&bytes).map(str::to_string))
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        Ok((bytes, media_type))
+    }
+}
+
+/// Decode the part of a `data:` URI after the scheme, returning the bytes and the media
+/// type it names, if any.
+fn decode_data_uri(data_uri: &str) -> Result<(Vec<u8>, Option<String>)> {
+    let (header, data) = data_uri
+        .split_once(',')
+        .ok_or_else(|| AgnoError::Protocol("data uri is missing its `,` separator".into()))?;
+    let (header, is_base64) = match header.strip_suffix(";base64") {
+        Some(header) => (header, true),
+        None => (header, false),
+    };
+    let bytes = if is_base64 {
+        base64::engine::general_purpose::STANDARD
+            .decode(data.trim())
+            .map_err(|err| AgnoError::Protocol(format!("invalid base64 in data uri: {err}")))?
+    } else {
+        urlencoding::decode_binary(data.as_bytes()).into_owned()
+    };
+    let media_type = header.split(';').next().unwrap_or_default().trim();
+    let media_type = (!media_type.is_empty()).then(|| media_type.to_ascii_lowercase());
+    Ok((bytes, media_type))
+}
+
+async fn download(uri: &str) -> Result<(Vec<u8>, Option<String>)> {
+    let failed = |err: reqwest::Error| {
+        // reqwest leaves the cause, such as a refused address, out of its own message.
+        let mut message = err.to_string();
+        let mut source = std::error::Error::source(&err);
+        while let Some(cause) = source {
+            message = format!("{message}: {cause}");
+            source = cause.source();
+        }
+        AgnoError::Protocol(format!("failed to fetch `{uri}`: {message}"))
+    };
+    let url = reqwest::Url::parse(uri)
+        .map_err(|err| AgnoError::Protocol(format!("invalid attachment uri `{uri}`: {err}")))?;
+    check_public_host(&url).map_err(AgnoError::Protocol)?;
+    let response = download_client()
+        .get(url)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status())
+        .map_err(failed)?;
+    if let Some(length) = response.content_length() {
+        check_size(uri, length as usize)?;
+    }
+    // Servers that don't know better label everything `application/octet-stream`; let the
+    // other sources name those.
+    let media_type = response
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty() && value != "application/octet-stream");
+    // `Content-Length` is only a hint: count the bytes as they arrive.
+    let mut bytes = Vec::new();
+    let mut body = response.bytes_stream();
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(failed)?;
+        check_size(uri, bytes.len() + chunk.len())?;
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok((bytes, media_type))
+}
+
+/// The client attachments are downloaded with: bounded in time, and unable to reach
+/// non-public addresses either by name or through redirects.
+fn download_client() -> &'static reqwest::Client {
+    static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+    CLIENT.get_or_init(|| {
+        let redirects = reqwest::redirect::Policy::custom(|attempt| {
+            if attempt.previous().len() >= MAX_ATTACHMENT_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            match check_public_host(attempt.url()) {
+                Ok(()) => attempt.follow(),
+                Err(err) => attempt.error(err),
+            }
+        });
+        reqwest::Client::builder()
+            .timeout(ATTACHMENT_TIMEOUT)
+            .redirect(redirects)
+            .dns_resolver(Arc::new(PublicResolver))
+            .build()
+            .expect("attachment http client")
+    })
+}
+
+/// Rejects URLs whose host is a non-public IP literal; host names are checked when
+/// [`PublicResolver`] resolves them.
+fn check_public_host(url: &reqwest::Url) -> std::result::Result<(), String> {
+    let host = url.host_str().unwrap_or_default();
+    match host.trim_start_matches('[').trim_end_matches(']').parse() {
+        Ok(ip) if !is_public(ip) => Err(format!("`{host}` is not a public address")),
+        _ => Ok(()),
+    }
+}
+
+/// Resolves host names to their public addresses only, failing when there are none.
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|addr| is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(format!("`{}` has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            // 100.64.0.0/10 is carrier-grade NAT, which std does not flag.
+            let shared = first == 100 && (second & 0xc0) == 64;
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || shared)
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => {
+                let first = ip.segments()[0];
+                // fc00::/7 is unique-local and fe80::/10 link-local.
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || (first & 0xfe00) == 0xfc00
+                    || (first & 0xffc0) == 0xfe80)
+            }
+        },
+    }
+}
+
+fn check_size(uri: &str, len: usize) -> Result<()> {
+    if len > MAX_ATTACHMENT_BYTES {
+        return Err(AgnoError::Protocol(format!(
+            "attachment `{uri}` is {len} bytes, over the {MAX_ATTACHMENT_BYTES}-byte limit"
+        )));
+    }
+    Ok(())
+}
+
+/// Media type for the extension of `uri`'s path, ignoring any query or fragment.
+fn media_type_from_extension(uri: &str) -> Option<&'static str> {
+    if uri.starts_with("data:") {
+        return None;
+    }
+    let path = uri.split(['?', '#']).next().unwrap_or(uri);
+    let extension = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    Some(match extension.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "bmp" => "image/bmp",
+        "pdf" => "application/pdf",
+        "json" => "application/json",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "ogg" => "audio/ogg",
+        "mp4" => "video/mp4",
+        "webm" => "video/webm",
+        "mov" => "video/quicktime",
+        _ => return None,
+    })
+}
+
+/// Media type for a few common formats, recognised by their signature bytes.
+fn media_type_from_bytes(bytes: &[u8]) -> Option<&'static str> {
+    match bytes {
+        [0x89, b'P', b'N', b'G', ..] => Some("image/png"),
+        [0xFF, 0xD8, 0xFF, ..] => Some("image/jpeg"),
+        [b'G', b'I', b'F', b'8', ..] => Some("image/gif"),
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some("image/webp"),
+        [b'%', b'P', b'D', b'F', ..] => Some("application/pdf"),
+        _ => None,
+    }
+}
+
 /// Types of attachments supported by the runtime.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum AttachmentKind {
@@ -128,3 +398,98 @@ impl Message {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(uri: &str, media_type: Option<&str>) -> Attachment {
+        Attachment {
+            kind: AttachmentKind::Image,
+            uri: uri.to_string(),
+            description: None,
+            media_type: media_type.map(str::to_string),
+        }
+    }
+
+    #[tokio::test]
+    async fn decodes_data_uris() {
+        let (bytes, media_type) = image("data:image/png;base64,iVBORw0KGgo=", None)
+            .fetch_bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"\x89PNG\r\n\x1a\n");
+        assert_eq!(media_type, "image/png");
+
+        let (bytes, media_type) = image("data:text/plain;charset=utf-8,hello%20world", None)
+            .fetch_bytes()
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"hello world");
+        assert_eq!(media_type, "text/plain");
+
+        assert!(image("data:image/png;base64,not base64!", None)
+            .fetch_bytes()
+            .await
+            .is_err());
+        assert!(image("ftp://example.com/cat.png", None)
+            .fetch_bytes()
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn resolves_the_media_type() {
+        // An untyped data uri falls back to the declared type, then to the content.
+        let (_, media_type) = image("data:;base64,/9j/4AAQ", Some("image/jpeg"))
+            .fetch_bytes()
+            .await
+            .unwrap();
+        assert_eq!(media_type, "image/jpeg");
+        let (_, media_type) = image("data:;base64,/9j/4AAQ", None)
+            .fetch_bytes()
+            .await
+            .unwrap();
+        assert_eq!(media_type, "image/jpeg");
+        let (_, media_type) = image("data:,plain", None).fetch_bytes().await.unwrap();
+        assert_eq!(media_type, "application/octet-stream");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("chart.webp"), b"not really webp").unwrap();
+        let sandbox = FsSandbox::new(dir.path()).unwrap();
+        let (bytes, media_type) = image("file:chart.webp", None)
+            .fetch_bytes_in(&sandbox)
+            .await
+            .unwrap();
+        assert_eq!(bytes, b"not really webp");
+        assert_eq!(media_type, "image/webp");
+
+        let outside = image("file:../secret.png", None)
+            .fetch_bytes_in(&sandbox)
+            .await
+            .unwrap_err();
+        assert!(
+            outside.to_string().contains("escapes the sandbox"),
+            "{outside}"
+        );
+        // Without a sandbox, local files are not read at all.
+        assert!(image("file:chart.webp", None).fetch_bytes().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn refuses_to_download_from_non_public_hosts() {
+        for uri in [
+            "http://127.0.0.1:9/cat.png",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/cat.png",
+            "http://[::1]:9/cat.png",
+            "http://[::ffff:192.168.1.1]/cat.png",
+            "http://localhost:9/cat.png",
+        ] {
+            let err = image(uri, None).fetch_bytes().await.unwrap_err();
+            assert!(err.to_string().contains("public address"), "{uri}: {err}");
+        }
+        assert!(is_public("93.184.216.34".parse().unwrap()));
+        assert!(is_public("2606:2800:220:1::".parse().unwrap()));
+    }
+}
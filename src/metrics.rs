@@ -1,6 +1,10 @@
 //! Metrics tracking and evaluation.
 #![allow(dead_code)]
 
+#[cfg(feature = "telemetry")]
+use std::collections::BTreeMap;
+#[cfg(feature = "telemetry")]
+use std::fmt::Write as _;
 #[cfg(feature = "telemetry")]
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
@@ -19,6 +23,8 @@ use serde::{Deserialize, Serialize};
 #[cfg(feature = "telemetry")]
 use sysinfo::System;
 
+#[cfg(feature = "telemetry")]
+use crate::llm::TokenUsage;
 #[cfg(feature = "telemetry")]
 use crate::telemetry::TelemetryLabels;
 
@@ -29,10 +35,41 @@ pub struct EvaluationReport {
     pub tool_calls: usize,
     pub failures: usize,
     pub success: bool,
+    /// Model spend in USD, priced by the agent's [`crate::CostModel`].
+    #[serde(default)]
+    pub cost_usd: f64,
+    /// Ranking quality over a labeled query set, filled in by
+    /// [`crate::KnowledgeBase::evaluate_batch`].
+    #[serde(default)]
+    pub retrieval: Option<RetrievalMetrics>,
     #[cfg(feature = "telemetry")]
     pub labels: TelemetryLabels,
 }
 
+/// Retrieval metrics averaged across every evaluated query.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct RetrievalMetrics {
+    pub mean_precision: f32,
+    pub mean_recall: f32,
+    /// Mean reciprocal rank of the first relevant document.
+    pub mrr: f32,
+    /// Mean nDCG with binary relevance, cut off at `k`.
+    pub ndcg: f32,
+    pub k: usize,
+    pub queries: Vec<QueryRetrievalMetrics>,
+}
+
+/// Retrieval metrics for a single labeled query.
+#[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
+pub struct QueryRetrievalMetrics {
+    pub query: String,
+    pub retrieved_ids: Vec<String>,
+    pub precision: f32,
+    pub recall: f32,
+    pub reciprocal_rank: f32,
+    pub ndcg: f32,
+}
+
 impl EvaluationReport {
     pub fn success_rate(reports: &[Self]) -> f32 {
         if reports.is_empty() {
@@ -43,14 +80,221 @@ impl EvaluationReport {
     }
 }
 
+/// Upper bounds (in milliseconds) of the run latency histogram buckets.
+#[cfg(feature = "telemetry")]
+pub const LATENCY_BUCKETS_MS: [f64; 9] = [
+    50.0, 100.0, 250.0, 500.0, 1000.0, 2500.0, 5000.0, 10000.0, 30000.0,
+];
+
+/// Run latency distribution for a single agent.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LatencyHistogram {
+    /// Non-cumulative counts, one per entry in [`LATENCY_BUCKETS_MS`] plus a final overflow bucket.
+    pub buckets: Vec<u64>,
+    pub sum_ms: f64,
+    pub count: u64,
+}
+
+#[cfg(feature = "telemetry")]
+impl LatencyHistogram {
+    fn observe(&mut self, millis: f64) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        }
+        let index = LATENCY_BUCKETS_MS
+            .iter()
+            .position(|bound| millis <= *bound)
+            .unwrap_or(LATENCY_BUCKETS_MS.len());
+        self.buckets[index] += 1;
+        self.sum_ms += millis;
+        self.count += 1;
+    }
+
+    fn merge(&mut self, other: &Self) {
+        if self.buckets.is_empty() {
+            self.buckets = vec![0; LATENCY_BUCKETS_MS.len() + 1];
+        }
+        for (bucket, count) in self.buckets.iter_mut().zip(&other.buckets) {
+            *bucket += count;
+        }
+        self.sum_ms += other.sum_ms;
+        self.count += other.count;
+    }
+
+    /// Upper bound in milliseconds of the bucket holding the `q` quantile. Runs in the
+    /// overflow bucket report the largest bound, so read that value as "at least".
+    pub fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                let last = LATENCY_BUCKETS_MS.len() - 1;
+                return Some(LATENCY_BUCKETS_MS[index.min(last)]);
+            }
+        }
+        None
+    }
+}
+
+/// Point-in-time aggregate of everything a [`MetricsTracker`] has recorded.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsSnapshot {
+    pub runs_started: u64,
+    pub runs_succeeded: u64,
+    pub runs_failed: u64,
+    pub tool_calls: BTreeMap<String, u64>,
+    pub failures: u64,
+    /// Latency histograms keyed by agent (the run's workflow label, or `default`).
+    pub latency: BTreeMap<String, LatencyHistogram>,
+    /// Model spend in USD keyed by tenant (or `default`).
+    pub cost_by_tenant: BTreeMap<String, f64>,
+    /// Model spend in USD keyed by `provider/model`.
+    pub cost_by_model: BTreeMap<String, f64>,
+}
+
+/// Headline throughput, latency and cost figures for dashboards.
+#[cfg(feature = "telemetry")]
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct MetricsSummary {
+    pub runs: u64,
+    pub runs_succeeded: u64,
+    pub runs_failed: u64,
+    /// Share of finished runs that produced a reply, from 0 to 1.
+    pub success_rate: f64,
+    pub p50_latency_ms: Option<f64>,
+    pub p95_latency_ms: Option<f64>,
+    pub tool_calls: BTreeMap<String, u64>,
+    pub total_cost_usd: f64,
+}
+
+#[cfg(feature = "telemetry")]
+impl MetricsSnapshot {
+    /// Summarize the snapshot, pooling latency across agents.
+    pub fn summary(&self) -> MetricsSummary {
+        let finished = self.runs_succeeded + self.runs_failed;
+        let mut latency = LatencyHistogram::default();
+        for histogram in self.latency.values() {
+            latency.merge(histogram);
+        }
+        MetricsSummary {
+            runs: self.runs_started,
+            runs_succeeded: self.runs_succeeded,
+            runs_failed: self.runs_failed,
+            success_rate: if finished == 0 {
+                0.0
+            } else {
+                self.runs_succeeded as f64 / finished as f64
+            },
+            p50_latency_ms: latency.quantile(0.5),
+            p95_latency_ms: latency.quantile(0.95),
+            tool_calls: self.tool_calls.clone(),
+            total_cost_usd: self.cost_by_tenant.values().sum(),
+        }
+    }
+
+    /// Render the snapshot in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let counters = [
+            (
+                "sayr_runs_started_total",
+                "Agent runs started.",
+                self.runs_started,
+            ),
+            (
+                "sayr_runs_succeeded_total",
+                "Agent runs that produced a reply.",
+                self.runs_succeeded,
+            ),
+            (
+                "sayr_runs_failed_total",
+                "Agent runs that ended in an error.",
+                self.runs_failed,
+            ),
+            (
+                "sayr_failures_total",
+                "Tool and model failures recorded during runs.",
+                self.failures,
+            ),
+        ];
+        for (name, help, value) in counters {
+            let _ = writeln!(out, "# HELP {name} {help}");
+            let _ = writeln!(out, "# TYPE {name} counter");
+            let _ = writeln!(out, "{name} {value}");
+        }
+
+        let _ = writeln!(out, "# HELP sayr_tool_calls_total Tool calls by tool name.");
+        let _ = writeln!(out, "# TYPE sayr_tool_calls_total counter");
+        for (tool, count) in &self.tool_calls {
+            let _ = writeln!(
+                out,
+                "sayr_tool_calls_total{{tool=\"{}\"}} {count}",
+                escape_label(tool)
+            );
+        }
+
+        let name = "sayr_run_duration_milliseconds";
+        let _ = writeln!(out, "# HELP {name} Agent run latency in milliseconds.");
+        let _ = writeln!(out, "# TYPE {name} histogram");
+        for (agent, histogram) in &self.latency {
+            let agent = escape_label(agent);
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS_MS.iter().zip(&histogram.buckets) {
+                cumulative += count;
+                let _ = writeln!(
+                    out,
+                    "{name}_bucket{{agent=\"{agent}\",le=\"{bound}\"}} {cumulative}"
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{name}_bucket{{agent=\"{agent}\",le=\"+Inf\"}} {}",
+                histogram.count
+            );
+            let _ = writeln!(out, "{name}_sum{{agent=\"{agent}\"}} {}", histogram.sum_ms);
+            let _ = writeln!(out, "{name}_count{{agent=\"{agent}\"}} {}", histogram.count);
+        }
+
+        let _ = writeln!(
+            out,
+            "# HELP sayr_cost_usd_total Model spend in USD by tenant."
+        );
+        let _ = writeln!(out, "# TYPE sayr_cost_usd_total counter");
+        for (tenant, cost) in &self.cost_by_tenant {
+            let _ = writeln!(
+                out,
+                "sayr_cost_usd_total{{tenant=\"{}\"}} {cost}",
+                escape_label(tenant)
+            );
+        }
+        out
+    }
+}
+
+#[cfg(feature = "telemetry")]
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
 #[cfg(feature = "telemetry")]
 #[derive(Clone)]
 pub struct MetricsTracker {
     reports: Arc<Mutex<Vec<EvaluationReport>>>,
+    aggregate: Arc<Mutex<MetricsSnapshot>>,
     meter: Meter,
     run_counter: Counter<u64>,
     tool_call_counter: Counter<u64>,
     failure_counter: Counter<u64>,
+    cost_counter: Counter<f64>,
     duration_histogram: Histogram<f64>,
 }
 
@@ -70,16 +314,22 @@ impl Default for MetricsTracker {
             .u64_counter("failure_total")
             .with_description("Failures")
             .init();
+        let cost_counter = meter
+            .f64_counter("cost_usd_total")
+            .with_description("Model spend in USD")
+            .init();
         let duration_histogram = meter
             .f64_histogram("run_duration_ms")
             .with_description("Run durations in milliseconds")
             .init();
         Self {
             reports: Arc::new(Mutex::new(Vec::new())),
+            aggregate: Arc::new(Mutex::new(MetricsSnapshot::default())),
             meter,
             run_counter,
             tool_call_counter,
             failure_counter,
+            cost_counter,
             duration_histogram,
         }
     }
@@ -89,36 +339,62 @@ impl Default for MetricsTracker {
 impl MetricsTracker {
     pub fn start_run(&self, labels: TelemetryLabels) -> RunGuard {
         self.run_counter.add(1, &labels.as_attributes());
+        self.aggregate.lock().unwrap().runs_started += 1;
         RunGuard {
             start: Instant::now(),
             tool_calls: 0,
             failures: 0,
+            cost_usd: 0.0,
             metrics: self.clone(),
             system: System::new_all(),
             labels,
+            finished: false,
         }
     }
 
     pub fn reports(&self) -> Vec<EvaluationReport> {
         self.reports.lock().unwrap().clone()
     }
+
+    /// Aggregate counters and latency histograms recorded so far.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        self.aggregate.lock().unwrap().clone()
+    }
+
+    /// Model spend in USD recorded so far, keyed by tenant.
+    pub fn cost_by_tenant(&self) -> BTreeMap<String, f64> {
+        self.aggregate.lock().unwrap().cost_by_tenant.clone()
+    }
 }
 
+/// Measures one agent run. A guard dropped without [`RunGuard::finish`], because the run
+/// ended in an error, was cancelled or timed out, records the run as failed.
 #[cfg(feature = "telemetry")]
 pub struct RunGuard {
     start: Instant,
     tool_calls: usize,
     failures: usize,
+    cost_usd: f64,
     metrics: MetricsTracker,
     system: System,
     labels: TelemetryLabels,
+    finished: bool,
 }
 
 #[cfg(feature = "telemetry")]
 impl RunGuard {
     pub fn record_tool_call(&mut self, tool: impl Into<String>) {
         self.tool_calls += 1;
-        let labels = self.labels.clone().with_tool(tool.into());
+        let tool = tool.into();
+        *self
+            .metrics
+            .aggregate
+            .lock()
+            .unwrap()
+            .tool_calls
+            .entry(tool.clone())
+            .or_default() += 1;
+        let labels = self.labels.clone().with_tool(tool);
         self.metrics
             .tool_call_counter
             .add(1, &labels.as_attributes());
@@ -131,21 +407,71 @@ impl RunGuard {
             None => self.labels.clone(),
         };
         self.metrics.failure_counter.add(1, &labels.as_attributes());
+        self.metrics.aggregate.lock().unwrap().failures += 1;
+    }
+
+    /// Add the cost of one completion, attributed to the run's tenant and the usage's model.
+    pub fn record_cost(&mut self, usage: &TokenUsage, cost_usd: f64) {
+        self.cost_usd += cost_usd;
+        let model = format!("{}/{}", usage.provider, usage.model);
+        {
+            let mut aggregate = self.metrics.aggregate.lock().unwrap();
+            let tenant = self
+                .labels
+                .tenant
+                .clone()
+                .unwrap_or_else(|| "default".into());
+            *aggregate.cost_by_tenant.entry(tenant).or_default() += cost_usd;
+            *aggregate.cost_by_model.entry(model.clone()).or_default() += cost_usd;
+        }
+        let mut attributes = self.labels.as_attributes();
+        attributes.push(opentelemetry::KeyValue::new("model", model));
+        self.metrics.cost_counter.add(cost_usd, &attributes);
+    }
+
+    /// Cost in USD accumulated by this run so far.
+    pub fn cost(&self) -> f64 {
+        self.cost_usd
     }
 
     pub fn finish(mut self, success: bool) -> EvaluationReport {
+        self.finished = true;
+        self.record_end(success)
+    }
+
+    fn record_end(&mut self, success: bool) -> EvaluationReport {
         let duration = self.start.elapsed();
         self.system.refresh_memory();
         let peak_memory_bytes = self.system.used_memory() * 1024;
         self.metrics
             .duration_histogram
             .record(duration.as_millis() as f64, &self.labels.as_attributes());
+        {
+            let mut aggregate = self.metrics.aggregate.lock().unwrap();
+            if success {
+                aggregate.runs_succeeded += 1;
+            } else {
+                aggregate.runs_failed += 1;
+            }
+            let agent = self
+                .labels
+                .workflow
+                .clone()
+                .unwrap_or_else(|| "default".into());
+            aggregate
+                .latency
+                .entry(agent)
+                .or_default()
+                .observe(duration.as_secs_f64() * 1000.0);
+        }
         let report = EvaluationReport {
             duration,
             peak_memory_bytes,
             tool_calls: self.tool_calls,
             failures: self.failures,
             success,
+            cost_usd: self.cost_usd,
+            retrieval: None,
             labels: self.labels.clone(),
         };
         self.metrics.reports.lock().unwrap().push(report.clone());
@@ -153,6 +479,15 @@ impl RunGuard {
     }
 }
 
+#[cfg(feature = "telemetry")]
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        if !self.finished {
+            self.record_end(false);
+        }
+    }
+}
+
 #[cfg(feature = "telemetry")]
 pub fn init_prometheus_registry() -> PromRegistry {
     let registry = PromRegistry::new();
@@ -171,6 +506,7 @@ mod tests {
             tenant: Some("tenant-a".into()),
             tool: Some("metrics".into()),
             workflow: Some("test".into()),
+            run_id: None,
         };
         let report = tracker.start_run(labels.clone()).finish(true);
         assert!(report.duration >= Duration::from_millis(0));
@@ -179,4 +515,99 @@ mod tests {
         assert_eq!(reports.len(), 1);
         assert_eq!(EvaluationReport::success_rate(&reports), 1.0);
     }
+
+    #[test]
+    fn unfinished_runs_count_as_failed() {
+        let tracker = MetricsTracker::default();
+        tracker.start_run(TelemetryLabels::default()).finish(true);
+        drop(tracker.start_run(TelemetryLabels::default()));
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.runs_succeeded, 1);
+        assert_eq!(snapshot.runs_failed, 1);
+        assert_eq!(tracker.reports().len(), 2);
+    }
+
+    #[test]
+    fn renders_snapshot_as_prometheus_text() {
+        let tracker = MetricsTracker::default();
+        let labels = TelemetryLabels::default().with_workflow("support");
+        let mut run = tracker.start_run(labels.clone());
+        run.record_tool_call("search");
+        run.record_tool_call("search");
+        run.finish(true);
+        tracker.start_run(labels).finish(false);
+
+        let snapshot = tracker.snapshot();
+        assert_eq!(snapshot.runs_started, 2);
+        assert_eq!(snapshot.runs_succeeded, 1);
+        assert_eq!(snapshot.runs_failed, 1);
+        assert_eq!(snapshot.tool_calls.get("search"), Some(&2));
+
+        let text = snapshot.to_prometheus();
+        assert!(text.contains("# TYPE sayr_runs_started_total counter"));
+        assert!(text.contains("sayr_tool_calls_total{tool=\"search\"} 2"));
+        assert!(
+            text.contains("sayr_run_duration_milliseconds_bucket{agent=\"support\",le=\"+Inf\"} 2")
+        );
+        assert!(text.contains("sayr_run_duration_milliseconds_count{agent=\"support\"} 2"));
+    }
+
+    #[test]
+    fn summarizes_latency_percentiles_across_agents() {
+        let mut snapshot = MetricsSnapshot {
+            runs_started: 20,
+            runs_succeeded: 15,
+            runs_failed: 5,
+            ..Default::default()
+        };
+        let mut fast = LatencyHistogram::default();
+        for _ in 0..18 {
+            fast.observe(80.0);
+        }
+        let mut slow = LatencyHistogram::default();
+        slow.observe(4_000.0);
+        slow.observe(45_000.0);
+        snapshot.latency.insert("fast".into(), fast);
+        snapshot.latency.insert("slow".into(), slow);
+        snapshot.cost_by_tenant.insert("acme".into(), 0.25);
+
+        let summary = snapshot.summary();
+        assert_eq!(summary.success_rate, 0.75);
+        assert_eq!(summary.p50_latency_ms, Some(100.0));
+        assert_eq!(summary.p95_latency_ms, Some(5_000.0));
+        assert_eq!(summary.total_cost_usd, 0.25);
+        assert_eq!(MetricsSnapshot::default().summary().p50_latency_ms, None);
+    }
+
+    #[test]
+    fn aggregates_cost_by_tenant_and_model() {
+        let tracker = MetricsTracker::default();
+        let usage = TokenUsage {
+            provider: "openai".into(),
+            model: "gpt-4o".into(),
+            input_tokens: 1_000,
+            output_tokens: 500,
+        };
+        let mut run = tracker.start_run(TelemetryLabels::default().with_tenant("acme"));
+        run.record_cost(&usage, 0.25);
+        run.record_cost(&usage, 0.5);
+        assert_eq!(run.cost(), 0.75);
+        assert_eq!(run.finish(true).cost_usd, 0.75);
+        tracker
+            .start_run(TelemetryLabels::default())
+            .record_cost(&usage, 1.0);
+
+        let by_tenant = tracker.cost_by_tenant();
+        assert_eq!(by_tenant.get("acme"), Some(&0.75));
+        assert_eq!(by_tenant.get("default"), Some(&1.0));
+        assert_eq!(
+            tracker.snapshot().cost_by_model.get("openai/gpt-4o"),
+            Some(&1.75)
+        );
+        assert!(tracker
+            .snapshot()
+            .to_prometheus()
+            .contains("sayr_cost_usd_total{tenant=\"acme\"} 0.75"));
+    }
 }
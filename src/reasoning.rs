@@ -6,9 +6,11 @@
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
-use crate::error::Result;
+use crate::agent::AgentDirective;
+use crate::error::{AgnoError, Result};
 use crate::llm::LanguageModel;
 use crate::message::Message;
+use crate::tool::ToolDescription;
 
 /// A single reasoning step in the chain-of-thought process
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -202,6 +204,155 @@ impl<M: LanguageModel> ReasoningAgent<M> {
     }
 }
 
+/// Controls how an [`Agent`](crate::Agent) asks for tool use and reads plain-text replies.
+///
+/// Without a strategy the agent relies on native tool calls or JSON directives.
+pub trait ReasoningStrategy: Send + Sync {
+    /// Instructions appended to the system prompt, after the tool list.
+    fn instructions(&self, tools: &[ToolDescription]) -> String;
+
+    /// Turn a plain-text model reply into the next directive.
+    fn parse(&self, content: &str) -> Result<AgentDirective>;
+}
+
+/// ReAct prompting: the model alternates `Thought:`/`Action:`/`Action Input:` lines with
+/// tool observations until it emits a `Final Answer:`.
+///
+/// Useful for models that are unreliable at JSON function calling.
+#[derive(Debug, Clone, Default)]
+pub struct ReActStrategy;
+
+impl ReActStrategy {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl ReasoningStrategy for ReActStrategy {
+    fn instructions(&self, tools: &[ToolDescription]) -> String {
+        let names: Vec<&str> = tools.iter().map(|tool| tool.name.as_str()).collect();
+        format!(
+            r#"
+Use the following format:
+
+Thought: think about what to do next
+Action: the tool to use, one of [{}]
+Action Input: the tool arguments as a JSON object
+Observation: the tool result (provided to you, do not write it yourself)
+... (Thought/Action/Action Input/Observation can repeat)
+Thought: I now know the final answer
+Final Answer: the reply to the user
+"#,
+            names.join(", ")
+        )
+    }
+
+    fn parse(&self, content: &str) -> Result<AgentDirective> {
+        let action = content.find("Action:");
+        let final_answer = content.find("Final Answer:");
+
+        match (action, final_answer) {
+            (Some(action), final_answer) if final_answer.is_none_or(|answer| action < answer) => {
+                let rest = &content[action + "Action:".len()..];
+                let name = rest
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .trim_matches(|c| c == '`' || c == '[' || c == ']')
+                    .trim()
+                    .to_string();
+                if name.is_empty() {
+                    return Err(AgnoError::Protocol(
+                        "ReAct reply has an `Action:` without a tool name".into(),
+                    ));
+                }
+                let arguments = match rest.find("Action Input:") {
+                    Some(start) => {
+                        let input = &rest[start + "Action Input:".len()..];
+                        let input = input.split("Observation:").next().unwrap_or_default();
+                        parse_action_input(input)
+                    }
+                    None => serde_json::json!({}),
+                };
+                Ok(AgentDirective::CallTool { name, arguments })
+            }
+            (_, Some(answer)) => Ok(AgentDirective::Respond {
+                content: content[answer + "Final Answer:".len()..].trim().to_string(),
+            }),
+            // Neither marker: the model answered directly.
+            _ => Ok(AgentDirective::Respond {
+                content: content.trim().to_string(),
+            }),
+        }
+    }
+}
+
+/// Parse an `Action Input:` block as JSON, tolerating code fences. Non-JSON input is passed
+/// through as `{"input": "..."}`.
+fn parse_action_input(input: &str) -> serde_json::Value {
+    let trimmed = input
+        .trim()
+        .trim_start_matches("```json")
+        .trim_matches('`')
+        .trim();
+    if trimmed.is_empty() {
+        return serde_json::json!({});
+    }
+    serde_json::from_str(trimmed).unwrap_or_else(|_| serde_json::json!({ "input": trimmed }))
+}
+
+/// Delimiters around a chain-of-thought section that is kept out of the reply and memory.
+///
+/// Defaults to `<thinking>` / `</thinking>`.
+#[derive(Debug, Clone)]
+pub struct HiddenReasoning {
+    open: String,
+    close: String,
+}
+
+impl Default for HiddenReasoning {
+    fn default() -> Self {
+        Self::new("<thinking>", "</thinking>")
+    }
+}
+
+impl HiddenReasoning {
+    pub fn new(open: impl Into<String>, close: impl Into<String>) -> Self {
+        Self {
+            open: open.into(),
+            close: close.into(),
+        }
+    }
+
+    /// Split `content` into the captured reasoning (if any) and the visible answer. Every
+    /// delimited block is removed; an unclosed block hides the rest of the reply.
+    pub fn split(&self, content: &str) -> (Option<String>, String) {
+        let mut thoughts = Vec::new();
+        let mut answer = String::new();
+        let mut rest = content;
+
+        while let Some(start) = rest.find(&self.open) {
+            answer.push_str(&rest[..start]);
+            let inner = &rest[start + self.open.len()..];
+            match inner.find(&self.close) {
+                Some(end) => {
+                    thoughts.push(inner[..end].trim().to_string());
+                    rest = &inner[end + self.close.len()..];
+                }
+                None => {
+                    thoughts.push(inner.trim().to_string());
+                    rest = "";
+                }
+            }
+        }
+        answer.push_str(rest);
+
+        let reasoning = (!thoughts.is_empty()).then(|| thoughts.join("\n\n"));
+        (reasoning, answer.trim().to_string())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -239,4 +390,62 @@ mod tests {
         assert!(prompt.contains("Reasoning Agent"));
         assert!(prompt.contains("step-by-step"));
     }
+
+    #[test]
+    fn react_parses_action_and_final_answer() {
+        let strategy = ReActStrategy::new();
+
+        let call = strategy
+            .parse("Thought: I need the weather.\nAction: weather\nAction Input: {\"city\": \"Oslo\"}\nObservation: sunny")
+            .unwrap();
+        assert_eq!(
+            call,
+            AgentDirective::CallTool {
+                name: "weather".into(),
+                arguments: serde_json::json!({"city": "Oslo"}),
+            }
+        );
+
+        let raw = strategy
+            .parse("Action: search\nAction Input: rust async")
+            .unwrap();
+        assert_eq!(
+            raw,
+            AgentDirective::CallTool {
+                name: "search".into(),
+                arguments: serde_json::json!({"input": "rust async"}),
+            }
+        );
+
+        let answer = strategy
+            .parse("Thought: I now know the final answer\nFinal Answer: It is sunny.")
+            .unwrap();
+        assert_eq!(
+            answer,
+            AgentDirective::Respond {
+                content: "It is sunny.".into()
+            }
+        );
+
+        assert!(strategy.parse("Action:\nAction Input: {}").is_err());
+    }
+
+    #[test]
+    fn hidden_reasoning_strips_delimited_blocks() {
+        let hidden = HiddenReasoning::default();
+
+        let (reasoning, answer) = hidden.split("<thinking>2 + 2 is 4</thinking>\nThe answer is 4.");
+        assert_eq!(reasoning.as_deref(), Some("2 + 2 is 4"));
+        assert_eq!(answer, "The answer is 4.");
+
+        let (reasoning, answer) = hidden.split("No hidden thoughts here.");
+        assert!(reasoning.is_none());
+        assert_eq!(answer, "No hidden thoughts here.");
+
+        let custom = HiddenReasoning::new("[scratch]", "[/scratch]");
+        let (reasoning, answer) =
+            custom.split("[scratch]draft[/scratch]Final. [scratch]unfinished");
+        assert_eq!(reasoning.as_deref(), Some("draft\n\nunfinished"));
+        assert_eq!(answer, "Final.");
+    }
 }
@@ -0,0 +1,84 @@
+//! Retry policies shared by model clients and tools.
+//!
+//! The policy itself carries no telemetry, so clients and tools can retry without the
+//! `telemetry` feature; the instrumented `retry` loops live in the telemetry module.
+
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::{AgnoError, Result};
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub backoff: Duration,
+}
+
+/// How a [`RetryPolicy`] retry loop should treat a failed attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Retry after the delay advertised by the error (see [`AgnoError::retry_after`]),
+    /// falling back to the policy's exponential backoff.
+    Retry,
+    /// Retry after the given delay, e.g. from a `Retry-After` header.
+    RetryAfter(Duration),
+    /// Give up and return the error immediately.
+    Abort,
+}
+
+impl RetryDecision {
+    /// Retry errors that [`AgnoError::is_retryable`] classifies as transient, abort otherwise.
+    pub fn for_error(err: &AgnoError) -> Self {
+        if err.is_retryable() {
+            RetryDecision::Retry
+        } else {
+            RetryDecision::Abort
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn default_external_call() -> Self {
+        Self {
+            max_retries: 3,
+            backoff: Duration::from_millis(200),
+        }
+    }
+
+    /// Delay before retrying after the given (zero-based) failed attempt: `backoff * 2^attempt`.
+    pub fn backoff_for(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+
+    /// The retry loop behind `retry_classified`: call `f` until it succeeds, a failure is
+    /// classified [`RetryDecision::Abort`] or the retries run out. `on_failure` sees every
+    /// failed attempt before the back-off.
+    pub(crate) async fn run<F, Fut, T>(
+        &self,
+        mut f: F,
+        mut on_failure: impl FnMut(u32, &AgnoError),
+    ) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: Future<Output = std::result::Result<T, (AgnoError, RetryDecision)>>,
+    {
+        for attempt in 0..=self.max_retries {
+            match f(attempt).await {
+                Ok(value) => return Ok(value),
+                Err((err, decision)) => {
+                    on_failure(attempt, &err);
+                    let delay = match decision {
+                        RetryDecision::Abort => return Err(err),
+                        _ if attempt == self.max_retries => return Err(err),
+                        RetryDecision::RetryAfter(delay) => delay,
+                        RetryDecision::Retry => err
+                            .retry_after()
+                            .unwrap_or_else(|| self.backoff_for(attempt)),
+                    };
+                    tokio::time::sleep(delay).await;
+                }
+            }
+        }
+        Err(AgnoError::Protocol("retry exhausted".into()))
+    }
+}
@@ -1,27 +1,40 @@
 use std::collections::HashMap;
 use std::convert::Infallible;
+use std::future::Future;
 use std::net::SocketAddr;
+use std::path::Path as FsPath;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
+use axum::extract::ws::{CloseFrame, Message as WsMessage, WebSocket, WebSocketUpgrade};
+use axum::extract::Request;
 use axum::extract::{Path, Query, State};
-use axum::http::{HeaderMap, StatusCode};
+use axum::http::{HeaderMap, HeaderValue, StatusCode};
+use axum::middleware::{self, Next};
 use axum::response::sse::{Event, Sse};
 use axum::response::{Html, IntoResponse, Response};
 use axum::routing::{get, post};
 use axum::Json;
 use axum::Router;
+use axum_server::tls_rustls::RustlsConfig;
 use futures::stream::Stream;
 use futures::StreamExt;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json::{json, Value};
-use tokio::sync::{broadcast, Mutex, RwLock};
-use tokio_stream::wrappers::BroadcastStream;
+use tokio::sync::{broadcast, mpsc, Mutex, OwnedSemaphorePermit, RwLock, Semaphore};
+use tokio_stream::wrappers::{BroadcastStream, UnboundedReceiverStream};
+use tokio_util::sync::CancellationToken;
 
+use crate::error::AgnoError;
 use crate::message::Message;
+#[cfg(feature = "persistence")]
+use crate::storage::ConversationStore;
 use crate::{
-    AccessController, Action, GovernanceRole, LanguageModel, Principal, Result, SecurityConfig,
-    Team, TelemetryCollector, Workflow,
+    AccessController, Action, AppConfig, DeploymentConfig, GovernanceRole, LanguageModel,
+    Principal, Result, SecurityConfig, ServerConfig, Team, TelemetryCollector, TelemetryConfig,
+    Workflow,
 };
 
 pub struct AgentRuntime<M: LanguageModel + 'static> {
@@ -31,11 +44,168 @@ pub struct AgentRuntime<M: LanguageModel + 'static> {
     pub events: broadcast::Sender<String>,
     trace_events: broadcast::Sender<TraceEvent>,
     security: SecurityConfig,
+    server: ServerConfig,
     access_control: AccessController,
     telemetry: TelemetryCollector,
     metrics: crate::MetricsTracker,
+    shutdown_timeout: Duration,
+    concurrency: Arc<ConcurrencyLimit>,
+    #[cfg(feature = "persistence")]
+    session_store: Option<SessionStoreFactory>,
+    #[cfg(feature = "persistence")]
+    reserved_sessions: Arc<SessionReservations>,
 }
 
+/// Builds the store for a session's storage key; see [`AgentRuntime::with_session_store`].
+#[cfg(feature = "persistence")]
+type SessionStoreFactory = Arc<dyn Fn(&str) -> Arc<dyn ConversationStore> + Send + Sync>;
+
+/// How long `POST /sessions` holds an id that has not received a message yet.
+#[cfg(feature = "persistence")]
+const SESSION_RESERVATION_TTL: Duration = Duration::from_secs(60 * 60);
+
+/// Most ids `POST /sessions` holds at once; further requests are refused until some lapse.
+#[cfg(feature = "persistence")]
+const MAX_RESERVED_SESSIONS: usize = 10_000;
+
+/// Storage keys handed out by `POST /sessions` that have no stored messages yet, so two
+/// requests cannot claim one id. Once a session's first turn is stored the store itself
+/// records it and the reservation is dropped; unused reservations lapse after
+/// [`SESSION_RESERVATION_TTL`] and are not kept across restarts.
+#[cfg(feature = "persistence")]
+#[derive(Default)]
+struct SessionReservations {
+    keys: std::sync::Mutex<HashMap<String, std::time::Instant>>,
+}
+
+#[cfg(feature = "persistence")]
+impl SessionReservations {
+    /// Claim `key`, answering with the response to send when that is not possible.
+    fn reserve(&self, key: &str) -> std::result::Result<(), Response> {
+        let mut keys = self.keys.lock().unwrap();
+        keys.retain(|_, reserved| reserved.elapsed() < SESSION_RESERVATION_TTL);
+        if keys.contains_key(key) {
+            return Err(json_error(StatusCode::CONFLICT, "session already exists"));
+        }
+        if keys.len() >= MAX_RESERVED_SESSIONS {
+            return Err(json_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "too many sessions are waiting for their first message; retry later",
+            ));
+        }
+        keys.insert(key.to_string(), std::time::Instant::now());
+        Ok(())
+    }
+
+    fn contains(&self, key: &str) -> bool {
+        self.keys
+            .lock()
+            .unwrap()
+            .get(key)
+            .is_some_and(|reserved| reserved.elapsed() < SESSION_RESERVATION_TTL)
+    }
+
+    fn release(&self, key: &str) {
+        self.keys.lock().unwrap().remove(key);
+    }
+}
+
+/// The stored session a chat runs in, named by the caller.
+#[cfg(feature = "persistence")]
+#[derive(Clone)]
+struct ChatSession {
+    id: String,
+    /// The principal-scoped key the session is stored under.
+    key: String,
+    store: Arc<dyn ConversationStore>,
+}
+
+/// Caps concurrent agent turns. The limit can change while permits are held: lowering it
+/// retires permits as they are released.
+struct ConcurrencyLimit {
+    semaphore: Arc<Semaphore>,
+    limit: std::sync::Mutex<usize>,
+    /// Permits to retire on release because the limit was lowered while they were in use.
+    retiring: AtomicUsize,
+}
+
+struct ConcurrencyPermit {
+    permit: Option<OwnedSemaphorePermit>,
+    limit: Arc<ConcurrencyLimit>,
+}
+
+impl ConcurrencyLimit {
+    fn new(limit: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(limit)),
+            limit: std::sync::Mutex::new(limit),
+            retiring: AtomicUsize::new(0),
+        }
+    }
+
+    fn limit(&self) -> usize {
+        *self.limit.lock().unwrap()
+    }
+
+    fn set_limit(&self, limit: usize) {
+        let mut current = self.limit.lock().unwrap();
+        if limit >= *current {
+            // Growing first cancels permits still waiting to be retired.
+            let grow = limit - *current;
+            let retiring = self.retiring.swap(0, Ordering::SeqCst);
+            let cancelled = retiring.min(grow);
+            self.retiring
+                .fetch_add(retiring - cancelled, Ordering::SeqCst);
+            self.semaphore.add_permits(grow - cancelled);
+        } else {
+            let shrink = *current - limit;
+            let forgotten = self.semaphore.forget_permits(shrink);
+            self.retiring
+                .fetch_add(shrink - forgotten, Ordering::SeqCst);
+        }
+        *current = limit;
+    }
+
+    fn try_acquire(self: &Arc<Self>) -> Option<ConcurrencyPermit> {
+        let permit = Arc::clone(&self.semaphore).try_acquire_owned().ok()?;
+        Some(ConcurrencyPermit {
+            permit: Some(permit),
+            limit: Arc::clone(self),
+        })
+    }
+}
+
+impl Drop for ConcurrencyPermit {
+    fn drop(&mut self) {
+        let retire = self
+            .limit
+            .retiring
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |retiring| {
+                retiring.checked_sub(1)
+            })
+            .is_ok();
+        if let (true, Some(permit)) = (retire, self.permit.take()) {
+            permit.forget();
+        }
+    }
+}
+
+/// Response for requests rejected because every concurrency permit is in use.
+fn saturated() -> Response {
+    let mut response = json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "server is at max concurrency; retry later",
+    );
+    response.headers_mut().insert(
+        axum::http::header::RETRY_AFTER,
+        HeaderValue::from_static("1"),
+    );
+    response
+}
+
+/// How long in-flight requests may run after a shutdown signal by default.
+const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(30);
+
 impl<M: LanguageModel + 'static> Clone for AgentRuntime<M> {
     fn clone(&self) -> Self {
         Self {
@@ -45,9 +215,16 @@ impl<M: LanguageModel + 'static> Clone for AgentRuntime<M> {
             events: self.events.clone(),
             trace_events: self.trace_events.clone(),
             security: self.security.clone(),
+            server: self.server.clone(),
             access_control: self.access_control.clone(),
             telemetry: self.telemetry.clone(),
             metrics: self.metrics.clone(),
+            shutdown_timeout: self.shutdown_timeout,
+            concurrency: Arc::clone(&self.concurrency),
+            #[cfg(feature = "persistence")]
+            session_store: self.session_store.clone(),
+            #[cfg(feature = "persistence")]
+            reserved_sessions: Arc::clone(&self.reserved_sessions),
         }
     }
 }
@@ -63,7 +240,6 @@ impl<M: LanguageModel + 'static> AgentRuntime<M> {
         let access_control = AccessController::new();
         access_control.allow(GovernanceRole::User, Action::SendMessage);
         access_control.allow(GovernanceRole::Service, Action::SendMessage);
-        access_control.allow(GovernanceRole::Admin, Action::SendMessage);
         access_control.allow(GovernanceRole::User, Action::ReadTranscript);
         access_control.allow(GovernanceRole::Service, Action::ReadTranscript);
         Self {
@@ -73,12 +249,82 @@ impl<M: LanguageModel + 'static> AgentRuntime<M> {
             events: tx,
             trace_events: trace_tx,
             security,
+            server: AppConfig::default().server,
             access_control,
             telemetry: TelemetryCollector::default(),
             metrics: crate::MetricsTracker::default(),
+            shutdown_timeout: DEFAULT_SHUTDOWN_TIMEOUT,
+            concurrency: Arc::new(ConcurrencyLimit::new(
+                DeploymentConfig::default().max_concurrency as usize,
+            )),
+            #[cfg(feature = "persistence")]
+            session_store: None,
+            #[cfg(feature = "persistence")]
+            reserved_sessions: Arc::default(),
         }
     }
 
+    /// Shared access controller, e.g. to `deny` dangerous tools for a role. Tools of
+    /// registered agents are allowed for every role by default.
+    pub fn access_control(&self) -> &AccessController {
+        &self.access_control
+    }
+
+    /// Maximum time to let in-flight requests finish once shutdown begins.
+    pub fn with_shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
+
+    /// Persist chat sessions in any [`ConversationStore`]: `/agents/:id/chat` requests that
+    /// carry a session id (the `x-session-id` header or `session_id` body field) run on a
+    /// fork of the agent seeded from the store `factory` returns for that id, and the new
+    /// turn is appended to it. Requests without a session id keep using the agent's own
+    /// in-memory transcript.
+    ///
+    /// Sessions belong to the tenant and principal that use them, so `factory` receives a
+    /// storage key rather than the bare session id: the encoded tenant, principal and id
+    /// joined with `.`, made only of `[A-Za-z0-9_%.-]` and safe to use as a file name.
+    #[cfg(feature = "persistence")]
+    pub fn with_session_store<S, F>(mut self, factory: F) -> Self
+    where
+        S: ConversationStore + 'static,
+        F: Fn(&str) -> S + Send + Sync + 'static,
+    {
+        self.session_store = Some(Arc::new(move |session_id: &str| {
+            Arc::new(factory(session_id)) as Arc<dyn ConversationStore>
+        }));
+        self
+    }
+
+    /// Size the concurrent agent turn limit from `deployment.max_concurrency`.
+    pub fn with_deployment_config(self, deployment: &DeploymentConfig) -> Self {
+        self.set_max_concurrency(deployment.max_concurrency as usize);
+        self
+    }
+
+    /// Change how many chat and workflow invocations may run at once. Requests beyond
+    /// the limit get `503 Service Unavailable` instead of queuing.
+    pub fn set_max_concurrency(&self, limit: usize) {
+        self.concurrency.set_limit(limit);
+    }
+
+    pub fn max_concurrency(&self) -> usize {
+        self.concurrency.limit()
+    }
+
+    /// Sample agent runs at `telemetry.sample_rate`. Call before registering agents, which
+    /// keep the collector they were registered with.
+    pub fn with_telemetry_config(mut self, telemetry: &TelemetryConfig) -> Self {
+        self.telemetry = self.telemetry.with_sample_rate(telemetry.sample_rate);
+        self
+    }
+
+    pub fn with_server_config(mut self, server: ServerConfig) -> Self {
+        self.server = server;
+        self
+    }
+
     pub async fn register_team(&self, name: impl Into<String>, team: Team<M>) {
         self.teams.write().await.insert(name.into(), team);
     }
@@ -95,12 +341,11 @@ impl<M: LanguageModel + 'static> AgentRuntime<M> {
         let controller = Arc::new(self.access_control.clone());
         agent.attach_access_control(controller);
         agent.attach_metrics(self.metrics.clone());
+        agent.label_runs_if_unset(&name);
         agent.attach_telemetry(self.telemetry.clone());
         for tool in agent.tool_names() {
             self.access_control
                 .allow(GovernanceRole::User, Action::CallTool(tool.clone()));
-            self.access_control
-                .allow(GovernanceRole::Admin, Action::CallTool(tool.clone()));
             self.access_control
                 .allow(GovernanceRole::Service, Action::CallTool(tool.clone()));
         }
@@ -145,6 +390,51 @@ impl<M: LanguageModel + 'static> AgentRuntime<M> {
         Ok(tenant)
     }
 
+    /// Check a request's `max_steps` override against the configured ceiling.
+    fn check_max_steps(&self, requested: Option<usize>) -> std::result::Result<(), String> {
+        match requested {
+            Some(steps) if steps == 0 || steps > self.server.max_steps_limit => Err(format!(
+                "max_steps must be between 1 and {}",
+                self.server.max_steps_limit
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// The session a chat names, when the runtime keeps sessions. Fails with the reason a
+    /// malformed id is rejected.
+    #[cfg(feature = "persistence")]
+    fn chat_session(
+        &self,
+        principal: &Principal,
+        session_id: Option<String>,
+    ) -> std::result::Result<Option<ChatSession>, &'static str> {
+        let (Some(factory), Some(id)) = (&self.session_store, session_id) else {
+            return Ok(None);
+        };
+        validate_session_id(&id)?;
+        let key = session_storage_key(principal, &id);
+        let store = factory(&key);
+        Ok(Some(ChatSession { id, key, store }))
+    }
+
+    /// Append a turn's messages to its session. Once the store holds the session,
+    /// `POST /sessions` no longer has to reserve its id.
+    #[cfg(feature = "persistence")]
+    async fn store_turn(&self, session: &ChatSession, new_segment: &[Message]) {
+        let mut stored = false;
+        for message in new_segment {
+            if let Err(err) = session.store.append(message).await {
+                tracing::warn!("failed to persist session message: {err}");
+                break;
+            }
+            stored = true;
+        }
+        if stored {
+            self.reserved_sessions.release(&session.key);
+        }
+    }
+
     fn build_principal(
         &self,
         headers: &HeaderMap,
@@ -204,25 +494,159 @@ impl<M: LanguageModel + 'static> AgentRuntime<M> {
         }
     }
 
-    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
-        let app = Router::new()
-            .route("/health", get(|| async { "ok" }))
-            .route("/metrics", get(prometheus_metrics))
+    fn router(&self) -> Router {
+        let router = Router::new()
+            .route("/metrics", get(prometheus_metrics::<M>))
+            .route("/metrics/summary", get(metrics_summary::<M>))
+            .route("/cost", get(cost_summary::<M>))
+            .route("/telemetry/recent", get(recent_telemetry::<M>))
             .route("/dashboard", get(dashboard))
             .route("/agents", get(list_agents::<M>))
             .route("/agents/:id/chat", post(chat_with_agent::<M>))
+            .route("/agents/:id/chat/stream", post(stream_chat_with_agent::<M>))
+            .route("/agents/:id/ws", get(agent_websocket::<M>))
             .route("/agents/:id/traces", get(stream_tool_traces::<M>))
             .route("/teams", get(list_teams::<M>))
             .route("/workflows", get(list_workflows::<M>))
             .route("/events", get(stream_events::<M>))
-            .route("/invoke", post(run_workflow::<M>))
-            .with_state(self.clone());
+            .route("/invoke", post(run_workflow::<M>));
+        #[cfg(feature = "persistence")]
+        let router = router
+            .route("/sessions", post(create_session::<M>))
+            .route("/sessions/:id/messages", get(session_messages::<M>));
+        router
+            .route_layer(middleware::from_fn_with_state(
+                self.clone(),
+                require_api_key::<M>,
+            ))
+            // Liveness probes stay reachable without credentials.
+            .route("/health", get(|| async { "ok" }))
+            .with_state(self.clone())
+    }
+
+    /// Serve the runtime until SIGINT or SIGTERM, switching to HTTPS when
+    /// `ServerConfig.tls_enabled` is set.
+    ///
+    /// On shutdown, in-flight requests are drained and telemetry is flushed.
+    pub async fn serve(self, addr: SocketAddr) -> Result<()> {
+        if self.server.tls_enabled {
+            let (Some(cert), Some(key)) = (
+                self.server.tls_cert_path.clone(),
+                self.server.tls_key_path.clone(),
+            ) else {
+                return Err(AgnoError::Protocol(
+                    "tls_enabled is set but tls_cert_path and tls_key_path are not both configured"
+                        .into(),
+                ));
+            };
+            return self.serve_tls(addr, cert, key).await;
+        }
+
+        self.serve_with_shutdown(addr, shutdown_signal()).await
+    }
 
+    /// Serve plain HTTP until `shutdown` resolves. New connections are then refused while
+    /// running requests get up to the shutdown timeout to finish, after which telemetry is
+    /// flushed.
+    pub async fn serve_with_shutdown(
+        self,
+        addr: SocketAddr,
+        shutdown: impl Future<Output = ()> + Send + 'static,
+    ) -> Result<()> {
+        let drain_timeout = self.shutdown_timeout;
+        let app = self.router();
         let listener = tokio::net::TcpListener::bind(addr).await?;
-        axum::serve(listener, app.into_make_service())
+        let (started_tx, started_rx) = tokio::sync::oneshot::channel();
+        let signal = async move {
+            shutdown.await;
+            let _ = started_tx.send(());
+        };
+        let server = axum::serve(listener, app.into_make_service()).with_graceful_shutdown(signal);
+
+        let result = tokio::select! {
+            result = server => {
+                result.map_err(|err| AgnoError::Protocol(format!("server error: {err}")))
+            }
+            _ = drain_deadline(started_rx, drain_timeout) => {
+                tracing::warn!(
+                    "in-flight requests still running after {drain_timeout:?}; shutting down"
+                );
+                Ok(())
+            }
+        };
+        crate::telemetry::flush_tracer();
+        result
+    }
+
+    /// Serve the runtime over HTTPS using a PEM-encoded certificate chain and private key.
+    pub async fn serve_tls(
+        self,
+        addr: SocketAddr,
+        cert_path: impl AsRef<FsPath>,
+        key_path: impl AsRef<FsPath>,
+    ) -> Result<()> {
+        // Several rustls crypto backends are linked in through dependencies, so pick one
+        // explicitly; an already-installed process default is left untouched.
+        let _ = rustls::crypto::ring::default_provider().install_default();
+        let tls = RustlsConfig::from_pem_file(cert_path.as_ref(), key_path.as_ref())
             .await
-            .map_err(|err| crate::error::AgnoError::Protocol(format!("server error: {err}")))?;
-        Ok(())
+            .map_err(|err| {
+                AgnoError::Protocol(format!(
+                    "failed to load TLS material from `{}`/`{}`: {err}",
+                    cert_path.as_ref().display(),
+                    key_path.as_ref().display()
+                ))
+            })?;
+        let handle = axum_server::Handle::new();
+        let drain_timeout = self.shutdown_timeout;
+        let signal_handle = handle.clone();
+        tokio::spawn(async move {
+            shutdown_signal().await;
+            signal_handle.graceful_shutdown(Some(drain_timeout));
+        });
+        let app = self.router();
+        let result = axum_server::bind_rustls(addr, tls)
+            .handle(handle)
+            .serve(app.into_make_service())
+            .await
+            .map_err(|err| AgnoError::Protocol(format!("server error: {err}")));
+        crate::telemetry::flush_tracer();
+        result
+    }
+}
+
+/// Resolve on Ctrl+C, or on SIGTERM where supported.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if tokio::signal::ctrl_c().await.is_err() {
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut stream) => {
+                stream.recv().await;
+            }
+            Err(_) => std::future::pending::<()>().await,
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("shutdown signal received; draining in-flight requests");
+}
+
+/// Resolve `timeout` after shutdown starts; never resolves if the server stops first.
+async fn drain_deadline(started: tokio::sync::oneshot::Receiver<()>, timeout: Duration) {
+    match started.await {
+        Ok(()) => tokio::time::sleep(timeout).await,
+        Err(_) => std::future::pending().await,
     }
 }
 
@@ -232,6 +656,13 @@ struct TeamSummary {
     agents: usize,
 }
 
+#[derive(Serialize)]
+struct CostSummary {
+    total_usd: f64,
+    by_tenant: std::collections::BTreeMap<String, f64>,
+    by_model: std::collections::BTreeMap<String, f64>,
+}
+
 #[derive(Serialize)]
 struct AgentSummary {
     name: String,
@@ -247,12 +678,34 @@ struct AgentChatRequest {
     role: Option<String>,
     #[serde(default)]
     tenant: Option<String>,
+    #[serde(default)]
+    session_id: Option<String>,
+    /// Model requests this turn may make, in place of the agent's own limit. Capped by
+    /// `ServerConfig.max_steps_limit`.
+    #[serde(default)]
+    max_steps: Option<usize>,
 }
 
+/// Reply to a chat request. Session chats return only the new assistant message, since
+/// their history is available from `GET /sessions/:id/messages`; other chats return the
+/// agent's whole transcript.
 #[derive(Serialize)]
 struct AgentChatResponse {
     reply: String,
-    transcript: Vec<Message>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transcript: Option<Vec<Message>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+#[cfg(feature = "persistence")]
+#[derive(Deserialize, Default)]
+struct CreateSessionRequest {
+    /// Name for the new session; a random id is used when absent.
+    #[serde(default)]
+    session_id: Option<String>,
 }
 
 #[derive(Deserialize, Default)]
@@ -260,6 +713,8 @@ struct TraceAuth {
     tenant: Option<String>,
     principal_id: Option<String>,
     role: Option<String>,
+    /// Session a WebSocket chat runs in, if the `x-session-id` header is not set.
+    session_id: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -279,6 +734,103 @@ enum TraceKind {
     Failed { error: String },
 }
 
+/// Reject requests without a configured `Authorization: Bearer` key. The matched key decides
+/// the caller's principal id and role, overriding any client-supplied identity headers.
+async fn require_api_key<M: LanguageModel + 'static>(
+    State(state): State<AgentRuntime<M>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if state.security.api_keys.is_empty() {
+        return next.run(request).await;
+    }
+
+    let token = request
+        .headers()
+        .get(axum::http::header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .map(str::trim);
+    let Some(token) = token else {
+        return json_error(StatusCode::UNAUTHORIZED, "missing bearer token");
+    };
+    let Some(key) = state
+        .security
+        .api_keys
+        .iter()
+        .find(|key| constant_time_eq(key.key.as_bytes(), token.as_bytes()))
+    else {
+        return json_error(StatusCode::UNAUTHORIZED, "invalid API key");
+    };
+
+    let (Ok(principal_id), Ok(role)) = (
+        HeaderValue::from_str(&key.principal_id),
+        HeaderValue::from_str(&key.role),
+    ) else {
+        return json_error(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            "API key has an invalid principal",
+        );
+    };
+    let headers = request.headers_mut();
+    headers.insert("x-principal-id", principal_id);
+    headers.insert("x-principal-role", role);
+    next.run(request).await
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The session a chat names: the `x-session-id` header, else the body's `session_id`.
+#[cfg(feature = "persistence")]
+fn requested_session(headers: &HeaderMap, req: &AgentChatRequest) -> Option<String> {
+    headers
+        .get("x-session-id")
+        .and_then(|h| h.to_str().ok())
+        .map(|s| s.to_string())
+        .or_else(|| req.session_id.clone())
+}
+
+/// Session ids become part of store keys and file names, so they are limited to
+/// `[A-Za-z0-9_-]`.
+#[cfg(feature = "persistence")]
+fn validate_session_id(id: &str) -> std::result::Result<(), &'static str> {
+    if id.is_empty() {
+        return Err("session id must not be empty");
+    }
+    if !id
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+    {
+        return Err("session id may only contain letters, digits, `_` and `-`");
+    }
+    Ok(())
+}
+
+/// The key a session is stored under: its owner's tenant and principal id, then the session
+/// id, joined with `.`. Owner parts are percent-encoded outside `[A-Za-z0-9_]`, and a missing
+/// tenant is `-`, so distinct owners never share a key.
+#[cfg(feature = "persistence")]
+fn session_storage_key(principal: &Principal, session_id: &str) -> String {
+    fn encode(part: &str) -> String {
+        part.bytes()
+            .map(|byte| {
+                if byte.is_ascii_alphanumeric() || byte == b'_' {
+                    char::from(byte).to_string()
+                } else {
+                    format!("%{byte:02X}")
+                }
+            })
+            .collect()
+    }
+    let tenant = principal
+        .tenant
+        .as_deref()
+        .map_or_else(|| "-".to_string(), encode);
+    format!("{tenant}.{}.{session_id}", encode(&principal.id))
+}
+
 fn json_error(status: StatusCode, message: &str) -> Response {
     (status, Json(json!({"error": message}))).into_response()
 }
@@ -366,6 +918,8 @@ async fn stream_tool_traces<M: LanguageModel + 'static>(
             principal_id: auth.principal_id.clone(),
             role: auth.role.clone(),
             tenant: tenant.clone(),
+            session_id: None,
+            max_steps: None,
         },
     ) {
         Ok(principal) => principal,
@@ -413,6 +967,55 @@ async fn stream_tool_traces<M: LanguageModel + 'static>(
     Sse::new(stream).into_response()
 }
 
+/// Records returned by `/telemetry/recent` when no `limit` is given.
+const DEFAULT_RECENT_TELEMETRY: usize = 50;
+
+#[derive(Deserialize)]
+struct RecentTelemetryQuery {
+    limit: Option<usize>,
+    tenant: Option<String>,
+    principal_id: Option<String>,
+    role: Option<String>,
+}
+
+/// The most recent buffered telemetry events and failures, scoped to the caller's tenant.
+/// Reading does not drain the collector, so it can be polled while no OTLP collector runs.
+async fn recent_telemetry<M: LanguageModel + 'static>(
+    State(state): State<AgentRuntime<M>>,
+    Query(query): Query<RecentTelemetryQuery>,
+    headers: HeaderMap,
+) -> Response {
+    let principal = match state.build_principal(
+        &headers,
+        &AgentChatRequest {
+            message: String::new(),
+            principal_id: query.principal_id,
+            role: query.role,
+            tenant: query.tenant,
+            session_id: None,
+            max_steps: None,
+        },
+    ) {
+        Ok(principal) => principal,
+        Err(resp) => return resp,
+    };
+    if !state
+        .access_control
+        .authorize(&principal, &Action::ReadTranscript)
+    {
+        return json_error(
+            StatusCode::FORBIDDEN,
+            "principal not authorized to read telemetry",
+        );
+    }
+
+    let (events, failures) = state.telemetry.recent(
+        query.limit.unwrap_or(DEFAULT_RECENT_TELEMETRY),
+        principal.tenant.as_deref(),
+    );
+    Json(json!({ "events": events, "failures": failures })).into_response()
+}
+
 #[derive(serde::Deserialize)]
 struct WorkflowRequest {
     name: String,
@@ -424,6 +1027,9 @@ async fn run_workflow<M: LanguageModel + 'static>(
 ) -> Response {
     let flow = { state.workflows.read().await.get(&req.name).cloned() };
     if let Some(flow) = flow {
+        let Some(_permit) = state.concurrency.try_acquire() else {
+            return saturated();
+        };
         let mut ctx = crate::WorkflowContext::default();
         match flow.run(&mut ctx).await {
             Ok(value) => {
@@ -467,6 +1073,9 @@ async fn chat_with_agent<M: LanguageModel + 'static>(
             "principal not authorized to message this agent",
         );
     }
+    if let Err(message) = state.check_max_steps(req.max_steps) {
+        return json_error(StatusCode::BAD_REQUEST, &message);
+    }
 
     let agent = { state.agents.read().await.get(&agent_id).cloned() };
     let Some(agent) = agent else {
@@ -479,13 +1088,53 @@ async fn chat_with_agent<M: LanguageModel + 'static>(
         crate::TelemetryLabels::default().with_tenant(principal.tenant.clone().unwrap_or_default()),
     );
 
-    let mut guard = agent.lock().await;
-    guard.set_principal(principal.clone());
-    guard.attach_access_control(Arc::new(state.access_control.clone()));
-    guard.attach_metrics(state.metrics.clone());
-    guard.attach_telemetry(state.telemetry.clone());
+    let Some(_permit) = state.concurrency.try_acquire() else {
+        return saturated();
+    };
+    #[cfg(feature = "persistence")]
+    let session = match state.chat_session(&principal, requested_session(&headers, &req)) {
+        Ok(session) => session,
+        Err(message) => return json_error(StatusCode::BAD_REQUEST, message),
+    };
+    #[cfg(feature = "persistence")]
+    let history = match &session {
+        Some(session) => match session.store.load().await {
+            Ok(messages) => Some(messages),
+            Err(err) => {
+                return json_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &format!("failed to load session: {err}"),
+                )
+            }
+        },
+        None => None,
+    };
+    let mut guard = Some(agent.lock().await);
+    // Sessions run on a fork of the registered agent so their transcripts stay isolated
+    // from each other and from session-less callers. The fork owns everything it needs,
+    // so the agent is unlocked right away and sessions on it run concurrently.
+    #[cfg(feature = "persistence")]
+    let mut session_agent = history.map(|messages| {
+        let mut fork = guard.take().expect("agent is locked").fork_session();
+        fork.sync_memory_from(&crate::ConversationMemory::with_messages(messages));
+        fork
+    });
+    #[cfg(feature = "persistence")]
+    let active = match session_agent.as_mut() {
+        Some(fork) => fork,
+        None => guard.as_deref_mut().expect("agent is locked"),
+    };
+    #[cfg(not(feature = "persistence"))]
+    let active = guard.as_deref_mut().expect("agent is locked");
+    active.set_principal(principal.clone());
+    active.attach_access_control(Arc::new(state.access_control.clone()));
+    active.attach_metrics(state.metrics.clone());
+    active.attach_telemetry(state.telemetry.clone());
+    if let Some(max_steps) = req.max_steps {
+        active.limit_steps_for_next_turn(max_steps);
+    }
 
-    let starting_len = guard.memory().len();
+    let starting_len = active.memory().len();
     state.publish_trace(
         &agent_id,
         principal.tenant.clone(),
@@ -494,13 +1143,45 @@ async fn chat_with_agent<M: LanguageModel + 'static>(
         },
     );
 
-    let result = guard
+    let result = active
         .respond_for(principal.clone(), req.message.clone())
         .await;
-    let transcript: Vec<Message> = guard.memory().iter().cloned().collect();
-    let new_segment: Vec<Message> = guard.memory().iter().skip(starting_len).cloned().collect();
+    let scrub = |mut message: Message| {
+        state
+            .access_control
+            .scrub_message(principal.tenant.as_deref(), &mut message);
+        message
+    };
+    let transcript: Vec<Message> = active.memory().iter().cloned().map(scrub).collect();
+    // Scrubbed once, before it is persisted or traced, so redacted fields reach neither.
+    let new_segment: Vec<Message> = active
+        .memory()
+        .iter()
+        .skip(starting_len)
+        .cloned()
+        .map(scrub)
+        .collect();
     drop(guard);
 
+    let mut response = AgentChatResponse {
+        reply: String::new(),
+        transcript: Some(transcript),
+        session_id: None,
+        message: None,
+    };
+    #[cfg(feature = "persistence")]
+    if let Some(session) = &session {
+        response.transcript = None;
+        response.session_id = Some(session.id.clone());
+        response.message = new_segment
+            .iter()
+            .rev()
+            .find(|message| {
+                message.role == crate::message::Role::Assistant && message.tool_call.is_none()
+            })
+            .cloned();
+        state.store_turn(session, &new_segment).await;
+    }
     state.emit_tool_traces(&agent_id, principal.tenant.clone(), &new_segment);
 
     match result {
@@ -517,7 +1198,8 @@ async fn chat_with_agent<M: LanguageModel + 'static>(
                 json!({"path": format!("/agents/{}/chat", agent_id), "status": 200, "tenant": principal.tenant}),
                 crate::TelemetryLabels::default().with_tenant(principal.tenant.clone().unwrap_or_default()),
             );
-            Json(AgentChatResponse { reply, transcript }).into_response()
+            response.reply = reply;
+            Json(response).into_response()
         }
         Err(err) => {
             state.publish_trace(
@@ -541,61 +1223,528 @@ async fn chat_with_agent<M: LanguageModel + 'static>(
     }
 }
 
-async fn prometheus_metrics() -> impl IntoResponse {
-    use prometheus::Encoder;
-    let registry = crate::metrics::init_prometheus_registry();
-    let metric_families = registry.gather();
-    let encoder = prometheus::TextEncoder::new();
-    let mut buffer = Vec::new();
-    if encoder.encode(&metric_families, &mut buffer).is_err() {
-        return (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            "failed to encode metrics",
-        )
-            .into_response();
+/// Start a chat session and return its id. Callers may name the session with
+/// `session_id`; naming one that already has messages is a conflict.
+#[cfg(feature = "persistence")]
+async fn create_session<M: LanguageModel + 'static>(
+    State(state): State<AgentRuntime<M>>,
+    headers: HeaderMap,
+    body: Option<Json<CreateSessionRequest>>,
+) -> Response {
+    let Some(factory) = &state.session_store else {
+        return json_error(StatusCode::NOT_IMPLEMENTED, "no session store configured");
+    };
+    let principal = match state.build_principal(
+        &headers,
+        &AgentChatRequest {
+            message: String::new(),
+            principal_id: None,
+            role: None,
+            tenant: None,
+            session_id: None,
+            max_steps: None,
+        },
+    ) {
+        Ok(principal) => principal,
+        Err(resp) => return resp,
+    };
+    if !state
+        .access_control
+        .authorize(&principal, &Action::SendMessage)
+    {
+        return json_error(
+            StatusCode::FORBIDDEN,
+            "principal not authorized to start sessions",
+        );
+    }
+
+    let session_id = match body.and_then(|Json(body)| body.session_id) {
+        Some(id) => {
+            if let Err(message) = validate_session_id(&id) {
+                return json_error(StatusCode::BAD_REQUEST, message);
+            }
+            id
+        }
+        None => uuid::Uuid::new_v4().to_string(),
+    };
+    // Reserve the id before looking at the store, so a concurrent request for the same
+    // name sees the conflict even while this one is still loading.
+    let key = session_storage_key(&principal, &session_id);
+    if let Err(resp) = state.reserved_sessions.reserve(&key) {
+        return resp;
+    }
+    match factory(&key).load().await {
+        Ok(messages) if messages.is_empty() => {}
+        Ok(_) => {
+            state.reserved_sessions.release(&key);
+            return json_error(StatusCode::CONFLICT, "session already exists");
+        }
+        Err(err) => {
+            state.reserved_sessions.release(&key);
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("failed to load session: {err}"),
+            );
+        }
     }
     (
-        [(axum::http::header::CONTENT_TYPE, "text/plain; charset=utf-8")],
-        buffer,
+        StatusCode::CREATED,
+        Json(json!({ "session_id": session_id })),
     )
         .into_response()
 }
 
-async fn dashboard() -> Html<&'static str> {
-    Html(
-        r#"
-<!doctype html>
-<html>
-<head>
-    <meta charset="utf-8" />
-    <title>AgentOS Dashboard</title>
-    <style>
-        body { font-family: sans-serif; margin: 2rem; }
-        .column { float: left; width: 45%; margin-right: 5%; }
-        .panel { border: 1px solid #ccc; padding: 1rem; margin-bottom: 1rem; border-radius: 8px; }
-        h2 { margin-top: 0; }
-        #events { background: #111; color: #0f0; height: 200px; overflow: auto; font-family: monospace; padding: 1rem; }
-        #trace-log { background: #f6f8fa; height: 200px; overflow: auto; font-family: monospace; padding: 0.75rem; }
-        #chat-output { background: #f6f8fa; min-height: 80px; padding: 0.75rem; white-space: pre-wrap; }
-        label { display: block; margin-top: 0.5rem; font-weight: 600; }
-        input, select, textarea { width: 100%; padding: 0.35rem; margin-top: 0.25rem; }
-        button { margin-top: 0.5rem; padding: 0.5rem 1rem; }
-    </style>
-</head>
-<body>
-    <h1>AgentOS</h1>
-    <div class="column">
-        <div class="panel">
-            <h2>Teams</h2>
-            <ul id="teams"></ul>
-        </div>
-        <div class="panel">
-            <h2>Workflows</h2>
-            <ul id="workflows"></ul>
-        </div>
-    </div>
-    <div class="column">
-        <div class="panel">
+/// A session's stored transcript, oldest first, scrubbed for the caller's tenant. Only the
+/// tenant and principal that own the session can read it; anyone else gets a 404.
+#[cfg(feature = "persistence")]
+async fn session_messages<M: LanguageModel + 'static>(
+    State(state): State<AgentRuntime<M>>,
+    Path(session_id): Path<String>,
+    Query(auth): Query<TraceAuth>,
+    headers: HeaderMap,
+) -> Response {
+    let Some(factory) = &state.session_store else {
+        return json_error(StatusCode::NOT_IMPLEMENTED, "no session store configured");
+    };
+    let principal = match state.build_principal(
+        &headers,
+        &AgentChatRequest {
+            message: String::new(),
+            principal_id: auth.principal_id,
+            role: auth.role,
+            tenant: auth.tenant,
+            session_id: None,
+            max_steps: None,
+        },
+    ) {
+        Ok(principal) => principal,
+        Err(resp) => return resp,
+    };
+    if !state
+        .access_control
+        .authorize(&principal, &Action::ReadTranscript)
+    {
+        return json_error(
+            StatusCode::FORBIDDEN,
+            "principal not authorized to read transcripts",
+        );
+    }
+
+    if let Err(message) = validate_session_id(&session_id) {
+        return json_error(StatusCode::BAD_REQUEST, message);
+    }
+    let key = session_storage_key(&principal, &session_id);
+    let reserved = state.reserved_sessions.contains(&key);
+    let messages = match factory(&key).load().await {
+        Ok(messages) if messages.is_empty() && !reserved => {
+            return json_error(StatusCode::NOT_FOUND, "session not found")
+        }
+        Ok(messages) => messages,
+        Err(err) => {
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &format!("failed to load session: {err}"),
+            )
+        }
+    };
+    let messages: Vec<Message> = messages
+        .into_iter()
+        .map(|mut message| {
+            state
+                .access_control
+                .scrub_message(principal.tenant.as_deref(), &mut message);
+            message
+        })
+        .collect();
+    Json(json!({ "session_id": session_id, "messages": messages })).into_response()
+}
+
+async fn stream_chat_with_agent<M: LanguageModel + 'static>(
+    State(state): State<AgentRuntime<M>>,
+    Path(agent_id): Path<String>,
+    headers: HeaderMap,
+    Json(req): Json<AgentChatRequest>,
+) -> Response {
+    let principal = match state.build_principal(&headers, &req) {
+        Ok(p) => p,
+        Err(resp) => return resp,
+    };
+
+    if !state
+        .access_control
+        .authorize(&principal, &Action::SendMessage)
+    {
+        return json_error(
+            StatusCode::FORBIDDEN,
+            "principal not authorized to message this agent",
+        );
+    }
+    if let Err(message) = state.check_max_steps(req.max_steps) {
+        return json_error(StatusCode::BAD_REQUEST, &message);
+    }
+
+    let agent = { state.agents.read().await.get(&agent_id).cloned() };
+    let Some(agent) = agent else {
+        return json_error(StatusCode::NOT_FOUND, "agent not registered");
+    };
+
+    state.telemetry.record(
+        "http_request",
+        json!({"path": format!("/agents/{}/chat/stream", agent_id), "tenant": principal.tenant, "principal": principal.id}),
+        crate::TelemetryLabels::default().with_tenant(principal.tenant.clone().unwrap_or_default()),
+    );
+
+    let Some(permit) = state.concurrency.try_acquire() else {
+        return saturated();
+    };
+    #[cfg(feature = "persistence")]
+    let session = match state.chat_session(&principal, requested_session(&headers, &req)) {
+        Ok(session) => session,
+        Err(message) => return json_error(StatusCode::BAD_REQUEST, message),
+    };
+    let (tx, rx) = mpsc::unbounded_channel();
+    let turn = ChatTurn {
+        message: req.message,
+        max_steps: req.max_steps,
+        #[cfg(feature = "persistence")]
+        session,
+    };
+    let cancel = spawn_streamed_run(state, agent_id, principal, agent, turn, tx, permit);
+
+    // Axum drops the body stream when the client disconnects, which cancels the run.
+    let cancel_on_disconnect = cancel.drop_guard();
+    let stream = UnboundedReceiverStream::new(rx).filter_map(move |event| {
+        let _ = &cancel_on_disconnect;
+        async move {
+            serde_json::to_string(&event)
+                .ok()
+                .map(|payload| Ok::<Event, Infallible>(Event::default().data(payload)))
+        }
+    });
+    Sse::new(stream).into_response()
+}
+
+/// Run the agent in the background, streaming its events to `events` and publishing
+/// start/tool/completion traces as it goes. A turn in a session runs on a fork loaded
+/// from the session's store, like `POST /agents/:id/chat`, and `events` stays open until
+/// the turn is stored. Cancelling the returned token stops the run at its next await point.
+fn spawn_streamed_run<M: LanguageModel + 'static>(
+    state: AgentRuntime<M>,
+    agent_id: String,
+    principal: Principal,
+    agent: Arc<Mutex<crate::Agent<M>>>,
+    turn: ChatTurn,
+    events: mpsc::UnboundedSender<crate::AgentEvent>,
+    permit: ConcurrencyPermit,
+) -> CancellationToken {
+    let cancel = CancellationToken::new();
+    let token = cancel.clone();
+    tokio::spawn(async move {
+        let _permit = permit;
+        let events_open = events.clone();
+        #[cfg(feature = "persistence")]
+        let history = match &turn.session {
+            Some(session) => match session.store.load().await {
+                Ok(messages) => Some(messages),
+                Err(err) => {
+                    let _ = events.send(crate::AgentEvent::Error {
+                        error: format!("failed to load session: {err}"),
+                    });
+                    return;
+                }
+            },
+            None => None,
+        };
+        let mut guard = Some(agent.lock().await);
+        #[cfg(feature = "persistence")]
+        let mut session_agent = history.map(|messages| {
+            let mut fork = guard.take().expect("agent is locked").fork_session();
+            fork.sync_memory_from(&crate::ConversationMemory::with_messages(messages));
+            fork
+        });
+        #[cfg(feature = "persistence")]
+        let active = match session_agent.as_mut() {
+            Some(fork) => fork,
+            None => guard.as_deref_mut().expect("agent is locked"),
+        };
+        #[cfg(not(feature = "persistence"))]
+        let active = guard.as_deref_mut().expect("agent is locked");
+        active.set_principal(principal.clone());
+        active.attach_access_control(Arc::new(state.access_control.clone()));
+        active.attach_metrics(state.metrics.clone());
+        active.attach_telemetry(state.telemetry.clone());
+        active.set_cancellation(Some(cancel));
+        if let Some(max_steps) = turn.max_steps {
+            active.limit_steps_for_next_turn(max_steps);
+        }
+
+        let starting_len = active.memory().len();
+        state.publish_trace(
+            &agent_id,
+            principal.tenant.clone(),
+            TraceKind::Started {
+                message: turn.message.clone(),
+            },
+        );
+
+        let result = active
+            .respond_stream_for(principal.clone(), turn.message, events)
+            .await;
+        active.set_cancellation(None);
+        let new_segment: Vec<Message> = active
+            .memory()
+            .iter()
+            .skip(starting_len)
+            .cloned()
+            .map(|mut message| {
+                state
+                    .access_control
+                    .scrub_message(principal.tenant.as_deref(), &mut message);
+                message
+            })
+            .collect();
+        drop(guard);
+
+        #[cfg(feature = "persistence")]
+        if let Some(session) = &turn.session {
+            state.store_turn(session, &new_segment).await;
+        }
+        drop(events_open);
+        state.emit_tool_traces(&agent_id, principal.tenant.clone(), &new_segment);
+        let kind = match result {
+            Ok(reply) => TraceKind::Completed { reply },
+            Err(err) => TraceKind::Failed {
+                error: err.to_string(),
+            },
+        };
+        state.publish_trace(&agent_id, principal.tenant.clone(), kind);
+    });
+    token
+}
+
+/// One message for a streamed run: a WebSocket frame, or the body of a streaming chat.
+#[derive(Deserialize)]
+struct ChatTurn {
+    message: String,
+    #[serde(default)]
+    max_steps: Option<usize>,
+    /// Set by the server from the request or connection, never read from the frame.
+    #[cfg(feature = "persistence")]
+    #[serde(skip)]
+    session: Option<ChatSession>,
+}
+
+async fn agent_websocket<M: LanguageModel + 'static>(
+    ws: WebSocketUpgrade,
+    State(state): State<AgentRuntime<M>>,
+    Path(agent_id): Path<String>,
+    Query(auth): Query<TraceAuth>,
+    headers: HeaderMap,
+) -> Response {
+    let request = AgentChatRequest {
+        message: String::new(),
+        principal_id: auth.principal_id,
+        role: auth.role,
+        tenant: auth.tenant,
+        session_id: auth.session_id,
+        max_steps: None,
+    };
+    #[cfg(feature = "persistence")]
+    let session_id = requested_session(&headers, &request);
+    let principal = state
+        .build_principal(&headers, &request)
+        .map_err(|_| "tenant not authorized for this deployment")
+        .and_then(|principal| {
+            if state
+                .access_control
+                .authorize(&principal, &Action::SendMessage)
+            {
+                Ok(principal)
+            } else {
+                Err("principal not authorized to message this agent")
+            }
+        });
+    let agent = { state.agents.read().await.get(&agent_id).cloned() };
+
+    ws.on_upgrade(move |mut socket| async move {
+        let (principal, agent) = match (principal, agent) {
+            (Ok(principal), Some(agent)) => (principal, agent),
+            (Err(reason), _) => return close_socket(socket, reason).await,
+            (_, None) => return close_socket(socket, "agent not registered").await,
+        };
+        // Every turn on the connection runs in the same session.
+        #[cfg(feature = "persistence")]
+        let session = match state.chat_session(&principal, session_id) {
+            Ok(session) => session,
+            Err(reason) => return close_socket(socket, reason).await,
+        };
+        state.telemetry.record(
+            "ws_connect",
+            json!({"agent": agent_id.clone(), "tenant": principal.tenant, "principal": principal.id}),
+            crate::TelemetryLabels::default().with_tenant(principal.tenant.clone().unwrap_or_default()),
+        );
+
+        while let Some(Ok(frame)) = socket.recv().await {
+            let text = match frame {
+                WsMessage::Text(text) => text,
+                WsMessage::Close(_) => break,
+                _ => continue,
+            };
+            // Accept either `{"message": "...", "max_steps": 2}` or the raw message text.
+            let turn = serde_json::from_str::<ChatTurn>(&text).unwrap_or(ChatTurn {
+                message: text,
+                max_steps: None,
+                #[cfg(feature = "persistence")]
+                session: None,
+            });
+            #[cfg(feature = "persistence")]
+            let turn = ChatTurn {
+                session: session.clone(),
+                ..turn
+            };
+            if let Err(error) = state.check_max_steps(turn.max_steps) {
+                let rejected = crate::AgentEvent::Error { error };
+                if let Ok(payload) = serde_json::to_string(&rejected) {
+                    let _ = socket.send(WsMessage::Text(payload)).await;
+                }
+                continue;
+            }
+
+            let Some(permit) = state.concurrency.try_acquire() else {
+                let saturated = crate::AgentEvent::Error {
+                    error: "server is at max concurrency; retry later".into(),
+                };
+                if let Ok(payload) = serde_json::to_string(&saturated) {
+                    let _ = socket.send(WsMessage::Text(payload)).await;
+                }
+                continue;
+            };
+            let (tx, mut rx) = mpsc::unbounded_channel();
+            let cancel = spawn_streamed_run(
+                state.clone(),
+                agent_id.clone(),
+                principal.clone(),
+                agent.clone(),
+                turn,
+                tx,
+                permit,
+            );
+            loop {
+                tokio::select! {
+                    event = rx.recv() => {
+                        let Some(event) = event else { break };
+                        let Ok(payload) = serde_json::to_string(&event) else { continue };
+                        if socket.send(WsMessage::Text(payload)).await.is_err() {
+                            cancel.cancel();
+                            return;
+                        }
+                    }
+                    incoming = socket.recv() => match incoming {
+                        Some(Ok(WsMessage::Close(_))) | Some(Err(_)) | None => {
+                            cancel.cancel();
+                            return;
+                        }
+                        Some(Ok(WsMessage::Text(_))) => {
+                            let busy = crate::AgentEvent::Error {
+                                error: "a run is already in progress".into(),
+                            };
+                            if let Ok(payload) = serde_json::to_string(&busy) {
+                                let _ = socket.send(WsMessage::Text(payload)).await;
+                            }
+                        }
+                        Some(Ok(_)) => {}
+                    },
+                }
+            }
+        }
+    })
+}
+
+async fn close_socket(mut socket: WebSocket, reason: &'static str) {
+    let _ = socket
+        .send(WsMessage::Close(Some(CloseFrame {
+            code: axum::extract::ws::close_code::POLICY,
+            reason: reason.into(),
+        })))
+        .await;
+}
+
+async fn prometheus_metrics<M: LanguageModel>(
+    State(state): State<AgentRuntime<M>>,
+) -> impl IntoResponse {
+    (
+        [(
+            axum::http::header::CONTENT_TYPE,
+            "text/plain; version=0.0.4; charset=utf-8",
+        )],
+        state.metrics.snapshot().to_prometheus(),
+    )
+}
+
+async fn metrics_summary<M: LanguageModel>(
+    State(state): State<AgentRuntime<M>>,
+) -> impl IntoResponse {
+    Json(state.metrics.snapshot().summary())
+}
+
+async fn cost_summary<M: LanguageModel>(State(state): State<AgentRuntime<M>>) -> impl IntoResponse {
+    let snapshot = state.metrics.snapshot();
+    Json(CostSummary {
+        total_usd: snapshot.cost_by_tenant.values().sum(),
+        by_tenant: snapshot.cost_by_tenant,
+        by_model: snapshot.cost_by_model,
+    })
+}
+
+async fn dashboard() -> Html<&'static str> {
+    Html(
+        r#"
+<!doctype html>
+<html>
+<head>
+    <meta charset="utf-8" />
+    <title>AgentOS Dashboard</title>
+    <style>
+        body { font-family: sans-serif; margin: 2rem; }
+        .column { float: left; width: 45%; margin-right: 5%; }
+        .panel { border: 1px solid #ccc; padding: 1rem; margin-bottom: 1rem; border-radius: 8px; }
+        h2 { margin-top: 0; }
+        #events { background: #111; color: #0f0; height: 200px; overflow: auto; font-family: monospace; padding: 1rem; }
+        #trace-log { background: #f6f8fa; height: 200px; overflow: auto; font-family: monospace; padding: 0.75rem; }
+        #chat-output { background: #f6f8fa; min-height: 80px; padding: 0.75rem; white-space: pre-wrap; }
+        label { display: block; margin-top: 0.5rem; font-weight: 600; }
+        input, select, textarea { width: 100%; padding: 0.35rem; margin-top: 0.25rem; }
+        button { margin-top: 0.5rem; padding: 0.5rem 1rem; }
+        h3 { margin: 0.75rem 0 0.25rem; font-size: 0.9rem; }
+        .stats { display: flex; flex-wrap: wrap; gap: 1rem; }
+        .stats span { display: block; font-size: 0.8rem; color: #666; }
+        .bar-row { display: flex; align-items: center; gap: 0.5rem; margin: 0.2rem 0; }
+        .bar-row span { width: 8rem; overflow: hidden; text-overflow: ellipsis; }
+        .bar { background: steelblue; color: white; padding: 0 0.3rem; min-width: 1.5rem; }
+    </style>
+</head>
+<body>
+    <h1>AgentOS</h1>
+    <div class="column">
+        <div class="panel">
+            <h2>Teams</h2>
+            <ul id="teams"></ul>
+        </div>
+        <div class="panel">
+            <h2>Workflows</h2>
+            <ul id="workflows"></ul>
+        </div>
+        <div class="panel">
+            <h2>Metrics</h2>
+            <div id="metrics-stats" class="stats"></div>
+            <h3>Latency, p50 (green) and p95 (orange)</h3>
+            <svg id="latency-chart" width="100%" height="60" viewBox="0 0 300 60" preserveAspectRatio="none"></svg>
+            <h3>Tool calls</h3>
+            <div id="tool-calls"></div>
+        </div>
+    </div>
+    <div class="column">
+        <div class="panel">
             <h2>Events</h2>
             <div id="events"></div>
         </div>
@@ -638,6 +1787,8 @@ async fn dashboard() -> Html<&'static str> {
             await refreshAgents();
         }
         load();
+        refreshMetrics();
+        setInterval(refreshMetrics, 5000);
         const evt = new EventSource('/events');
         evt.onmessage = (ev) => {
             const node = document.getElementById('events');
@@ -663,6 +1814,37 @@ async fn dashboard() -> Html<&'static str> {
             };
         }
 
+        const latencyHistory = [];
+        async function refreshMetrics() {
+            const res = await fetch('/metrics/summary');
+            if (!res.ok) return;
+            const m = await res.json();
+            const ms = v => v == null ? '-' : `${v} ms`;
+            document.getElementById('metrics-stats').innerHTML = [
+                ['Runs', m.runs],
+                ['Success', `${(m.success_rate * 100).toFixed(1)}%`],
+                ['p50', ms(m.p50_latency_ms)],
+                ['p95', ms(m.p95_latency_ms)],
+                ['Cost', `$${m.total_cost_usd.toFixed(4)}`],
+            ].map(([label, value]) => `<div><span>${label}</span><strong>${value}</strong></div>`).join('');
+
+            latencyHistory.push([m.p50_latency_ms || 0, m.p95_latency_ms || 0]);
+            if (latencyHistory.length > 30) latencyHistory.shift();
+            const peak = Math.max(1, ...latencyHistory.map(([, p95]) => p95));
+            const line = i => latencyHistory
+                .map((point, x) => `${x * 10},${58 - (point[i] / peak) * 56}`)
+                .join(' ');
+            document.getElementById('latency-chart').innerHTML =
+                `<polyline fill="none" stroke="green" stroke-width="2" points="${line(0)}" />` +
+                `<polyline fill="none" stroke="darkorange" stroke-width="2" points="${line(1)}" />`;
+
+            const tools = Object.entries(m.tool_calls);
+            const most = Math.max(1, ...tools.map(([, count]) => count));
+            document.getElementById('tool-calls').innerHTML = tools.length
+                ? tools.map(([name, count]) => `<div class="bar-row"><span>${name}</span><div class="bar" style="width: ${(count / most) * 60}%">${count}</div></div>`).join('')
+                : 'No tool calls yet.';
+        }
+
         function subscribeTraces() {
             const agent = document.getElementById('agent-select').value;
             if (!agent) return;
@@ -722,3 +1904,1088 @@ async fn dashboard() -> Html<&'static str> {
 "#,
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StubModel;
+
+    fn free_addr() -> SocketAddr {
+        std::net::TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+    }
+
+    /// Wait for a server spawned on `addr` to accept connections.
+    async fn wait_until_serving(addr: SocketAddr) {
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(addr).await.is_ok() {
+                return;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        panic!("server on {addr} did not start");
+    }
+
+    #[tokio::test]
+    async fn refuses_tls_without_certificate_paths() {
+        let runtime = AgentRuntime::<StubModel>::new().with_server_config(ServerConfig {
+            host: "127.0.0.1".into(),
+            port: 0,
+            tls_enabled: true,
+            tls_cert_path: None,
+            tls_key_path: None,
+            max_steps_limit: 12,
+        });
+
+        let err = runtime.serve(free_addr()).await.unwrap_err();
+        assert!(err.to_string().contains("tls_cert_path"));
+    }
+
+    #[tokio::test]
+    async fn serves_health_over_tls() {
+        let dir = tempfile::tempdir().unwrap();
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".into()]).unwrap();
+        let cert_path = dir.path().join("cert.pem");
+        let key_path = dir.path().join("key.pem");
+        std::fs::write(&cert_path, certified.cert.pem()).unwrap();
+        std::fs::write(&key_path, certified.key_pair.serialize_pem()).unwrap();
+
+        let addr = free_addr();
+        let runtime = AgentRuntime::<StubModel>::new().with_server_config(ServerConfig {
+            host: "127.0.0.1".into(),
+            port: addr.port(),
+            tls_enabled: true,
+            tls_cert_path: Some(cert_path.display().to_string()),
+            tls_key_path: Some(key_path.display().to_string()),
+            max_steps_limit: 12,
+        });
+        tokio::spawn(runtime.serve(addr));
+
+        let client = reqwest::Client::builder()
+            .danger_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+        wait_until_serving(addr).await;
+        let body = client
+            .get(format!("https://{addr}/health"))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert_eq!(body, "ok");
+    }
+
+    #[tokio::test]
+    async fn exposes_prometheus_metrics() {
+        let model = StubModel::new(vec![r#"{"action":"respond","content":"hi"}"#.into()]);
+        let runtime = AgentRuntime::<StubModel>::new();
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let agent = runtime.agents.read().await.get("greeter").cloned().unwrap();
+        agent.lock().await.respond("hello").await.unwrap();
+
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        wait_until_serving(addr).await;
+        let body = reqwest::get(format!("http://{addr}/metrics"))
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        for line in body.lines().filter(|line| line.starts_with('#')) {
+            let parts: Vec<&str> = line.splitn(4, ' ').collect();
+            assert!(matches!(parts[1], "HELP" | "TYPE"), "{line}");
+            assert_eq!(parts.len(), 4, "{line}");
+        }
+        assert!(body.contains("# HELP sayr_runs_started_total"));
+        assert!(body.contains("# TYPE sayr_run_duration_milliseconds histogram"));
+        assert!(body.contains("sayr_runs_succeeded_total 1"));
+        assert!(body.contains("sayr_run_duration_milliseconds_count{agent=\"greeter\"} 1"));
+    }
+
+    #[tokio::test]
+    async fn redacts_private_fields_from_http_transcript() {
+        struct LookupTool;
+
+        #[async_trait::async_trait]
+        impl crate::tool::Tool for LookupTool {
+            fn name(&self) -> &str {
+                "lookup"
+            }
+
+            fn description(&self) -> &str {
+                "Looks up a customer record"
+            }
+
+            async fn call(&self, _input: Value) -> crate::error::Result<Value> {
+                Ok(json!({"name": "Ada", "ssn": "123-45-6789"}))
+            }
+        }
+
+        let model = StubModel::new(vec![
+            r#"{"action":"call_tool","name":"lookup","arguments":{}}"#.into(),
+            r#"{"action":"respond","content":"Found Ada."}"#.into(),
+        ]);
+        let mut tools = crate::ToolRegistry::new();
+        tools.register(LookupTool);
+        let runtime = AgentRuntime::<StubModel>::new();
+        runtime
+            .access_control()
+            .add_privacy_rule(crate::PrivacyRule::new("ssn", "[redacted]"));
+        runtime
+            .register_agent("crm", crate::Agent::new(model).with_tools(tools))
+            .await;
+        let mut traces = runtime.trace_events.subscribe();
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let client = reqwest::Client::new();
+        wait_until_serving(addr).await;
+        let body = client
+            .post(format!("http://{addr}/agents/crm/chat"))
+            .json(&json!({"message": "who is the customer?"}))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        assert!(!body.contains("123-45-6789"), "{body}");
+        let body: Value = serde_json::from_str(&body).unwrap();
+        assert_eq!(body["reply"], "Found Ada.");
+        let result = body["transcript"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .find(|message| message.get("tool_result").is_some())
+            .unwrap();
+        assert_eq!(result["tool_result"]["output"]["ssn"], "[redacted]");
+
+        let mut traced = Vec::new();
+        while let Ok(event) = traces.try_recv() {
+            traced.push(serde_json::to_string(&event).unwrap());
+        }
+        assert!(traced.iter().any(|event| event.contains("[redacted]")));
+        assert!(!traced.iter().any(|event| event.contains("123-45-6789")));
+    }
+
+    #[tokio::test]
+    async fn audits_denied_messages() {
+        let runtime = AgentRuntime::<StubModel>::new();
+        let audit = crate::InMemoryAuditSink::new();
+        runtime
+            .access_control()
+            .set_audit_sink(Arc::new(audit.clone()));
+        runtime
+            .access_control()
+            .deny(GovernanceRole::Service, Action::SendMessage);
+        runtime
+            .register_agent("greeter", crate::Agent::new(StubModel::new(vec![])))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        wait_until_serving(addr).await;
+        let resp = reqwest::Client::new()
+            .post(format!("http://{addr}/agents/greeter/chat"))
+            .header("x-principal-id", "ci-bot")
+            .header("x-principal-role", "service")
+            .json(&json!({"message": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        let entries = audit.entries();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].principal.id, "ci-bot");
+        assert_eq!(entries[0].action, Action::SendMessage);
+        assert!(!entries[0].allowed);
+    }
+
+    #[tokio::test]
+    async fn caps_steps_per_request_without_changing_the_agent() {
+        struct LookupTool;
+
+        #[async_trait::async_trait]
+        impl crate::tool::Tool for LookupTool {
+            fn name(&self) -> &str {
+                "lookup"
+            }
+
+            fn description(&self) -> &str {
+                "Looks up a customer record"
+            }
+
+            async fn call(&self, _input: Value) -> crate::error::Result<Value> {
+                Ok(json!({"name": "Ada"}))
+            }
+        }
+
+        let model = StubModel::new(vec![
+            r#"{"action":"call_tool","name":"lookup","arguments":{}}"#.into(),
+            r#"{"action":"call_tool","name":"lookup","arguments":{}}"#.into(),
+            r#"{"action":"respond","content":"Found Ada."}"#.into(),
+        ]);
+        let mut tools = crate::ToolRegistry::new();
+        tools.register(LookupTool);
+        let runtime = AgentRuntime::<StubModel>::new();
+        runtime
+            .register_agent("crm", crate::Agent::new(model).with_tools(tools))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let client = reqwest::Client::new();
+        let url = format!("http://{addr}/agents/crm/chat");
+        wait_until_serving(addr).await;
+        let capped = client
+            .post(&url)
+            .json(&json!({"message": "who is the customer?", "max_steps": 1}))
+            .send()
+            .await
+            .unwrap();
+        // One step runs the tool call; there is no second step to answer with its result.
+        assert_eq!(capped.status(), StatusCode::BAD_GATEWAY);
+        let body: Value = capped.json().await.unwrap();
+        assert!(
+            body["error"].as_str().unwrap().contains("step limit"),
+            "{body}"
+        );
+
+        let over_limit = client
+            .post(&url)
+            .json(&json!({"message": "who is the customer?", "max_steps": 13}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(over_limit.status(), StatusCode::BAD_REQUEST);
+        let body: Value = over_limit.json().await.unwrap();
+        assert_eq!(body["error"], "max_steps must be between 1 and 12");
+
+        // The override lasted one turn: the next request gets the agent's own limit.
+        let body: Value = client
+            .post(&url)
+            .json(&json!({"message": "who is the customer?"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["reply"], "Found Ada.");
+    }
+
+    #[tokio::test]
+    async fn summarizes_cost_by_tenant() {
+        let runtime = AgentRuntime::<StubModel>::new();
+        let usage = crate::TokenUsage {
+            provider: "anthropic".into(),
+            model: "claude-3-haiku".into(),
+            input_tokens: 4_000,
+            output_tokens: 1_000,
+        };
+        runtime
+            .metrics
+            .start_run(crate::TelemetryLabels::default().with_tenant("acme"))
+            .record_cost(&usage, 0.5);
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        wait_until_serving(addr).await;
+        let body: Value = reqwest::get(format!("http://{addr}/cost"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(body["total_usd"], 0.5);
+        assert_eq!(body["by_tenant"]["acme"], 0.5);
+        assert_eq!(body["by_model"]["anthropic/claude-3-haiku"], 0.5);
+    }
+
+    #[tokio::test]
+    async fn summarizes_metrics_for_the_dashboard() {
+        let runtime = AgentRuntime::<StubModel>::new();
+        let mut run = runtime.metrics.start_run(crate::TelemetryLabels::default());
+        run.record_tool_call("search");
+        run.finish(true);
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        wait_until_serving(addr).await;
+        let body: Value = reqwest::get(format!("http://{addr}/metrics/summary"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        for key in [
+            "runs",
+            "runs_succeeded",
+            "runs_failed",
+            "success_rate",
+            "p50_latency_ms",
+            "p95_latency_ms",
+            "tool_calls",
+            "total_cost_usd",
+        ] {
+            assert!(body.get(key).is_some(), "missing `{key}`");
+        }
+        assert_eq!(body["runs"], 1);
+        assert_eq!(body["success_rate"], 1.0);
+        assert_eq!(body["tool_calls"]["search"], 1);
+    }
+
+    #[tokio::test]
+    async fn lists_recent_telemetry_scoped_to_the_tenant() {
+        let runtime = AgentRuntime::<StubModel>::new();
+        let acme = crate::TelemetryLabels::default().with_tenant("acme");
+        runtime
+            .telemetry
+            .record("tool_call", json!({"tool": "search"}), acme.clone());
+        runtime
+            .telemetry
+            .record("tool_call", json!({"tool": "fetch"}), acme.clone());
+        runtime.telemetry.record(
+            "tool_call",
+            json!({"tool": "other"}),
+            crate::TelemetryLabels::default().with_tenant("globex"),
+        );
+        runtime
+            .telemetry
+            .record_failure("tool::fetch", "timed out", 1, acme);
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let url = format!("http://{addr}/telemetry/recent?limit=5&tenant=acme");
+        wait_until_serving(addr).await;
+        let body: Value = reqwest::get(&url).await.unwrap().json().await.unwrap();
+        let tools: Vec<&Value> = body["events"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|event| &event["detail"]["tool"])
+            .collect();
+        assert_eq!(tools, vec![&json!("search"), &json!("fetch")]);
+        assert_eq!(body["failures"][0]["context"], "tool::fetch");
+
+        // Reading is non-destructive, and `limit` keeps the newest records.
+        let body: Value = reqwest::get(format!(
+            "http://{addr}/telemetry/recent?limit=1&tenant=acme"
+        ))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert_eq!(body["events"].as_array().unwrap().len(), 1);
+        assert_eq!(body["events"][0]["detail"]["tool"], "fetch");
+    }
+
+    #[tokio::test]
+    async fn drains_in_flight_requests_on_shutdown() {
+        struct SlowModel {
+            started: Arc<tokio::sync::Notify>,
+        }
+
+        #[async_trait::async_trait]
+        impl LanguageModel for SlowModel {
+            async fn complete_chat(
+                &self,
+                _messages: &[Message],
+                _tools: &[crate::tool::ToolDescription],
+                _stream: bool,
+            ) -> crate::error::Result<crate::ModelCompletion> {
+                self.started.notify_one();
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(crate::ModelCompletion {
+                    content: Some("finished".into()),
+                    tool_calls: Vec::new(),
+                    usage: None,
+                })
+            }
+        }
+
+        let started = Arc::new(tokio::sync::Notify::new());
+        let runtime = AgentRuntime::<SlowModel>::new();
+        let model = Arc::new(SlowModel {
+            started: started.clone(),
+        });
+        runtime
+            .register_agent("slow", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        let (stop_tx, stop_rx) = tokio::sync::oneshot::channel::<()>();
+        let server = tokio::spawn(runtime.serve_with_shutdown(addr, async {
+            let _ = stop_rx.await;
+        }));
+        wait_until_serving(addr).await;
+
+        let in_flight = tokio::spawn(
+            reqwest::Client::new()
+                .post(format!("http://{addr}/agents/slow/chat"))
+                .json(&json!({"message": "take your time"}))
+                .send(),
+        );
+        started.notified().await;
+        stop_tx.send(()).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        assert!(reqwest::Client::new()
+            .get(format!("http://{addr}/health"))
+            .send()
+            .await
+            .is_err());
+        let resp = in_flight.await.unwrap().unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["reply"], "finished");
+        server.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn lowering_concurrency_limit_retires_held_permits() {
+        let limit = Arc::new(ConcurrencyLimit::new(2));
+        let first = limit.try_acquire().unwrap();
+        let second = limit.try_acquire().unwrap();
+
+        limit.set_limit(1);
+        drop(first);
+        assert!(limit.try_acquire().is_none());
+        drop(second);
+        let third = limit.try_acquire().unwrap();
+        assert!(limit.try_acquire().is_none());
+
+        limit.set_limit(3);
+        assert!(limit.try_acquire().is_some());
+        drop(third);
+        assert_eq!(limit.semaphore.available_permits(), 3);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[test]
+    fn session_reservations_conflict_until_released() {
+        let reservations = SessionReservations::default();
+        assert!(reservations.reserve("-.anonymous.a").is_ok());
+        let taken = reservations.reserve("-.anonymous.a").unwrap_err();
+        assert_eq!(taken.status(), StatusCode::CONFLICT);
+        assert!(reservations.contains("-.anonymous.a"));
+
+        reservations.release("-.anonymous.a");
+        assert!(!reservations.contains("-.anonymous.a"));
+        assert!(reservations.reserve("-.anonymous.a").is_ok());
+    }
+
+    #[tokio::test]
+    async fn rejects_requests_beyond_max_concurrency() {
+        struct SlowModel;
+
+        #[async_trait::async_trait]
+        impl LanguageModel for SlowModel {
+            async fn complete_chat(
+                &self,
+                _messages: &[Message],
+                _tools: &[crate::tool::ToolDescription],
+                _stream: bool,
+            ) -> crate::error::Result<crate::ModelCompletion> {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                Ok(crate::ModelCompletion {
+                    content: Some("done".into()),
+                    tool_calls: Vec::new(),
+                    usage: None,
+                })
+            }
+        }
+
+        let deployment = DeploymentConfig {
+            max_concurrency: 2,
+            ..DeploymentConfig::default()
+        };
+        let runtime = AgentRuntime::<SlowModel>::new().with_deployment_config(&deployment);
+        runtime
+            .register_agent("slow", crate::Agent::new(Arc::new(SlowModel)))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.clone().serve(addr));
+        wait_until_serving(addr).await;
+
+        let client = reqwest::Client::new();
+        let requests = (0..=deployment.max_concurrency).map(|_| {
+            client
+                .post(format!("http://{addr}/agents/slow/chat"))
+                .json(&json!({"message": "hello"}))
+                .send()
+        });
+        let responses = futures::future::join_all(requests).await;
+        let rejected: Vec<_> = responses
+            .iter()
+            .map(|resp| resp.as_ref().unwrap())
+            .filter(|resp| resp.status() == reqwest::StatusCode::SERVICE_UNAVAILABLE)
+            .collect();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].headers()["retry-after"], "1");
+
+        runtime.set_max_concurrency(0);
+        let resp = client
+            .post(format!("http://{addr}/agents/slow/chat"))
+            .json(&json!({"message": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+        runtime.set_max_concurrency(1);
+        assert_eq!(runtime.max_concurrency(), 1);
+        let resp = client
+            .post(format!("http://{addr}/agents/slow/chat"))
+            .json(&json!({"message": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[tokio::test]
+    async fn resumes_chat_sessions_from_a_custom_store() {
+        type Sessions = Arc<std::sync::Mutex<HashMap<String, Vec<Message>>>>;
+
+        struct MapStore {
+            sessions: Sessions,
+            id: String,
+        }
+
+        #[async_trait::async_trait]
+        impl ConversationStore for MapStore {
+            async fn load(&self) -> Result<Vec<Message>> {
+                let sessions = self.sessions.lock().unwrap();
+                Ok(sessions.get(&self.id).cloned().unwrap_or_default())
+            }
+
+            async fn append(&self, message: &Message) -> Result<()> {
+                let mut sessions = self.sessions.lock().unwrap();
+                sessions
+                    .entry(self.id.clone())
+                    .or_default()
+                    .push(message.clone());
+                Ok(())
+            }
+
+            async fn clear(&self) -> Result<()> {
+                self.sessions.lock().unwrap().remove(&self.id);
+                Ok(())
+            }
+        }
+
+        let sessions = Sessions::default();
+        let store_sessions = sessions.clone();
+        let runtime = AgentRuntime::<StubModel>::new().with_session_store(move |id| MapStore {
+            sessions: store_sessions.clone(),
+            id: id.to_string(),
+        });
+        let model = StubModel::new(vec![
+            r#"{"action":"respond","content":"one"}"#.into(),
+            r#"{"action":"respond","content":"two"}"#.into(),
+            r#"{"action":"respond","content":"three"}"#.into(),
+            r#"{"action":"respond","content":"four"}"#.into(),
+        ]);
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let client = reqwest::Client::new();
+        let chat = |session: &'static str| {
+            client
+                .post(format!("http://{addr}/agents/greeter/chat"))
+                .json(&json!({"message": "hello", "session_id": session}))
+                .send()
+        };
+        wait_until_serving(addr).await;
+        let first: Value = chat("alpha").await.unwrap().json().await.unwrap();
+        assert_eq!(first["session_id"], "alpha");
+        assert_eq!(first["message"]["content"], "one");
+        assert_eq!(sessions.lock().unwrap()["-.anonymous.alpha"].len(), 2);
+
+        let other: Value = chat("beta").await.unwrap().json().await.unwrap();
+        assert_eq!(other["message"]["content"], "two");
+        assert_eq!(sessions.lock().unwrap()["-.anonymous.beta"].len(), 2);
+
+        let resumed: Value = chat("alpha").await.unwrap().json().await.unwrap();
+        assert_eq!(resumed["reply"], "three");
+        {
+            let transcript = &sessions.lock().unwrap()["-.anonymous.alpha"];
+            assert_eq!(transcript.len(), 4);
+            assert_eq!(transcript[1].content, "one");
+            assert_eq!(transcript[3].content, "three");
+        }
+
+        let escape = client
+            .post(format!("http://{addr}/agents/greeter/chat"))
+            .json(&json!({"message": "hello", "session_id": "../../x"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(escape.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // The same id under another tenant is a separate session.
+        let tenant: Value = client
+            .post(format!("http://{addr}/agents/greeter/chat"))
+            .header("x-tenant", "acme")
+            .json(&json!({"message": "hello", "session_id": "alpha"}))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(tenant["reply"], "four");
+        let sessions = sessions.lock().unwrap();
+        assert_eq!(sessions["acme.anonymous.alpha"].len(), 2);
+        assert_eq!(sessions["-.anonymous.alpha"].len(), 4);
+        assert_eq!(sessions.len(), 3);
+    }
+
+    #[cfg(feature = "persistence")]
+    #[tokio::test]
+    async fn sessions_survive_a_runtime_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let boot = |replies: &[&str]| {
+            let root = root.clone();
+            let runtime = AgentRuntime::<StubModel>::new().with_session_store(move |id| {
+                crate::FileConversationStore::new(
+                    root.join(format!("{id}.jsonl")).display().to_string(),
+                )
+            });
+            let model = StubModel::new(
+                replies
+                    .iter()
+                    .map(|reply| format!(r#"{{"action":"respond","content":"{reply}"}}"#))
+                    .collect(),
+            );
+            (runtime, crate::Agent::new(model))
+        };
+        async fn chat(addr: SocketAddr, session: Option<&str>, message: &str) -> Value {
+            wait_until_serving(addr).await;
+            let mut request = reqwest::Client::new()
+                .post(format!("http://{addr}/agents/greeter/chat"))
+                .json(&json!({ "message": message }));
+            if let Some(session) = session {
+                request = request.header("x-session-id", session);
+            }
+            request.send().await.unwrap().json().await.unwrap()
+        }
+
+        let (runtime, agent) = boot(&["noted, your name is Ada"]);
+        runtime.register_agent("greeter", agent).await;
+        let addr = free_addr();
+        let server = tokio::spawn(runtime.serve(addr));
+        chat(addr, Some("ada"), "my name is Ada").await;
+        server.abort();
+
+        let (restarted, agent) = boot(&["you are Ada", "no idea"]);
+        restarted.register_agent("greeter", agent).await;
+        let addr = free_addr();
+        tokio::spawn(restarted.serve(addr));
+        let resumed = chat(addr, Some("ada"), "what is my name?").await;
+        assert_eq!(resumed["reply"], "you are Ada");
+        let history: Value = reqwest::get(format!("http://{addr}/sessions/ada/messages"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let transcript = history["messages"].as_array().unwrap();
+        assert_eq!(transcript.len(), 4);
+        assert_eq!(transcript[0]["content"], "my name is Ada");
+
+        let anonymous = chat(addr, None, "who am I?").await;
+        assert_eq!(anonymous["transcript"].as_array().unwrap().len(), 2);
+        assert_eq!(anonymous["reply"], "no idea");
+    }
+
+    #[cfg(feature = "persistence")]
+    #[tokio::test]
+    async fn creates_sessions_and_serves_their_history() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let runtime = AgentRuntime::<StubModel>::new().with_session_store(move |id| {
+            crate::FileConversationStore::new(
+                root.join(format!("{id}.jsonl")).display().to_string(),
+            )
+        });
+        let model = StubModel::new(vec![
+            r#"{"action":"respond","content":"hello Ada"}"#.into(),
+            r#"{"action":"respond","content":"you are Ada"}"#.into(),
+        ]);
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.clone().serve(addr));
+
+        let client = reqwest::Client::new();
+        wait_until_serving(addr).await;
+        let created = client
+            .post(format!("http://{addr}/sessions"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(created.status(), reqwest::StatusCode::CREATED);
+        let session_id = created.json::<Value>().await.unwrap()["session_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+
+        let mut replies = Vec::new();
+        for message in ["I am Ada", "who am I?"] {
+            let reply: Value = client
+                .post(format!("http://{addr}/agents/greeter/chat"))
+                .json(&json!({ "message": message, "session_id": session_id }))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            assert_eq!(reply["session_id"], session_id.as_str());
+            assert!(reply.get("transcript").is_none());
+            assert_eq!(reply["message"]["role"], "Assistant");
+            replies.push(reply["message"]["content"].clone());
+        }
+        assert_eq!(replies, ["hello Ada", "you are Ada"]);
+        // Once the store holds the session, the runtime stops tracking it.
+        assert!(!runtime
+            .reserved_sessions
+            .contains(&format!("-.anonymous.{session_id}")));
+
+        let history: Value = client
+            .get(format!("http://{addr}/sessions/{session_id}/messages"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        let contents: Vec<&str> = history["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|message| message["content"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            contents,
+            ["I am Ada", "hello Ada", "who am I?", "you are Ada"]
+        );
+
+        let named = |name: &str| {
+            client
+                .post(format!("http://{addr}/sessions"))
+                .json(&json!({ "session_id": name }))
+                .send()
+        };
+        let fresh = named("support-42").await.unwrap();
+        assert_eq!(fresh.status(), reqwest::StatusCode::CREATED);
+        assert_eq!(
+            fresh.json::<Value>().await.unwrap()["session_id"],
+            "support-42"
+        );
+        let taken = named(&session_id).await.unwrap();
+        assert_eq!(taken.status(), reqwest::StatusCode::CONFLICT);
+        let racing = futures::future::join_all((0..4).map(|_| named("race"))).await;
+        let won = racing
+            .into_iter()
+            .filter(|resp| resp.as_ref().unwrap().status() == reqwest::StatusCode::CREATED)
+            .count();
+        assert_eq!(won, 1);
+        let escape = named("..%2F..%2Fetc").await.unwrap();
+        assert_eq!(escape.status(), reqwest::StatusCode::BAD_REQUEST);
+
+        // Sessions belong to their owner: another principal neither sees nor collides with them.
+        let read_as = |principal: &str| {
+            client
+                .get(format!("http://{addr}/sessions/{session_id}/messages"))
+                .header("x-principal-id", principal)
+                .send()
+        };
+        let stranger = read_as("mallory").await.unwrap();
+        assert_eq!(stranger.status(), reqwest::StatusCode::NOT_FOUND);
+        let traversal = client
+            .get(format!("http://{addr}/sessions/..%2F..%2Fsecret/messages"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(traversal.status(), reqwest::StatusCode::BAD_REQUEST);
+        let own = client
+            .post(format!("http://{addr}/sessions"))
+            .header("x-principal-id", "mallory")
+            .json(&json!({ "session_id": session_id }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(own.status(), reqwest::StatusCode::CREATED);
+    }
+
+    type WsClient = tokio_tungstenite::WebSocketStream<
+        tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>,
+    >;
+
+    async fn connect_ws(addr: SocketAddr, path: &str) -> WsClient {
+        wait_until_serving(addr).await;
+        let (socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}{path}"))
+            .await
+            .unwrap();
+        socket
+    }
+
+    #[tokio::test]
+    async fn streams_agent_events_over_websocket() {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let model = StubModel::new(vec![r#"{"action":"respond","content":"hi there"}"#.into()]);
+        let runtime = AgentRuntime::<StubModel>::new();
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let mut socket = connect_ws(addr, "/agents/greeter/ws").await;
+        socket
+            .send(Frame::Text(r#"{"message":"hello"}"#.into()))
+            .await
+            .unwrap();
+
+        let mut events = Vec::new();
+        while let Some(Ok(Frame::Text(text))) = socket.next().await {
+            let event: Value = serde_json::from_str(&text).unwrap();
+            let done = event["event"] == "done";
+            events.push(event);
+            if done {
+                break;
+            }
+        }
+        let done = events.last().unwrap();
+        assert_eq!(done["event"], "done");
+        assert_eq!(done["reply"], "hi there");
+    }
+
+    #[tokio::test]
+    async fn streams_chat_over_server_sent_events() {
+        let model = StubModel::new(vec![r#"{"action":"respond","content":"hi there"}"#.into()]);
+        let runtime = AgentRuntime::<StubModel>::new();
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        wait_until_serving(addr).await;
+        let resp = reqwest::Client::new()
+            .post(format!("http://{addr}/agents/greeter/chat/stream"))
+            .json(&json!({"message": "hello"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(resp.headers()["content-type"], "text/event-stream");
+
+        // The body ends once the run finishes and the event channel closes.
+        let body = resp.text().await.unwrap();
+        let events: Vec<Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(|data| serde_json::from_str(data).unwrap())
+            .collect();
+        let streamed: String = events
+            .iter()
+            .filter(|event| event["event"] == "content")
+            .map(|event| event["delta"].as_str().unwrap())
+            .collect();
+        assert_eq!(streamed, "hi there");
+        let done = events.last().unwrap();
+        assert_eq!(done["event"], "done");
+        assert_eq!(done["reply"], "hi there");
+    }
+
+    #[cfg(feature = "persistence")]
+    #[tokio::test]
+    async fn streamed_chats_resume_their_session() {
+        use futures::SinkExt;
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        /// Replies with how many messages it was sent, so a loaded history shows.
+        struct CountingModel;
+
+        #[async_trait::async_trait]
+        impl LanguageModel for CountingModel {
+            async fn complete_chat(
+                &self,
+                messages: &[Message],
+                _tools: &[crate::ToolDescription],
+                _stream: bool,
+            ) -> Result<crate::ModelCompletion> {
+                Ok(crate::ModelCompletion {
+                    content: Some(format!("{} messages", messages.len())),
+                    tool_calls: Vec::new(),
+                    usage: None,
+                })
+            }
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_path_buf();
+        let runtime = AgentRuntime::<CountingModel>::new().with_session_store(move |id| {
+            crate::FileConversationStore::new(
+                root.join(format!("{id}.jsonl")).display().to_string(),
+            )
+        });
+        runtime
+            .register_agent("greeter", crate::Agent::new(Arc::new(CountingModel)))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.clone().serve(addr));
+
+        wait_until_serving(addr).await;
+        let client = reqwest::Client::new();
+        let body = client
+            .post(format!("http://{addr}/agents/greeter/chat/stream"))
+            .json(&json!({"message": "my name is Ada", "session_id": "ada"}))
+            .send()
+            .await
+            .unwrap()
+            .text()
+            .await
+            .unwrap();
+        // System prompt and the user message.
+        assert!(body.contains(r#""reply":"2 messages""#), "{body}");
+
+        let mut socket = connect_ws(addr, "/agents/greeter/ws?session_id=ada").await;
+        socket
+            .send(Frame::Text("what is my name?".into()))
+            .await
+            .unwrap();
+        let mut done = Value::Null;
+        while let Some(Ok(Frame::Text(text))) = socket.next().await {
+            let event: Value = serde_json::from_str(&text).unwrap();
+            if event["event"] == "done" {
+                done = event;
+                break;
+            }
+        }
+        // The first turn was loaded from the session.
+        assert_eq!(done["reply"], "4 messages");
+
+        // The turn is stored just after `done` is sent.
+        let mut transcript = Vec::new();
+        for _ in 0..50 {
+            let history: Value = client
+                .get(format!("http://{addr}/sessions/ada/messages"))
+                .send()
+                .await
+                .unwrap()
+                .json()
+                .await
+                .unwrap();
+            transcript = history["messages"].as_array().unwrap().clone();
+            if transcript.len() == 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(transcript.len(), 4);
+        assert_eq!(transcript[2]["content"], "what is my name?");
+        let agent = runtime.agents.read().await["greeter"].clone();
+        assert_eq!(agent.lock().await.memory().len(), 0);
+
+        let invalid = client
+            .post(format!("http://{addr}/agents/greeter/chat/stream"))
+            .json(&json!({"message": "hello", "session_id": "../../x"}))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(invalid.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn closes_websocket_for_unauthorized_tenant() {
+        use tokio_tungstenite::tungstenite::Message as Frame;
+
+        let runtime = AgentRuntime::<StubModel>::with_security(SecurityConfig {
+            allowed_tenants: vec!["acme".into()],
+            ..SecurityConfig::default()
+        });
+        runtime
+            .register_agent("greeter", crate::Agent::new(StubModel::new(vec![])))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let mut socket = connect_ws(addr, "/agents/greeter/ws?tenant=other").await;
+        match socket.next().await {
+            Some(Ok(Frame::Close(Some(frame)))) => {
+                assert_eq!(frame.reason, "tenant not authorized for this deployment");
+            }
+            other => panic!("expected close frame, got {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn requires_valid_bearer_token_when_api_keys_configured() {
+        let runtime = AgentRuntime::<StubModel>::with_security(SecurityConfig {
+            api_keys: vec![crate::ApiKeyConfig {
+                key: "secret-key".into(),
+                principal_id: "ci-bot".into(),
+                role: "service".into(),
+            }],
+            ..SecurityConfig::default()
+        });
+        let model = StubModel::new(vec![r#"{"action":"respond","content":"ok"}"#.into()]);
+        runtime
+            .register_agent("greeter", crate::Agent::new(model))
+            .await;
+        let addr = free_addr();
+        tokio::spawn(runtime.serve(addr));
+
+        let client = reqwest::Client::new();
+        wait_until_serving(addr).await;
+        let health = client
+            .get(format!("http://{addr}/health"))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+        let chat = |token: Option<&str>| {
+            let mut request = client
+                .post(format!("http://{addr}/agents/greeter/chat"))
+                .json(&json!({"message": "hello"}));
+            if let Some(token) = token {
+                request = request.bearer_auth(token);
+            }
+            request.send()
+        };
+        assert_eq!(
+            chat(None).await.unwrap().status(),
+            reqwest::StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            chat(Some("wrong-key")).await.unwrap().status(),
+            reqwest::StatusCode::UNAUTHORIZED
+        );
+        let resp = chat(Some("secret-key")).await.unwrap();
+        assert_eq!(resp.status(), reqwest::StatusCode::OK);
+        let body: Value = resp.json().await.unwrap();
+        assert_eq!(body["reply"], "ok");
+    }
+}
@@ -4,12 +4,21 @@ use tokio::{fs, io::AsyncWriteExt};
 
 use crate::error::{AgnoError, Result};
 use crate::message::Message;
+use crate::workflow::{Checkpointer, WorkflowCheckpoint};
 
 /// Generic persistence contract for conversation state.
+///
+/// A store holds the transcript of a single conversation; backends that keep many
+/// sessions (such as `RedisConversationStore`) bind one store to each session id.
+/// Implement this trait to persist sessions anywhere else, then hand it to
+/// [`crate::PersistentConversationMemory`] or to `AgentRuntime::with_session_store`.
 #[async_trait]
 pub trait ConversationStore: Send + Sync {
+    /// Every stored message, oldest first. A conversation that was never written is empty.
     async fn load(&self) -> Result<Vec<Message>>;
+    /// Add `message` after the stored ones.
     async fn append(&self, message: &Message) -> Result<()>;
+    /// Remove the whole conversation.
     async fn clear(&self) -> Result<()>;
 }
 
@@ -50,15 +59,19 @@ impl ConversationStore for FileConversationStore {
     async fn append(&self, message: &Message) -> Result<()> {
         let mut serialized = serde_json::to_string(message)?;
         serialized.push('\n');
-        fs::OpenOptions::new()
+        let mut file = fs::OpenOptions::new()
             .create(true)
             .append(true)
             .open(&self.path)
             .await
             .map_err(|err| {
                 AgnoError::Storage(format!("failed to open `{}`: {err}", self.path.clone()))
-            })?
-            .write_all(serialized.as_bytes())
+            })?;
+        // Flush before returning so the next append cannot land ahead of this one.
+        file.write_all(serialized.as_bytes())
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed to persist message: {err}")))?;
+        file.flush()
             .await
             .map_err(|err| AgnoError::Storage(format!("failed to persist message: {err}")))
     }
@@ -150,10 +163,203 @@ impl ConversationStore for SqlConversationStore {
     }
 }
 
+/// Key prefix used by [`RedisConversationStore`] unless overridden.
+#[cfg(feature = "redis")]
+pub const DEFAULT_REDIS_PREFIX: &str = "sayr:session:";
+
+/// Keeps each session as a Redis list of JSON messages under `{prefix}{session_id}`.
+/// Clones share the underlying connection.
+#[cfg(feature = "redis")]
+#[derive(Clone)]
+pub struct RedisConversationStore {
+    conn: redis::aio::ConnectionManager,
+    prefix: String,
+    session_id: String,
+    ttl: Option<std::time::Duration>,
+}
+
+#[cfg(feature = "redis")]
+impl RedisConversationStore {
+    pub async fn connect(url: &str, session_id: impl Into<String>) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|err| AgnoError::Storage(format!("invalid Redis URL `{url}`: {err}")))?;
+        let conn = client.get_connection_manager().await.map_err(|err| {
+            AgnoError::Storage(format!("failed connecting to Redis `{url}`: {err}"))
+        })?;
+        Ok(Self {
+            conn,
+            prefix: DEFAULT_REDIS_PREFIX.to_string(),
+            session_id: session_id.into(),
+            ttl: None,
+        })
+    }
+
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Expire a session this long after its last write.
+    pub fn with_ttl(mut self, ttl: std::time::Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// The same connection and settings, bound to another session.
+    pub fn for_session(&self, session_id: impl Into<String>) -> Self {
+        Self {
+            session_id: session_id.into(),
+            ..self.clone()
+        }
+    }
+
+    pub fn session_id(&self) -> &str {
+        &self.session_id
+    }
+
+    fn key(&self) -> String {
+        format!("{}{}", self.prefix, self.session_id)
+    }
+
+    /// Replace the stored session with `messages`.
+    pub async fn save(&self, messages: &[Message]) -> Result<()> {
+        let key = self.key();
+        let payloads = messages
+            .iter()
+            .map(serde_json::to_string)
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut pipe = redis::pipe();
+        pipe.atomic().del(&key).ignore();
+        if !payloads.is_empty() {
+            pipe.rpush(&key, payloads).ignore();
+            if let Some(ttl) = self.ttl {
+                pipe.pexpire(&key, ttl.as_millis() as i64).ignore();
+            }
+        }
+        pipe.query_async::<()>(&mut self.conn.clone())
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed saving session `{key}`: {err}")))
+    }
+
+    /// Ids of every stored session under this store's prefix.
+    pub async fn list(&self) -> Result<Vec<String>> {
+        let mut conn = self.conn.clone();
+        let mut keys =
+            redis::AsyncCommands::scan_match::<_, String>(&mut conn, format!("{}*", self.prefix))
+                .await
+                .map_err(|err| AgnoError::Storage(format!("failed listing sessions: {err}")))?;
+        let mut sessions = Vec::new();
+        while let Some(key) = keys.next_item().await {
+            if let Some(id) = key.strip_prefix(&self.prefix) {
+                sessions.push(id.to_string());
+            }
+        }
+        sessions.sort();
+        Ok(sessions)
+    }
+
+    /// Remove this session.
+    pub async fn delete(&self) -> Result<()> {
+        self.clear().await
+    }
+}
+
+#[cfg(feature = "redis")]
+#[async_trait]
+impl ConversationStore for RedisConversationStore {
+    async fn load(&self) -> Result<Vec<Message>> {
+        let key = self.key();
+        let payloads: Vec<String> =
+            redis::AsyncCommands::lrange(&mut self.conn.clone(), &key, 0, -1)
+                .await
+                .map_err(|err| {
+                    AgnoError::Storage(format!("failed loading session `{key}`: {err}"))
+                })?;
+        payloads
+            .iter()
+            .map(|payload| {
+                serde_json::from_str(payload)
+                    .map_err(|err| AgnoError::Storage(format!("invalid message payload: {err}")))
+            })
+            .collect()
+    }
+
+    async fn append(&self, message: &Message) -> Result<()> {
+        let key = self.key();
+        let mut pipe = redis::pipe();
+        pipe.atomic()
+            .rpush(&key, serde_json::to_string(message)?)
+            .ignore();
+        if let Some(ttl) = self.ttl {
+            pipe.pexpire(&key, ttl.as_millis() as i64).ignore();
+        }
+        pipe.query_async::<()>(&mut self.conn.clone())
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed writing to `{key}`: {err}")))
+    }
+
+    async fn clear(&self) -> Result<()> {
+        let key = self.key();
+        redis::AsyncCommands::del::<_, ()>(&mut self.conn.clone(), &key)
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed deleting `{key}`: {err}")))
+    }
+}
+
+/// Stores one JSON checkpoint file per workflow run inside a directory.
+pub struct FileCheckpointer {
+    dir: std::path::PathBuf,
+}
+
+impl FileCheckpointer {
+    pub fn new(dir: impl Into<std::path::PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn path_for(&self, run_id: &str) -> std::path::PathBuf {
+        self.dir
+            .join(format!("{}.json", urlencoding::encode(run_id)))
+    }
+}
+
+#[async_trait]
+impl Checkpointer for FileCheckpointer {
+    async fn save(&self, run_id: &str, checkpoint: &WorkflowCheckpoint) -> Result<()> {
+        fs::create_dir_all(&self.dir).await.map_err(|err| {
+            AgnoError::Storage(format!("failed to create `{}`: {err}", self.dir.display()))
+        })?;
+        let path = self.path_for(run_id);
+        // Write then rename so a crash mid-write never leaves a truncated checkpoint behind.
+        let staging = path.with_extension("json.tmp");
+        fs::write(&staging, serde_json::to_vec(checkpoint)?)
+            .await
+            .map_err(|err| AgnoError::Storage(format!("failed to persist checkpoint: {err}")))?;
+        fs::rename(&staging, &path).await.map_err(|err| {
+            AgnoError::Storage(format!("failed to persist `{}`: {err}", path.display()))
+        })
+    }
+
+    async fn load(&self, run_id: &str) -> Result<Option<WorkflowCheckpoint>> {
+        let path = self.path_for(run_id);
+        match fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(AgnoError::Storage(format!(
+                "failed to read checkpoint `{}`: {err}",
+                path.display()
+            ))),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::message::Role;
+    use crate::workflow::{FunctionTask, Workflow, WorkflowContext, WorkflowNode};
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
     use tempfile::NamedTempFile;
 
     #[tokio::test]
@@ -190,4 +396,100 @@ mod tests {
         let cleared = store.load().await.unwrap();
         assert!(cleared.is_empty());
     }
+
+    #[tokio::test]
+    async fn resumes_workflow_from_file_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        let first_runs = Arc::new(AtomicUsize::new(0));
+        let crash = Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        let first = {
+            let first_runs = first_runs.clone();
+            FunctionTask::new(move |ctx: &mut WorkflowContext| {
+                let first_runs = first_runs.clone();
+                Box::pin(async move {
+                    first_runs.fetch_add(1, Ordering::SeqCst);
+                    ctx.insert("draft", json!("outline"));
+                    Ok(json!("drafted"))
+                })
+            })
+        };
+        let second = {
+            let crash = crash.clone();
+            FunctionTask::new(move |ctx: &mut WorkflowContext| {
+                let crash = crash.clone();
+                Box::pin(async move {
+                    if crash.load(Ordering::SeqCst) {
+                        return Err(AgnoError::Protocol("process killed".into()));
+                    }
+                    let draft = ctx.get("draft").and_then(|v| v.as_str()).unwrap_or("");
+                    Ok(json!(format!("{draft} reviewed")))
+                })
+            })
+        };
+        let flow = Workflow::new(
+            "review",
+            WorkflowNode::Sequence(vec![
+                WorkflowNode::Task(Arc::new(first)),
+                WorkflowNode::Task(Arc::new(second)),
+            ]),
+        )
+        .with_checkpointer(Arc::new(FileCheckpointer::new(dir.path())));
+
+        assert!(flow
+            .run_with_id("run-1", &mut WorkflowContext::default())
+            .await
+            .is_err());
+
+        crash.store(false, Ordering::SeqCst);
+        let mut resumed = WorkflowContext::default();
+        let output = flow.run_with_id("run-1", &mut resumed).await.unwrap();
+
+        assert_eq!(output, json!("outline reviewed"));
+        assert_eq!(first_runs.load(Ordering::SeqCst), 1);
+        assert_eq!(resumed.get("draft"), Some(&json!("outline")));
+
+        let saved = FileCheckpointer::new(dir.path())
+            .load("run-1")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(saved.next_step, 2);
+    }
+
+    #[cfg(feature = "redis")]
+    #[tokio::test]
+    #[ignore = "needs a Redis server at REDIS_URL"]
+    async fn redis_store_saves_loads_lists_and_expires_sessions() {
+        let url = std::env::var("REDIS_URL").expect("REDIS_URL not set");
+        let prefix = format!("sayr-test:{}:", uuid::Uuid::new_v4());
+        let store = RedisConversationStore::connect(&url, "first")
+            .await
+            .unwrap()
+            .with_prefix(prefix.clone());
+
+        store
+            .save(&[Message::user("hi"), Message::assistant("hello")])
+            .await
+            .unwrap();
+        store.append(&Message::user("again")).await.unwrap();
+        let loaded = store.load().await.unwrap();
+        assert_eq!(loaded.len(), 3);
+        assert_eq!(loaded[2].content, "again");
+
+        let expiring = store
+            .for_session("second")
+            .with_ttl(std::time::Duration::from_millis(200));
+        expiring
+            .append(&Message::user("short-lived"))
+            .await
+            .unwrap();
+        assert_eq!(store.list().await.unwrap(), vec!["first", "second"]);
+        tokio::time::sleep(std::time::Duration::from_millis(400)).await;
+        assert!(expiring.load().await.unwrap().is_empty());
+        assert_eq!(store.list().await.unwrap(), vec!["first"]);
+
+        store.delete().await.unwrap();
+        assert!(store.load().await.unwrap().is_empty());
+    }
 }
@@ -1,25 +1,301 @@
 use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
+use async_trait::async_trait;
+use regex::Regex;
 use serde_json::Value;
 use tokio::sync::{broadcast, Mutex, RwLock};
 
 use crate::agent::Agent;
+use crate::error::AgnoError;
 use crate::memory::ConversationMemory;
+use crate::message::{Message, Role};
 use crate::{LanguageModel, Result};
 
 /// Events emitted by the team bus.
 #[derive(Debug, Clone)]
 pub enum TeamEvent {
-    Broadcast { from: String, content: String },
+    Broadcast {
+        from: String,
+        content: String,
+    },
     KnowledgeAdded(String),
+    /// An incoming message was routed to `agent`.
+    Routed {
+        agent: String,
+    },
+    /// A member's answer during [`Team::respond_consensus`].
+    Contribution {
+        agent: String,
+        content: String,
+    },
+    /// The answer chosen by the team's [`Aggregator`].
+    Decision {
+        content: String,
+    },
+}
+
+/// A team member as seen by a [`RoutingStrategy`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AgentProfile {
+    pub id: String,
+    pub description: String,
+}
+
+/// Chooses which team member handles an incoming message.
+#[async_trait]
+pub trait RoutingStrategy: Send + Sync {
+    /// Return the id of one of `members` (never empty).
+    async fn route(&self, message: &str, members: &[AgentProfile]) -> Result<String>;
+}
+
+/// Hands messages to each member in turn.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+    next: AtomicUsize,
+}
+
+impl RoundRobin {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for RoundRobin {
+    async fn route(&self, _message: &str, members: &[AgentProfile]) -> Result<String> {
+        let index = self.next.fetch_add(1, Ordering::Relaxed) % members.len();
+        Ok(members[index].id.clone())
+    }
+}
+
+/// Resolves the replies collected by [`Team::respond_consensus`] into one answer.
+#[async_trait]
+pub trait Aggregator: Send + Sync {
+    /// `replies` holds `(agent id, reply)` pairs in registration order (never empty).
+    async fn aggregate(&self, question: &str, replies: &[(String, String)]) -> Result<String>;
+}
+
+/// Returns the most common reply after normalizing case, whitespace and trailing
+/// punctuation. Ties go to the group whose first reply came from the earliest member.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MajorityVote;
+
+impl MajorityVote {
+    fn normalize(reply: &str) -> String {
+        reply
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .trim_end_matches(['.', '!', '?'])
+            .to_lowercase()
+    }
+}
+
+#[async_trait]
+impl Aggregator for MajorityVote {
+    async fn aggregate(&self, _question: &str, replies: &[(String, String)]) -> Result<String> {
+        let mut groups: Vec<(String, usize, &str)> = Vec::new();
+        for (_, reply) in replies {
+            let key = Self::normalize(reply);
+            match groups.iter_mut().find(|(existing, _, _)| *existing == key) {
+                Some(group) => group.1 += 1,
+                None => groups.push((key, 1, reply)),
+            }
+        }
+        let mut winner = &groups[0];
+        for group in &groups[1..] {
+            if group.1 > winner.1 {
+                winner = group;
+            }
+        }
+        Ok(winner.2.to_string())
+    }
+}
+
+/// Asks a language model to pick or merge the best of the members' replies.
+pub struct LlmJudge<R: LanguageModel> {
+    model: Arc<R>,
+}
+
+impl<R: LanguageModel> LlmJudge<R> {
+    pub fn new(model: Arc<R>) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl<R: LanguageModel> Aggregator for LlmJudge<R> {
+    async fn aggregate(&self, question: &str, replies: &[(String, String)]) -> Result<String> {
+        let mut prompt = String::from(
+            "Several agents answered the user's question. Reply with the single best final \
+             answer, merging their points where they complement each other.\n",
+        );
+        for (agent, reply) in replies {
+            prompt.push_str(&format!("\n[{agent}]\n{reply}\n"));
+        }
+        let completion = self
+            .model
+            .complete_chat(
+                &[Message::system(prompt), Message::user(question)],
+                &[],
+                false,
+            )
+            .await?;
+        completion
+            .content
+            .filter(|content| !content.trim().is_empty())
+            .ok_or_else(|| AgnoError::LanguageModel("judge model returned no answer".into()))
+    }
+}
+
+/// Routes to the agent paired with the first regex matching the message.
+#[derive(Debug, Default)]
+pub struct KeywordRouter {
+    rules: Vec<(Regex, String)>,
+    fallback: Option<String>,
+}
+
+impl KeywordRouter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Send messages matching `pattern` (case-insensitive) to `agent`.
+    pub fn with_rule(mut self, pattern: &str, agent: impl Into<String>) -> Result<Self> {
+        let regex = regex::RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|err| AgnoError::Protocol(format!("invalid routing pattern: {err}")))?;
+        self.rules.push((regex, agent.into()));
+        Ok(self)
+    }
+
+    /// Agent used when no rule matches; defaults to the first member.
+    pub fn with_fallback(mut self, agent: impl Into<String>) -> Self {
+        self.fallback = Some(agent.into());
+        self
+    }
+}
+
+#[async_trait]
+impl RoutingStrategy for KeywordRouter {
+    async fn route(&self, message: &str, members: &[AgentProfile]) -> Result<String> {
+        Ok(self
+            .rules
+            .iter()
+            .find(|(pattern, _)| pattern.is_match(message))
+            .map(|(_, agent)| agent.clone())
+            .or_else(|| self.fallback.clone())
+            .unwrap_or_else(|| members[0].id.clone()))
+    }
+}
+
+/// Asks a language model to pick the member best suited to the message.
+pub struct ModelRouter<R: LanguageModel> {
+    model: Arc<R>,
+}
+
+impl<R: LanguageModel> ModelRouter<R> {
+    pub fn new(model: Arc<R>) -> Self {
+        Self { model }
+    }
+}
+
+#[async_trait]
+impl<R: LanguageModel> RoutingStrategy for ModelRouter<R> {
+    async fn route(&self, message: &str, members: &[AgentProfile]) -> Result<String> {
+        let mut prompt = String::from(
+            "Pick the agent best suited to handle the user's message. \
+             Reply with the agent id only.\nAgents:\n",
+        );
+        for member in members {
+            prompt.push_str(&format!("- {}: {}\n", member.id, member.description));
+        }
+        let completion = self
+            .model
+            .complete_chat(
+                &[Message::system(prompt), Message::user(message)],
+                &[],
+                false,
+            )
+            .await?;
+        let answer = completion.content.unwrap_or_default();
+        let answer = answer.trim().trim_matches(|c: char| c == '`' || c == '"');
+        members
+            .iter()
+            .find(|member| member.id.eq_ignore_ascii_case(answer))
+            .or_else(|| members.iter().find(|member| answer.contains(&member.id)))
+            .map(|member| member.id.clone())
+            .ok_or_else(|| {
+                AgnoError::Protocol(format!("router model chose unknown agent `{answer}`"))
+            })
+    }
+}
+
+/// Append-only transcript that team members read from and write to.
+///
+/// Only user and assistant turns are shared; tool traffic stays private to the agent that
+/// produced it. Clones share the same underlying log.
+#[derive(Debug, Clone, Default)]
+pub struct SharedMemory {
+    entries: Arc<RwLock<Vec<(String, Message)>>>,
+}
+
+impl SharedMemory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record `message` as written by `author`.
+    pub async fn append(&self, author: impl Into<String>, message: Message) {
+        self.entries.write().await.push((author.into(), message));
+    }
+
+    /// All entries as `(author, message)` pairs in the order they were written.
+    pub async fn entries(&self) -> Vec<(String, Message)> {
+        self.entries.read().await.clone()
+    }
+
+    pub async fn len(&self) -> usize {
+        self.entries.read().await.len()
+    }
+
+    pub async fn is_empty(&self) -> bool {
+        self.entries.read().await.is_empty()
+    }
+
+    /// The transcript as seen by `agent`: other authors' replies are prefixed with
+    /// `[author]` so the agent can tell them apart from its own.
+    pub async fn view_for(&self, agent: &str) -> ConversationMemory {
+        let entries = self.entries.read().await;
+        ConversationMemory::with_messages(
+            entries
+                .iter()
+                .map(|(author, message)| match message.role {
+                    Role::Assistant if author != agent => {
+                        Message::assistant(format!("[{author}] {}", message.content))
+                    }
+                    _ => message.clone(),
+                })
+                .collect(),
+        )
+    }
+
+    fn is_shared_turn(message: &Message) -> bool {
+        matches!(message.role, Role::User | Role::Assistant) && message.tool_call.is_none()
+    }
 }
 
 /// A coordination surface for multiple agents that share context and a message bus.
 pub struct Team<M: LanguageModel> {
     name: String,
     members: BTreeMap<String, Arc<Mutex<Agent<M>>>>,
-    shared_memory: Arc<RwLock<ConversationMemory>>,
+    descriptions: BTreeMap<String, String>,
+    router: Arc<dyn RoutingStrategy>,
+    aggregator: Arc<dyn Aggregator>,
+    shared_memory: Option<SharedMemory>,
     shared_context: Arc<RwLock<Value>>,
     knowledge: Arc<RwLock<Vec<String>>>,
     tx: broadcast::Sender<TeamEvent>,
@@ -30,7 +306,10 @@ impl<M: LanguageModel> Clone for Team<M> {
         Self {
             name: self.name.clone(),
             members: self.members.clone(),
-            shared_memory: Arc::clone(&self.shared_memory),
+            descriptions: self.descriptions.clone(),
+            router: Arc::clone(&self.router),
+            aggregator: Arc::clone(&self.aggregator),
+            shared_memory: self.shared_memory.clone(),
             shared_context: Arc::clone(&self.shared_context),
             knowledge: Arc::clone(&self.knowledge),
             tx: self.tx.clone(),
@@ -45,13 +324,46 @@ impl<M: LanguageModel> Team<M> {
         Self {
             name: name.into(),
             members: BTreeMap::new(),
-            shared_memory: Arc::new(RwLock::new(ConversationMemory::default())),
+            descriptions: BTreeMap::new(),
+            router: Arc::new(RoundRobin::new()),
+            aggregator: Arc::new(MajorityVote),
+            shared_memory: Some(SharedMemory::new()),
             shared_context: Arc::new(RwLock::new(Value::Null)),
             knowledge: Arc::new(RwLock::new(Vec::new())),
             tx,
         }
     }
 
+    /// Choose the member that handles each message passed to [`Team::respond`].
+    /// Defaults to [`RoundRobin`].
+    pub fn with_router(mut self, router: Arc<dyn RoutingStrategy>) -> Self {
+        self.router = router;
+        self
+    }
+
+    /// Choose how [`Team::respond_consensus`] settles on an answer. Defaults to [`MajorityVote`].
+    pub fn with_aggregator(mut self, aggregator: Arc<dyn Aggregator>) -> Self {
+        self.aggregator = aggregator;
+        self
+    }
+
+    /// Have members read and write through `memory`, e.g. to share one log across teams.
+    pub fn with_shared_memory(mut self, memory: SharedMemory) -> Self {
+        self.shared_memory = Some(memory);
+        self
+    }
+
+    /// Let every member keep its own private conversation memory.
+    pub fn without_shared_memory(mut self) -> Self {
+        self.shared_memory = None;
+        self
+    }
+
+    /// The transcript shared by members, if enabled.
+    pub fn shared_memory(&self) -> Option<&SharedMemory> {
+        self.shared_memory.as_ref()
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -61,6 +373,18 @@ impl<M: LanguageModel> Team<M> {
         self.members.insert(id.into(), Arc::new(Mutex::new(agent)));
     }
 
+    /// Register an agent along with a description that routers can use to pick it.
+    pub fn add_agent_with_description(
+        &mut self,
+        id: impl Into<String>,
+        description: impl Into<String>,
+        agent: Agent<M>,
+    ) {
+        let id = id.into();
+        self.descriptions.insert(id.clone(), description.into());
+        self.add_agent(id, agent);
+    }
+
     /// Number of registered agents.
     pub fn size(&self) -> usize {
         self.members.len()
@@ -92,31 +416,122 @@ impl<M: LanguageModel> Team<M> {
     pub async fn broadcast(&self, from: impl Into<String>, content: impl Into<String>) {
         let from = from.into();
         let content = content.into();
-        if let Ok(mut memory) = self.shared_memory.try_write() {
-            memory.push(crate::message::Message::assistant(format!(
-                "[{from}] {content}"
-            )));
+        if let Some(memory) = &self.shared_memory {
+            memory
+                .append(from.clone(), Message::assistant(content.clone()))
+                .await;
         }
         let _ = self.tx.send(TeamEvent::Broadcast { from, content });
     }
 
+    /// Route `message` to a single member and return its id along with the reply.
+    pub async fn respond(&self, message: &str) -> Result<(String, String)> {
+        if self.members.is_empty() {
+            return Err(AgnoError::Protocol(format!(
+                "team `{}` has no agents to route to",
+                self.name
+            )));
+        }
+        let profiles: Vec<AgentProfile> = self
+            .members
+            .keys()
+            .map(|id| AgentProfile {
+                id: id.clone(),
+                description: self.descriptions.get(id).cloned().unwrap_or_default(),
+            })
+            .collect();
+        let target = self.router.route(message, &profiles).await?;
+        let agent = self.members.get(&target).ok_or_else(|| {
+            AgnoError::Protocol(format!("router selected unknown agent `{target}`"))
+        })?;
+        let _ = self.tx.send(TeamEvent::Routed {
+            agent: target.clone(),
+        });
+
+        let reply = self.run_member(&target, agent, message).await?;
+        Ok((target, reply))
+    }
+
+    /// Ask every member concurrently and resolve their replies with the team's [`Aggregator`].
+    ///
+    /// Members that fail are left out of the vote; an error is returned only if all of them
+    /// fail. The question and the final decision are appended to the shared transcript.
+    pub async fn respond_consensus(&self, message: &str) -> Result<String> {
+        let runs = self.members.iter().map(|(id, agent)| async move {
+            let mut guard = agent.lock().await;
+            if let Some(memory) = &self.shared_memory {
+                guard.sync_memory_from(&memory.view_for(id).await);
+            }
+            (id, guard.respond(message).await)
+        });
+
+        let mut replies = Vec::new();
+        let mut last_error = None;
+        for (id, outcome) in futures::future::join_all(runs).await {
+            match outcome {
+                Ok(content) => {
+                    let _ = self.tx.send(TeamEvent::Contribution {
+                        agent: id.clone(),
+                        content: content.clone(),
+                    });
+                    replies.push((id.clone(), content));
+                }
+                Err(err) => {
+                    tracing::warn!("team member `{id}` failed to answer: {err}");
+                    last_error = Some(err);
+                }
+            }
+        }
+        if replies.is_empty() {
+            return Err(last_error.unwrap_or_else(|| {
+                AgnoError::Protocol(format!("team `{}` has no agents to consult", self.name))
+            }));
+        }
+
+        let decision = self.aggregator.aggregate(message, &replies).await?;
+        if let Some(memory) = &self.shared_memory {
+            memory
+                .append(self.name.clone(), Message::user(message))
+                .await;
+            memory
+                .append(self.name.clone(), Message::assistant(decision.clone()))
+                .await;
+        }
+        let _ = self.tx.send(TeamEvent::Decision {
+            content: decision.clone(),
+        });
+        Ok(decision)
+    }
+
     /// Run the same prompt through every agent, synchronizing memory back into the shared
     /// transcript after each response. Returns agent replies in registration order.
     pub async fn fan_out(&self, prompt: &str) -> Result<Vec<(String, String)>> {
         let mut replies = Vec::new();
         for (id, agent) in &self.members {
-            let mut guard = agent.lock().await;
-            // Share the latest transcript with the agent before it responds.
-            let snapshot = { self.shared_memory.read().await.clone() };
-            guard.sync_memory_from(&snapshot);
-            let reply = guard.respond(prompt).await?;
-            // Persist the updated transcript back into the shared memory.
-            let updated = guard.take_memory_snapshot();
-            *self.shared_memory.write().await = updated;
+            let reply = self.run_member(id, agent, prompt).await?;
             replies.push((id.clone(), reply));
         }
         Ok(replies)
     }
+
+    /// Let one member respond, reading the shared transcript beforehand and appending its
+    /// new turns afterwards.
+    async fn run_member(&self, id: &str, agent: &Mutex<Agent<M>>, message: &str) -> Result<String> {
+        let mut guard = agent.lock().await;
+        let Some(memory) = &self.shared_memory else {
+            return guard.respond(message).await;
+        };
+        let view = memory.view_for(id).await;
+        let seen = view.len();
+        guard.sync_memory_from(&view);
+        let reply = guard.respond(message).await?;
+        for turn in guard.memory().iter().skip(seen) {
+            if SharedMemory::is_shared_turn(turn) {
+                memory.append(id, turn.clone()).await;
+            }
+        }
+        Ok(reply)
+    }
 }
 
 #[cfg(test)]
@@ -149,4 +564,144 @@ mod tests {
         assert_eq!(replies[0].1, "a2");
         assert_eq!(replies[1].1, "b2");
     }
+
+    fn replying(content: &str, times: usize) -> Agent<StubModel> {
+        let reply = format!(r#"{{"action":"respond","content":"{content}"}}"#);
+        Agent::new(StubModel::new(vec![reply; times]))
+    }
+
+    #[tokio::test]
+    async fn routes_by_keyword() {
+        let mut team = Team::new("support").with_router(Arc::new(
+            KeywordRouter::new()
+                .with_rule(r"\b(invoice|refund)\b", "billing")
+                .unwrap()
+                .with_rule(r"crash|error", "tech")
+                .unwrap(),
+        ));
+        team.add_agent("billing", replying("billing here", 1));
+        team.add_agent("tech", replying("tech here", 1));
+        let mut events = team.subscribe();
+
+        let (agent, reply) = team
+            .respond("The app shows an ERROR on launch")
+            .await
+            .unwrap();
+        assert_eq!(agent, "tech");
+        assert_eq!(reply, "tech here");
+        assert!(
+            matches!(events.recv().await.unwrap(), TeamEvent::Routed { agent } if agent == "tech")
+        );
+
+        let (agent, _) = team.respond("Where is my refund?").await.unwrap();
+        assert_eq!(agent, "billing");
+    }
+
+    #[tokio::test]
+    async fn round_robin_cycles_through_members() {
+        let mut team = Team::new("rotation");
+        for id in ["a", "b", "c"] {
+            team.add_agent(id, replying(id, 2));
+        }
+
+        let mut picked = Vec::new();
+        for _ in 0..6 {
+            picked.push(team.respond("next").await.unwrap().0);
+        }
+        assert_eq!(picked, ["a", "b", "c", "a", "b", "c"]);
+    }
+
+    #[tokio::test]
+    async fn model_router_picks_described_agent() {
+        let router = ModelRouter::new(StubModel::new(vec!["`writer`".into()]));
+        let mut team = Team::new("studio").with_router(Arc::new(router));
+        team.add_agent_with_description("coder", "writes Rust code", replying("fn main", 1));
+        team.add_agent_with_description("writer", "drafts prose", replying("Once upon", 1));
+
+        let (agent, reply) = team.respond("Write me a short story").await.unwrap();
+        assert_eq!(agent, "writer");
+        assert_eq!(reply, "Once upon");
+    }
+
+    #[tokio::test]
+    async fn majority_vote_returns_agreed_answer() {
+        let mut team = Team::new("panel");
+        team.add_agent("alpha", replying("Paris.", 1));
+        team.add_agent("beta", replying("Lyon", 1));
+        team.add_agent("gamma", replying("paris", 1));
+        let mut events = team.subscribe();
+
+        let answer = team
+            .respond_consensus("What is the capital of France?")
+            .await
+            .unwrap();
+        assert_eq!(answer, "Paris.");
+
+        let mut contributions = 0;
+        loop {
+            match events.recv().await.unwrap() {
+                TeamEvent::Contribution { .. } => contributions += 1,
+                TeamEvent::Decision { content } => {
+                    assert_eq!(content, "Paris.");
+                    break;
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(contributions, 3);
+    }
+
+    /// Replies with the most recent fact another agent contributed to the transcript.
+    struct RecallModel;
+
+    #[async_trait]
+    impl LanguageModel for RecallModel {
+        async fn complete_chat(
+            &self,
+            messages: &[Message],
+            _tools: &[crate::ToolDescription],
+            _stream: bool,
+        ) -> Result<crate::ModelCompletion> {
+            let recalled = messages
+                .iter()
+                .rev()
+                .find(|m| m.role == Role::Assistant && m.content.starts_with('['))
+                .map(|m| format!("I heard: {}", m.content))
+                .unwrap_or_else(|| "I know nothing".into());
+            Ok(crate::ModelCompletion {
+                content: Some(recalled),
+                tool_calls: Vec::new(),
+                usage: None,
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn members_see_each_others_turns_through_shared_memory() {
+        let shared = SharedMemory::new();
+        let mut team = Team::new("research")
+            .with_shared_memory(shared.clone())
+            .with_router(Arc::new(
+                KeywordRouter::new()
+                    .with_rule("remember", "alpha")
+                    .unwrap()
+                    .with_fallback("beta"),
+            ));
+        team.add_agent("alpha", Agent::new(Arc::new(RecallModel)));
+        team.add_agent("beta", Agent::new(Arc::new(RecallModel)));
+        team.broadcast("alpha", "the launch code is 0420").await;
+
+        let (_, reply) = team.respond("what did alpha say?").await.unwrap();
+        assert_eq!(reply, "I heard: [alpha] the launch code is 0420");
+
+        let entries = shared.entries().await;
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries[2].0, "beta");
+
+        let mut isolated = Team::new("solo").without_shared_memory();
+        isolated.add_agent("beta", Agent::new(Arc::new(RecallModel)));
+        isolated.broadcast("alpha", "the launch code is 0420").await;
+        let (_, reply) = isolated.respond("what did alpha say?").await.unwrap();
+        assert_eq!(reply, "I know nothing");
+    }
 }
@@ -1,6 +1,8 @@
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
-use std::time::{Duration, SystemTime};
+use std::time::SystemTime;
 
+use futures::FutureExt;
 use opentelemetry::global;
 use opentelemetry::trace::{Span, SpanKind, Tracer};
 use opentelemetry::KeyValue;
@@ -8,19 +10,24 @@ use opentelemetry_otlp::WithExportConfig;
 use opentelemetry_sdk;
 use serde::{Deserialize, Serialize};
 use serde_json;
-use tokio::time::sleep;
 use tracing::{span, Level};
 use tracing_subscriber::layer::SubscriberExt;
 use tracing_subscriber::util::SubscriberInitExt;
 use tracing_subscriber::{EnvFilter, Registry};
 
+use crate::config::TelemetryConfig;
 use crate::error::{AgnoError, Result};
+use crate::retry::{RetryDecision, RetryPolicy};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
 pub struct TelemetryLabels {
     pub tenant: Option<String>,
     pub tool: Option<String>,
     pub workflow: Option<String>,
+    /// Agent run the record belongs to. Kept out of metric attributes, where every run
+    /// would otherwise create its own series.
+    #[serde(default)]
+    pub run_id: Option<String>,
 }
 
 impl TelemetryLabels {
@@ -39,6 +46,11 @@ impl TelemetryLabels {
         self
     }
 
+    pub fn with_run_id(mut self, run_id: impl Into<String>) -> Self {
+        self.run_id = Some(run_id.into());
+        self
+    }
+
     pub fn as_attributes(&self) -> Vec<KeyValue> {
         let mut attrs = Vec::new();
         if let Some(tenant) = &self.tenant {
@@ -70,25 +82,74 @@ pub struct FailureRecord {
     pub labels: TelemetryLabels,
 }
 
-#[derive(Default, Clone)]
+/// How many events, and separately failures, a [`TelemetryCollector`] buffers before it
+/// starts dropping the oldest ones.
+pub const TELEMETRY_BUFFER_CAPACITY: usize = 10_000;
+
+#[derive(Clone)]
 pub struct TelemetryCollector {
-    events: Arc<Mutex<Vec<TelemetryEvent>>>,
-    failures: Arc<Mutex<Vec<FailureRecord>>>,
+    events: Arc<Mutex<VecDeque<TelemetryEvent>>>,
+    failures: Arc<Mutex<VecDeque<FailureRecord>>>,
+    sample_rate: f32,
+}
+
+impl Default for TelemetryCollector {
+    fn default() -> Self {
+        Self {
+            events: Arc::default(),
+            failures: Arc::default(),
+            sample_rate: 1.0,
+        }
+    }
 }
 
 impl TelemetryCollector {
+    /// A collector that samples runs at `config.sample_rate`.
+    pub fn from_config(config: &TelemetryConfig) -> Self {
+        Self::default().with_sample_rate(config.sample_rate)
+    }
+
+    /// Fraction of agent runs, between 0 and 1, whose events are recorded. Agents check
+    /// [`TelemetryCollector::is_sampled`] once per run, so a run is traced fully or not at all.
+    pub fn with_sample_rate(mut self, rate: f32) -> Self {
+        self.sample_rate = if rate.is_nan() {
+            1.0
+        } else {
+            rate.clamp(0.0, 1.0)
+        };
+        self
+    }
+
+    pub fn sample_rate(&self) -> f32 {
+        self.sample_rate
+    }
+
+    /// Whether the run `run_id` falls inside the sample. The decision hashes the id, so it
+    /// is the same every time and on every process that sees the run.
+    pub fn is_sampled(&self, run_id: &str) -> bool {
+        if self.sample_rate >= 1.0 {
+            return true;
+        }
+        let hash = crate::knowledge::fnv1a(run_id.as_bytes());
+        let position = (hash >> 11) as f64 / (1u64 << 53) as f64;
+        position < f64::from(self.sample_rate)
+    }
+
     pub fn record(
         &self,
         kind: impl Into<String>,
         detail: serde_json::Value,
         labels: TelemetryLabels,
     ) {
-        self.events.lock().unwrap().push(TelemetryEvent {
-            kind: kind.into(),
-            timestamp: SystemTime::now(),
-            detail,
-            labels,
-        });
+        push_bounded(
+            &mut self.events.lock().unwrap(),
+            TelemetryEvent {
+                kind: kind.into(),
+                timestamp: SystemTime::now(),
+                detail,
+                labels,
+            },
+        );
     }
 
     pub fn record_failure(
@@ -98,19 +159,65 @@ impl TelemetryCollector {
         attempt: u32,
         labels: TelemetryLabels,
     ) {
-        self.failures.lock().unwrap().push(FailureRecord {
-            context: context.into(),
-            error: error.into(),
-            attempt,
-            labels,
-        });
+        push_bounded(
+            &mut self.failures.lock().unwrap(),
+            FailureRecord {
+                context: context.into(),
+                error: error.into(),
+                attempt,
+                labels,
+            },
+        );
     }
 
     pub fn drain(&self) -> (Vec<TelemetryEvent>, Vec<FailureRecord>) {
         let mut events = self.events.lock().unwrap();
         let mut failures = self.failures.lock().unwrap();
-        (std::mem::take(&mut *events), std::mem::take(&mut *failures))
+        (
+            std::mem::take(&mut *events).into(),
+            std::mem::take(&mut *failures).into(),
+        )
     }
+
+    /// Copies of the last `limit` buffered events and failures, oldest first, without
+    /// draining them. With a `tenant`, only records labelled with that tenant are returned;
+    /// without one, only records that carry no tenant label, so an untenanted caller never
+    /// sees another tenant's traces.
+    pub fn recent(
+        &self,
+        limit: usize,
+        tenant: Option<&str>,
+    ) -> (Vec<TelemetryEvent>, Vec<FailureRecord>) {
+        fn last<T: Clone>(
+            records: &VecDeque<T>,
+            limit: usize,
+            visible: impl Fn(&T) -> bool,
+        ) -> Vec<T> {
+            let mut kept: Vec<T> = records
+                .iter()
+                .rev()
+                .filter(|record| visible(record))
+                .take(limit)
+                .cloned()
+                .collect();
+            kept.reverse();
+            kept
+        }
+        let visible = |labels: &TelemetryLabels| labels.tenant.as_deref() == tenant;
+        (
+            last(&self.events.lock().unwrap(), limit, |e| visible(&e.labels)),
+            last(&self.failures.lock().unwrap(), limit, |f| {
+                visible(&f.labels)
+            }),
+        )
+    }
+}
+
+fn push_bounded<T>(buffer: &mut VecDeque<T>, record: T) {
+    if buffer.len() == TELEMETRY_BUFFER_CAPACITY {
+        buffer.pop_front();
+    }
+    buffer.push_back(record);
 }
 
 #[derive(Default, Clone)]
@@ -129,20 +236,7 @@ impl TelemetrySink {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct RetryPolicy {
-    pub max_retries: u32,
-    pub backoff: Duration,
-}
-
 impl RetryPolicy {
-    pub fn default_external_call() -> Self {
-        Self {
-            max_retries: 3,
-            backoff: Duration::from_millis(200),
-        }
-    }
-
     pub async fn retry<F, Fut, T>(
         &self,
         mut f: F,
@@ -153,31 +247,42 @@ impl RetryPolicy {
         F: FnMut(u32) -> Fut,
         Fut: std::future::Future<Output = Result<T>>,
     {
-        for attempt in 0..=self.max_retries {
-            match f(attempt).await {
-                Ok(value) => return Ok(value),
-                Err(err) => {
-                    if let Some(t) = telemetry {
-                        t.record_failure("retry", format!("{err}"), attempt, labels.clone());
-                    }
-                    let span = span!(
-                        Level::INFO,
-                        "retry_failure",
-                        attempt,
-                        tenant = labels.tenant.as_deref().unwrap_or(""),
-                        tool = labels.tool.as_deref().unwrap_or(""),
-                        workflow = labels.workflow.as_deref().unwrap_or("")
-                    );
-                    let _enter = span.enter();
-                    tracing::warn!("retry attempt {} failed: {}", attempt, err);
-                    if attempt == self.max_retries {
-                        return Err(err);
-                    }
-                    sleep(self.backoff * (attempt + 1)).await;
-                }
+        self.retry_classified(
+            |attempt| f(attempt).map(|result| result.map_err(|err| (err, RetryDecision::Retry))),
+            telemetry,
+            labels,
+        )
+        .await
+    }
+
+    /// Like [`RetryPolicy::retry`], but each failure carries a [`RetryDecision`] so callers
+    /// can stop on permanent errors or honour a server-provided delay.
+    pub async fn retry_classified<F, Fut, T>(
+        &self,
+        f: F,
+        telemetry: Option<&TelemetryCollector>,
+        labels: TelemetryLabels,
+    ) -> Result<T>
+    where
+        F: FnMut(u32) -> Fut,
+        Fut: std::future::Future<Output = std::result::Result<T, (AgnoError, RetryDecision)>>,
+    {
+        self.run(f, |attempt, err| {
+            if let Some(t) = telemetry {
+                t.record_failure("retry", format!("{err}"), attempt, labels.clone());
             }
-        }
-        Err(AgnoError::Protocol("retry exhausted".into()))
+            let span = span!(
+                Level::INFO,
+                "retry_failure",
+                attempt,
+                tenant = labels.tenant.as_deref().unwrap_or(""),
+                tool = labels.tool.as_deref().unwrap_or(""),
+                workflow = labels.workflow.as_deref().unwrap_or("")
+            );
+            let _enter = span.enter();
+            tracing::warn!("retry attempt {} failed: {}", attempt, err);
+        })
+        .await
     }
 }
 
@@ -319,6 +424,7 @@ pub fn flush_tracer() {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn retries_until_success() {
@@ -335,6 +441,7 @@ mod tests {
             tenant: Some("tenant-a".into()),
             tool: Some("retry".into()),
             workflow: Some("test".into()),
+            run_id: None,
         };
         let res = policy
             .retry(
@@ -360,6 +467,49 @@ mod tests {
         assert_eq!(drained.1[0].labels, labels);
     }
 
+    #[test]
+    fn samples_runs_deterministically_at_the_configured_rate() {
+        let half = TelemetryCollector::default().with_sample_rate(0.5);
+        let sampled = (0..1000)
+            .filter(|i| half.is_sampled(&format!("run-{i}")))
+            .count();
+        assert!((400..600).contains(&sampled), "sampled {sampled} of 1000");
+        assert!((0..100).all(|i| {
+            let id = format!("run-{i}");
+            half.is_sampled(&id) == half.is_sampled(&id)
+        }));
+
+        let none = TelemetryCollector::from_config(&TelemetryConfig {
+            sample_rate: 0.0,
+            ..TelemetryConfig::default()
+        });
+        assert!(!(0..100).any(|i| none.is_sampled(&format!("run-{i}"))));
+        assert!(TelemetryCollector::default().is_sampled("any"));
+    }
+
+    #[test]
+    fn recent_scopes_reads_and_bounds_the_buffer() {
+        let telemetry = TelemetryCollector::default();
+        telemetry.record("run", serde_json::json!({}), TelemetryLabels::default());
+        for _ in 0..TELEMETRY_BUFFER_CAPACITY {
+            telemetry.record(
+                "run",
+                serde_json::json!({}),
+                TelemetryLabels::default().with_tenant("acme"),
+            );
+        }
+        // The untenanted event was the oldest, so the full buffer pushed it out.
+        assert!(telemetry.recent(10, None).0.is_empty());
+        assert_eq!(telemetry.recent(5, Some("acme")).0.len(), 5);
+
+        telemetry.record("run", serde_json::json!({}), TelemetryLabels::default());
+        let (events, _) = telemetry.recent(10, None);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].labels.tenant, None);
+        assert!(telemetry.recent(10, Some("globex")).0.is_empty());
+        assert_eq!(telemetry.drain().0.len(), TELEMETRY_BUFFER_CAPACITY);
+    }
+
     #[test]
     fn runs_fallbacks() {
         let telemetry = TelemetryCollector::default();
@@ -367,6 +517,7 @@ mod tests {
             tenant: Some("tenant-a".into()),
             tool: Some("fallback".into()),
             workflow: Some("test".into()),
+            run_id: None,
         };
         let chain = FallbackChain::new()
             .with_step("primary", || Err(AgnoError::Protocol("nope".into())))
@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
@@ -13,10 +14,30 @@ pub trait Tool: Send + Sync {
     fn description(&self) -> &str;
 
     /// Optionally return a JSON Schema-like object describing expected arguments.
+    /// [`ToolRegistry`] checks arguments against it before calling the tool.
     fn parameters(&self) -> Option<Value> {
         None
     }
+
+    /// Whether results may be memoized by a registry cache. Only return `true` for tools
+    /// without side effects whose output depends solely on their arguments.
+    fn cacheable(&self) -> bool {
+        false
+    }
+
+    /// Whether calls have side effects that a human should approve first. Agents built with
+    /// [`crate::Agent::require_confirmation_for_side_effects`] only ask about these tools.
+    fn requires_confirmation(&self) -> bool {
+        false
+    }
     async fn call(&self, input: Value) -> Result<Value>;
+
+    /// Like [`Tool::call`], with a key that stays the same whenever the same tool call is
+    /// executed again (agents pass the tool call id), so side-effecting tools can drop
+    /// duplicate retries. Defaults to [`Tool::call`].
+    async fn call_idempotent(&self, input: Value, _idempotency_key: &str) -> Result<Value> {
+        self.call(input).await
+    }
 }
 
 /// Static description of a tool that can be embedded in prompts.
@@ -30,12 +51,135 @@ pub struct ToolDescription {
 #[derive(Default, Clone)]
 pub struct ToolRegistry {
     tools: HashMap<String, Arc<dyn Tool>>,
+    default_timeout: Option<Duration>,
+    /// Per-tool overrides; `None` disables the timeout for that tool.
+    timeouts: HashMap<String, Option<Duration>>,
+    cache: Option<Arc<Mutex<ToolCache>>>,
+}
+
+struct CachedResult {
+    value: Value,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+/// LRU cache of tool results keyed by tool name and canonical JSON arguments.
+struct ToolCache {
+    ttl: Duration,
+    capacity: usize,
+    clock: u64,
+    entries: HashMap<(String, String), CachedResult>,
+}
+
+impl ToolCache {
+    fn get(&mut self, key: &(String, String)) -> Option<Value> {
+        self.clock += 1;
+        let expired = self.entries.get(key)?.stored_at.elapsed() > self.ttl;
+        if expired {
+            self.entries.remove(key);
+            return None;
+        }
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.clock;
+        Some(entry.value.clone())
+    }
+
+    fn insert(&mut self, key: (String, String), value: Value) {
+        if self.capacity == 0 {
+            return;
+        }
+        self.clock += 1;
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            let ttl = self.ttl;
+            self.entries
+                .retain(|_, entry| entry.stored_at.elapsed() <= ttl);
+            if self.entries.len() >= self.capacity {
+                if let Some(oldest) = self
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone())
+                {
+                    self.entries.remove(&oldest);
+                }
+            }
+        }
+        self.entries.insert(
+            key,
+            CachedResult {
+                value,
+                stored_at: Instant::now(),
+                last_used: self.clock,
+            },
+        );
+    }
+}
+
+/// Serialize `value` with object keys sorted so equivalent arguments share a cache key.
+fn canonical_json(value: &Value) -> String {
+    fn sorted(value: &Value) -> Value {
+        match value {
+            Value::Object(map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                Value::Object(
+                    keys.into_iter()
+                        .map(|key| (key.clone(), sorted(&map[key])))
+                        .collect(),
+                )
+            }
+            Value::Array(items) => Value::Array(items.iter().map(sorted).collect()),
+            other => other.clone(),
+        }
+    }
+    sorted(value).to_string()
 }
 
 impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: HashMap::new(),
+            default_timeout: None,
+            timeouts: HashMap::new(),
+            cache: None,
+        }
+    }
+
+    /// Memoize results of tools that report [`Tool::cacheable`], keeping at most `capacity`
+    /// entries for up to `ttl` each. Clones of the registry share the same cache.
+    pub fn with_cache(mut self, ttl: Duration, capacity: usize) -> Self {
+        self.cache = Some(Arc::new(Mutex::new(ToolCache {
+            ttl,
+            capacity,
+            clock: 0,
+            entries: HashMap::new(),
+        })));
+        self
+    }
+
+    /// Abort any tool call that runs longer than `timeout`.
+    pub fn with_default_timeout(mut self, timeout: Duration) -> Self {
+        self.default_timeout = Some(timeout);
+        self
+    }
+
+    /// Override the timeout for a single tool.
+    pub fn with_tool_timeout(mut self, name: impl Into<String>, timeout: Duration) -> Self {
+        self.timeouts.insert(name.into(), Some(timeout));
+        self
+    }
+
+    /// Let a tool run without any time limit, even when a default timeout is set.
+    pub fn without_tool_timeout(mut self, name: impl Into<String>) -> Self {
+        self.timeouts.insert(name.into(), None);
+        self
+    }
+
+    /// The timeout applied to calls of `name`, if any.
+    pub fn timeout_for(&self, name: &str) -> Option<Duration> {
+        match self.timeouts.get(name) {
+            Some(timeout) => *timeout,
+            None => self.default_timeout,
         }
     }
 
@@ -68,16 +212,179 @@ impl ToolRegistry {
     }
 
     pub async fn call(&self, name: &str, input: Value) -> Result<Value> {
+        self.call_with_key(name, input, None).await
+    }
+
+    /// Call `name`, routing through [`Tool::call_idempotent`] when an idempotency key is given.
+    pub async fn call_with_key(
+        &self,
+        name: &str,
+        input: Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<Value> {
         let tool = self
             .tools
             .get(name)
             .ok_or_else(|| AgnoError::ToolNotFound(name.to_string()))?;
-        tool.call(input)
-            .await
-            .map_err(|source| AgnoError::ToolInvocation {
-                name: name.to_string(),
-                source: Box::new(source),
-            })
+        if let Some(schema) = tool.parameters() {
+            let mut violations = Vec::new();
+            schema_violations(&schema, &input, "", &mut violations);
+            if !violations.is_empty() {
+                return Err(AgnoError::ToolArguments {
+                    tool: name.to_string(),
+                    violations,
+                });
+            }
+        }
+        let cache = self.cache.as_ref().filter(|_| tool.cacheable());
+        let cache_key = cache.map(|_| (name.to_string(), canonical_json(&input)));
+        if let (Some(cache), Some(key)) = (cache, cache_key.as_ref()) {
+            if let Some(value) = cache.lock().unwrap().get(key) {
+                return Ok(value);
+            }
+        }
+
+        let invocation = async {
+            match idempotency_key {
+                Some(key) => tool.call_idempotent(input, key).await,
+                None => tool.call(input).await,
+            }
+        };
+        let result = match self.timeout_for(name) {
+            Some(timeout) => tokio::time::timeout(timeout, invocation)
+                .await
+                .map_err(|_| AgnoError::ToolTimeout {
+                    name: name.to_string(),
+                    timeout,
+                })?,
+            None => invocation.await,
+        };
+        let value = result.map_err(|source| AgnoError::ToolExecution {
+            tool: name.to_string(),
+            source: Box::new(source),
+        })?;
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            cache.lock().unwrap().insert(key, value.clone());
+        }
+        Ok(value)
+    }
+}
+
+/// Collect every way `value` breaks `schema`, covering the JSON Schema keywords tool
+/// parameters use: `type`, `enum`, `required`, `properties`, `additionalProperties: false`,
+/// `items`, `minimum` and `maximum`. Other keywords are ignored.
+fn schema_violations(schema: &Value, value: &Value, path: &str, violations: &mut Vec<String>) {
+    let at = |path: &str| {
+        if path.is_empty() {
+            "arguments".to_string()
+        } else {
+            format!("`{path}`")
+        }
+    };
+    let field_path = |key: &str| {
+        if path.is_empty() {
+            key.to_string()
+        } else {
+            format!("{path}.{key}")
+        }
+    };
+
+    if let Some(expected) = schema.get("type") {
+        let allowed: Vec<&str> = match expected {
+            Value::String(kind) => vec![kind.as_str()],
+            Value::Array(kinds) => kinds.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !allowed.is_empty() && !allowed.iter().any(|kind| matches_type(kind, value)) {
+            violations.push(format!(
+                "{} must be {}, got {}",
+                at(path),
+                allowed.join(" or "),
+                type_name(value)
+            ));
+            return;
+        }
+    }
+    if let Some(options) = schema.get("enum").and_then(Value::as_array) {
+        if !options.contains(value) {
+            violations.push(format!(
+                "{} must be one of {}",
+                at(path),
+                Value::Array(options.clone())
+            ));
+        }
+    }
+    if let Some(number) = value.as_f64() {
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if number < minimum {
+                violations.push(format!("{} must be at least {minimum}", at(path)));
+            }
+        }
+        if let Some(maximum) = schema.get("maximum").and_then(Value::as_f64) {
+            if number > maximum {
+                violations.push(format!("{} must be at most {maximum}", at(path)));
+            }
+        }
+    }
+
+    match value {
+        Value::Object(fields) => {
+            for required in schema
+                .get("required")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+                .filter_map(Value::as_str)
+            {
+                if !fields.contains_key(required) {
+                    violations.push(format!("missing required field `{}`", field_path(required)));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (key, field) in fields {
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(field_schema) => {
+                        schema_violations(field_schema, field, &field_path(key), violations)
+                    }
+                    None if schema.get("additionalProperties") == Some(&Value::Bool(false)) => {
+                        violations.push(format!("unexpected field `{}`", field_path(key)));
+                    }
+                    None => {}
+                }
+            }
+        }
+        Value::Array(items) => {
+            if let Some(item_schema) = schema.get("items") {
+                for (index, item) in items.iter().enumerate() {
+                    schema_violations(item_schema, item, &format!("{path}[{index}]"), violations);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn matches_type(kind: &str, value: &Value) -> bool {
+    match kind {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.as_f64().is_some_and(|n| n.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
     }
 }
 
@@ -134,6 +441,78 @@ mod tests {
         assert_eq!(output["text"], "hi");
     }
 
+    #[tokio::test]
+    async fn rejects_arguments_that_break_the_parameter_schema() {
+        let mut registry = ToolRegistry::new();
+        registry.register(Echo);
+
+        let err = registry
+            .call("echo", serde_json::json!({"txt": "hi"}))
+            .await
+            .unwrap_err();
+        match &err {
+            AgnoError::ToolArguments { tool, violations } => {
+                assert_eq!(tool, "echo");
+                assert_eq!(violations, &["missing required field `text`"]);
+            }
+            other => panic!("expected argument validation error, got {other:?}"),
+        }
+        assert!(err.to_string().contains("`text`"));
+
+        let err = registry
+            .call("echo", serde_json::json!({"text": 5}))
+            .await
+            .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`text` must be string, got number"));
+    }
+
+    #[test]
+    fn accepts_whole_floats_as_integers() {
+        let schema = serde_json::json!({"type": "integer"});
+        for (value, ok) in [
+            (serde_json::json!(3), true),
+            (serde_json::json!(3.0), true),
+            (serde_json::json!(3.5), false),
+            (serde_json::json!("3"), false),
+        ] {
+            let mut violations = Vec::new();
+            schema_violations(&schema, &value, "", &mut violations);
+            assert_eq!(violations.is_empty(), ok, "{value}");
+        }
+    }
+
+    #[test]
+    fn reports_nested_schema_violations() {
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "mode": {"type": "string", "enum": ["fast", "exact"]},
+                "limit": {"type": "integer", "minimum": 1, "maximum": 50},
+                "tags": {"type": "array", "items": {"type": "string"}},
+            },
+            "additionalProperties": false,
+        });
+        let mut violations = Vec::new();
+        schema_violations(
+            &schema,
+            &serde_json::json!({"mode": "slow", "limit": 0, "tags": ["a", 2], "extra": true}),
+            "",
+            &mut violations,
+        );
+        violations.sort();
+        assert_eq!(
+            violations,
+            [
+                "`limit` must be at least 1",
+                "`mode` must be one of [\"fast\",\"exact\"]",
+                "`tags[1]` must be string, got number",
+                "unexpected field `extra`",
+            ]
+        );
+    }
+
     #[tokio::test]
     async fn describes_tools_in_deterministic_order() {
         struct Second;
@@ -161,4 +540,151 @@ mod tests {
         let names: Vec<String> = descriptions.into_iter().map(|d| d.name).collect();
         assert_eq!(names, vec!["echo", "second"]);
     }
+
+    struct Sleepy;
+
+    #[async_trait]
+    impl Tool for Sleepy {
+        fn name(&self) -> &str {
+            "sleepy"
+        }
+
+        fn description(&self) -> &str {
+            "Sleeps before answering"
+        }
+
+        async fn call(&self, input: Value) -> Result<Value> {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            Ok(input)
+        }
+    }
+
+    #[tokio::test]
+    async fn times_out_slow_tools() {
+        let mut registry = ToolRegistry::new().with_default_timeout(Duration::from_millis(20));
+        registry.register(Sleepy);
+        registry.register(Echo);
+
+        let err = registry
+            .call("sleepy", serde_json::json!({}))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err,
+            AgnoError::ToolTimeout { ref name, timeout }
+                if name == "sleepy" && timeout == Duration::from_millis(20)
+        ));
+        assert!(registry
+            .call("echo", serde_json::json!({"text": "fast"}))
+            .await
+            .is_ok());
+    }
+
+    struct Counting {
+        calls: Arc<std::sync::atomic::AtomicUsize>,
+        cacheable: bool,
+    }
+
+    #[async_trait]
+    impl Tool for Counting {
+        fn name(&self) -> &str {
+            "counting"
+        }
+
+        fn description(&self) -> &str {
+            "Counts invocations"
+        }
+
+        fn cacheable(&self) -> bool {
+            self.cacheable
+        }
+
+        async fn call(&self, _input: Value) -> Result<Value> {
+            let calls = self.calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+            Ok(serde_json::json!({ "calls": calls }))
+        }
+    }
+
+    #[tokio::test]
+    async fn caches_results_of_cacheable_tools() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new().with_cache(Duration::from_secs(60), 8);
+        registry.register(Counting {
+            calls: calls.clone(),
+            cacheable: true,
+        });
+
+        let first = registry
+            .call("counting", serde_json::json!({"a": 1, "b": 2}))
+            .await
+            .unwrap();
+        let second = registry
+            .call("counting", serde_json::json!({"b": 2, "a": 1}))
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+        registry
+            .call("counting", serde_json::json!({"a": 2}))
+            .await
+            .unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn skips_cache_for_side_effecting_tools_and_expired_entries() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new().with_cache(Duration::from_secs(60), 8);
+        registry.register(Counting {
+            calls: calls.clone(),
+            cacheable: false,
+        });
+        registry.call("counting", Value::Null).await.unwrap();
+        registry.call("counting", Value::Null).await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let mut registry = ToolRegistry::new().with_cache(Duration::from_millis(10), 8);
+        registry.register(Counting {
+            calls: calls.clone(),
+            cacheable: true,
+        });
+        registry.call("counting", Value::Null).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        registry.call("counting", Value::Null).await.unwrap();
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entry() {
+        let mut cache = ToolCache {
+            ttl: Duration::from_secs(60),
+            capacity: 2,
+            clock: 0,
+            entries: HashMap::new(),
+        };
+        let key = |k: &str| ("tool".to_string(), k.to_string());
+        cache.insert(key("a"), Value::from(1));
+        cache.insert(key("b"), Value::from(2));
+        assert!(cache.get(&key("a")).is_some());
+        cache.insert(key("c"), Value::from(3));
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+    }
+
+    #[tokio::test]
+    async fn per_tool_timeout_overrides_default() {
+        let mut registry = ToolRegistry::new()
+            .with_default_timeout(Duration::from_millis(20))
+            .without_tool_timeout("sleepy");
+        registry.register(Sleepy);
+        assert!(registry.call("sleepy", serde_json::json!({})).await.is_ok());
+
+        let mut registry = ToolRegistry::new().with_tool_timeout("sleepy", Duration::from_secs(5));
+        registry.register(Sleepy);
+        assert_eq!(registry.timeout_for("sleepy"), Some(Duration::from_secs(5)));
+        assert!(registry.call("sleepy", serde_json::json!({})).await.is_ok());
+    }
 }
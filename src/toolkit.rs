@@ -34,8 +34,8 @@ impl Tool for ReadFileTool {
 
         let contents = fs::read_to_string(path)
             .await
-            .map_err(|err| AgnoError::ToolInvocation {
-                name: self.name().into(),
+            .map_err(|err| AgnoError::ToolExecution {
+                tool: self.name().into(),
                 source: Box::new(err),
             })?;
 
@@ -71,15 +71,15 @@ impl Tool for WriteFileTool {
             .truncate(true)
             .open(path)
             .await
-            .map_err(|err| AgnoError::ToolInvocation {
-                name: self.name().into(),
+            .map_err(|err| AgnoError::ToolExecution {
+                tool: self.name().into(),
                 source: Box::new(err),
             })?;
 
         file.write_all(contents.as_bytes())
             .await
-            .map_err(|err| AgnoError::ToolInvocation {
-                name: self.name().into(),
+            .map_err(|err| AgnoError::ToolExecution {
+                tool: self.name().into(),
                 source: Box::new(err),
             })?;
 
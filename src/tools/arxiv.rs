@@ -16,6 +16,8 @@ pub struct ArxivSearchTool {
     max_results: usize,
 }
 
+const ARXIV_API_URL: &str = "http://export.arxiv.org/api/query";
+
 impl ArxivSearchTool {
     pub fn new() -> Self {
         Self {
@@ -28,6 +30,65 @@ impl ArxivSearchTool {
         self.max_results = max;
         self
     }
+
+    /// Build the arXiv API URL for a search input, combining the free-text query with
+    /// the optional `cat:` and `submittedDate:[...]` clauses and the sort order.
+    fn search_url(&self, input: &Value) -> crate::Result<String> {
+        let query = input["query"]
+            .as_str()
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
+
+        let mut clauses = Vec::new();
+        if let Some(category) = input["category"].as_str() {
+            clauses.push(format!("cat:{}", category));
+        }
+        let start = input["start_date"].as_str();
+        let end = input["end_date"].as_str();
+        if start.is_some() || end.is_some() {
+            let from = match start {
+                Some(date) => format!("{}0000", arxiv_date(date, "start_date")?),
+                None => "000001010000".to_string(),
+            };
+            let to = match end {
+                Some(date) => format!("{}2359", arxiv_date(date, "end_date")?),
+                None => "999912312359".to_string(),
+            };
+            clauses.push(format!("submittedDate:[{} TO {}]", from, to));
+        }
+        clauses.push(format!("all:{}", query));
+
+        let sort_by = match input["sort_by"].as_str().unwrap_or("relevance") {
+            "relevance" => "relevance",
+            "submitted_date" => "submittedDate",
+            "last_updated_date" => "lastUpdatedDate",
+            other => {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "unknown 'sort_by' value '{}'",
+                    other
+                )))
+            }
+        };
+
+        Ok(format!(
+            "{}?search_query={}&start=0&max_results={}&sortBy={}&sortOrder=descending",
+            ARXIV_API_URL,
+            urlencoding::encode(&clauses.join(" AND ")),
+            self.max_results,
+            sort_by
+        ))
+    }
+}
+
+/// Convert a `YYYY-MM-DD` (or `YYYYMMDD`) date to the `YYYYMMDD` form arXiv expects.
+fn arxiv_date(date: &str, field: &str) -> crate::Result<String> {
+    let digits: String = date.chars().filter(|c| *c != '-').collect();
+    if digits.len() != 8 || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return Err(crate::error::AgnoError::Protocol(format!(
+            "'{}' must be a YYYY-MM-DD date",
+            field
+        )));
+    }
+    Ok(digits)
 }
 
 impl Default for ArxivSearchTool {
@@ -46,6 +107,10 @@ impl Tool for ArxivSearchTool {
         "Search arXiv for academic papers and preprints. Returns titles, authors, abstracts, and links."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -57,6 +122,19 @@ impl Tool for ArxivSearchTool {
                 "category": {
                     "type": "string",
                     "description": "Optional arXiv category (e.g., 'cs.AI', 'physics.hep-th')"
+                },
+                "start_date": {
+                    "type": "string",
+                    "description": "Only papers submitted on or after this date (YYYY-MM-DD)"
+                },
+                "end_date": {
+                    "type": "string",
+                    "description": "Only papers submitted on or before this date (YYYY-MM-DD)"
+                },
+                "sort_by": {
+                    "type": "string",
+                    "enum": ["relevance", "submitted_date", "last_updated_date"],
+                    "description": "Result order; dates sort newest first (default: relevance)"
                 }
             },
             "required": ["query"]
@@ -64,24 +142,8 @@ impl Tool for ArxivSearchTool {
     }
 
     async fn call(&self, input: Value) -> crate::Result<Value> {
-        let query = input["query"]
-            .as_str()
-            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
-
-        let category = input["category"].as_str();
-
-        // Build the search query
-        let search_query = if let Some(cat) = category {
-            format!("cat:{} AND all:{}", cat, query)
-        } else {
-            format!("all:{}", query)
-        };
-
-        let url = format!(
-            "http://export.arxiv.org/api/query?search_query={}&start=0&max_results={}",
-            urlencoding::encode(&search_query),
-            self.max_results
-        );
+        let url = self.search_url(&input)?;
+        let query = input["query"].as_str().unwrap_or_default();
 
         let response = self
             .client
@@ -96,40 +158,7 @@ impl Tool for ArxivSearchTool {
             .await
             .map_err(|e| crate::error::AgnoError::Protocol(format!("Failed to read response: {}", e)))?;
 
-        // Parse the Atom XML response (simple parsing)
-        let mut results = Vec::new();
-        
-        // Simple XML parsing for entries
-        for entry in xml.split("<entry>").skip(1) {
-            if let Some(end) = entry.find("</entry>") {
-                let entry_xml = &entry[..end];
-                
-                let title = extract_xml_content(entry_xml, "title")
-                    .map(|s| s.replace('\n', " ").trim().to_string());
-                let summary = extract_xml_content(entry_xml, "summary")
-                    .map(|s| s.replace('\n', " ").trim().to_string());
-                let id = extract_xml_content(entry_xml, "id");
-                let published = extract_xml_content(entry_xml, "published");
-                
-                // Extract authors
-                let mut authors = Vec::new();
-                for author_block in entry_xml.split("<author>").skip(1) {
-                    if let Some(name) = extract_xml_content(author_block, "name") {
-                        authors.push(name);
-                    }
-                }
-
-                if title.is_some() {
-                    results.push(json!({
-                        "title": title,
-                        "summary": summary,
-                        "authors": authors,
-                        "url": id,
-                        "published": published
-                    }));
-                }
-            }
-        }
+        let results = parse_entries(&xml);
 
         Ok(json!({
             "query": query,
@@ -139,6 +168,68 @@ impl Tool for ArxivSearchTool {
     }
 }
 
+/// Parse the entries of an arXiv Atom feed (simple parsing).
+fn parse_entries(xml: &str) -> Vec<Value> {
+    let mut results = Vec::new();
+
+    for entry in xml.split("<entry>").skip(1) {
+        if let Some(end) = entry.find("</entry>") {
+            let entry_xml = &entry[..end];
+
+            let title = extract_xml_content(entry_xml, "title")
+                .map(|s| s.replace('\n', " ").trim().to_string());
+            let summary = extract_xml_content(entry_xml, "summary")
+                .map(|s| s.replace('\n', " ").trim().to_string());
+            let id = extract_xml_content(entry_xml, "id");
+            let published = extract_xml_content(entry_xml, "published");
+
+            // Abstract URLs look like http://arxiv.org/abs/2401.01234v2
+            let arxiv_id = id
+                .as_deref()
+                .and_then(|id| id.split("/abs/").nth(1))
+                .map(str::to_string);
+            let pdf_url = extract_pdf_link(entry_xml).or_else(|| {
+                arxiv_id
+                    .as_ref()
+                    .map(|arxiv_id| format!("https://arxiv.org/pdf/{}", arxiv_id))
+            });
+
+            // Extract authors
+            let mut authors = Vec::new();
+            for author_block in entry_xml.split("<author>").skip(1) {
+                if let Some(name) = extract_xml_content(author_block, "name") {
+                    authors.push(name);
+                }
+            }
+
+            if title.is_some() {
+                results.push(json!({
+                    "arxiv_id": arxiv_id,
+                    "title": title,
+                    "authors": authors,
+                    "abstract": summary,
+                    "published": published,
+                    "url": id,
+                    "pdf_url": pdf_url
+                }));
+            }
+        }
+    }
+    results
+}
+
+/// Find the `href` of the entry's `<link title="pdf" .../>` element.
+fn extract_pdf_link(entry_xml: &str) -> Option<String> {
+    entry_xml
+        .split("<link")
+        .skip(1)
+        .filter_map(|link| link.split('>').next())
+        .find(|attrs| attrs.contains("title=\"pdf\""))
+        .and_then(|attrs| attrs.split("href=\"").nth(1))
+        .and_then(|rest| rest.split('"').next())
+        .map(str::to_string)
+}
+
 fn extract_xml_content(xml: &str, tag: &str) -> Option<String> {
     let start_tag = format!("<{}", tag);
     let end_tag = format!("</{}>", tag);
@@ -183,4 +274,55 @@ mod tests {
         assert_eq!(extract_xml_content(xml, "title"), Some("Test Paper Title".to_string()));
         assert_eq!(extract_xml_content(xml, "name"), Some("John Doe".to_string()));
     }
+
+    #[test]
+    fn builds_query_with_category_date_range_and_sort() {
+        let tool = ArxivSearchTool::new().with_max_results(5);
+        let url = tool
+            .search_url(&json!({
+                "query": "diffusion models",
+                "category": "cs.LG",
+                "start_date": "2024-01-01",
+                "end_date": "2024-03-31",
+                "sort_by": "submitted_date"
+            }))
+            .unwrap();
+        let decoded = urlencoding::decode(&url).unwrap();
+        assert!(decoded.contains(
+            "search_query=cat:cs.LG AND submittedDate:[202401010000 TO 202403312359] AND all:diffusion models"
+        ));
+        assert!(decoded.contains("max_results=5"));
+        assert!(decoded.contains("sortBy=submittedDate&sortOrder=descending"));
+
+        assert!(tool
+            .search_url(&json!({"query": "x", "start_date": "January 2024"}))
+            .is_err());
+        assert!(tool
+            .search_url(&json!({"query": "x", "sort_by": "popularity"}))
+            .is_err());
+    }
+
+    #[test]
+    fn parses_structured_entry_fields() {
+        let xml = r#"<feed><entry>
+            <id>http://arxiv.org/abs/2401.01234v2</id>
+            <published>2024-01-03T18:00:00Z</published>
+            <title>Scaling
+ Laws</title>
+            <summary>We study scaling.</summary>
+            <author><name>Ada Lovelace</name></author>
+            <author><name>Alan Turing</name></author>
+            <link href="http://arxiv.org/abs/2401.01234v2" rel="alternate" type="text/html"/>
+            <link title="pdf" href="http://arxiv.org/pdf/2401.01234v2" rel="related" type="application/pdf"/>
+        </entry></feed>"#;
+        let entries = parse_entries(xml);
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry["arxiv_id"], "2401.01234v2");
+        assert_eq!(entry["title"], "Scaling  Laws");
+        assert_eq!(entry["authors"], json!(["Ada Lovelace", "Alan Turing"]));
+        assert_eq!(entry["abstract"], "We study scaling.");
+        assert_eq!(entry["published"], "2024-01-03T18:00:00Z");
+        assert_eq!(entry["pdf_url"], "http://arxiv.org/pdf/2401.01234v2");
+    }
 }
@@ -1,9 +1,15 @@
 //! Calculator toolkit.
 //!
 //! Provides basic math operations: add, subtract, multiply, divide,
-//! exponentiate, factorial, is_prime, and square_root.
+//! exponentiate, factorial, is_prime, and square_root, plus unit-aware
+//! sums through `evaluate_units`. The binary operations accept `"exact": true`
+//! to compute on base-10 decimals instead of floats.
+
+use std::str::FromStr;
 
 use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::{Decimal, MathematicalOps};
 use serde_json::{json, Value};
 
 use crate::error::{AgnoError, Result};
@@ -20,6 +26,7 @@ pub fn calculator_toolkit() -> ToolRegistry {
     registry.register(FactorialTool);
     registry.register(IsPrimeTool);
     registry.register(SquareRootTool);
+    registry.register(EvaluateUnitsTool);
     registry
 }
 
@@ -32,14 +39,14 @@ impl Tool for AddTool {
     }
 
     fn description(&self) -> &str {
-        "Add two numbers. Expects {\"a\": number, \"b\": number}."
+        "Add two numbers. Expects {\"a\": number, \"b\": number}; set \"exact\": true for decimal arithmetic."
     }
 
     async fn call(&self, input: Value) -> Result<Value> {
-        let a = get_number(&input, "a", "add")?;
-        let b = get_number(&input, "b", "add")?;
-        let result = a + b;
-        Ok(json!({ "operation": "addition", "result": result }))
+        Ok(match get_operands(&input, "add")? {
+            Operands::Float(a, b) => float_outcome("addition", a + b),
+            Operands::Exact(a, b) => exact_outcome("addition", a.checked_add(b)),
+        })
     }
 }
 
@@ -52,14 +59,14 @@ impl Tool for SubtractTool {
     }
 
     fn description(&self) -> &str {
-        "Subtract second number from first. Expects {\"a\": number, \"b\": number}."
+        "Subtract second number from first. Expects {\"a\": number, \"b\": number}; set \"exact\": true for decimal arithmetic."
     }
 
     async fn call(&self, input: Value) -> Result<Value> {
-        let a = get_number(&input, "a", "subtract")?;
-        let b = get_number(&input, "b", "subtract")?;
-        let result = a - b;
-        Ok(json!({ "operation": "subtraction", "result": result }))
+        Ok(match get_operands(&input, "subtract")? {
+            Operands::Float(a, b) => float_outcome("subtraction", a - b),
+            Operands::Exact(a, b) => exact_outcome("subtraction", a.checked_sub(b)),
+        })
     }
 }
 
@@ -72,14 +79,14 @@ impl Tool for MultiplyTool {
     }
 
     fn description(&self) -> &str {
-        "Multiply two numbers. Expects {\"a\": number, \"b\": number}."
+        "Multiply two numbers. Expects {\"a\": number, \"b\": number}; set \"exact\": true for decimal arithmetic."
     }
 
     async fn call(&self, input: Value) -> Result<Value> {
-        let a = get_number(&input, "a", "multiply")?;
-        let b = get_number(&input, "b", "multiply")?;
-        let result = a * b;
-        Ok(json!({ "operation": "multiplication", "result": result }))
+        Ok(match get_operands(&input, "multiply")? {
+            Operands::Float(a, b) => float_outcome("multiplication", a * b),
+            Operands::Exact(a, b) => exact_outcome("multiplication", a.checked_mul(b)),
+        })
     }
 }
 
@@ -92,17 +99,16 @@ impl Tool for DivideTool {
     }
 
     fn description(&self) -> &str {
-        "Divide first number by second. Expects {\"a\": number, \"b\": number}."
+        "Divide first number by second. Expects {\"a\": number, \"b\": number}; set \"exact\": true for decimal arithmetic."
     }
 
     async fn call(&self, input: Value) -> Result<Value> {
-        let a = get_number(&input, "a", "divide")?;
-        let b = get_number(&input, "b", "divide")?;
-        if b == 0.0 {
-            return Ok(json!({ "operation": "division", "error": "Division by zero is undefined" }));
-        }
-        let result = a / b;
-        Ok(json!({ "operation": "division", "result": result }))
+        Ok(match get_operands(&input, "divide")? {
+            Operands::Float(_, 0.0) => division_by_zero(),
+            Operands::Exact(_, b) if b.is_zero() => division_by_zero(),
+            Operands::Float(a, b) => float_outcome("division", a / b),
+            Operands::Exact(a, b) => exact_outcome("division", a.checked_div(b)),
+        })
     }
 }
 
@@ -115,14 +121,20 @@ impl Tool for ExponentiateTool {
     }
 
     fn description(&self) -> &str {
-        "Raise first number to the power of second. Expects {\"a\": number, \"b\": number}."
+        "Raise first number to the power of second. Expects {\"a\": number, \"b\": number}; set \"exact\": true for decimal arithmetic."
     }
 
     async fn call(&self, input: Value) -> Result<Value> {
-        let a = get_number(&input, "a", "exponentiate")?;
-        let b = get_number(&input, "b", "exponentiate")?;
-        let result = a.powf(b);
-        Ok(json!({ "operation": "exponentiation", "result": result }))
+        Ok(match get_operands(&input, "exponentiate")? {
+            Operands::Float(a, b) => float_outcome("exponentiation", a.powf(b)),
+            Operands::Exact(a, b) => {
+                let result = match b.to_i64() {
+                    Some(exponent) if b.fract().is_zero() => a.checked_powi(exponent),
+                    _ => a.checked_powd(b),
+                };
+                exact_outcome("exponentiation", result)
+            }
+        })
     }
 }
 
@@ -145,13 +157,21 @@ impl Tool for FactorialTool {
             .ok_or_else(|| AgnoError::Protocol("missing `n` for factorial".into()))?;
 
         if n < 0 {
-            return Ok(
-                json!({ "operation": "factorial", "error": "Factorial of a negative number is undefined" }),
-            );
+            return Ok(error_outcome(
+                "factorial",
+                "undefined",
+                "Factorial of a negative number is undefined",
+            ));
         }
 
-        let result = factorial(n as u64);
-        Ok(json!({ "operation": "factorial", "result": result }))
+        Ok(match factorial(n as u64) {
+            Some(result) => json!({
+                "operation": "factorial",
+                "result": result,
+                "display": result.to_string()
+            }),
+            None => error_outcome("factorial", "overflow", "Result is too large to represent"),
+        })
     }
 }
 
@@ -193,12 +213,83 @@ impl Tool for SquareRootTool {
     async fn call(&self, input: Value) -> Result<Value> {
         let n = get_number(&input, "n", "square_root")?;
         if n < 0.0 {
-            return Ok(
-                json!({ "operation": "square_root", "error": "Square root of a negative number is undefined" }),
-            );
+            return Ok(error_outcome(
+                "square_root",
+                "undefined",
+                "Square root of a negative number is undefined",
+            ));
+        }
+        Ok(float_outcome("square_root", n.sqrt()))
+    }
+}
+
+struct EvaluateUnitsTool;
+
+#[async_trait]
+impl Tool for EvaluateUnitsTool {
+    fn name(&self) -> &str {
+        "evaluate_units"
+    }
+
+    fn description(&self) -> &str {
+        "Add and subtract quantities with units of length, mass or time. Expects {\"expression\": \"3 km + 200 m\"} and an optional target unit {\"to\": \"m\"}; defaults to the first unit in the expression."
+    }
+
+    async fn call(&self, input: Value) -> Result<Value> {
+        let expression = input
+            .get("expression")
+            .and_then(Value::as_str)
+            .ok_or_else(|| AgnoError::Protocol("missing `expression` for evaluate_units".into()))?;
+
+        let terms = match parse_quantities(expression) {
+            Ok(terms) => terms,
+            Err(message) => {
+                return Ok(error_outcome(
+                    "unit_arithmetic",
+                    "invalid_expression",
+                    &message,
+                ))
+            }
+        };
+        let target_name = input
+            .get("to")
+            .and_then(Value::as_str)
+            .unwrap_or(terms[0].1.name);
+        let Some(target) = unit(target_name) else {
+            return Ok(error_outcome(
+                "unit_arithmetic",
+                "unknown_unit",
+                &format!("Unknown unit `{}`", target_name),
+            ));
+        };
+
+        let mut total = Decimal::ZERO;
+        for (value, unit) in &terms {
+            if unit.dimension != target.dimension {
+                return Ok(error_outcome(
+                    "unit_arithmetic",
+                    "incompatible_units",
+                    &format!(
+                        "Cannot combine {} ({}) with {} ({})",
+                        unit.name, unit.dimension, target.name, target.dimension
+                    ),
+                ));
+            }
+            let Some(base) = value.checked_mul(unit.factor) else {
+                return Ok(exact_outcome("unit_arithmetic", None));
+            };
+            match total.checked_add(base) {
+                Some(sum) => total = sum,
+                None => return Ok(exact_outcome("unit_arithmetic", None)),
+            }
         }
-        let result = n.sqrt();
-        Ok(json!({ "operation": "square_root", "result": result }))
+
+        let mut outcome = exact_outcome("unit_arithmetic", total.checked_div(target.factor));
+        if let Some(display) = outcome["display"].as_str() {
+            outcome["display"] = json!(format!("{} {}", display, target.name));
+            outcome["unit"] = json!(target.name);
+        }
+        Ok(outcome)
     }
 }
 
@@ -211,11 +302,166 @@ fn get_number(input: &Value, field: &str, tool_name: &str) -> Result<f64> {
         .ok_or_else(|| AgnoError::Protocol(format!("missing `{}` for {}", field, tool_name)))
 }
 
-fn factorial(n: u64) -> u64 {
+/// Operands of a binary tool: floats by default, decimals when `exact` is set.
+enum Operands {
+    Float(f64, f64),
+    Exact(Decimal, Decimal),
+}
+
+fn get_operands(input: &Value, tool_name: &str) -> Result<Operands> {
+    if input.get("exact").and_then(Value::as_bool).unwrap_or(false) {
+        Ok(Operands::Exact(
+            get_decimal(input, "a", tool_name)?,
+            get_decimal(input, "b", tool_name)?,
+        ))
+    } else {
+        Ok(Operands::Float(
+            get_number(input, "a", tool_name)?,
+            get_number(input, "b", tool_name)?,
+        ))
+    }
+}
+
+/// Read a decimal from a JSON number or numeric string, keeping the digits as written
+/// (`0.1` stays exactly one tenth).
+fn get_decimal(input: &Value, field: &str, tool_name: &str) -> Result<Decimal> {
+    let text = match input.get(field) {
+        Some(Value::Number(n)) => n.to_string(),
+        Some(Value::String(s)) => s.trim().to_string(),
+        _ => {
+            return Err(AgnoError::Protocol(format!(
+                "missing `{}` for {}",
+                field, tool_name
+            )))
+        }
+    };
+    Decimal::from_str(&text)
+        .or_else(|_| Decimal::from_scientific(&text))
+        .map_err(|_| {
+            AgnoError::Protocol(format!(
+                "`{}` for {} is not a decimal number: {}",
+                field, tool_name, text
+            ))
+        })
+}
+
+fn float_outcome(operation: &str, result: f64) -> Value {
+    if result.is_nan() {
+        return error_outcome(operation, "undefined", "Result is not a number");
+    }
+    if result.is_infinite() {
+        return error_outcome(operation, "overflow", "Result is too large to represent");
+    }
+    json!({ "operation": operation, "result": result, "display": result.to_string() })
+}
+
+/// Response for a decimal result; `None` means the checked operation overflowed.
+fn exact_outcome(operation: &str, result: Option<Decimal>) -> Value {
+    match result {
+        Some(result) => {
+            let result = result.normalize();
+            json!({
+                "operation": operation,
+                "result": result.to_f64(),
+                "display": result.to_string(),
+                "exact": true
+            })
+        }
+        None => error_outcome(operation, "overflow", "Result is too large to represent"),
+    }
+}
+
+fn division_by_zero() -> Value {
+    error_outcome(
+        "division",
+        "division_by_zero",
+        "Division by zero is undefined",
+    )
+}
+
+fn error_outcome(operation: &str, code: &str, message: &str) -> Value {
+    json!({ "operation": operation, "error": message, "code": code })
+}
+
+/// A unit and its size in the base unit of its dimension (metre, kilogram, second).
+struct Unit {
+    name: &'static str,
+    dimension: &'static str,
+    factor: Decimal,
+}
+
+fn unit(name: &str) -> Option<Unit> {
+    let (name, dimension, factor) = match name {
+        "mm" => ("mm", "length", "0.001"),
+        "cm" => ("cm", "length", "0.01"),
+        "m" => ("m", "length", "1"),
+        "km" => ("km", "length", "1000"),
+        "in" => ("in", "length", "0.0254"),
+        "ft" => ("ft", "length", "0.3048"),
+        "yd" => ("yd", "length", "0.9144"),
+        "mi" => ("mi", "length", "1609.344"),
+        "mg" => ("mg", "mass", "0.000001"),
+        "g" => ("g", "mass", "0.001"),
+        "kg" => ("kg", "mass", "1"),
+        "t" => ("t", "mass", "1000"),
+        "oz" => ("oz", "mass", "0.028349523125"),
+        "lb" => ("lb", "mass", "0.45359237"),
+        "ms" => ("ms", "time", "0.001"),
+        "s" => ("s", "time", "1"),
+        "min" => ("min", "time", "60"),
+        "h" => ("h", "time", "3600"),
+        "d" => ("d", "time", "86400"),
+        _ => return None,
+    };
+    Some(Unit {
+        name,
+        dimension,
+        factor: Decimal::from_str(factor).ok()?,
+    })
+}
+
+/// Parse `3 km + 200 m - 5 cm` into signed quantities.
+fn parse_quantities(expression: &str) -> std::result::Result<Vec<(Decimal, Unit)>, String> {
+    let mut terms = Vec::new();
+    let mut chars = expression.chars().peekable();
+    let mut sign = Decimal::ONE;
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut number = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_digit() || *c == '.') {
+            number.push(c);
+        }
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_alphabetic()) {
+            name.push(c);
+        }
+        if number.is_empty() || name.is_empty() {
+            return Err(format!(
+                "Expected a quantity such as `3 km` in `{}`",
+                expression
+            ));
+        }
+        let value =
+            Decimal::from_str(&number).map_err(|_| format!("Invalid number `{}`", number))?;
+        let unit = unit(&name).ok_or_else(|| format!("Unknown unit `{}`", name))?;
+        terms.push((sign * value, unit));
+
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        sign = match chars.next() {
+            None => return Ok(terms),
+            Some('+') => Decimal::ONE,
+            Some('-') => Decimal::NEGATIVE_ONE,
+            Some(other) => return Err(format!("Unexpected `{}` in `{}`", other, expression)),
+        };
+    }
+}
+
+fn factorial(n: u64) -> Option<u64> {
     if n <= 1 {
-        1
+        Some(1)
     } else {
-        n * factorial(n - 1)
+        n.checked_mul(factorial(n - 1)?)
     }
 }
 
@@ -270,4 +516,79 @@ mod tests {
         let result = is_prime.call(json!({"n": 4})).await.unwrap();
         assert_eq!(result["result"], false);
     }
+
+    #[tokio::test]
+    async fn adds_decimals_exactly_and_reports_errors() {
+        let registry = calculator_toolkit();
+        let add = registry.get("add").unwrap();
+
+        let float = add.call(json!({"a": 0.1, "b": 0.2})).await.unwrap();
+        assert_eq!(float["display"], "0.30000000000000004");
+
+        let exact = add
+            .call(json!({"a": 0.1, "b": 0.2, "exact": true}))
+            .await
+            .unwrap();
+        assert_eq!(exact["result"], 0.3);
+        assert_eq!(exact["display"], "0.3");
+
+        let cents = registry
+            .get("multiply")
+            .unwrap()
+            .call(json!({"a": "19.99", "b": 3, "exact": true}))
+            .await
+            .unwrap();
+        assert_eq!(cents["display"], "59.97");
+
+        let divide = registry.get("divide").unwrap();
+        let by_zero = divide
+            .call(json!({"a": 1, "b": "0.00", "exact": true}))
+            .await
+            .unwrap();
+        assert_eq!(by_zero["code"], "division_by_zero");
+
+        let overflow = registry
+            .get("exponentiate")
+            .unwrap()
+            .call(json!({"a": 10, "b": 400}))
+            .await
+            .unwrap();
+        assert_eq!(overflow["code"], "overflow");
+        assert!(overflow.get("result").is_none());
+
+        let factorial = registry.get("factorial").unwrap();
+        let too_big = factorial.call(json!({"n": 30})).await.unwrap();
+        assert_eq!(too_big["code"], "overflow");
+    }
+
+    #[tokio::test]
+    async fn sums_quantities_in_compatible_units() {
+        let registry = calculator_toolkit();
+        let units = registry.get("evaluate_units").unwrap();
+
+        let km = units
+            .call(json!({"expression": "3 km + 200 m"}))
+            .await
+            .unwrap();
+        assert_eq!(km["result"], 3.2);
+        assert_eq!(km["display"], "3.2 km");
+
+        let metres = units
+            .call(json!({"expression": "3km + 200m - 50 cm", "to": "m"}))
+            .await
+            .unwrap();
+        assert_eq!(metres["display"], "3199.5 m");
+
+        let feet = units
+            .call(json!({"expression": "1 mi", "to": "ft"}))
+            .await
+            .unwrap();
+        assert_eq!(feet["display"], "5280 ft");
+
+        let mixed = units
+            .call(json!({"expression": "3 km + 2 kg"}))
+            .await
+            .unwrap();
+        assert_eq!(mixed["code"], "incompatible_units");
+    }
 }
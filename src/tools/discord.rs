@@ -2,10 +2,21 @@
 //!
 //! Provides tools for sending messages and listing channels via Discord bot API.
 
+use std::time::Duration;
+
 use crate::tool::Tool;
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
+/// Messages Discord returns per request at most.
+const PAGE_LIMIT: u64 = 100;
+/// Upper bound on `max_total`, so one call cannot page through an entire large server.
+const MAX_TOTAL_LIMIT: u64 = 5_000;
+/// How many times a rate-limited request is retried after waiting.
+const RATE_LIMIT_RETRIES: usize = 3;
+/// Longest wait accepted from a rate-limit response before giving up.
+const MAX_RATE_LIMIT_WAIT: Duration = Duration::from_secs(60);
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Discord Client
 // ─────────────────────────────────────────────────────────────────────────────
@@ -33,15 +44,42 @@ impl DiscordClient {
         Ok(Self::new(token))
     }
 
+    /// Send requests to `base_url` instead of `https://discord.com/api/v10`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// GET `endpoint`, waiting out `429 Too Many Requests` responses for as long as
+    /// Discord asks before retrying.
     async fn get(&self, endpoint: &str) -> crate::Result<Value> {
-        let response = self
-            .http
-            .get(format!("{}{}", self.base_url, endpoint))
-            .header("Authorization", format!("Bot {}", self.bot_token))
-            .header("Content-Type", "application/json")
-            .send()
-            .await
-            .map_err(|e| crate::error::AgnoError::Protocol(format!("Discord request failed: {}", e)))?;
+        let mut attempt = 0;
+        let response = loop {
+            let response = self
+                .http
+                .get(format!("{}{}", self.base_url, endpoint))
+                .header("Authorization", format!("Bot {}", self.bot_token))
+                .header("Content-Type", "application/json")
+                .send()
+                .await
+                .map_err(|e| {
+                    crate::error::AgnoError::Protocol(format!("Discord request failed: {}", e))
+                })?;
+            if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS
+                || attempt == RATE_LIMIT_RETRIES
+            {
+                break response;
+            }
+            let delay = rate_limit_delay(response.headers()).unwrap_or(Duration::from_secs(1));
+            if delay > MAX_RATE_LIMIT_WAIT {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "Discord rate limited the request for {:.0}s",
+                    delay.as_secs_f64()
+                )));
+            }
+            attempt += 1;
+            tokio::time::sleep(delay).await;
+        };
 
         if !response.status().is_success() {
             let status = response.status();
@@ -85,6 +123,24 @@ impl DiscordClient {
     }
 }
 
+/// How long Discord asked callers to wait, from `X-RateLimit-Reset-After` or `Retry-After`
+/// (both in seconds, possibly fractional).
+fn rate_limit_delay(headers: &reqwest::header::HeaderMap) -> Option<Duration> {
+    ["x-ratelimit-reset-after", "retry-after"]
+        .iter()
+        .find_map(|name| headers.get(*name)?.to_str().ok()?.parse::<f64>().ok())
+        .filter(|seconds| seconds.is_finite() && *seconds >= 0.0)
+        .map(Duration::from_secs_f64)
+}
+
+/// Numeric value of a Discord snowflake id, which orders messages by creation time.
+fn snowflake(message: &Value) -> u64 {
+    message["id"]
+        .as_str()
+        .and_then(|id| id.parse().ok())
+        .unwrap_or_default()
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Send Message Tool
 // ─────────────────────────────────────────────────────────────────────────────
@@ -262,7 +318,9 @@ impl Tool for DiscordGetMessagesTool {
     }
 
     fn description(&self) -> &str {
-        "Get recent messages from a Discord channel."
+        "Get messages from a Discord channel in chronological order. Pages back from `before` \
+         (or forward from `after`) until `max_total` messages are collected; `has_more` tells \
+         whether older (or newer) messages remain."
     }
 
     fn parameters(&self) -> Option<Value> {
@@ -275,7 +333,19 @@ impl Tool for DiscordGetMessagesTool {
                 },
                 "limit": {
                     "type": "integer",
-                    "description": "Number of messages to retrieve (max 100, default 50)"
+                    "description": "Number of messages to retrieve when max_total is not set (max 100, default 50)"
+                },
+                "before": {
+                    "type": "string",
+                    "description": "Only messages older than this message ID"
+                },
+                "after": {
+                    "type": "string",
+                    "description": "Only messages newer than this message ID"
+                },
+                "max_total": {
+                    "type": "integer",
+                    "description": "Total messages to collect across several requests (max 5000)"
                 }
             },
             "required": ["channel_id"]
@@ -286,33 +356,72 @@ impl Tool for DiscordGetMessagesTool {
         let channel_id = input["channel_id"]
             .as_str()
             .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'channel_id' parameter".into()))?;
-        let limit = input["limit"].as_i64().unwrap_or(50).min(100);
-
-        let response = self.client.get(&format!("/channels/{}/messages?limit={}", channel_id, limit)).await?;
-
-        let messages = response
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .map(|msg| {
-                        json!({
-                            "id": msg["id"],
-                            "content": msg["content"],
-                            "author": {
-                                "id": msg["author"]["id"],
-                                "username": msg["author"]["username"]
-                            },
-                            "timestamp": msg["timestamp"]
-                        })
-                    })
-                    .collect::<Vec<_>>()
+        let before = input["before"].as_str().map(str::to_string);
+        let after = input["after"].as_str().map(str::to_string);
+        if before.is_some() && after.is_some() {
+            return Err(crate::error::AgnoError::Protocol(
+                "pass either 'before' or 'after', not both".into(),
+            ));
+        }
+        let wanted = match input["max_total"].as_u64() {
+            Some(max_total) => max_total.clamp(1, MAX_TOTAL_LIMIT),
+            None => input["limit"].as_u64().unwrap_or(50).clamp(1, PAGE_LIMIT),
+        };
+        // Page forward from `after`; otherwise page back from `before` or the newest message.
+        let forward = after.is_some();
+        let mut cursor = after.or(before);
+
+        let mut collected: Vec<Value> = Vec::new();
+        let has_more = loop {
+            let page_size = (wanted - collected.len() as u64).min(PAGE_LIMIT);
+            let mut endpoint = format!("/channels/{}/messages?limit={}", channel_id, page_size);
+            if let Some(cursor) = &cursor {
+                let direction = if forward { "after" } else { "before" };
+                endpoint.push_str(&format!("&{direction}={cursor}"));
+            }
+            let page = self.client.get(&endpoint).await?;
+            let page = page.as_array().cloned().unwrap_or_default();
+            let full = page.len() as u64 == page_size;
+            // A full page may have more behind it; a short one means the channel ran out.
+            let next = if forward {
+                page.iter().map(snowflake).max()
+            } else {
+                page.iter().map(snowflake).min()
+            };
+            collected.extend(page);
+            if !full {
+                break false;
+            }
+            if collected.len() as u64 >= wanted {
+                break true;
+            }
+            match next {
+                Some(next) => cursor = Some(next.to_string()),
+                None => break false,
+            }
+        };
+        collected.sort_by_key(snowflake);
+
+        let messages = collected
+            .iter()
+            .map(|msg| {
+                json!({
+                    "id": msg["id"],
+                    "content": msg["content"],
+                    "author": {
+                        "id": msg["author"]["id"],
+                        "username": msg["author"]["username"]
+                    },
+                    "timestamp": msg["timestamp"]
+                })
             })
-            .unwrap_or_default();
+            .collect::<Vec<_>>();
 
         Ok(json!({
             "channel_id": channel_id,
             "messages": messages,
-            "count": messages.len()
+            "count": messages.len(),
+            "has_more": has_more
         }))
     }
 }
@@ -353,4 +462,83 @@ mod tests {
         let get = DiscordGetMessagesTool::new(client);
         assert_eq!(get.name(), "discord_get_messages");
     }
+
+    #[tokio::test]
+    async fn pages_back_through_history_until_max_total() {
+        use wiremock::matchers::{path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        // Messages 1..=300, served newest first like Discord does.
+        fn page(newest: u64, count: u64) -> ResponseTemplate {
+            let messages: Vec<Value> = (0..count)
+                .map(|offset| {
+                    let id = newest - offset;
+                    json!({
+                        "id": id.to_string(),
+                        "content": format!("message {id}"),
+                        "author": {"id": "1", "username": "ada"},
+                        "timestamp": "2024-01-01T00:00:00Z"
+                    })
+                })
+                .collect();
+            ResponseTemplate::new(200).set_body_json(messages)
+        }
+
+        let server = MockServer::start().await;
+        // The second page is rate limited once before it is served.
+        Mock::given(query_param("before", "201"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("X-RateLimit-Reset-After", "0.05")
+                    .set_body_json(json!({
+                        "message": "You are being rate limited.",
+                        "retry_after": 0.05
+                    })),
+            )
+            .up_to_n_times(1)
+            .mount(&server)
+            .await;
+        Mock::given(query_param("before", "201"))
+            .respond_with(page(200, 100))
+            .mount(&server)
+            .await;
+        Mock::given(query_param("before", "101"))
+            .respond_with(page(100, 50))
+            .mount(&server)
+            .await;
+        Mock::given(path("/channels/42/messages"))
+            .respond_with(page(300, 100))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        let client = DiscordClient::new("test").with_base_url(server.uri());
+        let tool = DiscordGetMessagesTool::new(client);
+        let result = tool
+            .call(json!({"channel_id": "42", "max_total": 250}))
+            .await
+            .unwrap();
+
+        let requests: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| format!("{}?{}", request.url.path(), request.url.query().unwrap()))
+            .collect();
+        assert_eq!(
+            requests,
+            [
+                "/channels/42/messages?limit=100",
+                "/channels/42/messages?limit=100&before=201",
+                "/channels/42/messages?limit=100&before=201",
+                "/channels/42/messages?limit=50&before=101",
+            ]
+        );
+        assert_eq!(result["count"], 250);
+        assert_eq!(result["has_more"], true);
+        let messages = result["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["id"], "51");
+        assert_eq!(messages[249]["id"], "300");
+    }
 }
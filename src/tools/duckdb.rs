@@ -1,13 +1,21 @@
 //! DuckDB toolkit for executing queries.
 //!
-//! Provides tools for querying DuckDB databases with safety restrictions.
+//! Provides tools for querying DuckDB databases with safety restrictions. CSV, Parquet
+//! and JSON files inside a sandbox directory can be attached as views before a query.
 
 use crate::tool::Tool;
+use crate::tools::fs::FsSandbox;
+use crate::tools::query_guard;
 use async_trait::async_trait;
+use base64::Engine;
 use serde_json::{json, Value};
 use std::sync::{Arc, Mutex};
+use duckdb::types::Value as DuckValue;
 use duckdb::Connection;
 
+/// Default cap on the number of rows returned by a query.
+const DEFAULT_MAX_ROWS: usize = 1000;
+
 // ─────────────────────────────────────────────────────────────────────────────
 // DuckDB Query Tool
 // ─────────────────────────────────────────────────────────────────────────────
@@ -16,6 +24,8 @@ use duckdb::Connection;
 pub struct DuckDbQueryTool {
     conn: Arc<Mutex<Connection>>,
     read_only: bool,
+    files: Option<Arc<FsSandbox>>,
+    max_rows: usize,
 }
 
 impl DuckDbQueryTool {
@@ -25,6 +35,8 @@ impl DuckDbQueryTool {
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             read_only: false,
+            files: None,
+            max_rows: DEFAULT_MAX_ROWS,
         })
     }
 
@@ -34,6 +46,8 @@ impl DuckDbQueryTool {
         Ok(Self {
             conn: Arc::new(Mutex::new(conn)),
             read_only: false,
+            files: None,
+            max_rows: DEFAULT_MAX_ROWS,
         })
     }
 
@@ -42,18 +56,193 @@ impl DuckDbQueryTool {
         self
     }
 
-    fn is_safe_query(&self, query: &str) -> bool {
-        let query_upper = query.to_uppercase();
-        
-        if self.read_only {
-            let dangerous = ["INSERT", "UPDATE", "DELETE", "DROP", "CREATE", "ALTER", "TRUNCATE", "COPY"];
-            for keyword in dangerous {
-                if query_upper.contains(keyword) {
-                    return false;
-                }
+    /// Allow the `attach` parameter to expose files inside `sandbox` as views. Queries
+    /// lose access to every other file and URL, so `read_csv('/etc/passwd')` fails even
+    /// when it passes the read-only check.
+    pub fn with_file_sandbox(mut self, sandbox: Arc<FsSandbox>) -> crate::Result<Self> {
+        // Once external access is off the allow list can no longer change, so set it first.
+        let lockdown = format!(
+            "SET allowed_directories = ['{}']; SET enable_external_access = false;",
+            sandbox.root().display().to_string().replace('\'', "''")
+        );
+        self.conn
+            .lock()
+            .map_err(|_| crate::error::AgnoError::Storage("Lock poisoned".into()))?
+            .execute_batch(&lockdown)
+            .map_err(|e| {
+                crate::error::AgnoError::Storage(format!("Failed to sandbox DuckDB: {}", e))
+            })?;
+        self.files = Some(sandbox);
+        Ok(self)
+    }
+
+    /// Return at most `max_rows` rows per query, flagging the result as truncated.
+    pub fn with_max_rows(mut self, max_rows: usize) -> Self {
+        self.max_rows = max_rows;
+        self
+    }
+
+    /// `CREATE VIEW` statement exposing the sandboxed file at `path` as `name`.
+    fn attach_statement(&self, name: &str, path: &str) -> crate::Result<String> {
+        let sandbox = self.files.as_ref().ok_or_else(|| {
+            crate::error::AgnoError::Protocol("attaching files requires a file sandbox".into())
+        })?;
+        let valid_name = name
+            .chars()
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if !valid_name {
+            return Err(crate::error::AgnoError::Protocol(format!(
+                "invalid view name '{}'",
+                name
+            )));
+        }
+        let resolved = sandbox.resolve(path)?;
+        let extension = resolved
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+        let reader = match extension.as_str() {
+            "parquet" => "read_parquet",
+            "csv" | "tsv" => "read_csv_auto",
+            "json" | "jsonl" | "ndjson" => "read_json_auto",
+            _ => {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "unsupported file type for '{}'",
+                    path
+                )))
             }
+        };
+        // Temporary views leave the database file untouched, so read-only tools may attach.
+        Ok(format!(
+            "CREATE OR REPLACE TEMP VIEW {} AS SELECT * FROM {}('{}')",
+            name,
+            reader,
+            resolved.display().to_string().replace('\'', "''")
+        ))
+    }
+
+    fn is_safe_query(&self, query: &str) -> bool {
+        !self.read_only || query_guard::is_read_only(query)
+    }
+}
+
+/// Convert JSON values to DuckDB values for `?` placeholders. Arrays and objects are
+/// bound as JSON text.
+fn to_duckdb_params(params: &[Value]) -> Vec<duckdb::types::Value> {
+    use duckdb::types::Value as DuckValue;
+
+    params
+        .iter()
+        .map(|param| match param {
+            Value::Null => DuckValue::Null,
+            Value::Bool(b) => DuckValue::Boolean(*b),
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => DuckValue::BigInt(i),
+                None => DuckValue::Double(n.as_f64().unwrap_or_default()),
+            },
+            Value::String(s) => DuckValue::Text(s.clone()),
+            other => DuckValue::Text(other.to_string()),
+        })
+        .collect()
+}
+
+/// Name of a result column's DuckDB type, e.g. `BIGINT` or `VARCHAR`.
+fn column_type_name(logical_type: duckdb::core::LogicalTypeHandle) -> String {
+    match logical_type.try_id() {
+        Ok(id) => format!("{:?}", id).to_uppercase(),
+        Err(raw) => format!("TYPE_{}", raw),
+    }
+}
+
+/// Convert a DuckDB value to JSON. Dates and times become ISO 8601 strings, blobs become
+/// base64, and decimals and huge integers fall back to strings when they do not fit a number.
+fn to_json(value: DuckValue) -> Value {
+    match value {
+        DuckValue::Null => Value::Null,
+        DuckValue::Boolean(b) => json!(b),
+        DuckValue::TinyInt(i) => json!(i),
+        DuckValue::SmallInt(i) => json!(i),
+        DuckValue::Int(i) => json!(i),
+        DuckValue::BigInt(i) => json!(i),
+        DuckValue::HugeInt(i) => {
+            i64::try_from(i).map_or_else(|_| json!(i.to_string()), |i| json!(i))
+        }
+        DuckValue::UTinyInt(i) => json!(i),
+        DuckValue::USmallInt(i) => json!(i),
+        DuckValue::UInt(i) => json!(i),
+        DuckValue::UBigInt(i) => json!(i),
+        DuckValue::Float(f) => json!(f),
+        DuckValue::Double(f) => json!(f),
+        DuckValue::Decimal(d) => {
+            let text = d.to_string();
+            text.parse::<f64>()
+                .map_or_else(|_| json!(text), |f| json!(f))
+        }
+        DuckValue::Timestamp(unit, value) => {
+            let micros = unit.to_micros(value);
+            let (days, micros) = (
+                micros.div_euclid(86_400_000_000),
+                micros.rem_euclid(86_400_000_000),
+            );
+            json!(format!("{}T{}", civil_date(days), time_of_day(micros)))
         }
-        true
+        DuckValue::Date32(days) => json!(civil_date(days as i64)),
+        DuckValue::Time64(unit, value) => json!(time_of_day(unit.to_micros(value))),
+        DuckValue::Interval {
+            months,
+            days,
+            nanos,
+        } => {
+            json!({"months": months, "days": days, "nanos": nanos})
+        }
+        DuckValue::Text(s) | DuckValue::Enum(s) => json!(s),
+        DuckValue::Blob(bytes) => json!(base64::engine::general_purpose::STANDARD.encode(bytes)),
+        DuckValue::List(items) | DuckValue::Array(items) => {
+            Value::Array(items.into_iter().map(to_json).collect())
+        }
+        DuckValue::Struct(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), to_json(value.clone())))
+                .collect(),
+        ),
+        DuckValue::Map(entries) => Value::Array(
+            entries
+                .iter()
+                .map(|(key, value)| json!([to_json(key.clone()), to_json(value.clone())]))
+                .collect(),
+        ),
+        DuckValue::Union(value) => to_json(*value),
+    }
+}
+
+/// `YYYY-MM-DD` for a count of days since 1970-01-01.
+fn civil_date(days: i64) -> String {
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// `HH:MM:SS[.ffffff]` for microseconds since midnight.
+fn time_of_day(micros: i64) -> String {
+    let secs = micros / 1_000_000;
+    let fraction = micros % 1_000_000;
+    let clock = format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60);
+    if fraction == 0 {
+        clock
+    } else {
+        format!("{}.{:06}", clock, fraction)
     }
 }
 
@@ -73,7 +262,23 @@ impl Tool for DuckDbQueryTool {
             "properties": {
                 "query": {
                     "type": "string",
-                    "description": "SQL query to execute"
+                    "description": "SQL query to execute, using ? placeholders for values"
+                },
+                "params": {
+                    "type": "array",
+                    "description": "Values bound to the query placeholders, in order"
+                },
+                "attach": {
+                    "type": "array",
+                    "description": "CSV, Parquet or JSON files to expose as views before the query runs",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "name": {"type": "string", "description": "View name"},
+                            "path": {"type": "string", "description": "File path relative to the sandbox root"}
+                        },
+                        "required": ["name", "path"]
+                    }
                 }
             },
             "required": ["query"]
@@ -85,54 +290,86 @@ impl Tool for DuckDbQueryTool {
             .as_str()
             .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
 
+        let params = match &input["params"] {
+            Value::Null => Vec::new(),
+            Value::Array(params) => to_duckdb_params(params),
+            _ => {
+                return Err(crate::error::AgnoError::Protocol(
+                    "'params' must be an array".into(),
+                ))
+            }
+        };
+
+        let attach = match &input["attach"] {
+            Value::Null => Vec::new(),
+            Value::Array(files) => files
+                .iter()
+                .map(|file| match (file["name"].as_str(), file["path"].as_str()) {
+                    (Some(name), Some(path)) => self.attach_statement(name, path),
+                    _ => Err(crate::error::AgnoError::Protocol(
+                        "each 'attach' entry needs a 'name' and a 'path'".into(),
+                    )),
+                })
+                .collect::<crate::Result<Vec<_>>>()?,
+            _ => {
+                return Err(crate::error::AgnoError::Protocol(
+                    "'attach' must be an array".into(),
+                ))
+            }
+        };
+
         if !self.is_safe_query(query) {
-            return Ok(json!({
-                "error": true,
-                "message": "Query contains blocked operations in read-only mode."
-            }));
+            return Ok(query_guard::read_only_violation(query));
         }
 
         let conn = self.conn.lock().map_err(|_| crate::error::AgnoError::Storage("Lock poisoned".into()))?;
+        for statement in &attach {
+            conn.execute_batch(statement)
+                .map_err(|e| crate::error::AgnoError::Storage(format!("Attach failed: {}", e)))?;
+        }
         let mut stmt = conn.prepare(query)
             .map_err(|e| crate::error::AgnoError::Storage(format!("Prepare failed: {}", e)))?;
 
-        // DuckDB generic query execution is tricky without specific types.
-        // We'll use query_arrow if possible or just execute and try to map basic types.
-        // For simplicity in this toolkit, we'll assume JSON output via DuckDB's magic or basic implementation.
-        // DuckDB doesn't have a simple "fetch all as JSON" in the rust binding easily exposed without iterating rows.
-
-        // Simpler approach: Map columns manually.
-        let column_count = stmt.column_count();
-        let column_names: Vec<String> = (0..column_count).map(|i| stmt.column_name(i).map(|s| s.to_string()).unwrap_or_else(|_| "unknown".to_string())).collect();
-
-        let mut rows = stmt.query([])
+        // Column metadata is only available once the statement has run.
+        let mut rows = stmt.query(duckdb::params_from_iter(params))
             .map_err(|e| crate::error::AgnoError::Storage(format!("Query failed: {}", e)))?;
+        let columns: Vec<(String, String)> = rows
+            .as_ref()
+            .map(|stmt| {
+                stmt.column_names()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, name)| (name, column_type_name(stmt.column_logical_type(i))))
+                    .collect()
+            })
+            .unwrap_or_default();
 
         let mut results = Vec::new();
+        let mut truncated = false;
         while let Some(row) = rows.next().map_err(|e| crate::error::AgnoError::Storage(format!("Row error: {}", e)))? {
+            if results.len() == self.max_rows {
+                truncated = true;
+                break;
+            }
             let mut row_map = serde_json::Map::new();
-            for (i, name) in column_names.iter().enumerate() {
-                // Try basic types
-                let val: Value = if let Ok(v) = row.get::<_, i64>(i) {
-                    json!(v)
-                } else if let Ok(v) = row.get::<_, f64>(i) {
-                    json!(v)
-                } else if let Ok(v) = row.get::<_, String>(i) {
-                    json!(v)
-                } else if let Ok(v) = row.get::<_, bool>(i) {
-                    json!(v)
-                } else {
-                    json!(null)
-                };
-                row_map.insert(name.clone(), val);
+            for (i, (name, _)) in columns.iter().enumerate() {
+                let value = row
+                    .get::<_, DuckValue>(i)
+                    .map_err(|e| crate::error::AgnoError::Storage(format!("Row error: {}", e)))?;
+                row_map.insert(name.clone(), to_json(value));
             }
             results.push(Value::Object(row_map));
         }
 
         Ok(json!({
             "query": query,
+            "columns": columns
+                .iter()
+                .map(|(name, data_type)| json!({"name": name, "type": data_type}))
+                .collect::<Vec<_>>(),
             "rows": results,
-            "row_count": results.len()
+            "row_count": results.len(),
+            "truncated": truncated
         }))
     }
 }
@@ -164,4 +401,129 @@ mod tests {
         assert_eq!(tool.name(), "duckdb_query");
         assert!(tool.parameters().is_some());
     }
+
+    #[tokio::test]
+    async fn binds_params_and_rejects_writes_when_read_only() {
+        let tool = DuckDbQueryTool::new_in_memory().unwrap();
+        tool.call(json!({"query": "CREATE TABLE items (id INTEGER, name TEXT)"}))
+            .await
+            .unwrap();
+        tool.call(json!({
+            "query": "INSERT INTO items VALUES (?, ?), (?, ?)",
+            "params": [1, "apple", 2, "pear"]
+        }))
+        .await
+        .unwrap();
+
+        let tool = tool.with_read_only();
+        let result = tool
+            .call(json!({"query": "SELECT id FROM items WHERE name = ?", "params": ["pear"]}))
+            .await
+            .unwrap();
+        assert_eq!(result["row_count"], 1);
+        assert_eq!(result["rows"][0]["id"], 2);
+
+        let rejected = tool
+            .call(json!({"query": "UPDATE items SET name = 'x'"}))
+            .await
+            .unwrap();
+        assert_eq!(rejected["error"], true);
+    }
+
+    #[tokio::test]
+    async fn attaches_sandboxed_files_and_returns_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("sales.csv"),
+            "region,units,price,sold_on\nnorth,3,2.5,2024-03-01\nsouth,5,4.0,2024-03-02\neast,1,9.75,2024-03-03\n",
+        )
+        .unwrap();
+        let sandbox = Arc::new(FsSandbox::new(dir.path()).unwrap());
+        let tool = DuckDbQueryTool::new_in_memory()
+            .unwrap()
+            .with_read_only()
+            .with_file_sandbox(sandbox)
+            .unwrap()
+            .with_max_rows(2);
+
+        let result = tool
+            .call(json!({
+                "query": "SELECT region, units, price, sold_on FROM sales ORDER BY units DESC",
+                "attach": [{"name": "sales", "path": "sales.csv"}]
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            result["columns"],
+            json!([
+                {"name": "region", "type": "VARCHAR"},
+                {"name": "units", "type": "BIGINT"},
+                {"name": "price", "type": "DOUBLE"},
+                {"name": "sold_on", "type": "DATE"}
+            ])
+        );
+        assert_eq!(
+            result["rows"][0],
+            json!({"region": "south", "units": 5, "price": 4.0, "sold_on": "2024-03-02"})
+        );
+        assert_eq!(result["row_count"], 2);
+        assert_eq!(result["truncated"], true);
+
+        let parquet = dir.path().join("totals.parquet");
+        Connection::open_in_memory()
+            .unwrap()
+            .execute_batch(&format!(
+                "COPY (SELECT 'north' AS region, 42 AS total) TO '{}' (FORMAT PARQUET)",
+                parquet.display()
+            ))
+            .unwrap();
+        let result = tool
+            .call(json!({
+                "query": "SELECT total FROM totals",
+                "attach": [{"name": "totals", "path": "totals.parquet"}]
+            }))
+            .await
+            .unwrap();
+        assert_eq!(result["columns"][0]["type"], "INTEGER");
+        assert_eq!(result["rows"][0]["total"], 42);
+        assert_eq!(result["truncated"], false);
+
+        let escaped = tool
+            .call(json!({
+                "query": "SELECT 1",
+                "attach": [{"name": "secrets", "path": "../secrets.csv"}]
+            }))
+            .await;
+        assert!(escaped.is_err());
+    }
+
+    #[tokio::test]
+    async fn sandboxed_queries_cannot_read_outside_the_sandbox() {
+        let outside = tempfile::tempdir().unwrap();
+        let secrets = outside.path().join("secrets.csv");
+        std::fs::write(&secrets, "token\nhunter2\n").unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("sales.csv"), "region,units\nnorth,3\n").unwrap();
+        let tool = DuckDbQueryTool::new_in_memory()
+            .unwrap()
+            .with_read_only()
+            .with_file_sandbox(Arc::new(FsSandbox::new(dir.path()).unwrap()))
+            .unwrap();
+
+        let leaked = tool
+            .call(json!({
+                "query": format!("SELECT * FROM read_csv_auto('{}')", secrets.display())
+            }))
+            .await;
+        assert!(leaked.is_err(), "{leaked:?}");
+
+        let inside = tool
+            .call(json!({
+                "query": "SELECT units FROM sales",
+                "attach": [{"name": "sales", "path": "sales.csv"}]
+            }))
+            .await
+            .unwrap();
+        assert_eq!(inside["rows"][0]["units"], 3);
+    }
 }
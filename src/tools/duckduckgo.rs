@@ -2,12 +2,17 @@
 //!
 //! Provides web search and news search via DuckDuckGo's HTML interface.
 
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 
 use crate::error::{AgnoError, Result};
 use crate::tool::{Tool, ToolRegistry};
+use crate::tools::query_cache::{normalize_query, QueryCache, DEFAULT_QUERY_CACHE_TTL};
 
 /// DuckDuckGo search result
 #[derive(Debug, Serialize, Deserialize)]
@@ -17,11 +22,37 @@ pub struct SearchResult {
     pub body: String,
 }
 
+/// DuckDuckGo safe-search level, sent as the `kp` parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SafeSearch {
+    Strict,
+    #[default]
+    Moderate,
+    Off,
+}
+
+impl SafeSearch {
+    fn param(self) -> &'static str {
+        match self {
+            SafeSearch::Strict => "1",
+            SafeSearch::Moderate => "-1",
+            SafeSearch::Off => "-2",
+        }
+    }
+}
+
 /// Configuration for DuckDuckGo tools
 #[derive(Clone)]
 pub struct DuckDuckGoConfig {
+    /// Results to collect, fetching further pages until reached.
     pub max_results: usize,
     pub timeout_secs: u64,
+    /// Region code such as `us-en` or `de-de`, sent as the `kl` parameter.
+    pub region: Option<String>,
+    pub safe_search: SafeSearch,
+    pub base_url: String,
+    /// How long results for a query are reused before searching again; zero disables it.
+    pub cache_ttl: Duration,
 }
 
 impl Default for DuckDuckGoConfig {
@@ -29,22 +60,32 @@ impl Default for DuckDuckGoConfig {
         Self {
             max_results: 5,
             timeout_secs: 10,
+            region: None,
+            safe_search: SafeSearch::default(),
+            base_url: "https://html.duckduckgo.com/html/".into(),
+            cache_ttl: DEFAULT_QUERY_CACHE_TTL,
         }
     }
 }
 
+/// Upper bound on pages fetched for a single search.
+const MAX_PAGES: usize = 10;
+
 /// Create a DuckDuckGo toolkit with search and news tools
 pub fn duckduckgo_toolkit(config: DuckDuckGoConfig) -> ToolRegistry {
     let mut registry = ToolRegistry::new();
+    let cache = Arc::new(QueryCache::new(config.cache_ttl));
     registry.register(DuckDuckGoSearchTool {
         config: config.clone(),
+        cache: cache.clone(),
     });
-    registry.register(DuckDuckGoNewsTool { config });
+    registry.register(DuckDuckGoNewsTool { config, cache });
     registry
 }
 
 struct DuckDuckGoSearchTool {
     config: DuckDuckGoConfig,
+    cache: Arc<QueryCache>,
 }
 
 #[async_trait]
@@ -57,6 +98,10 @@ impl Tool for DuckDuckGoSearchTool {
         "Search the web using DuckDuckGo. Expects {\"query\": string, \"max_results\": number (optional)}."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     async fn call(&self, input: Value) -> Result<Value> {
         let query = input
             .get("query")
@@ -69,13 +114,15 @@ impl Tool for DuckDuckGoSearchTool {
             .map(|n| n as usize)
             .unwrap_or(self.config.max_results);
 
-        let results = search_duckduckgo(query, max_results, self.config.timeout_secs).await?;
+        let results =
+            cached_search(&self.cache, "search", query, max_results, &self.config).await?;
         Ok(json!({ "query": query, "results": results }))
     }
 }
 
 struct DuckDuckGoNewsTool {
     config: DuckDuckGoConfig,
+    cache: Arc<QueryCache>,
 }
 
 #[async_trait]
@@ -101,56 +148,115 @@ impl Tool for DuckDuckGoNewsTool {
             .unwrap_or(self.config.max_results);
 
         // For news, we append "news" to the query
-        let results =
-            search_duckduckgo(&format!("{} news", query), max_results, self.config.timeout_secs)
-                .await?;
+        let results = cached_search(
+            &self.cache,
+            "news",
+            &format!("{} news", query),
+            max_results,
+            &self.config,
+        )
+        .await?;
         Ok(json!({ "query": query, "results": results }))
     }
 }
 
-/// Perform a DuckDuckGo search using the HTML interface
+/// Search through `cache`, keyed by tool `kind`, normalized query and result count.
+async fn cached_search(
+    cache: &QueryCache,
+    kind: &str,
+    query: &str,
+    max_results: usize,
+    config: &DuckDuckGoConfig,
+) -> Result<Value> {
+    let key = format!("{}:{}:{}", kind, max_results, normalize_query(query));
+    if let Some(results) = cache.get(&key) {
+        return Ok(results);
+    }
+    let results = serde_json::to_value(search_duckduckgo(query, max_results, config).await?)?;
+    cache.insert(key, results.clone());
+    Ok(results)
+}
+
+/// Perform a DuckDuckGo search using the HTML interface, paging through results
+/// until `max_results` unique URLs are collected or a page comes back empty.
 async fn search_duckduckgo(
     query: &str,
     max_results: usize,
-    timeout_secs: u64,
+    config: &DuckDuckGoConfig,
 ) -> Result<Vec<SearchResult>> {
-    use std::time::Duration;
-
     let client = reqwest::Client::builder()
-        .timeout(Duration::from_secs(timeout_secs))
+        .timeout(Duration::from_secs(config.timeout_secs))
         .user_agent("Mozilla/5.0 (compatible; AgnoBot/1.0)")
         .build()
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "duckduckgo_search".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "duckduckgo_search".into(),
             source: Box::new(e),
         })?;
 
-    // Use DuckDuckGo HTML endpoint
-    let url = format!(
-        "https://html.duckduckgo.com/html/?q={}",
-        urlencoding::encode(query)
-    );
+    let mut results = Vec::new();
+    let mut seen = HashSet::new();
+    let mut offset = 0;
+
+    for _ in 0..MAX_PAGES {
+        if results.len() >= max_results {
+            break;
+        }
+
+        let html = fetch_page(&client, query, offset, config).await?;
+        let page = parse_duckduckgo_html(&html, usize::MAX);
+        if page.is_empty() {
+            break;
+        }
+        offset += page.len();
+
+        for result in page {
+            if results.len() >= max_results {
+                break;
+            }
+            if seen.insert(result.href.clone()) {
+                results.push(result);
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Fetch one page of results starting at `offset`.
+async fn fetch_page(
+    client: &reqwest::Client,
+    query: &str,
+    offset: usize,
+    config: &DuckDuckGoConfig,
+) -> Result<String> {
+    let mut params = vec![
+        ("q", query.to_string()),
+        ("kp", config.safe_search.param().to_string()),
+    ];
+    if let Some(region) = &config.region {
+        params.push(("kl", region.clone()));
+    }
+    if offset > 0 {
+        params.push(("s", offset.to_string()));
+    }
 
     let response = client
-        .get(&url)
+        .get(&config.base_url)
+        .query(&params)
         .send()
         .await
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "duckduckgo_search".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "duckduckgo_search".into(),
             source: Box::new(e),
         })?;
 
-    let html = response
+    response
         .text()
         .await
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "duckduckgo_search".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "duckduckgo_search".into(),
             source: Box::new(e),
-        })?;
-
-    // Parse results from HTML (simple extraction)
-    let results = parse_duckduckgo_html(&html, max_results);
-    Ok(results)
+        })
 }
 
 /// Parse DuckDuckGo HTML response to extract search results
@@ -214,4 +320,130 @@ mod tests {
         assert!(registry.get("duckduckgo_search").is_some());
         assert!(registry.get("duckduckgo_news").is_some());
     }
+
+    fn results_page(urls: &[&str]) -> String {
+        urls.iter()
+            .map(|url| format!(r#"<a class="result__a" href="{url}">Title {url}</a>"#))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn reuses_results_for_repeated_query_within_ttl() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_raw(
+                results_page(&["https://a.example", "https://b.example"]),
+                "text/html",
+            ))
+            .mount(&server)
+            .await;
+        let requests = || async { server.received_requests().await.unwrap().len() };
+
+        let config = DuckDuckGoConfig {
+            max_results: 2,
+            base_url: format!("{}/html/", server.uri()),
+            ..DuckDuckGoConfig::default()
+        };
+        let registry = duckduckgo_toolkit(config.clone());
+        let first = registry
+            .call("duckduckgo_search", json!({"query": "Rust language"}))
+            .await
+            .unwrap();
+        let second = registry
+            .call("duckduckgo_search", json!({"query": "  rust LANGUAGE? "}))
+            .await
+            .unwrap();
+        assert_eq!(requests().await, 1);
+        assert_eq!(first["results"], second["results"]);
+        assert_eq!(second["query"], "  rust LANGUAGE? ");
+
+        registry
+            .call("duckduckgo_news", json!({"query": "rust language"}))
+            .await
+            .unwrap();
+        assert_eq!(requests().await, 2);
+
+        let uncached = duckduckgo_toolkit(DuckDuckGoConfig {
+            cache_ttl: Duration::ZERO,
+            ..config
+        });
+        for _ in 0..2 {
+            uncached
+                .call("duckduckgo_search", json!({"query": "rust language"}))
+                .await
+                .unwrap();
+        }
+        assert_eq!(requests().await, 4);
+    }
+
+    #[tokio::test]
+    async fn paginates_until_max_results_and_dedupes() {
+        use wiremock::matchers::{any, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let page = |urls: &[&str]| {
+            ResponseTemplate::new(200).set_body_raw(results_page(urls), "text/html")
+        };
+        let server = MockServer::start().await;
+        for (offset, urls) in [
+            ("2", &["https://b.example", "https://c.example"][..]),
+            ("4", &["https://d.example", "https://e.example"]),
+            ("6", &["https://f.example"]),
+        ] {
+            Mock::given(query_param("s", offset))
+                .respond_with(page(urls))
+                .mount(&server)
+                .await;
+        }
+        Mock::given(any())
+            .respond_with(page(&["https://a.example", "https://b.example"]))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+
+        let registry = duckduckgo_toolkit(DuckDuckGoConfig {
+            max_results: 4,
+            region: Some("de-de".into()),
+            safe_search: SafeSearch::Off,
+            base_url: format!("{}/html/", server.uri()),
+            ..DuckDuckGoConfig::default()
+        });
+        let output = registry
+            .call("duckduckgo_search", json!({"query": "rust"}))
+            .await
+            .unwrap();
+
+        let hrefs: Vec<_> = output["results"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|r| r["href"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(
+            hrefs,
+            vec![
+                "https://a.example",
+                "https://b.example",
+                "https://c.example",
+                "https://d.example"
+            ]
+        );
+
+        let requests: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| request.url.to_string())
+            .collect();
+        assert_eq!(requests.len(), 3);
+        assert!(requests[0].contains("kl=de-de"));
+        assert!(requests[0].contains("kp=-2"));
+        assert!(!requests[0].contains("&s="));
+        assert!(requests[1].contains("&s=2"));
+        assert!(requests[2].contains("&s=4"));
+    }
 }
@@ -0,0 +1,534 @@
+//! Filesystem toolkit confined to a sandbox directory.
+//!
+//! Provides tools for reading, writing, listing, and deleting files under a root
+//! directory. Every path is resolved against the root and rejected if it escapes it,
+//! whether through `..`, an absolute path, or a symlink.
+
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::AgnoError;
+use crate::tool::{Tool, ToolRegistry};
+
+/// Default cap on the size of a single write.
+const DEFAULT_MAX_WRITE_BYTES: usize = 10 * 1024 * 1024;
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Sandbox
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// A root directory that all filesystem tool paths are resolved against
+#[derive(Debug, Clone)]
+pub struct FsSandbox {
+    root: PathBuf,
+    max_write_bytes: usize,
+}
+
+impl FsSandbox {
+    /// Use `root` as the sandbox, creating it if missing.
+    pub fn new(root: impl AsRef<Path>) -> crate::Result<Self> {
+        std::fs::create_dir_all(root.as_ref())?;
+        Ok(Self {
+            root: root.as_ref().canonicalize()?,
+            max_write_bytes: DEFAULT_MAX_WRITE_BYTES,
+        })
+    }
+
+    pub fn with_max_write_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_write_bytes = max_bytes;
+        self
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve `path` (relative to the root, or absolute inside it) to a real path,
+    /// following symlinks on the part that already exists. A symlink is only followed when
+    /// its target is inside the root; dangling symlinks are rejected.
+    pub fn resolve(&self, path: &str) -> crate::Result<PathBuf> {
+        let escape = || AgnoError::Protocol(format!("path '{}' escapes the sandbox root", path));
+
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.root.join(requested)
+        };
+
+        // Normalize `.` and `..` lexically before touching the filesystem.
+        let mut normalized = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::ParentDir => {
+                    if !normalized.pop() {
+                        return Err(escape());
+                    }
+                }
+                Component::CurDir => {}
+                other => normalized.push(other),
+            }
+        }
+        if !normalized.starts_with(&self.root) {
+            return Err(escape());
+        }
+
+        // Walk down from the root without following links blindly: each symlink must
+        // resolve inside the root, and a dangling one could create its target anywhere.
+        let parts: Vec<Component> = normalized
+            .strip_prefix(&self.root)
+            .map_err(|_| escape())?
+            .components()
+            .collect();
+        let mut resolved = self.root.clone();
+        for (index, part) in parts.iter().enumerate() {
+            let candidate = resolved.join(part);
+            match std::fs::symlink_metadata(&candidate) {
+                Ok(metadata) if metadata.file_type().is_symlink() => {
+                    let target = candidate.canonicalize().map_err(|_| escape())?;
+                    if !target.starts_with(&self.root) {
+                        return Err(escape());
+                    }
+                    resolved = target;
+                }
+                Ok(_) => resolved = candidate,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                    resolved = candidate;
+                    resolved.extend(&parts[index + 1..]);
+                    break;
+                }
+                Err(err) => return Err(err.into()),
+            }
+        }
+        Ok(resolved)
+    }
+
+    /// Display `path` relative to the root.
+    fn relative(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        if relative.as_os_str().is_empty() {
+            ".".to_string()
+        } else {
+            relative.display().to_string()
+        }
+    }
+}
+
+fn path_param(input: &Value) -> crate::Result<&str> {
+    input["path"]
+        .as_str()
+        .ok_or_else(|| AgnoError::Protocol("missing 'path' parameter".into()))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Read File Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for reading a text file inside the sandbox
+pub struct FsReadFileTool {
+    sandbox: Arc<FsSandbox>,
+}
+
+impl FsReadFileTool {
+    pub fn new(sandbox: Arc<FsSandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait]
+impl Tool for FsReadFileTool {
+    fn name(&self) -> &str {
+        "fs_read_file"
+    }
+
+    fn description(&self) -> &str {
+        "Read a text file from the sandbox directory."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File path relative to the sandbox root"
+                }
+            },
+            "required": ["path"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let path = self.sandbox.resolve(path_param(&input)?)?;
+        let bytes = tokio::fs::read(&path).await?;
+
+        Ok(json!({
+            "path": self.sandbox.relative(&path),
+            "content": String::from_utf8_lossy(&bytes),
+            "size": bytes.len()
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Write File Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for writing a text file inside the sandbox, creating parent directories
+pub struct FsWriteFileTool {
+    sandbox: Arc<FsSandbox>,
+}
+
+impl FsWriteFileTool {
+    pub fn new(sandbox: Arc<FsSandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait]
+impl Tool for FsWriteFileTool {
+    fn name(&self) -> &str {
+        "fs_write_file"
+    }
+
+    fn description(&self) -> &str {
+        "Write a text file in the sandbox directory, replacing it or appending to it."
+    }
+
+    fn requires_confirmation(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "File path relative to the sandbox root"
+                },
+                "content": {
+                    "type": "string",
+                    "description": "Text to write"
+                },
+                "append": {
+                    "type": "boolean",
+                    "description": "Append instead of overwriting (default: false)"
+                }
+            },
+            "required": ["path", "content"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let content = input["content"]
+            .as_str()
+            .ok_or_else(|| AgnoError::Protocol("missing 'content' parameter".into()))?;
+        if content.len() > self.sandbox.max_write_bytes {
+            return Err(AgnoError::Protocol(format!(
+                "content is {} bytes, over the {} byte write limit",
+                content.len(),
+                self.sandbox.max_write_bytes
+            )));
+        }
+
+        let path = self.sandbox.resolve(path_param(&input)?)?;
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        if input["append"].as_bool().unwrap_or(false) {
+            use tokio::io::AsyncWriteExt;
+            let mut file = tokio::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .await?;
+            file.write_all(content.as_bytes()).await?;
+        } else {
+            tokio::fs::write(&path, content).await?;
+        }
+
+        Ok(json!({
+            "path": self.sandbox.relative(&path),
+            "bytes_written": content.len()
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// List Directory Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for listing a directory inside the sandbox
+pub struct FsListDirTool {
+    sandbox: Arc<FsSandbox>,
+}
+
+impl FsListDirTool {
+    pub fn new(sandbox: Arc<FsSandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait]
+impl Tool for FsListDirTool {
+    fn name(&self) -> &str {
+        "fs_list_dir"
+    }
+
+    fn description(&self) -> &str {
+        "List the files and directories in a sandbox directory."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Directory path relative to the sandbox root (default: root)"
+                }
+            }
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let path = self
+            .sandbox
+            .resolve(input["path"].as_str().unwrap_or("."))?;
+
+        let mut entries = Vec::new();
+        let mut dir = tokio::fs::read_dir(&path).await?;
+        while let Some(entry) = dir.next_entry().await? {
+            let metadata = entry.metadata().await?;
+            entries.push(json!({
+                "name": entry.file_name().to_string_lossy(),
+                "is_dir": metadata.is_dir(),
+                "size": metadata.len()
+            }));
+        }
+        entries.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+
+        Ok(json!({
+            "path": self.sandbox.relative(&path),
+            "entries": entries,
+            "count": entries.len()
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Delete Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for deleting a file or directory inside the sandbox
+pub struct FsDeleteTool {
+    sandbox: Arc<FsSandbox>,
+}
+
+impl FsDeleteTool {
+    pub fn new(sandbox: Arc<FsSandbox>) -> Self {
+        Self { sandbox }
+    }
+}
+
+#[async_trait]
+impl Tool for FsDeleteTool {
+    fn name(&self) -> &str {
+        "fs_delete"
+    }
+
+    fn description(&self) -> &str {
+        "Delete a file, or a directory when `recursive` is true, from the sandbox directory."
+    }
+
+    fn requires_confirmation(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "path": {
+                    "type": "string",
+                    "description": "Path relative to the sandbox root"
+                },
+                "recursive": {
+                    "type": "boolean",
+                    "description": "Delete a directory and its contents (default: false)"
+                }
+            },
+            "required": ["path"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let path = self.sandbox.resolve(path_param(&input)?)?;
+        if path == self.sandbox.root {
+            return Err(AgnoError::Protocol(
+                "refusing to delete the sandbox root".into(),
+            ));
+        }
+
+        let metadata = tokio::fs::symlink_metadata(&path).await?;
+        if metadata.is_dir() {
+            if input["recursive"].as_bool().unwrap_or(false) {
+                tokio::fs::remove_dir_all(&path).await?;
+            } else {
+                tokio::fs::remove_dir(&path).await?;
+            }
+        } else {
+            tokio::fs::remove_file(&path).await?;
+        }
+
+        Ok(json!({
+            "path": self.sandbox.relative(&path),
+            "deleted": true
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Filesystem Toolkit
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Register all filesystem tools with a registry, sandboxed to `root_dir`
+pub fn register_filesystem_tools(
+    registry: &mut ToolRegistry,
+    root_dir: impl AsRef<Path>,
+) -> crate::Result<()> {
+    let sandbox = Arc::new(FsSandbox::new(root_dir)?);
+    registry.register(FsReadFileTool::new(sandbox.clone()));
+    registry.register(FsWriteFileTool::new(sandbox.clone()));
+    registry.register(FsListDirTool::new(sandbox.clone()));
+    registry.register(FsDeleteTool::new(sandbox));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry(root: &Path) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        register_filesystem_tools(&mut registry, root).unwrap();
+        registry
+    }
+
+    #[tokio::test]
+    async fn round_trips_files_inside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("sandbox");
+        let tools = registry(&root);
+        assert!(root.is_dir());
+
+        tools
+            .call(
+                "fs_write_file",
+                json!({"path": "notes/todo.txt", "content": "ship it"}),
+            )
+            .await
+            .unwrap();
+        let read = tools
+            .call("fs_read_file", json!({"path": "./notes/../notes/todo.txt"}))
+            .await
+            .unwrap();
+        assert_eq!(read["content"], "ship it");
+        assert_eq!(read["path"], "notes/todo.txt");
+
+        let listing = tools.call("fs_list_dir", json!({})).await.unwrap();
+        assert_eq!(listing["entries"][0]["name"], "notes");
+
+        tools
+            .call("fs_delete", json!({"path": "notes", "recursive": true}))
+            .await
+            .unwrap();
+        assert!(!root.join("notes").exists());
+    }
+
+    #[tokio::test]
+    async fn rejects_paths_that_escape_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("sandbox");
+        let tools = registry(&root);
+        std::fs::write(dir.path().join("secret.txt"), "top secret").unwrap();
+
+        for path in ["../etc/passwd", "../secret.txt", "a/../../secret.txt"] {
+            let err = tools
+                .call("fs_read_file", json!({"path": path}))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("escapes the sandbox"), "{path}");
+        }
+        let outside = dir.path().join("secret.txt");
+        assert!(tools
+            .call("fs_read_file", json!({"path": outside.to_str().unwrap()}))
+            .await
+            .is_err());
+        assert!(tools
+            .call(
+                "fs_write_file",
+                json!({"path": "../evil.txt", "content": "x"})
+            )
+            .await
+            .is_err());
+        assert!(!dir.path().join("evil.txt").exists());
+        assert!(tools.call("fs_delete", json!({"path": "."})).await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn rejects_symlinks_pointing_outside_the_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("sandbox");
+        let tools = registry(&root);
+        std::os::unix::fs::symlink(dir.path(), root.join("link")).unwrap();
+        std::fs::write(dir.path().join("secret.txt"), "top secret").unwrap();
+
+        let err = tools
+            .call("fs_read_file", json!({"path": "link/secret.txt"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("escapes the sandbox"));
+        assert!(tools
+            .call(
+                "fs_write_file",
+                json!({"path": "link/new.txt", "content": "x"})
+            )
+            .await
+            .is_err());
+
+        let outside = dir.path().join("planted.txt");
+        std::os::unix::fs::symlink(&outside, root.join("dangling")).unwrap();
+        assert!(tools
+            .call("fs_write_file", json!({"path": "dangling", "content": "x"}))
+            .await
+            .is_err());
+        assert!(!outside.exists());
+
+        std::fs::write(root.join("real.txt"), "inside").unwrap();
+        std::os::unix::fs::symlink(root.join("real.txt"), root.join("alias")).unwrap();
+        let read = tools
+            .call("fs_read_file", json!({"path": "alias"}))
+            .await
+            .unwrap();
+        assert_eq!(read["content"], "inside");
+    }
+
+    #[tokio::test]
+    async fn enforces_write_size_cap() {
+        let dir = tempfile::tempdir().unwrap();
+        let sandbox = Arc::new(FsSandbox::new(dir.path()).unwrap().with_max_write_bytes(4));
+        let tool = FsWriteFileTool::new(sandbox);
+
+        assert!(tool
+            .call(json!({"path": "big.txt", "content": "too large"}))
+            .await
+            .is_err());
+        assert!(!dir.path().join("big.txt").exists());
+    }
+}
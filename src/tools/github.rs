@@ -1,6 +1,7 @@
 //! GitHub toolkit for interacting with GitHub repositories.
 //!
-//! Provides tools for searching repos, issues, PRs, and reading file contents.
+//! Provides tools for searching repos, issues, PRs, and reading file contents, plus
+//! token-authenticated tools for opening issues and commenting on them.
 
 use crate::tool::Tool;
 use async_trait::async_trait;
@@ -62,6 +63,40 @@ impl GitHubClient {
             .await
             .map_err(|e| crate::error::AgnoError::Protocol(format!("Failed to parse response: {}", e)))
     }
+
+    /// POST `body` to `endpoint`. Write operations always need a token.
+    async fn post(&self, endpoint: &str, body: &Value) -> crate::Result<Value> {
+        let token = self.token.as_ref().ok_or_else(|| {
+            crate::error::AgnoError::Protocol(
+                "GitHub token required for write operations (set GITHUB_TOKEN)".into(),
+            )
+        })?;
+
+        let response = self
+            .http
+            .post(format!("{}{}", self.base_url, endpoint))
+            .header("User-Agent", "sayr-engine/0.3.0")
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("Bearer {}", token))
+            .json(body)
+            .send()
+            .await
+            .map_err(|e| crate::error::AgnoError::Protocol(format!("GitHub request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(crate::error::AgnoError::Protocol(format!(
+                "GitHub API error {}: {}",
+                status, body
+            )));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| crate::error::AgnoError::Protocol(format!("Failed to parse response: {}", e)))
+    }
 }
 
 impl Default for GitHubClient {
@@ -107,6 +142,10 @@ impl Tool for GitHubSearchReposTool {
         "Search GitHub repositories by query. Returns repository names, descriptions, stars, and URLs."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -213,6 +252,10 @@ impl Tool for GitHubGetRepoTool {
         "Get detailed information about a GitHub repository."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -396,6 +439,10 @@ impl Tool for GitHubReadFileTool {
         "Read the contents of a file from a GitHub repository."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -464,6 +511,230 @@ impl Tool for GitHubReadFileTool {
     }
 }
 
+// ─────────────────────────────────────────────────────────────────────────────
+// Create Issue Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+fn required_str<'a>(input: &'a Value, key: &str) -> crate::Result<&'a str> {
+    input[key]
+        .as_str()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| crate::error::AgnoError::Protocol(format!("missing '{}' parameter", key)))
+}
+
+/// Like [`required_str`], for an `owner` or `repo` that becomes a path segment: only
+/// `[A-Za-z0-9_.-]` is allowed and `..` is rejected, so it cannot reach other endpoints.
+fn required_name<'a>(input: &'a Value, key: &str) -> crate::Result<&'a str> {
+    let name = required_str(input, key)?;
+    let allowed = name
+        .bytes()
+        .all(|byte| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'-'));
+    if !allowed || name.contains("..") {
+        return Err(crate::error::AgnoError::Protocol(format!(
+            "invalid '{}' parameter: {:?}",
+            key, name
+        )));
+    }
+    Ok(name)
+}
+
+/// Tool for opening a new issue. Requires a token and asks for confirmation.
+pub struct GitHubCreateIssueTool {
+    client: GitHubClient,
+}
+
+impl GitHubCreateIssueTool {
+    pub fn new() -> Self {
+        Self {
+            client: GitHubClient::new(),
+        }
+    }
+
+    pub fn with_client(client: GitHubClient) -> Self {
+        Self { client }
+    }
+
+    /// Validate the input and build `(endpoint, body)` for the create-issue request.
+    fn request(input: &Value) -> crate::Result<(String, Value)> {
+        let owner = required_name(input, "owner")?;
+        let repo = required_name(input, "repo")?;
+        let title = required_str(input, "title")?;
+
+        let mut body = json!({ "title": title });
+        if let Some(text) = input["body"].as_str() {
+            body["body"] = json!(text);
+        }
+        match &input["labels"] {
+            Value::Null => {}
+            Value::Array(labels) if labels.iter().all(Value::is_string) => {
+                body["labels"] = json!(labels);
+            }
+            _ => {
+                return Err(crate::error::AgnoError::Protocol(
+                    "'labels' must be an array of strings".into(),
+                ))
+            }
+        }
+        Ok((format!("/repos/{}/{}/issues", owner, repo), body))
+    }
+}
+
+impl Default for GitHubCreateIssueTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for GitHubCreateIssueTool {
+    fn name(&self) -> &str {
+        "github_create_issue"
+    }
+
+    fn description(&self) -> &str {
+        "Open a new issue in a GitHub repository. Returns the issue number and URL."
+    }
+
+    fn requires_confirmation(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "owner": {
+                    "type": "string",
+                    "description": "Repository owner"
+                },
+                "repo": {
+                    "type": "string",
+                    "description": "Repository name"
+                },
+                "title": {
+                    "type": "string",
+                    "description": "Issue title"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Issue description in Markdown"
+                },
+                "labels": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Labels to apply"
+                }
+            },
+            "required": ["owner", "repo", "title"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let (endpoint, body) = Self::request(&input)?;
+        let response = self.client.post(&endpoint, &body).await?;
+
+        Ok(json!({
+            "number": response["number"],
+            "title": response["title"],
+            "url": response["html_url"]
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Comment Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for commenting on an issue or pull request. Requires a token and asks for
+/// confirmation.
+pub struct GitHubCommentTool {
+    client: GitHubClient,
+}
+
+impl GitHubCommentTool {
+    pub fn new() -> Self {
+        Self {
+            client: GitHubClient::new(),
+        }
+    }
+
+    pub fn with_client(client: GitHubClient) -> Self {
+        Self { client }
+    }
+
+    /// Validate the input and build `(endpoint, body)` for the comment request.
+    fn request(input: &Value) -> crate::Result<(String, Value)> {
+        let owner = required_name(input, "owner")?;
+        let repo = required_name(input, "repo")?;
+        let number = input["number"].as_u64().ok_or_else(|| {
+            crate::error::AgnoError::Protocol("missing 'number' parameter".into())
+        })?;
+        let body = required_str(input, "body")?;
+
+        Ok((
+            format!("/repos/{}/{}/issues/{}/comments", owner, repo, number),
+            json!({ "body": body }),
+        ))
+    }
+}
+
+impl Default for GitHubCommentTool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl Tool for GitHubCommentTool {
+    fn name(&self) -> &str {
+        "github_comment"
+    }
+
+    fn description(&self) -> &str {
+        "Add a comment to a GitHub issue or pull request. Returns the comment URL."
+    }
+
+    fn requires_confirmation(&self) -> bool {
+        true
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "owner": {
+                    "type": "string",
+                    "description": "Repository owner"
+                },
+                "repo": {
+                    "type": "string",
+                    "description": "Repository name"
+                },
+                "number": {
+                    "type": "integer",
+                    "description": "Issue or pull request number"
+                },
+                "body": {
+                    "type": "string",
+                    "description": "Comment text in Markdown"
+                }
+            },
+            "required": ["owner", "repo", "number", "body"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let (endpoint, body) = Self::request(&input)?;
+        let response = self.client.post(&endpoint, &body).await?;
+
+        Ok(json!({
+            "id": response["id"],
+            "number": input["number"],
+            "url": response["html_url"]
+        }))
+    }
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // GitHub Toolkit
 // ─────────────────────────────────────────────────────────────────────────────
@@ -476,7 +747,9 @@ pub fn register_github_tools(registry: &mut ToolRegistry) {
     registry.register(GitHubSearchReposTool::with_client(client.clone()));
     registry.register(GitHubGetRepoTool::with_client(client.clone()));
     registry.register(GitHubListIssuesTool::with_client(client.clone()));
-    registry.register(GitHubReadFileTool::with_client(client));
+    registry.register(GitHubReadFileTool::with_client(client.clone()));
+    registry.register(GitHubCreateIssueTool::with_client(client.clone()));
+    registry.register(GitHubCommentTool::with_client(client));
 }
 
 #[cfg(test)]
@@ -497,4 +770,84 @@ mod tests {
         let read_file = GitHubReadFileTool::new();
         assert_eq!(read_file.name(), "github_read_file");
     }
+
+    #[test]
+    fn builds_create_issue_request() {
+        let (endpoint, body) = GitHubCreateIssueTool::request(&json!({
+            "owner": "octo",
+            "repo": "demo",
+            "title": "Crash on start",
+            "body": "Steps to reproduce...",
+            "labels": ["bug", "p1"]
+        }))
+        .unwrap();
+        assert_eq!(endpoint, "/repos/octo/demo/issues");
+        assert_eq!(
+            body,
+            json!({"title": "Crash on start", "body": "Steps to reproduce...", "labels": ["bug", "p1"]})
+        );
+
+        let missing_title =
+            GitHubCreateIssueTool::request(&json!({"owner": "octo", "repo": "demo"}));
+        assert!(missing_title.unwrap_err().to_string().contains("'title'"));
+        let bad_labels = GitHubCreateIssueTool::request(
+            &json!({"owner": "octo", "repo": "demo", "title": "x", "labels": "bug"}),
+        );
+        assert!(bad_labels.is_err());
+
+        let (endpoint, _) = GitHubCreateIssueTool::request(
+            &json!({"owner": "my-org", "repo": "site.github.io", "title": "x"}),
+        )
+        .unwrap();
+        assert_eq!(endpoint, "/repos/my-org/site.github.io/issues");
+        for (owner, repo) in [
+            ("octo", "demo/../../user"),
+            ("..", "demo"),
+            ("octo/demo", "issues"),
+            ("octo", "demo?per_page=1"),
+        ] {
+            let err = GitHubCreateIssueTool::request(
+                &json!({"owner": owner, "repo": repo, "title": "x"}),
+            )
+            .unwrap_err();
+            assert!(err.to_string().contains("invalid"), "{owner}/{repo}: {err}");
+            assert!(GitHubCommentTool::request(
+                &json!({"owner": owner, "repo": repo, "number": 1, "body": "hi"})
+            )
+            .is_err());
+        }
+    }
+
+    #[test]
+    fn builds_comment_request() {
+        let (endpoint, body) = GitHubCommentTool::request(&json!({
+            "owner": "octo",
+            "repo": "demo",
+            "number": 42,
+            "body": "Fixed in main."
+        }))
+        .unwrap();
+        assert_eq!(endpoint, "/repos/octo/demo/issues/42/comments");
+        assert_eq!(body, json!({"body": "Fixed in main."}));
+
+        let missing_number =
+            GitHubCommentTool::request(&json!({"owner": "octo", "repo": "demo", "body": "hi"}));
+        assert!(missing_number.unwrap_err().to_string().contains("'number'"));
+    }
+
+    #[tokio::test]
+    async fn write_tools_require_token_and_confirmation() {
+        let tool = GitHubCommentTool::with_client(GitHubClient {
+            token: None,
+            ..GitHubClient::new()
+        });
+        assert!(tool.requires_confirmation());
+        assert!(!GitHubGetRepoTool::new().requires_confirmation());
+
+        let err = tool
+            .call(json!({"owner": "octo", "repo": "demo", "number": 1, "body": "hi"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("token required"));
+    }
 }
@@ -277,19 +277,146 @@ impl Tool for GmailReadMessageTool {
 // Send Message Tool
 // ─────────────────────────────────────────────────────────────────────────────
 
+/// Default cap on the decoded size of all attachments, matching Gmail's 25 MB limit.
+const DEFAULT_MAX_ATTACHMENT_BYTES: usize = 25 * 1024 * 1024;
+
+/// A decoded file to attach to an outgoing message
+#[derive(Debug)]
+struct MimeAttachment {
+    filename: String,
+    mime_type: String,
+    content: Vec<u8>,
+}
+
 /// Tool for sending Gmail messages
 pub struct GmailSendMessageTool {
     client: GmailClient,
+    max_attachment_bytes: usize,
 }
 
 impl GmailSendMessageTool {
     pub fn new(client: GmailClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            max_attachment_bytes: DEFAULT_MAX_ATTACHMENT_BYTES,
+        }
     }
 
     pub fn from_env() -> crate::Result<Self> {
         Ok(Self::new(GmailClient::from_env()?))
     }
+
+    /// Limit the combined decoded size of attachments on a single message.
+    pub fn with_max_attachment_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_attachment_bytes = max_bytes;
+        self
+    }
+
+    /// Decode and validate the optional `attachments` array.
+    fn parse_attachments(&self, input: &Value) -> crate::Result<Vec<MimeAttachment>> {
+        use base64::Engine;
+
+        let Some(items) = input["attachments"].as_array() else {
+            return Ok(Vec::new());
+        };
+
+        let mut attachments = Vec::new();
+        let mut total = 0usize;
+        for item in items {
+            let field = |name: &str| {
+                item[name].as_str().ok_or_else(|| {
+                    crate::error::AgnoError::Protocol(format!("attachment missing '{}'", name))
+                })
+            };
+            let filename = field("filename")?;
+            let mime_type = field("mime_type")?;
+            if !is_mime_type(mime_type) {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "attachment '{}' has an invalid mime_type '{}'",
+                    filename,
+                    mime_type.escape_debug()
+                )));
+            }
+            let content = base64::engine::general_purpose::STANDARD
+                .decode(field("base64_content")?)
+                .map_err(|e| {
+                    crate::error::AgnoError::Protocol(format!(
+                        "attachment '{}' is not valid base64: {}",
+                        filename, e
+                    ))
+                })?;
+
+            total += content.len();
+            if total > self.max_attachment_bytes {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "attachments exceed the {} byte limit",
+                    self.max_attachment_bytes
+                )));
+            }
+            attachments.push(MimeAttachment {
+                filename: filename.replace(['"', '\r', '\n'], ""),
+                mime_type: mime_type.to_string(),
+                content,
+            });
+        }
+        Ok(attachments)
+    }
+}
+
+/// Whether `value` is a bare `type/subtype` of RFC 2045 tokens, so it can be written into
+/// a `Content-Type` header without smuggling in parameters or extra header lines.
+fn is_mime_type(value: &str) -> bool {
+    let is_token = |part: &str| {
+        !part.is_empty()
+            && part
+                .chars()
+                .all(|c| c.is_ascii_graphic() && !"()<>@,;:\\\"/[]?=".contains(c))
+    };
+    matches!(value.split_once('/'), Some((kind, subtype)) if is_token(kind) && is_token(subtype))
+}
+
+/// Build an RFC 2822 message. With attachments it becomes `multipart/mixed`: a
+/// text part followed by one base64 part per attachment.
+fn build_mime_message(
+    to: &str,
+    subject: &str,
+    body: &str,
+    attachments: &[MimeAttachment],
+    boundary: &str,
+) -> String {
+    use base64::Engine;
+
+    if attachments.is_empty() {
+        return format!(
+            "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
+            to, subject, body
+        );
+    }
+
+    let mut message = format!(
+        "To: {}\r\nSubject: {}\r\nMIME-Version: 1.0\r\nContent-Type: multipart/mixed; boundary=\"{}\"\r\n\r\n",
+        to, subject, boundary
+    );
+    message.push_str(&format!(
+        "--{}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}\r\n",
+        boundary, body
+    ));
+    for attachment in attachments {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&attachment.content);
+        // MIME limits encoded lines to 76 characters
+        let wrapped = encoded
+            .as_bytes()
+            .chunks(76)
+            .map(|line| std::str::from_utf8(line).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join("\r\n");
+        message.push_str(&format!(
+            "--{}\r\nContent-Type: {}; name=\"{}\"\r\nContent-Disposition: attachment; filename=\"{}\"\r\nContent-Transfer-Encoding: base64\r\n\r\n{}\r\n",
+            boundary, attachment.mime_type, attachment.filename, attachment.filename, wrapped
+        ));
+    }
+    message.push_str(&format!("--{}--\r\n", boundary));
+    message
 }
 
 #[async_trait]
@@ -317,6 +444,19 @@ impl Tool for GmailSendMessageTool {
                 "body": {
                     "type": "string",
                     "description": "Email body content"
+                },
+                "attachments": {
+                    "type": "array",
+                    "description": "Optional files to attach",
+                    "items": {
+                        "type": "object",
+                        "properties": {
+                            "filename": { "type": "string" },
+                            "mime_type": { "type": "string" },
+                            "base64_content": { "type": "string" }
+                        },
+                        "required": ["filename", "mime_type", "base64_content"]
+                    }
                 }
             },
             "required": ["to", "subject", "body"]
@@ -334,11 +474,9 @@ impl Tool for GmailSendMessageTool {
             .as_str()
             .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'body' parameter".into()))?;
 
-        // Create RFC 2822 formatted message
-        let raw_message = format!(
-            "To: {}\r\nSubject: {}\r\nContent-Type: text/plain; charset=utf-8\r\n\r\n{}",
-            to, subject, body
-        );
+        let attachments = self.parse_attachments(&input)?;
+        let boundary = format!("sayr-{}", uuid::Uuid::new_v4().simple());
+        let raw_message = build_mime_message(to, subject, body, &attachments, &boundary);
 
         // Base64url encode
         use base64::Engine;
@@ -381,4 +519,80 @@ mod tests {
         let client = GmailClient::new("test-token");
         assert_eq!(client.access_token, "test-token");
     }
+
+    #[test]
+    fn builds_multipart_message_with_attachment() {
+        let tool = GmailSendMessageTool::new(GmailClient::new("test-token"));
+        let attachments = tool
+            .parse_attachments(&json!({
+                "attachments": [{
+                    "filename": "report.csv",
+                    "mime_type": "text/csv",
+                    "base64_content": "YSxiCjEsMgo="
+                }]
+            }))
+            .unwrap();
+
+        let message = build_mime_message(
+            "ops@example.com",
+            "Weekly report",
+            "See attached.",
+            &attachments,
+            "BOUNDARY",
+        );
+
+        assert!(message.contains("Content-Type: multipart/mixed; boundary=\"BOUNDARY\""));
+        let parts: Vec<&str> = message.split("--BOUNDARY").collect();
+        // preamble, text part, attachment part, closing marker
+        assert_eq!(parts.len(), 4);
+        assert!(parts[1].contains("text/plain") && parts[1].contains("See attached."));
+        assert!(parts[2].contains("Content-Disposition: attachment; filename=\"report.csv\""));
+        assert!(parts[2].contains("Content-Transfer-Encoding: base64\r\n\r\nYSxiCjEsMgo="));
+        assert_eq!(parts[3], "--\r\n");
+    }
+
+    #[test]
+    fn rejects_invalid_or_oversized_attachments() {
+        let tool = GmailSendMessageTool::new(GmailClient::new("test-token"))
+            .with_max_attachment_bytes(4);
+        let attachment = |content: &str| {
+            json!({"attachments": [{
+                "filename": "a.bin",
+                "mime_type": "application/octet-stream",
+                "base64_content": content
+            }]})
+        };
+
+        assert!(tool.parse_attachments(&attachment("not base64!")).is_err());
+        assert!(tool.parse_attachments(&attachment("YWJj")).is_ok());
+        let err = tool.parse_attachments(&attachment("YWJjZGVm")).unwrap_err();
+        assert!(err.to_string().contains("byte limit"));
+    }
+
+    #[test]
+    fn rejects_mime_types_that_would_inject_headers() {
+        let tool = GmailSendMessageTool::new(GmailClient::new("test-token"));
+        let attachment = |mime_type: &str| {
+            json!({"attachments": [{
+                "filename": "a.txt",
+                "mime_type": mime_type,
+                "base64_content": "YWJj"
+            }]})
+        };
+
+        assert!(tool.parse_attachments(&attachment("text/plain")).is_ok());
+        assert!(tool
+            .parse_attachments(&attachment("application/vnd.ms-excel"))
+            .is_ok());
+        for bad in [
+            "text/plain\r\nBcc: victim@example.com",
+            "text/html; charset=utf-8",
+            "text",
+            "text/",
+            "a/b/c",
+        ] {
+            let err = tool.parse_attachments(&attachment(bad)).unwrap_err();
+            assert!(err.to_string().contains("invalid mime_type"), "{bad}");
+        }
+    }
 }
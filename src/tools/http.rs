@@ -10,16 +10,76 @@ use std::collections::HashMap;
 use std::time::Duration;
 
 use crate::error::{AgnoError, Result};
+use crate::retry::RetryPolicy;
 use crate::tool::{Tool, ToolRegistry};
 
+/// Headers whose values never appear in traces or tool output.
+const SENSITIVE_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "cookie",
+    "set-cookie",
+    "x-api-key",
+    "api-key",
+];
+
+/// Credentials attached to every request made by the HTTP tool
+#[derive(Clone)]
+pub enum HttpAuth {
+    /// `Authorization: Bearer <token>`
+    Bearer(String),
+    /// `Authorization: Basic <base64(username:password)>`
+    Basic {
+        username: String,
+        password: Option<String>,
+    },
+    /// A custom header such as `X-Api-Key: <value>`
+    ApiKey { header: String, value: String },
+}
+
+impl HttpAuth {
+    fn header(&self) -> Option<(HeaderName, HeaderValue)> {
+        use base64::Engine;
+
+        let value = match self {
+            HttpAuth::Bearer(token) => format!("Bearer {}", token),
+            HttpAuth::Basic { username, password } => {
+                let credentials = format!("{}:{}", username, password.as_deref().unwrap_or(""));
+                format!(
+                    "Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
+                )
+            }
+            HttpAuth::ApiKey { value, .. } => value.clone(),
+        };
+        let name = HeaderName::try_from(self.header_name()).ok()?;
+        let mut value = HeaderValue::from_str(&value).ok()?;
+        value.set_sensitive(true);
+        Some((name, value))
+    }
+
+    fn header_name(&self) -> &str {
+        match self {
+            HttpAuth::Bearer(_) | HttpAuth::Basic { .. } => "authorization",
+            HttpAuth::ApiKey { header, .. } => header,
+        }
+    }
+}
+
 /// Configuration for HTTP API tools
 #[derive(Clone)]
 pub struct HttpApiConfig {
     pub base_url: Option<String>,
+    /// Shorthand for `HttpAuth::Bearer`; ignored when `auth` is set.
     pub api_key: Option<String>,
+    pub auth: Option<HttpAuth>,
     pub default_headers: HashMap<String, String>,
     pub timeout_secs: u64,
     pub verify_ssl: bool,
+    /// Response bodies longer than this are truncated and flagged with `"truncated": true`.
+    pub max_response_bytes: usize,
+    /// Retry GET/HEAD requests that fail with a 5xx status or a transport error.
+    pub retry_policy: Option<RetryPolicy>,
 }
 
 impl Default for HttpApiConfig {
@@ -27,9 +87,12 @@ impl Default for HttpApiConfig {
         Self {
             base_url: None,
             api_key: None,
+            auth: None,
             default_headers: HashMap::new(),
             timeout_secs: 30,
             verify_ssl: true,
+            max_response_bytes: 1024 * 1024,
+            retry_policy: None,
         }
     }
 }
@@ -45,10 +108,79 @@ impl HttpApiConfig {
         self
     }
 
+    pub fn with_auth(mut self, auth: HttpAuth) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
     pub fn with_header(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
         self.default_headers.insert(key.into(), value.into());
         self
     }
+
+    pub fn with_max_response_bytes(mut self, max_bytes: usize) -> Self {
+        self.max_response_bytes = max_bytes;
+        self
+    }
+
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(policy);
+        self
+    }
+
+    fn auth(&self) -> Option<HttpAuth> {
+        self.auth
+            .clone()
+            .or_else(|| self.api_key.clone().map(HttpAuth::Bearer))
+    }
+
+    /// Delay before retrying a failed `method` request, or `None` once retries are
+    /// exhausted. Only idempotent methods are retried.
+    fn retry_delay(&self, method: &str, attempt: u32) -> Option<Duration> {
+        let policy = self.retry_policy.as_ref()?;
+        (matches!(method, "GET" | "HEAD") && attempt < policy.max_retries)
+            .then(|| policy.backoff_for(attempt))
+    }
+}
+
+fn is_sensitive(name: &str, auth: Option<&HttpAuth>) -> bool {
+    SENSITIVE_HEADERS.contains(&name)
+        || auth.is_some_and(|auth| auth.header_name().eq_ignore_ascii_case(name))
+}
+
+/// Copy `headers` into a map, replacing credential values with `[REDACTED]`.
+fn redact_headers(headers: &HeaderMap, auth: Option<&HttpAuth>) -> HashMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if is_sensitive(name.as_str(), auth) {
+                "[REDACTED]".to_string()
+            } else {
+                value.to_str().unwrap_or("").to_string()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+/// Read at most `limit` bytes of the body, reporting whether anything was cut off.
+async fn read_limited(mut response: reqwest::Response, limit: usize) -> Result<(Vec<u8>, bool)> {
+    let mut body = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "http_request".into(),
+            source: Box::new(e),
+        })?
+    {
+        if body.len() + chunk.len() > limit {
+            body.extend_from_slice(&chunk[..limit - body.len()]);
+            return Ok((body, true));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    Ok((body, false))
 }
 
 /// Create an HTTP API toolkit
@@ -94,7 +226,7 @@ impl Tool for HttpRequestTool {
             "type": "object",
             "properties": {
                 "endpoint": {"type": "string", "description": "URL or path to request"},
-                "method": {"type": "string", "enum": ["GET", "POST", "PUT", "DELETE", "PATCH"]},
+                "method": {"type": "string", "enum": ["GET", "HEAD", "POST", "PUT", "DELETE", "PATCH"]},
                 "params": {"type": "object", "description": "Query parameters"},
                 "headers": {"type": "object", "description": "Additional headers"},
                 "body": {"type": "object", "description": "JSON body for POST/PUT/PATCH"}
@@ -124,8 +256,8 @@ impl Tool for HttpRequestTool {
             .timeout(Duration::from_secs(self.config.timeout_secs))
             .danger_accept_invalid_certs(!self.config.verify_ssl)
             .build()
-            .map_err(|e| AgnoError::ToolInvocation {
-                name: "http_request".into(),
+            .map_err(|e| AgnoError::ToolExecution {
+                tool: "http_request".into(),
                 source: Box::new(e),
             })?;
 
@@ -133,6 +265,7 @@ impl Tool for HttpRequestTool {
         let method = req.method.to_uppercase();
         let mut request = match method.as_str() {
             "GET" => client.get(&url),
+            "HEAD" => client.head(&url),
             "POST" => client.post(&url),
             "PUT" => client.put(&url),
             "DELETE" => client.delete(&url),
@@ -160,13 +293,6 @@ impl Tool for HttpRequestTool {
             }
         }
 
-        // Add API key if configured
-        if let Some(ref api_key) = self.config.api_key {
-            if let Ok(value) = HeaderValue::from_str(&format!("Bearer {}", api_key)) {
-                headers.insert(reqwest::header::AUTHORIZATION, value);
-            }
-        }
-
         // Add custom headers
         if let Some(custom_headers) = req.headers {
             for (k, v) in custom_headers {
@@ -179,6 +305,12 @@ impl Tool for HttpRequestTool {
             }
         }
 
+        // Add auth after custom headers so credentials cannot be overridden by the model
+        let auth = self.config.auth();
+        if let Some((name, value)) = auth.as_ref().and_then(HttpAuth::header) {
+            headers.insert(name, value);
+        }
+
         request = request.headers(headers);
 
         // Add body for POST/PUT/PATCH
@@ -188,9 +320,36 @@ impl Tool for HttpRequestTool {
             }
         }
 
-        // Execute request
-        let response = request.send().await.map_err(|e| AgnoError::ToolInvocation {
-            name: "http_request".into(),
+        if let Some(built) = request.try_clone().and_then(|r| r.build().ok()) {
+            tracing::debug!(
+                method = %built.method(),
+                url = %built.url(),
+                headers = ?redact_headers(built.headers(), auth.as_ref()),
+                "http_request"
+            );
+        }
+
+        // Execute request, retrying idempotent methods on 5xx and transport errors
+        let mut attempt = 0;
+        let response = loop {
+            let result = match request.try_clone() {
+                Some(request) => request.send().await,
+                None => break request.send().await,
+            };
+            let retryable = match &result {
+                Ok(response) => response.status().is_server_error(),
+                Err(_) => true,
+            };
+            match self.config.retry_delay(&method, attempt) {
+                Some(delay) if retryable => {
+                    tokio::time::sleep(delay).await;
+                    attempt += 1;
+                }
+                _ => break result,
+            }
+        }
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "http_request".into(),
             source: Box::new(e),
         })?;
 
@@ -198,19 +357,29 @@ impl Tool for HttpRequestTool {
         let response_headers: HashMap<String, String> = response
             .headers()
             .iter()
+            .filter(|(k, _)| !is_sensitive(k.as_str(), auth.as_ref()))
             .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or("").to_string()))
             .collect();
+        let is_json = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|v| v.contains("json"));
 
         // Parse response body
-        let body_text = response.text().await.unwrap_or_default();
-        let body_json: Value = serde_json::from_str(&body_text)
-            .unwrap_or_else(|_| json!({ "text": body_text }));
+        let (bytes, truncated) = read_limited(response, self.config.max_response_bytes).await?;
+        let body_text = String::from_utf8_lossy(&bytes).into_owned();
+        let body_json: Value = is_json
+            .then(|| serde_json::from_str(&body_text).ok())
+            .flatten()
+            .unwrap_or_else(|| json!({ "text": body_text }));
 
         Ok(json!({
             "status_code": status,
             "headers": response_headers,
             "data": body_json,
-            "success": status >= 200 && status < 300
+            "truncated": truncated,
+            "success": (200..300).contains(&status)
         }))
     }
 }
@@ -227,4 +396,105 @@ mod tests {
         let registry = http_api_toolkit(config);
         assert!(registry.get("http_request").is_some());
     }
+
+    /// Serve `(status, body)` responses in order, one per request.
+    async fn mock_server(responses: Vec<(u16, String)>) -> wiremock::MockServer {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        for (status, body) in responses {
+            Mock::given(any())
+                .respond_with(
+                    ResponseTemplate::new(status)
+                        .insert_header("Set-Cookie", "session=abc")
+                        .set_body_raw(body, "application/json"),
+                )
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    #[tokio::test]
+    async fn injects_bearer_auth_and_parses_json() {
+        let server = mock_server(vec![(200, r#"{"ok":true}"#.into())]).await;
+        let registry = http_api_toolkit(
+            HttpApiConfig::default()
+                .with_base_url(server.uri())
+                .with_auth(HttpAuth::Bearer("secret-token".into())),
+        );
+
+        let result = registry
+            .call(
+                "http_request",
+                json!({"endpoint": "/items", "headers": {"Authorization": "Bearer forged"}}),
+            )
+            .await
+            .unwrap();
+
+        let request = &server.received_requests().await.unwrap()[0];
+        let authorization: Vec<_> = request.headers.get_all("authorization").iter().collect();
+        assert_eq!(authorization, ["Bearer secret-token"]);
+        assert_eq!(result["data"], json!({"ok": true}));
+        assert!(result["headers"].get("set-cookie").is_none());
+    }
+
+    #[tokio::test]
+    async fn truncates_responses_over_the_size_limit() {
+        let body = format!(r#"{{"items":"{}"}}"#, "x".repeat(100));
+        let server = mock_server(vec![(200, body)]).await;
+        let registry = http_api_toolkit(
+            HttpApiConfig::default()
+                .with_base_url(server.uri())
+                .with_max_response_bytes(16),
+        );
+
+        let result = registry
+            .call("http_request", json!({"endpoint": "/big"}))
+            .await
+            .unwrap();
+
+        assert_eq!(result["truncated"], true);
+        assert_eq!(result["data"]["text"].as_str().unwrap().len(), 16);
+    }
+
+    #[tokio::test]
+    async fn retries_get_requests_on_server_errors() {
+        let server = mock_server(vec![(503, "{}".into()), (200, r#"{"ok":true}"#.into())]).await;
+        let registry = http_api_toolkit(
+            HttpApiConfig::default()
+                .with_base_url(server.uri())
+                .with_retry_policy(RetryPolicy {
+                    max_retries: 2,
+                    backoff: Duration::from_millis(1),
+                }),
+        );
+
+        let result = registry
+            .call("http_request", json!({"endpoint": "/flaky"}))
+            .await
+            .unwrap();
+
+        assert_eq!(result["status_code"], 200);
+        assert_eq!(server.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn redacts_credentials_in_traced_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("authorization", HeaderValue::from_static("Bearer secret"));
+        headers.insert("x-custom-key", HeaderValue::from_static("secret"));
+        headers.insert("accept", HeaderValue::from_static("application/json"));
+        let auth = HttpAuth::ApiKey {
+            header: "X-Custom-Key".into(),
+            value: "secret".into(),
+        };
+
+        let redacted = redact_headers(&headers, Some(&auth));
+        assert_eq!(redacted["authorization"], "[REDACTED]");
+        assert_eq!(redacted["x-custom-key"], "[REDACTED]");
+        assert_eq!(redacted["accept"], "application/json");
+    }
 }
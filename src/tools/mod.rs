@@ -14,11 +14,14 @@
 //! - Slack: Messaging
 //! - Gmail: Email
 //! - Discord: Chat
+//! - Filesystem: Sandboxed file access
+//! - Weather: Current conditions and forecasts from Open-Meteo
 
 pub mod arxiv;
 pub mod calculator;
 pub mod discord;
 pub mod duckduckgo;
+pub mod fs;
 pub mod github;
 pub mod gmail;
 pub mod http;
@@ -26,21 +29,31 @@ pub mod json;
 #[cfg(feature = "persistence")]
 pub mod postgres;
 pub mod pubmed;
+mod query_cache;
+#[cfg(any(feature = "persistence", feature = "duckdb"))]
+mod query_guard;
 pub mod shell;
 pub mod slack;
 #[cfg(feature = "persistence")]
 pub mod sql;
 #[cfg(feature = "duckdb")]
 pub mod duckdb;
+pub mod weather;
 pub mod wikipedia;
 
 pub use arxiv::{register_arxiv_tools, ArxivSearchTool};
 pub use calculator::calculator_toolkit;
 pub use discord::{register_discord_tools, DiscordClient};
-pub use duckduckgo::{duckduckgo_toolkit, DuckDuckGoConfig, SearchResult};
-pub use github::{register_github_tools, GitHubClient};
+pub use duckduckgo::{duckduckgo_toolkit, DuckDuckGoConfig, SafeSearch, SearchResult};
+pub use fs::{
+    register_filesystem_tools, FsDeleteTool, FsListDirTool, FsReadFileTool, FsSandbox,
+    FsWriteFileTool,
+};
+pub use github::{
+    register_github_tools, GitHubClient, GitHubCommentTool, GitHubCreateIssueTool,
+};
 pub use gmail::{register_gmail_tools, GmailClient};
-pub use http::{http_api_toolkit, HttpApiConfig};
+pub use http::{http_api_toolkit, HttpApiConfig, HttpAuth};
 pub use json::json_toolkit;
 #[cfg(feature = "persistence")]
 pub use postgres::{register_postgres_tools, PostgresQueryTool};
@@ -51,4 +64,8 @@ pub use slack::{register_slack_tools, SlackClient};
 pub use sql::{register_sql_tools, SqlQueryTool, SqlSchemaTool};
 #[cfg(feature = "duckdb")]
 pub use duckdb::{register_duckdb_tools, DuckDbQueryTool};
-pub use wikipedia::wikipedia_toolkit;
+pub use weather::{
+    register_weather_tools, register_weather_tools_with_config, WeatherConfig,
+    WeatherCurrentTool, WeatherForecastTool,
+};
+pub use wikipedia::{wikipedia_toolkit, wikipedia_toolkit_with_config, WikipediaConfig};
@@ -5,24 +5,39 @@ mod inner {
     //! Provides tools for querying PostgreSQL databases with safety restrictions.
 
     use crate::tool::Tool;
+    use crate::tools::query_guard;
     use async_trait::async_trait;
     use serde_json::{json, Value};
+    use sqlx::postgres::{PgPool, PgPoolOptions};
+    use std::time::Duration;
+
+    /// Statement timeout applied to each query unless overridden.
+    pub const DEFAULT_STATEMENT_TIMEOUT: Duration = Duration::from_secs(30);
+
+    /// Connections kept by the pool that `register_postgres_tools` creates.
+    const DEFAULT_MAX_CONNECTIONS: u32 = 5;
 
     // ─────────────────────────────────────────────────────────────────────────────
     // Postgres Query Tool
     // ─────────────────────────────────────────────────────────────────────────────
 
-    /// Tool for executing queries against a PostgreSQL database
+    /// Tool for executing queries against a PostgreSQL database.
+    ///
+    /// Each call runs in its own transaction on a shared pool. In read-only mode (the
+    /// default) the transaction is `READ ONLY`, so a write hidden inside a `SELECT` is
+    /// rejected by the server even when it passes the statement check.
     pub struct PostgresQueryTool {
-        connection_string: String,
+        pool: PgPool,
         read_only: bool,
+        statement_timeout: Duration,
     }
 
     impl PostgresQueryTool {
-        pub fn new(connection_string: impl Into<String>) -> Self {
+        pub fn new(pool: PgPool) -> Self {
             Self {
-                connection_string: connection_string.into(),
+                pool,
                 read_only: true,
+                statement_timeout: DEFAULT_STATEMENT_TIMEOUT,
             }
         }
 
@@ -31,18 +46,82 @@ mod inner {
             self
         }
 
+        /// Cancel any query that runs longer than `timeout`.
+        pub fn with_statement_timeout(mut self, timeout: Duration) -> Self {
+            self.statement_timeout = timeout;
+            self
+        }
+
         fn is_safe_query(&self, query: &str) -> bool {
-            let query_upper = query.to_uppercase();
-            
-            if self.read_only {
-                let dangerous = ["INSERT", "UPDATE", "DELETE", "DROP", "CREATE", "ALTER", "TRUNCATE", "COPY"];
-                for keyword in dangerous {
-                    if query_upper.contains(keyword) {
-                        return false;
-                    }
-                }
-            }
-            true
+            !self.read_only || query_guard::is_read_only(query)
+        }
+    }
+
+    /// Bind JSON values to `$1`, `$2`, ... placeholders in order. Arrays and objects are
+    /// bound as JSON text.
+    fn bind_params<'q>(
+        mut query: sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments>,
+        params: &[Value],
+    ) -> sqlx::query::Query<'q, sqlx::Postgres, sqlx::postgres::PgArguments> {
+        for param in params {
+            query = match param {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64()),
+                },
+                Value::String(s) => query.bind(s.clone()),
+                other => query.bind(other.to_string()),
+            };
+        }
+        query
+    }
+
+    /// Create a pool that connects on first use, so registration does not need the
+    /// database to be up.
+    fn lazy_pool(connection_string: &str) -> crate::Result<PgPool> {
+        PgPoolOptions::new()
+            .max_connections(DEFAULT_MAX_CONNECTIONS)
+            .connect_lazy(connection_string)
+            .map_err(|e| {
+                crate::error::AgnoError::Storage(format!(
+                    "Invalid Postgres connection string: {}",
+                    e
+                ))
+            })
+    }
+
+    fn is_connection_error(error: &sqlx::Error) -> bool {
+        matches!(
+            error,
+            sqlx::Error::Io(_)
+                | sqlx::Error::Tls(_)
+                | sqlx::Error::Configuration(_)
+                | sqlx::Error::PoolTimedOut
+                | sqlx::Error::PoolClosed
+                | sqlx::Error::WorkerCrashed
+        )
+    }
+
+    /// Turn a failure into the tool result. Errors reported by the server (syntax,
+    /// read-only violations, statement timeouts) go back to the model with their
+    /// SQLSTATE; failures to reach the database are returned as errors.
+    fn query_failure(error: sqlx::Error) -> crate::Result<Value> {
+        match error {
+            sqlx::Error::Database(db) => Ok(json!({
+                "error": true,
+                "message": db.message(),
+                "code": db.code(),
+            })),
+            e if is_connection_error(&e) => Err(crate::error::AgnoError::Storage(format!(
+                "Failed to connect to Postgres: {}",
+                e
+            ))),
+            e => Err(crate::error::AgnoError::Storage(format!(
+                "Query failed: {}",
+                e
+            ))),
         }
     }
 
@@ -62,7 +141,11 @@ mod inner {
                 "properties": {
                     "query": {
                         "type": "string",
-                        "description": "SQL query to execute"
+                        "description": "SQL query to execute, using $1, $2, ... placeholders for values"
+                    },
+                    "params": {
+                        "type": "array",
+                        "description": "Values bound to the query placeholders, in order"
                     }
                 },
                 "required": ["query"]
@@ -74,23 +157,50 @@ mod inner {
                 .as_str()
                 .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
 
+            let params = match &input["params"] {
+                Value::Null => Vec::new(),
+                Value::Array(params) => params.clone(),
+                _ => {
+                    return Err(crate::error::AgnoError::Protocol(
+                        "'params' must be an array".into(),
+                    ))
+                }
+            };
+
             if !self.is_safe_query(query) {
-                return Ok(json!({
-                    "error": true,
-                    "message": "Query contains blocked operations in read-only mode."
-                }));
+                return Ok(query_guard::read_only_violation(query));
             }
 
-            let pool = sqlx::postgres::PgPoolOptions::new()
-                .max_connections(1)
-                .connect(&self.connection_string)
+            let mut tx = match self.pool.begin().await {
+                Ok(tx) => tx,
+                Err(e) => return query_failure(e),
+            };
+            if self.read_only {
+                if let Err(e) = sqlx::query("SET TRANSACTION READ ONLY")
+                    .execute(&mut *tx)
+                    .await
+                {
+                    return query_failure(e);
+                }
+            }
+            if let Err(e) = sqlx::query("SELECT set_config('statement_timeout', $1, true)")
+                .bind(self.statement_timeout.as_millis().to_string())
+                .execute(&mut *tx)
                 .await
-                .map_err(|e| crate::error::AgnoError::Storage(format!("Failed to connect to Postgres: {}", e)))?;
+            {
+                return query_failure(e);
+            }
 
-            let rows = sqlx::query(query)
-                .fetch_all(&pool)
+            let rows = match bind_params(sqlx::query(query), &params)
+                .fetch_all(&mut *tx)
                 .await
-                .map_err(|e| crate::error::AgnoError::Storage(format!("Query failed: {}", e)))?;
+            {
+                Ok(rows) => rows,
+                Err(e) => return query_failure(e),
+            };
+            if let Err(e) = tx.commit().await {
+                return query_failure(e);
+            }
 
             use sqlx::{Column, Row};
             let mut results = Vec::new();
@@ -137,9 +247,90 @@ mod inner {
 
     use crate::tool::ToolRegistry;
 
-    /// Register Postgres tools with a registry
-    pub fn register_postgres_tools(registry: &mut ToolRegistry, connection_string: impl Into<String>) {
-        registry.register(PostgresQueryTool::new(connection_string));
+    /// Register Postgres tools with a registry. The tools share one connection pool.
+    pub fn register_postgres_tools(
+        registry: &mut ToolRegistry,
+        connection_string: impl Into<String>,
+    ) -> crate::Result<()> {
+        let pool = lazy_pool(&connection_string.into())?;
+        registry.register(PostgresQueryTool::new(pool));
+        Ok(())
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn rejects_update_before_connecting_in_read_only_mode() {
+            let tool = PostgresQueryTool::new(lazy_pool("postgres://invalid:1/none").unwrap());
+            let result = tool
+                .call(json!({"query": "UPDATE users SET name = $1", "params": ["x"]}))
+                .await
+                .unwrap();
+            assert_eq!(result["error"], true);
+            assert!(result["message"].as_str().unwrap().contains("UPDATE"));
+        }
+
+        #[tokio::test]
+        async fn reports_unreachable_database_as_connection_error() {
+            let pool = PgPoolOptions::new()
+                .acquire_timeout(Duration::from_millis(500))
+                .connect_lazy("postgres://nobody@127.0.0.1:1/none")
+                .unwrap();
+            let err = PostgresQueryTool::new(pool)
+                .call(json!({"query": "SELECT 1"}))
+                .await
+                .unwrap_err();
+            assert!(err.to_string().contains("Failed to connect to Postgres"));
+        }
+
+        #[tokio::test]
+        #[ignore = "needs a Postgres server at DATABASE_URL"]
+        async fn reuses_pool_and_enforces_read_only_transactions() {
+            let url = std::env::var("DATABASE_URL").expect("DATABASE_URL not set");
+            let pool = PgPoolOptions::new()
+                .max_connections(1)
+                .connect(&url)
+                .await
+                .unwrap();
+            let sequence = format!("sayr_test_{}", uuid::Uuid::new_v4().simple());
+            sqlx::query(&format!("CREATE SEQUENCE {}", sequence))
+                .execute(&pool)
+                .await
+                .unwrap();
+
+            let tool = PostgresQueryTool::new(pool.clone());
+            for n in [1, 2] {
+                let result = tool
+                    .call(json!({"query": "SELECT $1::bigint AS n", "params": [n]}))
+                    .await
+                    .unwrap();
+                assert_eq!(result["rows"][0]["n"], n);
+            }
+            assert_eq!(pool.size(), 1);
+
+            // Passes the statement check but writes; the read-only transaction stops it.
+            let next = json!({"query": format!("SELECT nextval('{}') AS id", sequence)});
+            let rejected = tool.call(next.clone()).await.unwrap();
+            assert_eq!(rejected["error"], true);
+            assert_eq!(rejected["code"], "25006");
+
+            let timed_out = PostgresQueryTool::new(pool.clone())
+                .with_statement_timeout(Duration::from_millis(50))
+                .call(json!({"query": "SELECT pg_sleep(1)::text AS slept"}))
+                .await
+                .unwrap();
+            assert_eq!(timed_out["code"], "57014");
+
+            let writer = PostgresQueryTool::new(pool.clone()).with_write_access();
+            assert_eq!(writer.call(next).await.unwrap()["rows"][0]["id"], 1);
+
+            sqlx::query(&format!("DROP SEQUENCE {}", sequence))
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
     }
 }
 
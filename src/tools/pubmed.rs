@@ -5,15 +5,21 @@
 use crate::tool::Tool;
 use async_trait::async_trait;
 use serde_json::{json, Value};
+use std::time::{Duration, Instant};
 
 // ─────────────────────────────────────────────────────────────────────────────
 // PubMed Search Tool
 // ─────────────────────────────────────────────────────────────────────────────
 
+const EUTILS_URL: &str = "https://eutils.ncbi.nlm.nih.gov/entrez/eutils";
+
 /// Tool for searching PubMed for biomedical literature
 pub struct PubmedSearchTool {
     client: reqwest::Client,
     max_results: usize,
+    base_url: String,
+    api_key: Option<String>,
+    last_request: tokio::sync::Mutex<Option<Instant>>,
 }
 
 impl PubmedSearchTool {
@@ -21,6 +27,9 @@ impl PubmedSearchTool {
         Self {
             client: reqwest::Client::new(),
             max_results: 10,
+            base_url: EUTILS_URL.into(),
+            api_key: None,
+            last_request: tokio::sync::Mutex::new(None),
         }
     }
 
@@ -28,6 +37,64 @@ impl PubmedSearchTool {
         self.max_results = max;
         self
     }
+
+    /// Point the tool at another E-utilities root (e.g. a mirror or a test server).
+    pub fn with_base_url(mut self, url: impl Into<String>) -> Self {
+        self.base_url = url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// NCBI API key, sent as `api_key`. Raises the allowed rate from 3 to 10 requests
+    /// per second.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// GET an E-utilities endpoint, spacing requests to stay within NCBI's rate limit.
+    async fn get(&self, endpoint: &str, params: &str) -> crate::Result<reqwest::Response> {
+        let mut url = format!("{}/{}?{}", self.base_url, endpoint, params);
+        if let Some(key) = &self.api_key {
+            url.push_str(&format!("&api_key={}", urlencoding::encode(key)));
+        }
+        let interval = if self.api_key.is_some() {
+            Duration::from_millis(100)
+        } else {
+            Duration::from_millis(334)
+        };
+
+        let mut last_request = self.last_request.lock().await;
+        if let Some(last) = *last_request {
+            tokio::time::sleep_until((last + interval).into()).await;
+        }
+        *last_request = Some(Instant::now());
+        drop(last_request);
+
+        self.client
+            .get(&url)
+            .header("User-Agent", "sayr-engine/0.3.0")
+            .send()
+            .await
+            .map_err(|e| {
+                crate::error::AgnoError::Protocol(format!("PubMed {} failed: {}", endpoint, e))
+            })
+    }
+
+    /// Fetch full records for `ids` with a single `efetch` call.
+    async fn fetch_details(&self, ids: &[&str]) -> crate::Result<Vec<Value>> {
+        let xml = self
+            .get(
+                "efetch.fcgi",
+                &format!("db=pubmed&id={}&retmode=xml", ids.join(",")),
+            )
+            .await?
+            .text()
+            .await
+            .map_err(|e| {
+                crate::error::AgnoError::Protocol(format!("Failed to read efetch response: {}", e))
+            })?;
+        Ok(parse_articles(&xml))
+    }
 }
 
 impl Default for PubmedSearchTool {
@@ -46,6 +113,10 @@ impl Tool for PubmedSearchTool {
         "Search PubMed for biomedical and life science literature. Returns article titles, authors, abstracts, and PubMed IDs."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -53,6 +124,10 @@ impl Tool for PubmedSearchTool {
                 "query": {
                     "type": "string",
                     "description": "Search query for biomedical papers"
+                },
+                "details": {
+                    "type": "boolean",
+                    "description": "Fetch full abstracts and MeSH terms for each hit (default: false)"
                 }
             },
             "required": ["query"]
@@ -65,19 +140,12 @@ impl Tool for PubmedSearchTool {
             .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
 
         // Step 1: Search for IDs using esearch
-        let search_url = format!(
-            "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esearch.fcgi?db=pubmed&term={}&retmax={}&retmode=json",
+        let search_params = format!(
+            "db=pubmed&term={}&retmax={}&retmode=json",
             urlencoding::encode(query),
             self.max_results
         );
-
-        let search_resp = self
-            .client
-            .get(&search_url)
-            .header("User-Agent", "sayr-engine/0.3.0")
-            .send()
-            .await
-            .map_err(|e| crate::error::AgnoError::Protocol(format!("PubMed search failed: {}", e)))?;
+        let search_resp = self.get("esearch.fcgi", &search_params).await?;
 
         let search_json: Value = search_resp
             .json()
@@ -97,20 +165,18 @@ impl Tool for PubmedSearchTool {
             }));
         }
 
-        // Step 2: Fetch summaries using esummary
-        let ids_str = ids.join(",");
-        let summary_url = format!(
-            "https://eutils.ncbi.nlm.nih.gov/entrez/eutils/esummary.fcgi?db=pubmed&id={}&retmode=json",
-            ids_str
-        );
+        if input["details"].as_bool().unwrap_or(false) {
+            let results = self.fetch_details(&ids).await?;
+            return Ok(json!({
+                "query": query,
+                "results": results,
+                "total_results": results.len()
+            }));
+        }
 
-        let summary_resp = self
-            .client
-            .get(&summary_url)
-            .header("User-Agent", "sayr-engine/0.3.0")
-            .send()
-            .await
-            .map_err(|e| crate::error::AgnoError::Protocol(format!("PubMed summary failed: {}", e)))?;
+        // Step 2: Fetch summaries using esummary
+        let summary_params = format!("db=pubmed&id={}&retmode=json", ids.join(","));
+        let summary_resp = self.get("esummary.fcgi", &summary_params).await?;
 
         let summary_json: Value = summary_resp
             .json()
@@ -119,6 +185,7 @@ impl Tool for PubmedSearchTool {
 
         let mut results = Vec::new();
         
+
         if let Some(result_obj) = summary_json["result"].as_object() {
             for id in &ids {
                 if let Some(article) = result_obj.get(*id) {
@@ -156,6 +223,129 @@ impl Tool for PubmedSearchTool {
     }
 }
 
+/// Parse the `<PubmedArticle>` records of an `efetch` XML response.
+fn parse_articles(xml: &str) -> Vec<Value> {
+    elements(xml, "PubmedArticle")
+        .into_iter()
+        .map(|(_, article)| {
+            let pmid = element(article, "PMID").map(text).unwrap_or_default();
+
+            let abstract_text = elements(article, "AbstractText")
+                .into_iter()
+                .map(|(attrs, part)| match attribute(attrs, "Label") {
+                    Some(label) => format!("{}: {}", label, text(part)),
+                    None => text(part),
+                })
+                .collect::<Vec<_>>()
+                .join("\n");
+
+            let authors: Vec<String> = elements(article, "Author")
+                .into_iter()
+                .filter_map(|(_, author)| {
+                    if let Some(collective) = element(author, "CollectiveName") {
+                        return Some(text(collective));
+                    }
+                    let last = text(element(author, "LastName")?);
+                    Some(match element(author, "ForeName") {
+                        Some(fore) => format!("{} {}", text(fore), last),
+                        None => last,
+                    })
+                })
+                .collect();
+
+            let journal = element(article, "Journal");
+            let pub_date = journal
+                .and_then(|journal| element(journal, "PubDate"))
+                .map(|date| match element(date, "MedlineDate") {
+                    Some(medline) => text(medline),
+                    None => ["Year", "Month", "Day"]
+                        .iter()
+                        .filter_map(|part| element(date, part).map(text))
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                });
+
+            let mesh_terms: Vec<String> = elements(article, "DescriptorName")
+                .into_iter()
+                .map(|(_, name)| text(name))
+                .collect();
+
+            json!({
+                "pmid": pmid,
+                "title": element(article, "ArticleTitle").map(text),
+                "abstract": abstract_text,
+                "authors": authors,
+                "journal": journal.and_then(|journal| element(journal, "Title")).map(text),
+                "pub_date": pub_date,
+                "mesh_terms": mesh_terms,
+                "url": format!("https://pubmed.ncbi.nlm.nih.gov/{}/", pmid)
+            })
+        })
+        .collect()
+}
+
+/// Attributes and inner XML of each `<tag ...>...</tag>` element, in document order.
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<(&'a str, &'a str)> {
+    let open = format!("<{}", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let after = &rest[start + open.len()..];
+        // Skip longer tag names that share the prefix, e.g. `<AuthorList` for `Author`.
+        if !after.starts_with(|c: char| c == '>' || c.is_whitespace()) {
+            rest = after;
+            continue;
+        }
+        let Some(attrs_end) = after.find('>') else {
+            break;
+        };
+        let body = &after[attrs_end + 1..];
+        let Some(end) = body.find(&close) else {
+            break;
+        };
+        found.push((&after[..attrs_end], &body[..end]));
+        rest = &body[end + close.len()..];
+    }
+    found
+}
+
+fn element<'a>(xml: &'a str, tag: &str) -> Option<&'a str> {
+    elements(xml, tag)
+        .into_iter()
+        .next()
+        .map(|(_, inner)| inner)
+}
+
+fn attribute<'a>(attrs: &'a str, name: &str) -> Option<&'a str> {
+    let value = attrs.split(&format!("{}=\"", name)).nth(1)?;
+    value.split('"').next()
+}
+
+/// Plain text of an XML fragment: nested markup (e.g. `<i>`) removed, entities decoded
+/// and whitespace collapsed.
+fn text(xml: &str) -> String {
+    let mut plain = String::with_capacity(xml.len());
+    let mut in_tag = false;
+    for c in xml.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            c if !in_tag => plain.push(c),
+            _ => {}
+        }
+    }
+    plain
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
 // ─────────────────────────────────────────────────────────────────────────────
 // PubMed Toolkit
 // ─────────────────────────────────────────────────────────────────────────────
@@ -177,4 +367,77 @@ mod tests {
         assert_eq!(tool.name(), "pubmed_search");
         assert!(tool.parameters().is_some());
     }
+
+    #[tokio::test]
+    async fn fetches_details_for_all_pmids_in_one_efetch_call() {
+        use wiremock::matchers::path;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let search = json!({"esearchresult": {"idlist": ["101", "202"]}});
+        let fetch = r#"<?xml version="1.0"?><PubmedArticleSet>
+            <PubmedArticle><MedlineCitation><PMID Version="1">101</PMID><Article>
+                <Journal><JournalIssue><PubDate><Year>2023</Year><Month>Mar</Month></PubDate></JournalIssue>
+                <Title>The Lancet</Title></Journal>
+                <ArticleTitle>Statins &amp; <i>outcomes</i></ArticleTitle>
+                <Abstract><AbstractText Label="BACKGROUND">Why.</AbstractText><AbstractText Label="RESULTS">What.</AbstractText></Abstract>
+                <AuthorList><Author><LastName>Curie</LastName><ForeName>Marie</ForeName></Author></AuthorList>
+            </Article><MeshHeadingList>
+                <MeshHeading><DescriptorName UI="D006801">Humans</DescriptorName></MeshHeading>
+                <MeshHeading><DescriptorName UI="D019161">Statins</DescriptorName></MeshHeading>
+            </MeshHeadingList></MedlineCitation></PubmedArticle>
+            <PubmedArticle><MedlineCitation><PMID Version="1">202</PMID><Article>
+                <Journal><JournalIssue><PubDate><MedlineDate>2022 Nov-Dec</MedlineDate></PubDate></JournalIssue>
+                <Title>BMJ</Title></Journal>
+                <ArticleTitle>Second</ArticleTitle>
+                <Abstract><AbstractText>Plain abstract.</AbstractText></Abstract>
+                <AuthorList><Author><CollectiveName>Trial Group</CollectiveName></Author></AuthorList>
+            </Article></MedlineCitation></PubmedArticle>
+        </PubmedArticleSet>"#;
+
+        let server = MockServer::start().await;
+        Mock::given(path("/esearch.fcgi"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(search))
+            .mount(&server)
+            .await;
+        Mock::given(path("/efetch.fcgi"))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(fetch, "text/xml"))
+            .mount(&server)
+            .await;
+
+        let tool = PubmedSearchTool::new()
+            .with_base_url(format!("{}/", server.uri()))
+            .with_api_key("secret");
+        let output = tool
+            .call(json!({"query": "statins", "details": true}))
+            .await
+            .unwrap();
+
+        let requests: Vec<String> = server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let query = request.url.query().unwrap_or_default();
+                format!("{} {}?{query}", request.method, request.url.path())
+            })
+            .collect();
+        assert_eq!(requests.len(), 2);
+        assert!(requests[0].starts_with("GET /esearch.fcgi?"));
+        assert!(requests[1].starts_with("GET /efetch.fcgi?db=pubmed&id=101,202&retmode=xml"));
+        assert!(requests.iter().all(|line| line.contains("api_key=secret")));
+
+        let results = output["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["pmid"], "101");
+        assert_eq!(results[0]["title"], "Statins & outcomes");
+        assert_eq!(results[0]["abstract"], "BACKGROUND: Why.\nRESULTS: What.");
+        assert_eq!(results[0]["authors"], json!(["Marie Curie"]));
+        assert_eq!(results[0]["journal"], "The Lancet");
+        assert_eq!(results[0]["pub_date"], "2023 Mar");
+        assert_eq!(results[0]["mesh_terms"], json!(["Humans", "Statins"]));
+        assert_eq!(results[1]["authors"], json!(["Trial Group"]));
+        assert_eq!(results[1]["pub_date"], "2022 Nov-Dec");
+        assert_eq!(results[1]["abstract"], "Plain abstract.");
+    }
 }
@@ -0,0 +1,68 @@
+//! Short-lived result cache shared by the web search tools, so repeated lookups within a
+//! session do not hit upstream rate limits.
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+/// How long search results are reused unless a toolkit config says otherwise.
+pub(crate) const DEFAULT_QUERY_CACHE_TTL: Duration = Duration::from_secs(300);
+
+/// Results keyed by normalized query. A zero TTL disables caching.
+pub(crate) struct QueryCache {
+    ttl: Duration,
+    entries: Mutex<HashMap<String, (Instant, Value)>>,
+}
+
+impl QueryCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<Value> {
+        let entries = self.entries.lock().expect("query cache poisoned");
+        let (stored_at, value) = entries.get(key)?;
+        (stored_at.elapsed() < self.ttl).then(|| value.clone())
+    }
+
+    pub(crate) fn insert(&self, key: String, value: Value) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut entries = self.entries.lock().expect("query cache poisoned");
+        let ttl = self.ttl;
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < ttl);
+        entries.insert(key, (Instant::now(), value));
+    }
+}
+
+/// Cache key for a search query: case-folded, whitespace collapsed and trailing
+/// sentence punctuation dropped, so `Rust?` and `rust` share an entry while `C++` keeps
+/// its pluses.
+pub(crate) fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['.', ',', '!', '?', ';', ':'])
+        .trim_end()
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizes_case_spacing_and_trailing_punctuation() {
+        assert_eq!(normalize_query("  Rust   Language?! "), "rust language");
+        assert_eq!(normalize_query("rust language"), "rust language");
+        assert_eq!(normalize_query("C++"), "c++");
+        assert_eq!(normalize_query("node.js"), "node.js");
+    }
+}
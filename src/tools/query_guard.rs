@@ -0,0 +1,117 @@
+//! Statement checks shared by the SQL-backed tools.
+
+/// Keywords that modify data or schema. Their presence anywhere in a `WITH` query
+/// (e.g. `WITH x AS (...) DELETE ...`) makes it a write.
+const WRITE_KEYWORDS: &[&str] = &[
+    "INSERT", "UPDATE", "DELETE", "MERGE", "UPSERT", "REPLACE", "DROP", "CREATE", "ALTER",
+    "TRUNCATE", "COPY", "GRANT", "REVOKE", "ATTACH", "DETACH", "PRAGMA", "VACUUM", "CALL",
+];
+
+/// Return the upper-cased words of `query`, skipping string literals, quoted identifiers
+/// and comments. `None` means the query holds more than one statement.
+fn keywords(query: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut chars = query.chars().peekable();
+    let mut statement_ended = false;
+
+    while let Some(c) = chars.next() {
+        if c.is_alphanumeric() || c == '_' {
+            if statement_ended {
+                return None;
+            }
+            current.push(c.to_ascii_uppercase());
+            continue;
+        }
+        if !current.is_empty() {
+            words.push(std::mem::take(&mut current));
+        }
+        match c {
+            '\'' | '"' | '`' => {
+                if statement_ended {
+                    return None;
+                }
+                for next in chars.by_ref() {
+                    if next == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek() == Some(&'-') => {
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        break;
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut previous = ' ';
+                for next in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        break;
+                    }
+                    previous = next;
+                }
+            }
+            ';' => statement_ended = true,
+            c if c.is_whitespace() || c == '(' => {}
+            _ if statement_ended => return None,
+            _ => {}
+        }
+    }
+    if !current.is_empty() {
+        words.push(current);
+    }
+    Some(words)
+}
+
+/// Whether `query` is a single read-only statement: it must start with `SELECT` (or a
+/// `WITH` clause that contains no write keyword).
+pub(crate) fn is_read_only(query: &str) -> bool {
+    let Some(words) = keywords(query) else {
+        return false;
+    };
+    match words.first().map(String::as_str) {
+        Some("SELECT") => true,
+        Some("WITH") => !words
+            .iter()
+            .any(|word| WRITE_KEYWORDS.contains(&word.as_str())),
+        _ => false,
+    }
+}
+
+/// Error returned by the query tools when a read-only check fails.
+pub(crate) fn read_only_violation(query: &str) -> serde_json::Value {
+    let keyword = keywords(query)
+        .and_then(|words| words.into_iter().next())
+        .unwrap_or_default();
+    serde_json::json!({
+        "error": true,
+        "message": format!(
+            "Only single SELECT statements are allowed in read-only mode (got '{}').",
+            keyword
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_only_single_select_statements() {
+        assert!(is_read_only(
+            "select created_at, updated_by from t where id = ?"
+        ));
+        assert!(is_read_only("  (SELECT 1);  "));
+        assert!(is_read_only("WITH x AS (SELECT 1) SELECT * FROM x"));
+        assert!(is_read_only("SELECT 'DROP TABLE t; --' AS s"));
+
+        assert!(!is_read_only("UPDATE t SET a = 1"));
+        assert!(!is_read_only("-- comment\nDELETE FROM t"));
+        assert!(!is_read_only("WITH x AS (SELECT 1) DELETE FROM t"));
+        assert!(!is_read_only("SELECT 1; DROP TABLE t"));
+        assert!(!is_read_only(""));
+    }
+}
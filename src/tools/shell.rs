@@ -22,6 +22,11 @@ pub struct ShellConfig {
     pub timeout_secs: u64,
     /// List of blocked commands for safety
     pub blocked_commands: Vec<String>,
+    /// Executables that may run. Empty allows any program not blocked above.
+    pub allowed_commands: Vec<String>,
+    /// Run `command` strings through `sh -c`. When false, or when an allowlist is set,
+    /// the string is split into argv and the program is executed directly.
+    pub use_shell: bool,
 }
 
 impl Default for ShellConfig {
@@ -37,6 +42,8 @@ impl Default for ShellConfig {
                 "dd if=".into(),
                 ":(){:|:&};:".into(), // fork bomb
             ],
+            allowed_commands: Vec::new(),
+            use_shell: true,
         }
     }
 }
@@ -48,10 +55,54 @@ pub fn shell_toolkit(config: ShellConfig) -> ToolRegistry {
     registry
 }
 
+/// Characters that let a shell chain, substitute, or redirect commands.
+const SHELL_OPERATORS: &[char] = &[';', '&', '|', '`', '$', '<', '>', '(', ')', '\n'];
+
+/// Split a command string into argv, honoring single and double quotes. Unquoted
+/// shell operators are rejected and returned as the error.
+fn split_command(command: &str) -> std::result::Result<Vec<String>, char> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote: Option<char> = None;
+
+    for c in command.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => current.push(c),
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if SHELL_OPERATORS.contains(&c) => return Err(c),
+            None if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        args.push(current);
+    }
+    Ok(args)
+}
+
 struct RunShellCommandTool {
     config: ShellConfig,
 }
 
+impl RunShellCommandTool {
+    fn exec_directly(&self) -> bool {
+        !self.config.use_shell || !self.config.allowed_commands.is_empty()
+    }
+}
+
 #[async_trait]
 impl Tool for RunShellCommandTool {
     fn name(&self) -> &str {
@@ -65,14 +116,33 @@ impl Tool for RunShellCommandTool {
     async fn call(&self, input: Value) -> Result<Value> {
         // Get command either as a single string or args array
         let (program, args): (String, Vec<String>) = if let Some(cmd) = input.get("command").and_then(Value::as_str) {
-            // Parse command string
-            #[cfg(unix)]
-            {
-                ("sh".into(), vec!["-c".into(), cmd.into()])
-            }
-            #[cfg(windows)]
-            {
-                ("cmd".into(), vec!["/C".into(), cmd.into()])
+            if self.exec_directly() {
+                let mut argv = match split_command(cmd) {
+                    Ok(argv) if !argv.is_empty() => argv,
+                    Ok(_) => {
+                        return Err(AgnoError::Protocol(
+                            "empty `command` for run_shell_command".into(),
+                        ))
+                    }
+                    Err(operator) => {
+                        return Ok(json!({
+                            "error": format!("Shell operator '{}' is not allowed", operator),
+                            "exit_code": -1
+                        }));
+                    }
+                };
+                let program = argv.remove(0);
+                (program, argv)
+            } else {
+                // Parse command string
+                #[cfg(unix)]
+                {
+                    ("sh".into(), vec!["-c".into(), cmd.into()])
+                }
+                #[cfg(windows)]
+                {
+                    ("cmd".into(), vec!["/C".into(), cmd.into()])
+                }
             }
         } else if let Some(args) = input.get("args").and_then(Value::as_array) {
             let args: Vec<String> = args
@@ -104,6 +174,15 @@ impl Tool for RunShellCommandTool {
                 }));
             }
         }
+        if !self.config.allowed_commands.is_empty()
+            && !self.config.allowed_commands.contains(&program)
+        {
+            return Ok(json!({
+                "error": format!("Command '{}' is not in the allowed list", program),
+                "blocked_command": program,
+                "exit_code": -1
+            }));
+        }
 
         // Build command
         let mut cmd = Command::new(&program);
@@ -121,12 +200,12 @@ impl Tool for RunShellCommandTool {
             cmd.output(),
         )
         .await
-        .map_err(|_| AgnoError::ToolInvocation {
-            name: "run_shell_command".into(),
+        .map_err(|_| AgnoError::ToolExecution {
+            tool: "run_shell_command".into(),
             source: "Command timed out".into(),
         })?
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "run_shell_command".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "run_shell_command".into(),
             source: Box::new(e),
         })?;
 
@@ -187,4 +266,52 @@ mod tests {
             .unwrap();
         assert!(result["error"].as_str().unwrap().contains("blocked"));
     }
+
+    #[tokio::test]
+    async fn test_allowlisted_command_runs_without_shell() {
+        let config = ShellConfig {
+            allowed_commands: vec!["echo".into()],
+            ..ShellConfig::default()
+        };
+        let registry = shell_toolkit(config);
+        let shell = registry.get("run_shell_command").unwrap();
+
+        let result = shell
+            .call(json!({"command": "echo 'hello world'"}))
+            .await
+            .unwrap();
+        assert_eq!(result["stdout"].as_str().unwrap().trim(), "hello world");
+        assert_eq!(result["exit_code"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_allowlist_rejects_other_and_chained_commands() {
+        let config = ShellConfig {
+            allowed_commands: vec!["echo".into()],
+            ..ShellConfig::default()
+        };
+        let registry = shell_toolkit(config);
+        let shell = registry.get("run_shell_command").unwrap();
+
+        let result = shell.call(json!({"args": ["ls", "-la"]})).await.unwrap();
+        assert_eq!(result["blocked_command"], "ls");
+        assert_eq!(result["exit_code"], -1);
+
+        let result = shell
+            .call(json!({"command": "echo hi; touch pwned"}))
+            .await
+            .unwrap();
+        assert!(result["error"].as_str().unwrap().contains("';'"));
+        assert!(result.get("stdout").is_none());
+    }
+
+    #[test]
+    fn test_split_command() {
+        assert_eq!(
+            split_command(r#"grep -n "a b" 'c;d'"#).unwrap(),
+            vec!["grep", "-n", "a b", "c;d"]
+        );
+        assert_eq!(split_command("echo $(id)"), Err('$'));
+        assert_eq!(split_command("a && b"), Err('&'));
+    }
 }
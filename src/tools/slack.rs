@@ -1,11 +1,19 @@
 //! Slack toolkit for interacting with Slack workspaces.
 //!
-//! Provides tools for sending messages, listing channels, and searching messages.
+//! Provides tools for sending messages and thread replies, reacting to messages,
+//! listing channels, searching messages and reading threads.
 
 use crate::tool::Tool;
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
+/// Results Slack returns per `search.messages` request at most.
+const SEARCH_PAGE_LIMIT: u64 = 100;
+/// Replies requested per `conversations.replies` page.
+const REPLIES_PAGE_LIMIT: u64 = 200;
+/// Upper bound on results collected by one search or thread fetch.
+const MAX_RESULTS: u64 = 1_000;
+
 // ─────────────────────────────────────────────────────────────────────────────
 // Slack Client
 // ─────────────────────────────────────────────────────────────────────────────
@@ -34,6 +42,12 @@ impl SlackClient {
         Ok(Self::new(token))
     }
 
+    /// Send requests to `base_url` instead of `https://slack.com/api`.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
     async fn post(&self, endpoint: &str, body: Value) -> crate::Result<Value> {
         let response = self
             .http
@@ -45,20 +59,23 @@ impl SlackClient {
             .await
             .map_err(|e| crate::error::AgnoError::Protocol(format!("Slack request failed: {}", e)))?;
 
-        let result: Value = response
-            .json()
+        check_response(endpoint, response).await
+    }
+
+    /// POST `params` form-encoded, as Slack's read methods expect.
+    async fn post_form(&self, endpoint: &str, params: &[(&str, String)]) -> crate::Result<Value> {
+        let response = self
+            .http
+            .post(format!("{}/{}", self.base_url, endpoint))
+            .header("Authorization", format!("Bearer {}", self.token))
+            .form(params)
+            .send()
             .await
-            .map_err(|e| crate::error::AgnoError::Protocol(format!("Failed to parse response: {}", e)))?;
-
-        if result["ok"].as_bool() != Some(true) {
-            let error = result["error"].as_str().unwrap_or("unknown error");
-            return Err(crate::error::AgnoError::Protocol(format!(
-                "Slack API error: {}",
-                error
-            )));
-        }
+            .map_err(|e| {
+                crate::error::AgnoError::Protocol(format!("Slack request failed: {}", e))
+            })?;
 
-        Ok(result)
+        check_response(endpoint, response).await
     }
 
     async fn get(&self, endpoint: &str) -> crate::Result<Value> {
@@ -70,20 +87,58 @@ impl SlackClient {
             .await
             .map_err(|e| crate::error::AgnoError::Protocol(format!("Slack request failed: {}", e)))?;
 
-        let result: Value = response
-            .json()
-            .await
-            .map_err(|e| crate::error::AgnoError::Protocol(format!("Failed to parse response: {}", e)))?;
-
-        if result["ok"].as_bool() != Some(true) {
-            let error = result["error"].as_str().unwrap_or("unknown error");
-            return Err(crate::error::AgnoError::Protocol(format!(
-                "Slack API error: {}",
-                error
-            )));
+        let method = endpoint.split('?').next().unwrap_or(endpoint);
+        check_response(method, response).await
+    }
+}
+
+/// Parse a Slack Web API response, turning `ok: false` into an error. Token and scope
+/// problems get messages that say what to fix rather than Slack's bare error code.
+async fn check_response(method: &str, response: reqwest::Response) -> crate::Result<Value> {
+    let result: Value = response.json().await.map_err(|e| {
+        crate::error::AgnoError::Protocol(format!("Failed to parse response: {}", e))
+    })?;
+
+    if result["ok"].as_bool() == Some(true) {
+        return Ok(result);
+    }
+    let error = result["error"].as_str().unwrap_or("unknown error");
+    let message = match error {
+        "missing_scope" => format!(
+            "Slack token lacks the `{}` scope needed for {} (granted: {})",
+            result["needed"].as_str().unwrap_or("required"),
+            method,
+            result["provided"].as_str().unwrap_or("unknown"),
+        ),
+        "not_allowed_token_type" => format!(
+            "{} cannot be called with this kind of token; Slack requires a user token (xoxp-) for it",
+            method
+        ),
+        "not_authed" | "invalid_auth" | "token_revoked" | "token_expired" | "account_inactive" => {
+            format!("Slack rejected the token ({}); check SLACK_BOT_TOKEN", error)
         }
+        _ => format!("Slack API error: {}", error),
+    };
+    Err(crate::error::AgnoError::Protocol(message))
+}
 
-        Ok(result)
+/// `next_cursor` from a paginated response, if there is another page.
+fn next_cursor(response: &Value) -> Option<String> {
+    response["response_metadata"]["next_cursor"]
+        .as_str()
+        .filter(|cursor| !cursor.is_empty())
+        .map(str::to_string)
+}
+
+/// Permalink to `reply_ts` in a thread, derived from the thread parent's permalink
+/// (`https://team.slack.com/archives/C123/p1700000000000100`).
+fn reply_permalink(parent: &str, channel: &str, thread_ts: &str, reply_ts: &str) -> Option<String> {
+    let (archive, _) = parent.rsplit_once("/p")?;
+    let reply = format!("{}/p{}", archive, reply_ts.replace('.', ""));
+    if reply_ts == thread_ts {
+        Some(reply)
+    } else {
+        Some(format!("{}?thread_ts={}&cid={}", reply, thread_ts, channel))
     }
 }
 
@@ -104,6 +159,27 @@ impl SlackSendMessageTool {
     pub fn from_env() -> crate::Result<Self> {
         Ok(Self::new(SlackClient::from_env()?))
     }
+
+    /// Build the `chat.postMessage` body, threading the reply when `thread_ts` is set.
+    fn body(input: &Value) -> crate::Result<Value> {
+        let channel = input["channel"]
+            .as_str()
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'channel' parameter".into()))?;
+        let text = input["text"]
+            .as_str()
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'text' parameter".into()))?;
+
+        let mut body = json!({
+            "channel": channel,
+            "text": text
+        });
+
+        if let Some(thread_ts) = input["thread_ts"].as_str() {
+            body["thread_ts"] = json!(thread_ts);
+        }
+
+        Ok(body)
+    }
 }
 
 #[async_trait]
@@ -130,7 +206,7 @@ impl Tool for SlackSendMessageTool {
                 },
                 "thread_ts": {
                     "type": "string",
-                    "description": "Optional thread timestamp to reply to"
+                    "description": "Optional `ts` of the parent message to reply in its thread"
                 }
             },
             "required": ["channel", "text"]
@@ -138,29 +214,99 @@ impl Tool for SlackSendMessageTool {
     }
 
     async fn call(&self, input: Value) -> crate::Result<Value> {
+        let body = Self::body(&input)?;
+        let response = self.client.post("chat.postMessage", body).await?;
+
+        Ok(json!({
+            "success": true,
+            "channel": response["channel"],
+            "ts": response["ts"],
+            "thread_ts": response["message"]["thread_ts"],
+            "message": response["message"]
+        }))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Add Reaction Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for adding an emoji reaction to a Slack message
+pub struct SlackAddReactionTool {
+    client: SlackClient,
+}
+
+impl SlackAddReactionTool {
+    pub fn new(client: SlackClient) -> Self {
+        Self { client }
+    }
+
+    pub fn from_env() -> crate::Result<Self> {
+        Ok(Self::new(SlackClient::from_env()?))
+    }
+
+    /// Build the `reactions.add` body. Surrounding colons are stripped from the emoji name.
+    fn body(input: &Value) -> crate::Result<Value> {
         let channel = input["channel"]
             .as_str()
             .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'channel' parameter".into()))?;
-        let text = input["text"]
+        let timestamp = input["timestamp"]
             .as_str()
-            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'text' parameter".into()))?;
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'timestamp' parameter".into()))?;
+        let name = input["name"]
+            .as_str()
+            .map(|name| name.trim_matches(':'))
+            .filter(|name| !name.is_empty())
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'name' parameter".into()))?;
 
-        let mut body = json!({
+        Ok(json!({
             "channel": channel,
-            "text": text
-        });
+            "timestamp": timestamp,
+            "name": name
+        }))
+    }
+}
 
-        if let Some(thread_ts) = input["thread_ts"].as_str() {
-            body["thread_ts"] = json!(thread_ts);
-        }
+#[async_trait]
+impl Tool for SlackAddReactionTool {
+    fn name(&self) -> &str {
+        "slack_add_reaction"
+    }
 
-        let response = self.client.post("chat.postMessage", body).await?;
+    fn description(&self) -> &str {
+        "Add an emoji reaction to a Slack message."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "channel": {
+                    "type": "string",
+                    "description": "Channel ID containing the message"
+                },
+                "timestamp": {
+                    "type": "string",
+                    "description": "`ts` of the message to react to"
+                },
+                "name": {
+                    "type": "string",
+                    "description": "Emoji name without colons (e.g., thumbsup)"
+                }
+            },
+            "required": ["channel", "timestamp", "name"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let body = Self::body(&input)?;
+        self.client.post("reactions.add", body.clone()).await?;
 
         Ok(json!({
             "success": true,
-            "channel": response["channel"],
-            "ts": response["ts"],
-            "message": response["message"]
+            "channel": body["channel"],
+            "timestamp": body["timestamp"],
+            "name": body["name"]
         }))
     }
 }
@@ -241,7 +387,8 @@ impl Tool for SlackListChannelsTool {
 // Search Messages Tool
 // ─────────────────────────────────────────────────────────────────────────────
 
-/// Tool for searching Slack messages
+/// Tool for searching Slack messages. `search.messages` only accepts user tokens
+/// (`xoxp-`) with the `search:read` scope.
 pub struct SlackSearchTool {
     client: SlackClient,
 }
@@ -254,6 +401,31 @@ impl SlackSearchTool {
     pub fn from_env() -> crate::Result<Self> {
         Ok(Self::new(SlackClient::from_env()?))
     }
+
+    /// Build the `search.messages` form for one page of at most `count` results.
+    fn body(input: &Value, count: u64, cursor: &str) -> crate::Result<Vec<(&'static str, String)>> {
+        let query = input["query"]
+            .as_str()
+            .filter(|query| !query.trim().is_empty())
+            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
+        let sort = match input["sort"].as_str().unwrap_or("score") {
+            sort @ ("score" | "timestamp") => sort,
+            other => {
+                return Err(crate::error::AgnoError::Protocol(format!(
+                    "unknown sort '{}'; use 'score' or 'timestamp'",
+                    other
+                )))
+            }
+        };
+
+        Ok(vec![
+            ("query", query.to_string()),
+            ("count", count.min(SEARCH_PAGE_LIMIT).to_string()),
+            ("sort", sort.to_string()),
+            ("sort_dir", "desc".to_string()),
+            ("cursor", cursor.to_string()),
+        ])
+    }
 }
 
 #[async_trait]
@@ -263,7 +435,7 @@ impl Tool for SlackSearchTool {
     }
 
     fn description(&self) -> &str {
-        "Search for messages in Slack."
+        "Search for messages in Slack. Supports Slack search modifiers such as `in:#eng` and `after:2024-01-31`."
     }
 
     fn parameters(&self) -> Option<Value> {
@@ -276,7 +448,12 @@ impl Tool for SlackSearchTool {
                 },
                 "count": {
                     "type": "integer",
-                    "description": "Number of results to return (default: 20)"
+                    "description": "Number of results to return (default: 20, max: 1000)"
+                },
+                "sort": {
+                    "type": "string",
+                    "enum": ["score", "timestamp"],
+                    "description": "Order by relevance (default) or newest first"
                 }
             },
             "required": ["query"]
@@ -284,39 +461,185 @@ impl Tool for SlackSearchTool {
     }
 
     async fn call(&self, input: Value) -> crate::Result<Value> {
-        let query = input["query"]
-            .as_str()
-            .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
-        let count = input["count"].as_i64().unwrap_or(20);
+        let wanted = input["count"].as_u64().unwrap_or(20).clamp(1, MAX_RESULTS);
+
+        let mut messages = Vec::new();
+        let mut cursor = "*".to_string();
+        let mut total;
+        let mut has_more = false;
+        loop {
+            let remaining = wanted - messages.len() as u64;
+            let body = Self::body(&input, remaining, &cursor)?;
+            let response = self.client.post_form("search.messages", &body).await?;
+            total = response["messages"]["total"].clone();
+
+            let matches = response["messages"]["matches"]
+                .as_array()
+                .cloned()
+                .unwrap_or_default();
+            messages.extend(matches.iter().take(remaining as usize).map(|msg| {
+                json!({
+                    "author": msg["user"],
+                    "username": msg["username"],
+                    "channel": {"id": msg["channel"]["id"], "name": msg["channel"]["name"]},
+                    "ts": msg["ts"],
+                    "text": msg["text"],
+                    "permalink": msg["permalink"]
+                })
+            }));
+
+            match next_cursor(&response) {
+                Some(next) if !matches.is_empty() => {
+                    if messages.len() as u64 >= wanted {
+                        has_more = true;
+                        break;
+                    }
+                    cursor = next;
+                }
+                _ => break,
+            }
+        }
 
-        let endpoint = format!(
-            "search.messages?query={}&count={}",
-            urlencoding::encode(query),
-            count
-        );
-        let response = self.client.get(&endpoint).await?;
+        Ok(json!({
+            "query": input["query"],
+            "messages": messages,
+            "count": messages.len(),
+            "total": total,
+            "has_more": has_more
+        }))
+    }
+}
 
-        let messages = response["messages"]["matches"]
-            .as_array()
-            .map(|arr| {
-                arr.iter()
-                    .map(|msg| {
-                        json!({
-                            "text": msg["text"],
-                            "user": msg["user"],
-                            "channel": msg["channel"]["name"],
-                            "ts": msg["ts"],
-                            "permalink": msg["permalink"]
-                        })
-                    })
-                    .collect::<Vec<_>>()
+// ─────────────────────────────────────────────────────────────────────────────
+// Get Thread Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Tool for reading a Slack thread: the parent message and its replies, oldest first
+pub struct SlackGetThreadTool {
+    client: SlackClient,
+}
+
+impl SlackGetThreadTool {
+    pub fn new(client: SlackClient) -> Self {
+        Self { client }
+    }
+
+    pub fn from_env() -> crate::Result<Self> {
+        Ok(Self::new(SlackClient::from_env()?))
+    }
+}
+
+#[async_trait]
+impl Tool for SlackGetThreadTool {
+    fn name(&self) -> &str {
+        "slack_get_thread"
+    }
+
+    fn description(&self) -> &str {
+        "Fetch a Slack thread: the parent message and its replies in order."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": {
+                "channel": {
+                    "type": "string",
+                    "description": "Channel ID containing the thread"
+                },
+                "thread_ts": {
+                    "type": "string",
+                    "description": "`ts` of the thread's parent message"
+                },
+                "limit": {
+                    "type": "integer",
+                    "description": "Maximum number of messages to return, parent included (default: 200, max: 1000)"
+                }
+            },
+            "required": ["channel", "thread_ts"]
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let channel = input["channel"].as_str().ok_or_else(|| {
+            crate::error::AgnoError::Protocol("missing 'channel' parameter".into())
+        })?;
+        let thread_ts = input["thread_ts"].as_str().ok_or_else(|| {
+            crate::error::AgnoError::Protocol("missing 'thread_ts' parameter".into())
+        })?;
+        let wanted = input["limit"]
+            .as_u64()
+            .unwrap_or(REPLIES_PAGE_LIMIT)
+            .clamp(1, MAX_RESULTS);
+
+        let mut replies = Vec::new();
+        let mut cursor = None;
+        let mut has_more = false;
+        loop {
+            let remaining = wanted - replies.len() as u64;
+            let mut body = vec![
+                ("channel", channel.to_string()),
+                ("ts", thread_ts.to_string()),
+                ("limit", remaining.min(REPLIES_PAGE_LIMIT).to_string()),
+            ];
+            if let Some(cursor) = cursor.take() {
+                body.push(("cursor", cursor));
+            }
+            let response = self
+                .client
+                .post_form("conversations.replies", &body)
+                .await?;
+            let page = response["messages"].as_array().cloned().unwrap_or_default();
+            replies.extend(page.iter().take(remaining as usize).cloned());
+
+            match next_cursor(&response) {
+                Some(next) if !page.is_empty() => {
+                    if replies.len() as u64 >= wanted {
+                        has_more = true;
+                        break;
+                    }
+                    cursor = Some(next);
+                }
+                _ => break,
+            }
+        }
+
+        // Replies carry no permalink; derive them from the parent's. This is best-effort,
+        // so a failed lookup leaves the permalinks empty rather than failing the fetch.
+        let parent_permalink = self
+            .client
+            .post_form(
+                "chat.getPermalink",
+                &[
+                    ("channel", channel.to_string()),
+                    ("message_ts", thread_ts.to_string()),
+                ],
+            )
+            .await
+            .ok()
+            .and_then(|response| response["permalink"].as_str().map(str::to_string));
+
+        let messages: Vec<Value> = replies
+            .iter()
+            .map(|msg| {
+                let ts = msg["ts"].as_str().unwrap_or_default();
+                json!({
+                    "author": msg["user"].as_str().or(msg["bot_id"].as_str()),
+                    "ts": msg["ts"],
+                    "text": msg["text"],
+                    "permalink": parent_permalink
+                        .as_deref()
+                        .and_then(|parent| reply_permalink(parent, channel, thread_ts, ts))
+                })
             })
-            .unwrap_or_default();
+            .collect();
 
         Ok(json!({
-            "query": query,
+            "channel": channel,
+            "thread_ts": thread_ts,
             "messages": messages,
-            "total": response["messages"]["total"]
+            "count": messages.len(),
+            "has_more": has_more
         }))
     }
 }
@@ -331,17 +654,198 @@ use crate::tool::ToolRegistry;
 pub fn register_slack_tools(registry: &mut ToolRegistry, token: impl Into<String>) {
     let client = SlackClient::new(token);
     registry.register(SlackSendMessageTool::new(client.clone()));
+    registry.register(SlackAddReactionTool::new(client.clone()));
     registry.register(SlackListChannelsTool::new(client.clone()));
-    registry.register(SlackSearchTool::new(client));
+    registry.register(SlackSearchTool::new(client.clone()));
+    registry.register(SlackGetThreadTool::new(client));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use wiremock::matchers::any;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[test]
     fn test_slack_client_creation() {
         let client = SlackClient::new("test-token");
         assert_eq!(client.token, "test-token");
     }
+
+    #[test]
+    fn builds_threaded_reply_body() {
+        let body = SlackSendMessageTool::body(&json!({
+            "channel": "C123",
+            "text": "On it!",
+            "thread_ts": "1700000000.000100"
+        }))
+        .unwrap();
+        assert_eq!(
+            body,
+            json!({"channel": "C123", "text": "On it!", "thread_ts": "1700000000.000100"})
+        );
+
+        let top_level =
+            SlackSendMessageTool::body(&json!({"channel": "C123", "text": "hi"})).unwrap();
+        assert!(top_level.get("thread_ts").is_none());
+    }
+
+    #[test]
+    fn builds_reaction_body() {
+        let body = SlackAddReactionTool::body(&json!({
+            "channel": "C123",
+            "timestamp": "1700000000.000100",
+            "name": ":white_check_mark:"
+        }))
+        .unwrap();
+        assert_eq!(
+            body,
+            json!({"channel": "C123", "timestamp": "1700000000.000100", "name": "white_check_mark"})
+        );
+
+        assert!(SlackAddReactionTool::body(&json!({"channel": "C123", "name": "eyes"})).is_err());
+    }
+
+    /// Serve `responses` in order, one per request.
+    async fn serve(responses: Vec<Value>) -> MockServer {
+        let server = MockServer::start().await;
+        for response in responses {
+            Mock::given(any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(response))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+        }
+        server
+    }
+
+    /// Path and form body of every request `server` received, in order.
+    async fn received(server: &MockServer) -> Vec<(String, String)> {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|request| {
+                let body = String::from_utf8(request.body).unwrap();
+                (request.url.path().to_string(), body)
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn builds_search_request_body_and_explains_scope_errors() {
+        let body = SlackSearchTool::body(
+            &json!({"query": "in:#eng decided", "sort": "timestamp"}),
+            250,
+            "*",
+        )
+        .unwrap();
+        assert_eq!(
+            body,
+            [
+                ("query", "in:#eng decided".to_string()),
+                ("count", "100".to_string()),
+                ("sort", "timestamp".to_string()),
+                ("sort_dir", "desc".to_string()),
+                ("cursor", "*".to_string()),
+            ]
+        );
+        assert!(SlackSearchTool::body(&json!({"query": " "}), 20, "*").is_err());
+        assert!(SlackSearchTool::body(&json!({"query": "x", "sort": "new"}), 20, "*").is_err());
+
+        let server = serve(vec![json!({
+            "ok": false,
+            "error": "missing_scope",
+            "needed": "search:read",
+            "provided": "chat:write,channels:read"
+        })])
+        .await;
+        let tool = SlackSearchTool::new(SlackClient::new("xoxb-test").with_base_url(server.uri()));
+        let err = tool
+            .call(json!({"query": "in:#eng decided"}))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "protocol error: Slack token lacks the `search:read` scope needed for search.messages (granted: chat:write,channels:read)"
+        );
+
+        let requests = received(&server).await;
+        assert_eq!(requests[0].0, "/search.messages");
+        assert_eq!(
+            requests[0].1,
+            "query=in%3A%23eng+decided&count=20&sort=score&sort_dir=desc&cursor=*"
+        );
+    }
+
+    #[tokio::test]
+    async fn follows_reply_cursors_until_the_thread_ends() {
+        let server = serve(vec![
+            json!({
+                "ok": true,
+                "messages": [
+                    {"user": "U1", "ts": "1700000000.000100", "text": "Postgres or SQLite?"},
+                    {"user": "U2", "ts": "1700000001.000200", "text": "Postgres"}
+                ],
+                "has_more": true,
+                "response_metadata": {"next_cursor": "bmV4dA=="}
+            }),
+            json!({
+                "ok": true,
+                "messages": [
+                    {"bot_id": "B1", "ts": "1700000002.000300", "text": "Decision recorded"}
+                ],
+                "has_more": false,
+                "response_metadata": {"next_cursor": ""}
+            }),
+            json!({
+                "ok": true,
+                "channel": "C123",
+                "permalink": "https://acme.slack.com/archives/C123/p1700000000000100"
+            }),
+        ])
+        .await;
+        let tool =
+            SlackGetThreadTool::new(SlackClient::new("xoxb-test").with_base_url(server.uri()));
+        let result = tool
+            .call(json!({"channel": "C123", "thread_ts": "1700000000.000100"}))
+            .await
+            .unwrap();
+
+        let requests = received(&server).await;
+        assert_eq!(
+            requests,
+            [
+                (
+                    "/conversations.replies".to_string(),
+                    "channel=C123&ts=1700000000.000100&limit=200".to_string()
+                ),
+                (
+                    "/conversations.replies".to_string(),
+                    "channel=C123&ts=1700000000.000100&limit=198&cursor=bmV4dA%3D%3D".to_string()
+                ),
+                (
+                    "/chat.getPermalink".to_string(),
+                    "channel=C123&message_ts=1700000000.000100".to_string()
+                ),
+            ]
+        );
+        assert_eq!(result["count"], 3);
+        assert_eq!(result["has_more"], false);
+        assert_eq!(
+            result["messages"][0],
+            json!({
+                "author": "U1",
+                "ts": "1700000000.000100",
+                "text": "Postgres or SQLite?",
+                "permalink": "https://acme.slack.com/archives/C123/p1700000000000100"
+            })
+        );
+        assert_eq!(result["messages"][2]["author"], "B1");
+        assert_eq!(
+            result["messages"][2]["permalink"],
+            "https://acme.slack.com/archives/C123/p1700000002000300?thread_ts=1700000000.000100&cid=C123"
+        );
+    }
 }
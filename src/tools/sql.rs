@@ -4,6 +4,7 @@ mod inner {
     //!
     //! Provides tools for querying SQLite databases with safety restrictions.
 
+    use crate::tools::query_guard;
     use crate::tool::Tool;
     use async_trait::async_trait;
     use serde_json::{json, Value};
@@ -34,16 +35,6 @@ mod inner {
 
         fn is_safe_query(&self, query: &str) -> bool {
             let query_upper = query.to_uppercase();
-            
-            // In read-only mode, only allow SELECT queries
-            if self.read_only {
-                let dangerous = ["INSERT", "UPDATE", "DELETE", "DROP", "CREATE", "ALTER", "TRUNCATE"];
-                for keyword in dangerous {
-                    if query_upper.contains(keyword) {
-                        return false;
-                    }
-                }
-            }
 
             // Always block dangerous operations
             let always_blocked = ["DROP DATABASE", "DROP TABLE", "TRUNCATE"];
@@ -57,6 +48,27 @@ mod inner {
         }
     }
 
+    /// Bind JSON values to `?` placeholders in order. Arrays and objects are bound as
+    /// JSON text.
+    fn bind_params<'q>(
+        mut query: sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>>,
+        params: &[Value],
+    ) -> sqlx::query::Query<'q, sqlx::Sqlite, sqlx::sqlite::SqliteArguments<'q>> {
+        for param in params {
+            query = match param {
+                Value::Null => query.bind(None::<String>),
+                Value::Bool(b) => query.bind(*b),
+                Value::Number(n) => match n.as_i64() {
+                    Some(i) => query.bind(i),
+                    None => query.bind(n.as_f64()),
+                },
+                Value::String(s) => query.bind(s.clone()),
+                other => query.bind(other.to_string()),
+            };
+        }
+        query
+    }
+
     #[async_trait]
     impl Tool for SqlQueryTool {
         fn name(&self) -> &str {
@@ -73,7 +85,11 @@ mod inner {
                 "properties": {
                     "query": {
                         "type": "string",
-                        "description": "SQL query to execute"
+                        "description": "SQL query to execute, using ? placeholders for values"
+                    },
+                    "params": {
+                        "type": "array",
+                        "description": "Values bound to the query placeholders, in order"
                     }
                 },
                 "required": ["query"]
@@ -85,10 +101,23 @@ mod inner {
                 .as_str()
                 .ok_or_else(|| crate::error::AgnoError::Protocol("missing 'query' parameter".into()))?;
 
+            let params = match &input["params"] {
+                Value::Null => Vec::new(),
+                Value::Array(params) => params.clone(),
+                _ => {
+                    return Err(crate::error::AgnoError::Protocol(
+                        "'params' must be an array".into(),
+                    ))
+                }
+            };
+
+            if self.read_only && !query_guard::is_read_only(query) {
+                return Ok(query_guard::read_only_violation(query));
+            }
             if !self.is_safe_query(query) {
                 return Ok(json!({
                     "error": true,
-                    "message": "Query contains blocked operations. Only SELECT queries are allowed in read-only mode."
+                    "message": "Query contains blocked operations."
                 }));
             }
 
@@ -99,7 +128,7 @@ mod inner {
                 .await
                 .map_err(|e| crate::error::AgnoError::Storage(format!("Failed to connect to database: {}", e)))?;
 
-            let rows: Vec<sqlx::sqlite::SqliteRow> = sqlx::query(query)
+            let rows: Vec<sqlx::sqlite::SqliteRow> = bind_params(sqlx::query(query), &params)
                 .fetch_all(&pool)
                 .await
                 .map_err(|e| crate::error::AgnoError::Storage(format!("Query failed: {}", e)))?;
@@ -228,6 +257,49 @@ mod inner {
         registry.register(SqlQueryTool::new(path.clone()));
         registry.register(SqlSchemaTool::new(path));
     }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[tokio::test]
+        async fn binds_params_and_rejects_writes_when_read_only() {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("test.db");
+            std::fs::File::create(&path).unwrap();
+
+            let writer = SqlQueryTool::new(&path).with_write_access();
+            writer
+                .call(json!({"query": "CREATE TABLE users (id INTEGER, name TEXT)"}))
+                .await
+                .unwrap();
+            writer
+                .call(json!({
+                    "query": "INSERT INTO users VALUES (?, ?), (?, ?)",
+                    "params": [1, "ada", 2, "Robert'); DROP TABLE users;--"]
+                }))
+                .await
+                .unwrap();
+
+            let reader = SqlQueryTool::new(&path);
+            let result = reader
+                .call(json!({
+                    "query": "SELECT id, name FROM users WHERE name = ?",
+                    "params": ["Robert'); DROP TABLE users;--"]
+                }))
+                .await
+                .unwrap();
+            assert_eq!(result["row_count"], 1);
+            assert_eq!(result["rows"][0]["id"], 2);
+
+            let rejected = reader
+                .call(json!({"query": "UPDATE users SET name = ? WHERE id = 1", "params": ["x"]}))
+                .await
+                .unwrap();
+            assert_eq!(rejected["error"], true);
+            assert!(rejected["message"].as_str().unwrap().contains("UPDATE"));
+        }
+    }
 }
 
 #[cfg(feature = "persistence")]
@@ -0,0 +1,494 @@
+//! Weather toolkit backed by the free Open-Meteo API (no key required).
+//!
+//! Locations can be given by name, which is geocoded through Open-Meteo's geocoding API
+//! and remembered for the lifetime of the registry, or directly as latitude/longitude.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+use crate::error::AgnoError;
+use crate::tool::{Tool, ToolRegistry};
+
+const FORECAST_URL: &str = "https://api.open-meteo.com";
+const GEOCODING_URL: &str = "https://geocoding-api.open-meteo.com";
+
+/// Longest forecast Open-Meteo serves.
+const MAX_FORECAST_DAYS: u64 = 16;
+
+/// Endpoints used by the weather tools; override them to use a self-hosted Open-Meteo or
+/// a test server.
+#[derive(Clone, Debug)]
+pub struct WeatherConfig {
+    pub forecast_base_url: String,
+    pub geocoding_base_url: String,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        Self {
+            forecast_base_url: FORECAST_URL.into(),
+            geocoding_base_url: GEOCODING_URL.into(),
+        }
+    }
+}
+
+/// HTTP client and geocoding cache shared by the weather tools.
+struct OpenMeteo {
+    client: reqwest::Client,
+    config: WeatherConfig,
+    locations: Mutex<HashMap<String, Value>>,
+}
+
+impl OpenMeteo {
+    fn new(config: WeatherConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+            locations: Mutex::new(HashMap::new()),
+        }
+    }
+
+    async fn get_json(&self, url: &str) -> crate::Result<Value> {
+        let response = self
+            .client
+            .get(url)
+            .header("User-Agent", "sayr-engine/0.3.0")
+            .send()
+            .await
+            .map_err(|e| AgnoError::Protocol(format!("Open-Meteo request failed: {}", e)))?;
+        let status = response.status();
+        let body: Value = response.json().await.map_err(|e| {
+            AgnoError::Protocol(format!("Failed to parse Open-Meteo response: {}", e))
+        })?;
+        if !status.is_success() {
+            let reason = body["reason"].as_str().unwrap_or("unknown error");
+            return Err(AgnoError::Protocol(format!(
+                "Open-Meteo returned {}: {}",
+                status, reason
+            )));
+        }
+        Ok(body)
+    }
+
+    /// Resolve the `location` name or `latitude`/`longitude` pair in `input`.
+    async fn resolve_location(&self, input: &Value) -> crate::Result<Value> {
+        if let (Some(latitude), Some(longitude)) =
+            (input["latitude"].as_f64(), input["longitude"].as_f64())
+        {
+            return Ok(json!({ "latitude": latitude, "longitude": longitude }));
+        }
+        let name = input["location"].as_str().map(str::trim).ok_or_else(|| {
+            AgnoError::Protocol("provide either 'location' or 'latitude' and 'longitude'".into())
+        })?;
+
+        let key = name.to_lowercase();
+        if let Some(location) = self.locations.lock().unwrap().get(&key) {
+            return Ok(location.clone());
+        }
+        let url = format!(
+            "{}/v1/search?name={}&count=1&language=en&format=json",
+            self.config.geocoding_base_url.trim_end_matches('/'),
+            urlencoding::encode(name)
+        );
+        let body = self.get_json(&url).await?;
+        let place = body["results"]
+            .get(0)
+            .ok_or_else(|| AgnoError::Protocol(format!("no location found for '{}'", name)))?;
+        let location = json!({
+            "name": place["name"],
+            "country": place["country"],
+            "admin1": place["admin1"],
+            "latitude": place["latitude"],
+            "longitude": place["longitude"],
+            "timezone": place["timezone"],
+        });
+        self.locations.lock().unwrap().insert(key, location.clone());
+        Ok(location)
+    }
+
+    fn forecast_url(&self, location: &Value, input: &Value, query: &str) -> String {
+        let mut url = format!(
+            "{}/v1/forecast?latitude={}&longitude={}&timezone=auto&{}",
+            self.config.forecast_base_url.trim_end_matches('/'),
+            location["latitude"],
+            location["longitude"],
+            query
+        );
+        if input["units"].as_str() == Some("imperial") {
+            url.push_str(
+                "&temperature_unit=fahrenheit&wind_speed_unit=mph&precipitation_unit=inch",
+            );
+        }
+        url
+    }
+}
+
+fn units_parameter() -> Value {
+    json!({
+        "type": "string",
+        "enum": ["metric", "imperial"],
+        "description": "metric (°C, km/h, mm) or imperial (°F, mph, inch); default metric"
+    })
+}
+
+fn location_parameters() -> serde_json::Map<String, Value> {
+    let properties = json!({
+        "location": {
+            "type": "string",
+            "description": "Place name, e.g. 'Berlin' or 'Austin, Texas'"
+        },
+        "latitude": { "type": "number", "minimum": -90, "maximum": 90 },
+        "longitude": { "type": "number", "minimum": -180, "maximum": 180 },
+        "units": units_parameter(),
+    });
+    match properties {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// A reading paired with its unit, e.g. `{"value": 21.4, "unit": "°C"}`.
+fn measurement(values: &Value, units: &Value, field: &str) -> Value {
+    json!({ "value": values[field], "unit": units[field] })
+}
+
+/// Human-readable description of a WMO weather interpretation code.
+fn describe_weather_code(code: u64) -> &'static str {
+    match code {
+        0 => "Clear sky",
+        1 => "Mainly clear",
+        2 => "Partly cloudy",
+        3 => "Overcast",
+        45 | 48 => "Fog",
+        51 | 53 | 55 => "Drizzle",
+        56 | 57 => "Freezing drizzle",
+        61 => "Light rain",
+        63 => "Rain",
+        65 => "Heavy rain",
+        66 | 67 => "Freezing rain",
+        71 => "Light snow",
+        73 => "Snow",
+        75 => "Heavy snow",
+        77 => "Snow grains",
+        80..=82 => "Rain showers",
+        85 | 86 => "Snow showers",
+        95 => "Thunderstorm",
+        96 | 99 => "Thunderstorm with hail",
+        _ => "Unknown",
+    }
+}
+
+fn conditions(code: &Value) -> Value {
+    code.as_u64()
+        .map(|code| json!(describe_weather_code(code)))
+        .unwrap_or(Value::Null)
+}
+
+/// Shape an Open-Meteo `current` block into the tool output.
+fn parse_current(location: Value, body: &Value) -> Value {
+    let current = &body["current"];
+    let units = &body["current_units"];
+    json!({
+        "location": location,
+        "time": current["time"],
+        "conditions": conditions(&current["weather_code"]),
+        "weather_code": current["weather_code"],
+        "temperature": measurement(current, units, "temperature_2m"),
+        "apparent_temperature": measurement(current, units, "apparent_temperature"),
+        "humidity": measurement(current, units, "relative_humidity_2m"),
+        "wind_speed": measurement(current, units, "wind_speed_10m"),
+    })
+}
+
+/// Shape an Open-Meteo `daily` block into one entry per day.
+fn parse_forecast(location: Value, body: &Value) -> Value {
+    let daily = &body["daily"];
+    let units = &body["daily_units"];
+    let dates = daily["time"].as_array().cloned().unwrap_or_default();
+    let days: Vec<Value> = dates
+        .iter()
+        .enumerate()
+        .map(|(i, date)| {
+            json!({
+                "date": date,
+                "conditions": conditions(&daily["weather_code"][i]),
+                "weather_code": daily["weather_code"][i],
+                "temperature_max": daily["temperature_2m_max"][i],
+                "temperature_min": daily["temperature_2m_min"][i],
+                "precipitation_sum": daily["precipitation_sum"][i],
+                "precipitation_probability": daily["precipitation_probability_max"][i],
+            })
+        })
+        .collect();
+    json!({
+        "location": location,
+        "units": {
+            "temperature": units["temperature_2m_max"],
+            "precipitation": units["precipitation_sum"],
+            "precipitation_probability": units["precipitation_probability_max"],
+        },
+        "days": days,
+    })
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Current Weather Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Current conditions for a location
+pub struct WeatherCurrentTool {
+    api: Arc<OpenMeteo>,
+}
+
+#[async_trait]
+impl Tool for WeatherCurrentTool {
+    fn name(&self) -> &str {
+        "weather_current"
+    }
+
+    fn description(&self) -> &str {
+        "Get the current weather for a place name or latitude/longitude: temperature, feels-like temperature, humidity, wind and conditions, each with units."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        Some(json!({
+            "type": "object",
+            "properties": location_parameters(),
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let location = self.api.resolve_location(&input).await?;
+        let url = self.api.forecast_url(
+            &location,
+            &input,
+            "current=temperature_2m,apparent_temperature,relative_humidity_2m,weather_code,wind_speed_10m",
+        );
+        let body = self.api.get_json(&url).await?;
+        Ok(parse_current(location, &body))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Forecast Tool
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Daily forecast for a location
+pub struct WeatherForecastTool {
+    api: Arc<OpenMeteo>,
+}
+
+#[async_trait]
+impl Tool for WeatherForecastTool {
+    fn name(&self) -> &str {
+        "weather_forecast"
+    }
+
+    fn description(&self) -> &str {
+        "Get a daily weather forecast (up to 16 days) for a place name or latitude/longitude: conditions, high/low temperature and precipitation per day, with units."
+    }
+
+    fn parameters(&self) -> Option<Value> {
+        let mut properties = location_parameters();
+        properties.insert(
+            "days".into(),
+            json!({
+                "type": "integer",
+                "minimum": 1,
+                "maximum": MAX_FORECAST_DAYS,
+                "description": "Number of days to forecast (default: 7)"
+            }),
+        );
+        Some(json!({
+            "type": "object",
+            "properties": properties,
+        }))
+    }
+
+    async fn call(&self, input: Value) -> crate::Result<Value> {
+        let days = input["days"]
+            .as_u64()
+            .unwrap_or(7)
+            .clamp(1, MAX_FORECAST_DAYS);
+        let location = self.api.resolve_location(&input).await?;
+        let url = self.api.forecast_url(
+            &location,
+            &input,
+            &format!(
+                "daily=weather_code,temperature_2m_max,temperature_2m_min,precipitation_sum,precipitation_probability_max&forecast_days={}",
+                days
+            ),
+        );
+        let body = self.api.get_json(&url).await?;
+        Ok(parse_forecast(location, &body))
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Weather Toolkit
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Register the weather tools against the public Open-Meteo API
+pub fn register_weather_tools(registry: &mut ToolRegistry) {
+    register_weather_tools_with_config(registry, WeatherConfig::default());
+}
+
+/// Register the weather tools with custom endpoints. Both tools share one geocoding cache.
+pub fn register_weather_tools_with_config(registry: &mut ToolRegistry, config: WeatherConfig) {
+    let api = Arc::new(OpenMeteo::new(config));
+    registry.register(WeatherCurrentTool { api: api.clone() });
+    registry.register(WeatherForecastTool { api });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{any, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    /// Serve geocoding and forecast requests from fixed bodies.
+    async fn open_meteo_stub(forecast: Value) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(path("/v1/search"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_json(json!({"results": [{
+                    "name": "Berlin", "country": "Germany", "admin1": "Land Berlin",
+                    "latitude": 52.52, "longitude": 13.41, "timezone": "Europe/Berlin"
+                }]})),
+            )
+            .mount(&server)
+            .await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(forecast))
+            .with_priority(10)
+            .mount(&server)
+            .await;
+        server
+    }
+
+    /// Method, path and query of every request `server` received, in order.
+    async fn request_lines(server: &MockServer) -> Vec<String> {
+        server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|request| {
+                let query = request.url.query().unwrap_or_default();
+                format!("{} {}?{query}", request.method, request.url.path())
+            })
+            .collect()
+    }
+
+    fn registry_for(base_url: &str) -> ToolRegistry {
+        let mut registry = ToolRegistry::new();
+        register_weather_tools_with_config(
+            &mut registry,
+            WeatherConfig {
+                forecast_base_url: base_url.into(),
+                geocoding_base_url: base_url.into(),
+            },
+        );
+        registry
+    }
+
+    #[tokio::test]
+    async fn geocodes_city_name_once_per_session() {
+        let current = json!({
+            "current_units": {
+                "temperature_2m": "°C", "apparent_temperature": "°C",
+                "relative_humidity_2m": "%", "wind_speed_10m": "km/h"
+            },
+            "current": {
+                "time": "2024-05-01T12:00", "temperature_2m": 18.3,
+                "apparent_temperature": 17.1, "relative_humidity_2m": 52,
+                "weather_code": 2, "wind_speed_10m": 11.5
+            }
+        });
+        let server = open_meteo_stub(current).await;
+        let registry = registry_for(&server.uri());
+
+        let output = registry
+            .call("weather_current", json!({"location": "Berlin"}))
+            .await
+            .unwrap();
+        registry
+            .call("weather_current", json!({"location": "berlin "}))
+            .await
+            .unwrap();
+
+        assert_eq!(output["location"]["name"], "Berlin");
+        assert_eq!(output["location"]["latitude"], 52.52);
+        assert_eq!(output["conditions"], "Partly cloudy");
+        assert_eq!(output["temperature"], json!({"value": 18.3, "unit": "°C"}));
+        assert_eq!(output["wind_speed"]["unit"], "km/h");
+
+        let requests = request_lines(&server).await;
+        let geocoding: Vec<&String> = requests
+            .iter()
+            .filter(|line| line.starts_with("GET /v1/search"))
+            .collect();
+        assert_eq!(geocoding.len(), 1);
+        assert!(geocoding[0].contains("name=Berlin"));
+        assert_eq!(requests.len(), 3);
+        assert!(requests[1].starts_with("GET /v1/forecast?latitude=52.52&longitude=13.41"));
+    }
+
+    #[tokio::test]
+    async fn parses_daily_forecast_for_coordinates() {
+        let forecast = json!({
+            "daily_units": {
+                "temperature_2m_max": "°F", "temperature_2m_min": "°F",
+                "precipitation_sum": "inch", "precipitation_probability_max": "%"
+            },
+            "daily": {
+                "time": ["2024-05-01", "2024-05-02"],
+                "weather_code": [61, 0],
+                "temperature_2m_max": [64.2, 70.0],
+                "temperature_2m_min": [50.1, 52.3],
+                "precipitation_sum": [0.2, 0.0],
+                "precipitation_probability_max": [80, 5]
+            }
+        });
+        let server = open_meteo_stub(forecast).await;
+        let registry = registry_for(&server.uri());
+
+        let output = registry
+            .call(
+                "weather_forecast",
+                json!({"latitude": 40.7, "longitude": -74.0, "days": 2, "units": "imperial"}),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(
+            output["location"],
+            json!({"latitude": 40.7, "longitude": -74.0})
+        );
+        assert_eq!(output["units"]["temperature"], "°F");
+        let days = output["days"].as_array().unwrap();
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0]["date"], "2024-05-01");
+        assert_eq!(days[0]["conditions"], "Light rain");
+        assert_eq!(days[0]["temperature_max"], 64.2);
+        assert_eq!(days[1]["precipitation_probability"], 5);
+
+        let requests = request_lines(&server).await;
+        assert_eq!(requests.len(), 1);
+        assert!(requests[0].contains("forecast_days=2"));
+        assert!(requests[0].contains("temperature_unit=fahrenheit"));
+    }
+
+    #[tokio::test]
+    async fn requires_a_location() {
+        let registry = registry_for("http://127.0.0.1:1");
+        let err = registry
+            .call("weather_current", json!({"units": "metric"}))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("'location'"));
+    }
+}
@@ -2,20 +2,52 @@
 //!
 //! Provides tools for searching Wikipedia and retrieving article summaries.
 
+use std::time::Duration;
+
 use async_trait::async_trait;
 use serde_json::{json, Value};
 
 use crate::error::{AgnoError, Result};
 use crate::tool::{Tool, ToolRegistry};
+use crate::tools::query_cache::{normalize_query, QueryCache, DEFAULT_QUERY_CACHE_TTL};
+
+/// Configuration for the Wikipedia toolkit
+#[derive(Clone)]
+pub struct WikipediaConfig {
+    /// Site root, e.g. `https://de.wikipedia.org` for another language edition.
+    pub base_url: String,
+    /// How long a summary is reused before fetching it again; zero disables it.
+    pub cache_ttl: Duration,
+}
+
+impl Default for WikipediaConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://en.wikipedia.org".into(),
+            cache_ttl: DEFAULT_QUERY_CACHE_TTL,
+        }
+    }
+}
 
 /// Create a Wikipedia toolkit
 pub fn wikipedia_toolkit() -> ToolRegistry {
+    wikipedia_toolkit_with_config(WikipediaConfig::default())
+}
+
+/// Create a Wikipedia toolkit for a custom site or cache window
+pub fn wikipedia_toolkit_with_config(config: WikipediaConfig) -> ToolRegistry {
     let mut registry = ToolRegistry::new();
-    registry.register(WikipediaSearchTool);
+    registry.register(WikipediaSearchTool {
+        cache: QueryCache::new(config.cache_ttl),
+        base_url: config.base_url.trim_end_matches('/').to_string(),
+    });
     registry
 }
 
-struct WikipediaSearchTool;
+struct WikipediaSearchTool {
+    base_url: String,
+    cache: QueryCache,
+}
 
 #[async_trait]
 impl Tool for WikipediaSearchTool {
@@ -27,6 +59,10 @@ impl Tool for WikipediaSearchTool {
         "Search Wikipedia for a topic and get a summary. Expects {\"query\": string}."
     }
 
+    fn cacheable(&self) -> bool {
+        true
+    }
+
     fn parameters(&self) -> Option<Value> {
         Some(json!({
             "type": "object",
@@ -43,15 +79,23 @@ impl Tool for WikipediaSearchTool {
             .and_then(Value::as_str)
             .ok_or_else(|| AgnoError::Protocol("missing `query` for wikipedia_search".into()))?;
 
-        // Use Wikipedia API to get summary
-        let summary = fetch_wikipedia_summary(query).await?;
-
-        Ok(json!({
-            "query": query,
-            "title": summary.title,
-            "extract": summary.extract,
-            "url": format!("https://en.wikipedia.org/wiki/{}", urlencoding::encode(&summary.title))
-        }))
+        let key = normalize_query(query);
+        let mut output = match self.cache.get(&key) {
+            Some(cached) => cached,
+            None => {
+                // Use Wikipedia API to get summary
+                let summary = fetch_wikipedia_summary(&self.base_url, query).await?;
+                let output = json!({
+                    "title": summary.title,
+                    "extract": summary.extract,
+                    "url": format!("{}/wiki/{}", self.base_url, urlencoding::encode(&summary.title))
+                });
+                self.cache.insert(key, output.clone());
+                output
+            }
+        };
+        output["query"] = json!(query);
+        Ok(output)
     }
 }
 
@@ -61,12 +105,13 @@ struct WikipediaSummary {
     extract: String,
 }
 
-async fn fetch_wikipedia_summary(query: &str) -> Result<WikipediaSummary> {
+async fn fetch_wikipedia_summary(base_url: &str, query: &str) -> Result<WikipediaSummary> {
     let client = reqwest::Client::new();
 
     // Use Wikipedia API for summary
     let url = format!(
-        "https://en.wikipedia.org/api/rest_v1/page/summary/{}",
+        "{}/api/rest_v1/page/summary/{}",
+        base_url,
         urlencoding::encode(query)
     );
 
@@ -75,18 +120,18 @@ async fn fetch_wikipedia_summary(query: &str) -> Result<WikipediaSummary> {
         .header("User-Agent", "SayrEngine/1.0 (https://github.com/YASSERRMD/sayr-engine)")
         .send()
         .await
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "wikipedia_search".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "wikipedia_search".into(),
             source: Box::new(e),
         })?;
 
     if !response.status().is_success() {
         // Try search API as fallback
-        return search_wikipedia_fallback(query).await;
+        return search_wikipedia_fallback(base_url, query).await;
     }
 
-    let json: Value = response.json().await.map_err(|e| AgnoError::ToolInvocation {
-        name: "wikipedia_search".into(),
+    let json: Value = response.json().await.map_err(|e| AgnoError::ToolExecution {
+        tool: "wikipedia_search".into(),
         source: Box::new(e),
     })?;
 
@@ -102,12 +147,13 @@ async fn fetch_wikipedia_summary(query: &str) -> Result<WikipediaSummary> {
     Ok(WikipediaSummary { title, extract })
 }
 
-async fn search_wikipedia_fallback(query: &str) -> Result<WikipediaSummary> {
+async fn search_wikipedia_fallback(base_url: &str, query: &str) -> Result<WikipediaSummary> {
     let client = reqwest::Client::new();
 
     // Use search API
     let url = format!(
-        "https://en.wikipedia.org/w/api.php?action=query&list=search&srsearch={}&format=json&srprop=snippet",
+        "{}/w/api.php?action=query&list=search&srsearch={}&format=json&srprop=snippet",
+        base_url,
         urlencoding::encode(query)
     );
 
@@ -116,13 +162,13 @@ async fn search_wikipedia_fallback(query: &str) -> Result<WikipediaSummary> {
         .header("User-Agent", "AgnoRust/1.0")
         .send()
         .await
-        .map_err(|e| AgnoError::ToolInvocation {
-            name: "wikipedia_search".into(),
+        .map_err(|e| AgnoError::ToolExecution {
+            tool: "wikipedia_search".into(),
             source: Box::new(e),
         })?;
 
-    let json: Value = response.json().await.map_err(|e| AgnoError::ToolInvocation {
-        name: "wikipedia_search".into(),
+    let json: Value = response.json().await.map_err(|e| AgnoError::ToolExecution {
+        tool: "wikipedia_search".into(),
         source: Box::new(e),
     })?;
 
@@ -155,4 +201,40 @@ mod tests {
         let registry = wikipedia_toolkit();
         assert!(registry.get("wikipedia_search").is_some());
     }
+
+    #[tokio::test]
+    async fn serves_repeated_query_from_cache() {
+        use wiremock::matchers::any;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(any())
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "title": "Rust (programming language)",
+                "extract": "A language."
+            })))
+            .mount(&server)
+            .await;
+
+        let registry = wikipedia_toolkit_with_config(WikipediaConfig {
+            base_url: server.uri(),
+            ..WikipediaConfig::default()
+        });
+        let first = registry
+            .call("wikipedia_search", json!({"query": "Rust"}))
+            .await
+            .unwrap();
+        let second = registry
+            .call("wikipedia_search", json!({"query": "rust."}))
+            .await
+            .unwrap();
+
+        assert_eq!(server.received_requests().await.unwrap().len(), 1);
+        assert_eq!(second["title"], first["title"]);
+        assert_eq!(second["query"], "rust.");
+        assert_eq!(
+            first["url"],
+            format!("{}/wiki/Rust%20%28programming%20language%29", server.uri())
+        );
+    }
 }
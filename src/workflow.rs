@@ -1,11 +1,17 @@
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 
 use crate::agent::Agent;
+use crate::error::AgnoError;
+#[cfg(feature = "telemetry")]
+use crate::telemetry::{TelemetryCollector, TelemetryLabels};
 use crate::{LanguageModel, Result};
 
 /// Shared state threaded through a workflow execution.
@@ -105,6 +111,260 @@ impl<M: LanguageModel> WorkflowTask for AgentTask<M> {
 
 pub type Condition = Arc<dyn Fn(&WorkflowContext) -> bool + Send + Sync>;
 
+/// Runs independent tasks concurrently and stores each result under its own key.
+///
+/// Every branch works on a snapshot of the context; afterwards the state each branch changed
+/// is merged back. Two branches writing the same key is reported as an error rather than
+/// letting one silently overwrite the other.
+#[derive(Clone, Default)]
+pub struct ParallelNode {
+    branches: Vec<(String, Arc<dyn WorkflowTask>)>,
+}
+
+impl ParallelNode {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a task whose result is written to `output_key`.
+    pub fn branch(mut self, output_key: impl Into<String>, task: Arc<dyn WorkflowTask>) -> Self {
+        self.branches.push((output_key.into(), task));
+        self
+    }
+
+    async fn execute(&self, ctx: &mut WorkflowContext) -> Result<Value> {
+        let snapshot = ctx.clone();
+        let runs = self.branches.iter().map(|(key, task)| {
+            let mut branch_ctx = snapshot.clone();
+            async move {
+                let output = task.run(&mut branch_ctx).await;
+                (key, output, branch_ctx)
+            }
+        });
+        let finished = futures::future::join_all(runs).await;
+
+        let mut written: HashMap<String, String> = HashMap::new();
+        let mut outputs = Map::new();
+        for (key, output, branch_ctx) in finished {
+            let output = output?;
+            let changed = branch_ctx
+                .state
+                .into_iter()
+                .filter(|(k, v)| snapshot.state.get(k) != Some(v))
+                .chain(std::iter::once((key.clone(), output.clone())));
+            for (state_key, value) in changed {
+                if let Some(other) = written.insert(state_key.clone(), key.clone()) {
+                    if &other != key {
+                        return Err(AgnoError::Protocol(format!(
+                            "parallel branches `{other}` and `{key}` both wrote `{state_key}`"
+                        )));
+                    }
+                }
+                ctx.state.insert(state_key, value);
+            }
+            ctx.logs
+                .extend(branch_ctx.logs.into_iter().skip(snapshot.logs.len()));
+            outputs.insert(key.clone(), output);
+        }
+        Ok(Value::Object(outputs))
+    }
+}
+
+/// Picks the name of the next node in a [`WorkflowGraph`], or `None` to finish.
+pub type EdgeSelector = Arc<dyn Fn(&WorkflowContext) -> Option<String> + Send + Sync>;
+
+/// Outgoing edge of a named node in a [`WorkflowGraph`].
+#[derive(Clone)]
+pub enum WorkflowEdge {
+    Next(String),
+    Select(EdgeSelector),
+}
+
+/// Named nodes connected by edges that are resolved against the context after each node
+/// runs, allowing if/else and switch-style flows.
+#[derive(Clone)]
+pub struct WorkflowGraph {
+    entry: String,
+    nodes: HashMap<String, WorkflowNode>,
+    edges: HashMap<String, WorkflowEdge>,
+    max_steps: usize,
+}
+
+impl WorkflowGraph {
+    pub fn new(entry: impl Into<String>) -> Self {
+        Self {
+            entry: entry.into(),
+            nodes: HashMap::new(),
+            edges: HashMap::new(),
+            max_steps: 100,
+        }
+    }
+
+    pub fn node(mut self, name: impl Into<String>, node: WorkflowNode) -> Self {
+        self.nodes.insert(name.into(), node);
+        self
+    }
+
+    /// Always continue from `from` to `to`.
+    pub fn edge(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.edges
+            .insert(from.into(), WorkflowEdge::Next(to.into()));
+        self
+    }
+
+    /// Continue to `if_true` or `if_false` depending on `condition`.
+    pub fn branch(
+        self,
+        from: impl Into<String>,
+        condition: Condition,
+        if_true: impl Into<String>,
+        if_false: impl Into<String>,
+    ) -> Self {
+        let (if_true, if_false) = (if_true.into(), if_false.into());
+        self.select(
+            from,
+            Arc::new(move |ctx: &WorkflowContext| {
+                Some(if condition(ctx) {
+                    if_true.clone()
+                } else {
+                    if_false.clone()
+                })
+            }),
+        )
+    }
+
+    /// Continue to whichever node `selector` names; stop when it returns `None`.
+    pub fn select(mut self, from: impl Into<String>, selector: EdgeSelector) -> Self {
+        self.edges
+            .insert(from.into(), WorkflowEdge::Select(selector));
+        self
+    }
+
+    /// Upper bound on visited nodes, guarding against cycles that never exit.
+    pub fn with_max_steps(mut self, max_steps: usize) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    async fn execute(&self, ctx: &mut WorkflowContext, env: RunEnv<'_>) -> Result<Value> {
+        let mut current = self.entry.clone();
+        for _ in 0..self.max_steps {
+            let node = self.nodes.get(&current).ok_or_else(|| {
+                AgnoError::Protocol(format!("workflow node `{current}` is not defined"))
+            })?;
+            ctx.logs.push(format!("entering node `{current}`"));
+            let last = node.execute(ctx, env).await?;
+            current = match self.edges.get(&current) {
+                None => return Ok(last),
+                Some(WorkflowEdge::Next(next)) => next.clone(),
+                Some(WorkflowEdge::Select(selector)) => match selector(ctx) {
+                    Some(next) => next,
+                    None => return Ok(last),
+                },
+            };
+        }
+        Err(AgnoError::Protocol(format!(
+            "workflow graph exceeded {} steps",
+            self.max_steps
+        )))
+    }
+}
+
+/// What a [`ResilientNode`] does once its retries are exhausted.
+#[derive(Clone, Default)]
+pub enum ErrorPolicy {
+    /// Propagate the error and stop the workflow.
+    #[default]
+    FailFast,
+    /// Yield `null` and carry on with the next node.
+    Skip,
+    /// Run the given node in place of the failed one.
+    Fallback(Box<WorkflowNode>),
+}
+
+/// Wraps a node with retries and an [`ErrorPolicy`].
+///
+/// The context is restored to its state before the node ran after every failed attempt, so
+/// partial writes from a failed attempt never leak into retries or the fallback.
+#[derive(Clone)]
+pub struct ResilientNode {
+    node: Box<WorkflowNode>,
+    name: Option<String>,
+    retries: u32,
+    backoff: Duration,
+    on_error: ErrorPolicy,
+}
+
+impl ResilientNode {
+    fn wrap(node: WorkflowNode) -> Self {
+        Self {
+            node: Box::new(node),
+            name: None,
+            retries: 0,
+            backoff: Duration::ZERO,
+            on_error: ErrorPolicy::FailFast,
+        }
+    }
+
+    async fn execute(&self, ctx: &mut WorkflowContext, env: RunEnv<'_>) -> Result<Value> {
+        let name = self.name.as_deref().unwrap_or("unnamed");
+        let snapshot = ctx.clone();
+        let mut attempt = 0;
+        let err = loop {
+            match self.node.execute(ctx, env).await {
+                Ok(value) => return Ok(value),
+                Err(err) => {
+                    *ctx = snapshot.clone();
+                    env.record_failure(name, &err, attempt);
+                    ctx.logs
+                        .push(format!("node `{name}` failed on attempt {attempt}: {err}"));
+                    if attempt == self.retries {
+                        break err;
+                    }
+                    tokio::time::sleep(self.backoff.saturating_mul(2u32.saturating_pow(attempt)))
+                        .await;
+                    attempt += 1;
+                }
+            }
+        };
+        match &self.on_error {
+            ErrorPolicy::FailFast => Err(err),
+            ErrorPolicy::Skip => {
+                ctx.logs.push(format!("skipping node `{name}`"));
+                Ok(Value::Null)
+            }
+            ErrorPolicy::Fallback(fallback) => {
+                ctx.logs.push(format!("running fallback for node `{name}`"));
+                fallback.execute(ctx, env).await
+            }
+        }
+    }
+}
+
+/// Per-run settings shared by every node of a workflow.
+#[derive(Clone, Copy)]
+struct RunEnv<'a> {
+    #[cfg_attr(not(feature = "telemetry"), allow(dead_code))]
+    workflow: &'a str,
+    #[cfg(feature = "telemetry")]
+    telemetry: Option<&'a TelemetryCollector>,
+}
+
+impl RunEnv<'_> {
+    fn record_failure(&self, node: &str, err: &AgnoError, attempt: u32) {
+        tracing::warn!(node, attempt, "workflow node failed: {}", err);
+        #[cfg(feature = "telemetry")]
+        if let Some(telemetry) = self.telemetry {
+            telemetry.record_failure(
+                node,
+                err.to_string(),
+                attempt,
+                TelemetryLabels::default().with_workflow(self.workflow),
+            );
+        }
+    }
+}
+
 #[derive(Clone)]
 pub enum WorkflowNode {
     Task(Arc<dyn WorkflowTask>),
@@ -120,12 +380,46 @@ pub enum WorkflowNode {
         body: Box<WorkflowNode>,
         max_iterations: usize,
     },
+    Graph(WorkflowGraph),
+    /// Fan out to concurrently running tasks, then continue once all have finished.
+    FanOut(ParallelNode),
+    Resilient(ResilientNode),
 }
 
 impl WorkflowNode {
+    /// Name the node in logs and failure telemetry.
+    pub fn named(self, name: impl Into<String>) -> Self {
+        let mut node = self.into_resilient();
+        node.name = Some(name.into());
+        WorkflowNode::Resilient(node)
+    }
+
+    /// Retry the node up to `retries` more times, waiting `backoff * 2^attempt` in between.
+    pub fn with_retries(self, retries: u32, backoff: Duration) -> Self {
+        let mut node = self.into_resilient();
+        node.retries = retries;
+        node.backoff = backoff;
+        WorkflowNode::Resilient(node)
+    }
+
+    /// Decide what happens once the node has failed and exhausted its retries.
+    pub fn with_error_policy(self, policy: ErrorPolicy) -> Self {
+        let mut node = self.into_resilient();
+        node.on_error = policy;
+        WorkflowNode::Resilient(node)
+    }
+
+    fn into_resilient(self) -> ResilientNode {
+        match self {
+            WorkflowNode::Resilient(node) => node,
+            other => ResilientNode::wrap(other),
+        }
+    }
+
     fn execute<'a>(
         &'a self,
         ctx: &'a mut WorkflowContext,
+        env: RunEnv<'a>,
     ) -> std::pin::Pin<Box<dyn Future<Output = Result<Value>> + Send + 'a>> {
         Box::pin(async move {
             match self {
@@ -133,14 +427,14 @@ impl WorkflowNode {
                 WorkflowNode::Sequence(steps) => {
                     let mut last = Value::Null;
                     for step in steps {
-                        last = step.execute(ctx).await?;
+                        last = step.execute(ctx, env).await?;
                     }
                     Ok(last)
                 }
                 WorkflowNode::Parallel(steps) => {
                     let mut combined = Vec::new();
                     for step in steps {
-                        combined.push(step.execute(ctx).await?);
+                        combined.push(step.execute(ctx, env).await?);
                     }
                     Ok(Value::Array(combined))
                 }
@@ -150,9 +444,9 @@ impl WorkflowNode {
                     else_branch,
                 } => {
                     if condition(ctx) {
-                        then_branch.execute(ctx).await
+                        then_branch.execute(ctx, env).await
                     } else if let Some(other) = else_branch {
-                        other.execute(ctx).await
+                        other.execute(ctx, env).await
                     } else {
                         Ok(Value::Null)
                     }
@@ -167,19 +461,43 @@ impl WorkflowNode {
                         if !(condition)(ctx) {
                             break;
                         }
-                        last = body.execute(ctx).await?;
+                        last = body.execute(ctx, env).await?;
                     }
                     Ok(last)
                 }
+                WorkflowNode::Graph(graph) => graph.execute(ctx, env).await,
+                WorkflowNode::FanOut(parallel) => parallel.execute(ctx).await,
+                WorkflowNode::Resilient(node) => node.execute(ctx, env).await,
             }
         })
     }
 }
 
+/// Progress of a workflow run, persisted after every top-level step.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorkflowCheckpoint {
+    pub state: Map<String, Value>,
+    pub logs: Vec<String>,
+    /// Index of the next top-level step to execute.
+    pub next_step: usize,
+    /// Output of the most recently completed step.
+    pub last_output: Value,
+}
+
+/// Storage for [`WorkflowCheckpoint`]s keyed by run id.
+#[async_trait]
+pub trait Checkpointer: Send + Sync {
+    async fn save(&self, run_id: &str, checkpoint: &WorkflowCheckpoint) -> Result<()>;
+    async fn load(&self, run_id: &str) -> Result<Option<WorkflowCheckpoint>>;
+}
+
 #[derive(Clone)]
 pub struct Workflow {
     pub name: String,
     pub root: WorkflowNode,
+    checkpointer: Option<Arc<dyn Checkpointer>>,
+    #[cfg(feature = "telemetry")]
+    telemetry: Option<TelemetryCollector>,
 }
 
 impl Workflow {
@@ -187,11 +505,72 @@ impl Workflow {
         Self {
             name: name.into(),
             root,
+            checkpointer: None,
+            #[cfg(feature = "telemetry")]
+            telemetry: None,
         }
     }
 
+    /// Persist progress through `checkpointer` when running with [`Workflow::run_with_id`].
+    pub fn with_checkpointer(mut self, checkpointer: Arc<dyn Checkpointer>) -> Self {
+        self.checkpointer = Some(checkpointer);
+        self
+    }
+
+    /// Record failed node attempts, labelled with the workflow and node name.
+    #[cfg(feature = "telemetry")]
+    pub fn with_telemetry(mut self, telemetry: TelemetryCollector) -> Self {
+        self.telemetry = Some(telemetry);
+        self
+    }
+
     pub async fn run(&self, ctx: &mut WorkflowContext) -> Result<Value> {
-        self.root.execute(ctx).await
+        self.root.execute(ctx, self.env()).await
+    }
+
+    /// Run under `run_id`, checkpointing after each top-level step.
+    ///
+    /// The steps of a root [`WorkflowNode::Sequence`] are checkpointed individually; any other
+    /// root is a single step. If a checkpoint for `run_id` exists, `ctx` is replaced by the saved
+    /// state and execution resumes at the first step that had not completed.
+    pub async fn run_with_id(&self, run_id: &str, ctx: &mut WorkflowContext) -> Result<Value> {
+        let Some(checkpointer) = &self.checkpointer else {
+            return self.run(ctx).await;
+        };
+        let steps = match &self.root {
+            WorkflowNode::Sequence(steps) => steps.as_slice(),
+            root => std::slice::from_ref(root),
+        };
+
+        let mut checkpoint = match checkpointer.load(run_id).await? {
+            Some(saved) => {
+                ctx.state = saved.state.clone();
+                ctx.logs = saved.logs.clone();
+                ctx.logs.push(format!(
+                    "resuming run `{run_id}` at step {}",
+                    saved.next_step
+                ));
+                saved
+            }
+            None => WorkflowCheckpoint::default(),
+        };
+
+        for step in steps.iter().skip(checkpoint.next_step) {
+            checkpoint.last_output = step.execute(ctx, self.env()).await?;
+            checkpoint.next_step += 1;
+            checkpoint.state = ctx.state.clone();
+            checkpoint.logs = ctx.logs.clone();
+            checkpointer.save(run_id, &checkpoint).await?;
+        }
+        Ok(checkpoint.last_output)
+    }
+
+    fn env(&self) -> RunEnv<'_> {
+        RunEnv {
+            workflow: &self.name,
+            #[cfg(feature = "telemetry")]
+            telemetry: self.telemetry.as_ref(),
+        }
     }
 }
 
@@ -268,4 +647,205 @@ mod tests {
         flow.run(&mut ctx).await.unwrap();
         assert_eq!(ctx.get("count").unwrap(), &json!(3));
     }
+
+    #[tokio::test]
+    async fn follows_branch_written_by_first_task() {
+        fn review(approved: bool) -> WorkflowNode {
+            WorkflowNode::Task(Arc::new(FunctionTask::new(
+                move |ctx: &mut WorkflowContext| {
+                    Box::pin(async move {
+                        ctx.insert("approved", json!(approved));
+                        Ok(json!("reviewed"))
+                    })
+                },
+            )))
+        }
+        fn outcome(label: &'static str) -> WorkflowNode {
+            WorkflowNode::Task(Arc::new(FunctionTask::new(
+                move |ctx: &mut WorkflowContext| {
+                    Box::pin(async move {
+                        ctx.insert("outcome", json!(label));
+                        Ok(json!(label))
+                    })
+                },
+            )))
+        }
+
+        for (approved, expected) in [(true, "published"), (false, "rejected")] {
+            let is_approved: Condition = Arc::new(|ctx: &WorkflowContext| {
+                ctx.get("approved")
+                    .and_then(|v| v.as_bool())
+                    .unwrap_or(false)
+            });
+            let flow = Workflow::new(
+                "review",
+                WorkflowNode::Graph(
+                    WorkflowGraph::new("review")
+                        .node("review", review(approved))
+                        .node("publish", outcome("published"))
+                        .node("reject", outcome("rejected"))
+                        .branch("review", is_approved, "publish", "reject"),
+                ),
+            );
+
+            let mut ctx = WorkflowContext::default();
+            let result = flow.run(&mut ctx).await.unwrap();
+            assert_eq!(result, json!(expected));
+            assert_eq!(ctx.get("outcome").unwrap(), &json!(expected));
+        }
+    }
+
+    #[tokio::test]
+    async fn rejects_edges_to_unknown_nodes() {
+        let flow = Workflow::new(
+            "broken",
+            WorkflowNode::Graph(
+                WorkflowGraph::new("start")
+                    .node("start", WorkflowNode::Sequence(vec![]))
+                    .edge("start", "missing"),
+            ),
+        );
+
+        let err = flow.run(&mut WorkflowContext::default()).await.unwrap_err();
+        assert!(err.to_string().contains("`missing`"));
+    }
+
+    #[tokio::test]
+    async fn fans_out_tasks_concurrently_and_joins_results() {
+        fn slow(label: &'static str) -> Arc<dyn WorkflowTask> {
+            Arc::new(FunctionTask::new(move |_ctx: &mut WorkflowContext| {
+                Box::pin(async move {
+                    tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                    Ok(json!(format!("{label} done")))
+                })
+            }))
+        }
+        let join = FunctionTask::new(|ctx: &mut WorkflowContext| {
+            Box::pin(async move {
+                let merged: Vec<String> = ["summary", "entities", "sentiment"]
+                    .iter()
+                    .filter_map(|k| ctx.get(k).and_then(|v| v.as_str()).map(String::from))
+                    .collect();
+                Ok(json!(merged.join(", ")))
+            })
+        });
+
+        let flow = Workflow::new(
+            "documents",
+            WorkflowNode::Sequence(vec![
+                WorkflowNode::FanOut(
+                    ParallelNode::new()
+                        .branch("summary", slow("summary"))
+                        .branch("entities", slow("entities"))
+                        .branch("sentiment", slow("sentiment")),
+                ),
+                WorkflowNode::Task(Arc::new(join)),
+            ]),
+        );
+
+        let mut ctx = WorkflowContext::default();
+        let started = std::time::Instant::now();
+        let result = flow.run(&mut ctx).await.unwrap();
+        assert!(started.elapsed() < std::time::Duration::from_millis(500));
+        assert_eq!(result, json!("summary done, entities done, sentiment done"));
+        assert_eq!(ctx.get("entities").unwrap(), &json!("entities done"));
+    }
+
+    #[tokio::test]
+    async fn rejects_parallel_branches_writing_same_key() {
+        fn writer(value: i64) -> Arc<dyn WorkflowTask> {
+            Arc::new(FunctionTask::new(move |ctx: &mut WorkflowContext| {
+                Box::pin(async move {
+                    ctx.insert("shared", json!(value));
+                    Ok(json!(value))
+                })
+            }))
+        }
+        let node = WorkflowNode::FanOut(
+            ParallelNode::new()
+                .branch("first", writer(1))
+                .branch("second", writer(2)),
+        );
+
+        let err = Workflow::new("conflict", node)
+            .run(&mut WorkflowContext::default())
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("`shared`"));
+    }
+
+    fn flaky(failures: usize, calls: Arc<std::sync::atomic::AtomicUsize>) -> WorkflowNode {
+        WorkflowNode::Task(Arc::new(FunctionTask::new(
+            move |ctx: &mut WorkflowContext| {
+                let calls = calls.clone();
+                Box::pin(async move {
+                    let call = calls.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                    ctx.insert("partial", json!(call));
+                    if call < failures {
+                        Err(AgnoError::Protocol("transient failure".into()))
+                    } else {
+                        Ok(json!("recovered"))
+                    }
+                })
+            },
+        )))
+    }
+
+    #[cfg(feature = "telemetry")]
+    #[tokio::test]
+    async fn retries_failing_node_until_it_succeeds() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let telemetry = TelemetryCollector::default();
+        let flow = Workflow::new(
+            "retrying",
+            flaky(2, calls.clone())
+                .named("summarize")
+                .with_retries(3, Duration::from_millis(1)),
+        )
+        .with_telemetry(telemetry.clone());
+
+        let result = flow.run(&mut WorkflowContext::default()).await.unwrap();
+        assert_eq!(result, json!("recovered"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 3);
+
+        let (_, failures) = telemetry.drain();
+        assert_eq!(failures.len(), 2);
+        assert!(failures.iter().all(|f| f.context == "summarize"));
+        assert_eq!(failures[1].attempt, 1);
+        assert_eq!(failures[0].labels.workflow.as_deref(), Some("retrying"));
+    }
+
+    #[tokio::test]
+    async fn runs_fallback_once_retries_are_exhausted() {
+        let calls = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let fallback =
+            WorkflowNode::Task(Arc::new(FunctionTask::new(|ctx: &mut WorkflowContext| {
+                Box::pin(async move {
+                    ctx.insert("used_fallback", json!(true));
+                    Ok(json!("fallback"))
+                })
+            })));
+        let flow = Workflow::new(
+            "fallback",
+            flaky(usize::MAX, calls.clone())
+                .with_retries(1, Duration::ZERO)
+                .with_error_policy(ErrorPolicy::Fallback(Box::new(fallback))),
+        );
+
+        let mut ctx = WorkflowContext::default();
+        let result = flow.run(&mut ctx).await.unwrap();
+        assert_eq!(result, json!("fallback"));
+        assert_eq!(calls.load(std::sync::atomic::Ordering::SeqCst), 2);
+        assert!(ctx.get("partial").is_none());
+        assert_eq!(ctx.get("used_fallback"), Some(&json!(true)));
+
+        let skipped = Workflow::new(
+            "skip",
+            flaky(usize::MAX, calls).with_error_policy(ErrorPolicy::Skip),
+        );
+        assert_eq!(
+            skipped.run(&mut WorkflowContext::default()).await.unwrap(),
+            Value::Null
+        );
+    }
 }
@@ -8,6 +8,7 @@ async fn emits_metrics_and_traces_with_labels() {
         tenant: Some("tenant-a".into()),
         tool: Some("integration".into()),
         workflow: Some("workflow-a".into()),
+        run_id: None,
     };
 
     let telemetry = TelemetryCollector::default();